@@ -1,38 +1,766 @@
-// Build script to compile GLSL shaders to SPIR-V
+// Build script to compile shaders to SPIR-V
+//
+// - Every shader in shaders/ is discovered by extension:
+//     GLSL  .vert/.frag/.comp          -> <file>.spv            (naga)
+//     WGSL  .wgsl                      -> <file>.<entry>.spv    (naga, one per entry point)
+//     HLSL  .vert.hlsl/.frag.hlsl/...  -> <file>.spv            (DXC, entry point `main`,
+//                                                                 `hlsl` feature only)
+//   .glsl and stage-less .hlsl/.hlsli files are include-only
+// - `#include "file"` is resolved relative to the including file, then shaders/
+// - Extra defines come from SHADER_DEFINES (e.g. "SHADOWS,MAX_LIGHTS=8")
+// - `#pragma keywords A B` in GLSL/HLSL compiles every combination of the
+//   keywords as defines: <file>.spv (none), <file>+A.spv, <file>+A+B.spv, ...
+//   and $OUT_DIR/shader_permutations.rs lists them for backend::permutation
+// - `#pragma spec_constant NAME ID DEFAULT` in GLSL declares a uint
+//   specialization constant: NAME expands to a marker literal, and once naga
+//   has written the SPIR-V its OpConstant becomes an OpSpecConstant with
+//   SpecId ID (naga can't emit specialization constants itself). Read NAME
+//   through a local variable (`uint model = NAME;`) so naga doesn't fold it
+//   into a constant expression
+// - GLSL/WGSL need no external tools. HLSL is opt-in: without the `hlsl`
+//   cargo feature a .hlsl shader is a build error; with it, `dxc` must be on
+//   PATH (or set $DXC)
+// - Every SPIR-V output is re-parsed and validated before it is written
+// - Any error fails the build, so a stale .spv can never be embedded
+// - Output goes to $OUT_DIR/shaders/; a shader is skipped when its stamp
+//   matches: an FNV hash of the source, defines, language and compile flags,
+//   BUILD_SCRIPT_VERSION and the naga version
 
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::path::Path;
+
+const SHADER_DIR: &str = "shaders";
+
+/// Part of every stamp: bump it with any change here that alters the
+/// SPIR-V written for an unchanged source (passes, options, validation)
+const BUILD_SCRIPT_VERSION: u32 = 1;
+
+/// naga writes GLSL for Vulkan's clip space as is; WGSL follows WebGPU's
+/// Y-up clip space and is flipped like wgpu does on Vulkan
+const GLSL_ADJUST_COORDINATE_SPACE: bool = false;
+const WGSL_ADJUST_COORDINATE_SPACE: bool = true;
+
+/// SPIR-V environment DXC targets
+const DXC_TARGET_ENV: &str = "-fspv-target-env=vulkan1.3";
+
+/// Keywords per shader are capped so a typo can't trigger 2^N compiles
+const MAX_KEYWORDS: usize = 6;
+
+/// `#pragma spec_constant` names expand to this | ID, a value no shader
+/// uses by accident
+const SPEC_MARKER: u32 = 0x5EC0_0000;
+
+// SPIR-V opcodes and decorations the specialization pass touches
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FIRST: u32 = 19;
+const OP_TYPE_LAST: u32 = 39;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_DECORATE: u32 = 71;
+const DECORATION_SPEC_ID: u32 = 1;
+
+/// A uint specialization constant declared with `#pragma spec_constant`
+struct SpecConstant {
+    name: String,
+    id: u32,
+    default: u32,
+}
+
+/// One runtime-loadable shader and its keyword permutations
+struct ShaderEntry {
+    /// Name used by load_shader!/permutation lookups ("cube.frag", "sky.wgsl.vs_main")
+    name: String,
+    keywords: Vec<String>,
+}
 
 fn main() {
-    println!("cargo:rerun-if-changed=shaders/");
+    // A directory makes cargo rescan it, so new and deleted shaders are noticed too
+    println!("cargo:rerun-if-changed={}", SHADER_DIR);
+    println!("cargo:rerun-if-env-changed=SHADER_DEFINES");
+    println!("cargo:rerun-if-env-changed=DXC");
+    
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set")).join("shaders");
+    fs::create_dir_all(&out_dir).expect("Failed to create shader output directory");
+    
+    let defines = parse_defines(&env::var("SHADER_DEFINES").unwrap_or_default());
+    let naga_version = naga_version();
+    
+    let mut shaders = Vec::new();
+    discover_shaders(Path::new(SHADER_DIR), &mut shaders);
+    shaders.sort();
+    
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    for path in &shaders {
+        match compile_shader(path, &out_dir, &defines, &naga_version) {
+            Ok(compiled) => entries.extend(compiled),
+            Err(e) => errors.push(e),
+        }
+    }
+    
+    // Outputs of deleted shaders must not linger where include_bytes! can find them
+    remove_stale_outputs(&out_dir, &out_dir, &shaders);
+    
+    if !errors.is_empty() {
+        panic!(
+            "{} shader(s) failed to compile:\n\n{}",
+            errors.len(),
+            errors.join("\n\n")
+        );
+    }
+    
+    write_permutation_table(&out_dir, &entries);
+}
+
+/// Recursively collect every compilable shader under `dir`
+fn discover_shaders(dir: &Path, shaders: &mut Vec<PathBuf>) {
+    let entries = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", dir.display(), e));
+    
+    for entry in entries {
+        let path = entry.expect("Failed to read directory entry").path();
+        if path.is_dir() {
+            discover_shaders(&path, shaders);
+        } else if ShaderLanguage::detect(&path).is_some() {
+            shaders.push(path);
+        }
+    }
+}
+
+/// Delete compiled outputs whose source shader no longer exists
+fn remove_stale_outputs(dir: &Path, out_root: &Path, shaders: &[PathBuf]) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            remove_stale_outputs(&path, out_root, shaders);
+            continue;
+        }
+        
+        let relative = path.strip_prefix(out_root).unwrap_or(&path).display().to_string();
+        let Some(source) = relative
+            .strip_suffix(".hash")
+            .or_else(|| relative.strip_suffix(".spv"))
+        else {
+            continue;
+        };
+        
+        // Keyword variants are named <file>+A+B.spv
+        let source = source.split('+').next().unwrap_or(source);
+        
+        // WGSL outputs are named <file>.wgsl.<entry>.spv
+        let source = Path::new(SHADER_DIR).join(source);
+        let is_wgsl_entry = source
+            .file_stem()
+            .map(|stem| source.with_file_name(stem))
+            .is_some_and(|file| file.extension().is_some_and(|e| e == "wgsl") && shaders.contains(&file));
+        
+        if !shaders.contains(&source) && !is_wgsl_entry {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+/// Source language of a compilable shader
+#[derive(Clone, Copy, Debug)]
+enum ShaderLanguage {
+    Glsl(naga::ShaderStage),
+    /// Stages come from the entry points declared in the file
+    Wgsl,
+    Hlsl(naga::ShaderStage),
+}
+
+impl ShaderLanguage {
+    /// Language from the file name (None for includes and other files)
+    fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        match path.extension()?.to_str()? {
+            "wgsl" => Some(Self::Wgsl),
+            // foo.vert.hlsl -> stage from the inner extension
+            "hlsl" => {
+                let stem = name.strip_suffix(".hlsl")?;
+                stage_from_extension(Path::new(stem).extension()?.to_str()?).map(Self::Hlsl)
+            }
+            ext => stage_from_extension(ext).map(Self::Glsl),
+        }
+    }
     
-    // Compile shaders using glslc (part of Vulkan SDK)
-    compile_shader("shaders/cube.vert", "shaders/cube.vert.spv");
-    compile_shader("shaders/cube.frag", "shaders/cube.frag.spv");
+    /// Everything besides the source and defines that shapes the output
+    fn compile_flags(self) -> String {
+        match self {
+            Self::Glsl(_) => format!("adjust_coordinate_space={} spec_marker={:#x}", GLSL_ADJUST_COORDINATE_SPACE, SPEC_MARKER),
+            Self::Wgsl => format!("adjust_coordinate_space={}", WGSL_ADJUST_COORDINATE_SPACE),
+            Self::Hlsl(_) => format!("{} -E main", DXC_TARGET_ENV),
+        }
+    }
+}
+
+/// Versions of naga in Cargo.lock (other dependencies may pull in their
+/// own), so upgrading it recompiles every shader; cargo reruns the build
+/// script whenever a build dependency changes
+fn naga_version() -> String {
+    let lock = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap_or_default()).join("Cargo.lock");
+    let text = fs::read_to_string(lock).unwrap_or_default();
+    let mut versions = Vec::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        if line == "name = \"naga\"" {
+            if let Some(version) = lines.next().and_then(|line| line.strip_prefix("version = ")) {
+                versions.push(version.trim_matches('"'));
+            }
+        }
+    }
+    if versions.is_empty() {
+        "unknown".to_string()
+    } else {
+        versions.join(",")
+    }
 }
 
-fn compile_shader(input: &str, output: &str) {
-    let input_path = Path::new(input);
-    let output_path = Path::new(output);
+/// 64-bit FNV-1a: stable across Rust releases, unlike `DefaultHasher`
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
     
-    // Check if glslc is available
-    let result = Command::new("glslc")
-        .arg(input_path)
-        .arg("-o")
-        .arg(output_path)
-        .status();
+    /// Add `text` and a separator, so adjacent fields can't run together
+    fn write(&mut self, text: &str) {
+        for byte in text.bytes().chain([0]) {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Shader stage from a GLSL-style extension
+fn stage_from_extension(extension: &str) -> Option<naga::ShaderStage> {
+    match extension {
+        "vert" => Some(naga::ShaderStage::Vertex),
+        "frag" => Some(naga::ShaderStage::Fragment),
+        "comp" => Some(naga::ShaderStage::Compute),
+        _ => None,
+    }
+}
+
+/// Parse "A,B=2" into sorted (name, value) pairs
+fn parse_defines(spec: &str) -> Vec<(String, String)> {
+    let mut defines: Vec<(String, String)> = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| match d.split_once('=') {
+            Some((name, value)) => (name.trim().to_string(), value.trim().to_string()),
+            None => (d.to_string(), "1".to_string()),
+        })
+        .collect();
+    defines.sort();
+    defines
+}
+
+/// Keywords declared with `#pragma keywords A B` (in declaration order)
+fn parse_keywords(source: &str, path: &Path) -> Result<Vec<String>, String> {
+    let mut keywords: Vec<String> = Vec::new();
+    for line in source.lines() {
+        let Some(rest) = line.trim_start().strip_prefix("#pragma keywords") else {
+            continue;
+        };
+        for keyword in rest.split_whitespace() {
+            if !keyword.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!("{}: invalid keyword `{}`", path.display(), keyword));
+            }
+            if !keywords.iter().any(|k| k == keyword) {
+                keywords.push(keyword.to_string());
+            }
+        }
+    }
+    
+    if keywords.len() > MAX_KEYWORDS {
+        return Err(format!(
+            "{}: {} keywords declared, at most {} are allowed ({} variants)",
+            path.display(),
+            keywords.len(),
+            MAX_KEYWORDS,
+            1 << MAX_KEYWORDS
+        ));
+    }
+    Ok(keywords)
+}
+
+/// Constants declared with `#pragma spec_constant NAME ID DEFAULT`
+fn parse_spec_constants(source: &str, path: &Path) -> Result<Vec<SpecConstant>, String> {
+    let mut constants: Vec<SpecConstant> = Vec::new();
+    for line in source.lines() {
+        let Some(rest) = line.trim_start().strip_prefix("#pragma spec_constant") else {
+            continue;
+        };
+        let invalid = || format!("{}: expected `#pragma spec_constant NAME ID DEFAULT`, got `{}`", path.display(), line.trim());
+        let [name, id, default] = rest.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(invalid());
+        };
+        let (Ok(id), Ok(default)) = (id.parse::<u32>(), default.parse::<u32>()) else {
+            return Err(invalid());
+        };
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') || id > 0xffff {
+            return Err(invalid());
+        }
+        if constants.iter().any(|c| c.name == name || c.id == id) {
+            return Err(format!("{}: spec constant `{}` or id {} declared twice", path.display(), name, id));
+        }
+        constants.push(SpecConstant { name: name.to_string(), id, default });
+    }
+    Ok(constants)
+}
+
+/// Turn the marker literal of each of `constants` into an OpSpecConstant
+/// decorated with its SpecId; returns the names found (a variant may not
+/// read every constant)
+fn specialize(words: &mut Vec<u32>, constants: &[SpecConstant]) -> Vec<String> {
+    let mut uint_types = HashSet::new();
+    let mut found = Vec::new();
+    let mut decorations = Vec::new();
+    let mut first_type = None;
     
-    match result {
-        Ok(status) if status.success() => {
-            println!("Compiled {} -> {}", input, output);
+    let mut cursor = 5;
+    while cursor < words.len() {
+        let word_count = (words[cursor] >> 16) as usize;
+        let opcode = words[cursor] & 0xffff;
+        if word_count == 0 || cursor + word_count > words.len() {
+            break;
+        }
+        let operands = word_count - 1;
+        
+        if (OP_TYPE_FIRST..=OP_TYPE_LAST).contains(&opcode) && first_type.is_none() {
+            first_type = Some(cursor);
         }
-        Ok(status) => {
-            panic!("Failed to compile {}: exit code {:?}", input, status.code());
+        if opcode == OP_TYPE_INT && operands == 3 && words[cursor + 2] == 32 && words[cursor + 3] == 0 {
+            uint_types.insert(words[cursor + 1]);
         }
-        Err(e) => {
-            eprintln!("Warning: glslc not found ({})", e);
-            eprintln!("Shaders will not be compiled. Install Vulkan SDK or compile manually:");
-            eprintln!("  glslc {} -o {}", input, output);
+        if opcode == OP_CONSTANT && operands == 3 && uint_types.contains(&words[cursor + 1]) {
+            let value = words[cursor + 3];
+            if let Some(constant) = constants.iter().find(|c| value == SPEC_MARKER | c.id) {
+                words[cursor] = (word_count as u32) << 16 | OP_SPEC_CONSTANT;
+                words[cursor + 3] = constant.default;
+                decorations.extend([4 << 16 | OP_DECORATE, words[cursor + 2], DECORATION_SPEC_ID, constant.id]);
+                found.push(constant.name.clone());
+            }
+        }
+        cursor += word_count;
+    }
+    
+    // Annotations come before the first type declaration
+    if let Some(at) = first_type {
+        words.splice(at..at, decorations);
+    }
+    found
+}
+
+/// Output name of one keyword combination: "cube.frag" + mask 0b10 -> "cube.frag+SHADOWS"
+fn variant_name(name: &str, keywords: &[String], mask: usize) -> String {
+    let mut variant = name.to_string();
+    for (bit, keyword) in keywords.iter().enumerate() {
+        if mask & (1 << bit) != 0 {
+            variant.push('+');
+            variant.push_str(keyword);
         }
     }
+    variant
+}
+
+/// Compile one shader into `out_dir`, skipping it if nothing changed
+///
+/// Returns the runtime-loadable entries it produced (one per WGSL entry point).
+fn compile_shader(
+    path: &Path,
+    out_dir: &Path,
+    defines: &[(String, String)],
+    naga_version: &str,
+) -> Result<Vec<ShaderEntry>, String> {
+    let language = ShaderLanguage::detect(path).expect("discovered shaders have a language");
+    
+    // Resolve includes first so the cache key covers every file involved
+    let mut included = HashSet::new();
+    let source = preprocess(path, &mut included)?;
+    for dependency in &included {
+        println!("cargo:rerun-if-changed={}", dependency.display());
+    }
+    
+    let relative = path.strip_prefix(SHADER_DIR).unwrap_or(path);
+    let name = relative.display().to_string().replace('\\', "/");
+    let stamp = out_dir.join(format!("{}.hash", name));
+    
+    let mut hasher = Fnv::new();
+    hasher.write(&BUILD_SCRIPT_VERSION.to_string());
+    hasher.write(naga_version);
+    hasher.write(&format!("{:?} {}", language, language.compile_flags()));
+    for (define, value) in defines {
+        hasher.write(define);
+        hasher.write(value);
+    }
+    hasher.write(&source);
+    let hash = format!("{:016x}", hasher.0);
+    let up_to_date = fs::read_to_string(&stamp).ok().as_deref() == Some(hash.as_str());
+    
+    // (output name relative to out_dir, SPIR-V words)
+    let mut outputs = Vec::new();
+    let entries = match language {
+        ShaderLanguage::Wgsl => {
+            let compiled = compile_wgsl(&source, path, !up_to_date)?;
+            let mut entries = Vec::new();
+            for (entry, words) in compiled {
+                let entry_name = format!("{}.{}", name, entry);
+                if let Some(words) = words {
+                    outputs.push((format!("{}.spv", entry_name), words));
+                }
+                entries.push(ShaderEntry { name: entry_name, keywords: Vec::new() });
+            }
+            entries
+        }
+        ShaderLanguage::Glsl(stage) | ShaderLanguage::Hlsl(stage) => {
+            let keywords = parse_keywords(&source, path)?;
+            let spec_constants = match language {
+                ShaderLanguage::Glsl(_) => parse_spec_constants(&source, path)?,
+                _ => Vec::new(),
+            };
+            if !up_to_date {
+                let mut specialized = HashSet::new();
+                for mask in 0..1usize << keywords.len() {
+                    let mut variant_defines = defines.to_vec();
+                    variant_defines.extend(
+                        spec_constants
+                            .iter()
+                            .map(|c| (c.name.clone(), format!("{:#x}u", SPEC_MARKER | c.id))),
+                    );
+                    variant_defines.extend(
+                        keywords
+                            .iter()
+                            .enumerate()
+                            .filter(|(bit, _)| mask & (1 << bit) != 0)
+                            .map(|(_, keyword)| (keyword.clone(), "1".to_string())),
+                    );
+                    
+                    let mut words = match language {
+                        ShaderLanguage::Hlsl(_) => compile_hlsl(&source, stage, &variant_defines, path),
+                        _ => compile_glsl(&source, stage, &variant_defines, path),
+                    }
+                    .map_err(|e| match mask {
+                        0 => e,
+                        _ => format!("[variant {}]\n{}", variant_name(&name, &keywords, mask), e),
+                    })?;
+                    specialized.extend(specialize(&mut words, &spec_constants));
+                    outputs.push((format!("{}.spv", variant_name(&name, &keywords, mask)), words));
+                }
+                if let Some(missing) = spec_constants.iter().find(|c| !specialized.contains(&c.name)) {
+                    return Err(format!(
+                        "{}: spec constant `{}` never reaches the SPIR-V; read it through a local \
+                         variable so it isn't folded into a constant expression",
+                        path.display(),
+                        missing.name
+                    ));
+                }
+            }
+            vec![ShaderEntry { name: name.clone(), keywords }]
+        }
+    };
+    
+    if up_to_date {
+        return Ok(entries);
+    }
+    
+    // Variants of a previous keyword list must not outlive it
+    remove_variants(&out_dir.join(&name));
+    
+    for (output_name, words) in &outputs {
+        validate_spirv(words).map_err(|e| format!("{}: invalid SPIR-V output: {}", path.display(), e))?;
+        
+        let output = out_dir.join(output_name);
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("{}: {}", output.display(), e))?;
+        }
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        fs::write(&output, bytes).map_err(|e| format!("{}: {}", output.display(), e))?;
+        println!("Compiled {} -> {}", path.display(), output.display());
+    }
+    
+    if let Some(parent) = stamp.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", stamp.display(), e))?;
+    }
+    fs::write(&stamp, hash).map_err(|e| format!("{}: {}", stamp.display(), e))?;
+    Ok(entries)
+}
+
+/// Delete `<base>+*.spv` keyword variants
+fn remove_variants(base: &Path) {
+    let (Some(dir), Some(file_name)) = (base.parent(), base.file_name()) else {
+        return;
+    };
+    let prefix = format!("{}+", file_name.to_string_lossy());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Generate the table backend::permutation includes: every shader with its
+/// keywords and one embedded SPIR-V blob per keyword mask
+fn write_permutation_table(out_dir: &Path, entries: &[ShaderEntry]) {
+    let mut table = String::from("// Generated by build.rs - do not edit\n\n");
+    table.push_str("pub static SHADER_PERMUTATIONS: &[ShaderPermutations] = &[\n");
+    for entry in entries {
+        table.push_str(&format!("    ShaderPermutations {{\n        name: {:?},\n        keywords: &[", entry.name));
+        for keyword in &entry.keywords {
+            table.push_str(&format!("{:?}, ", keyword));
+        }
+        table.push_str("],\n        variants: &[\n");
+        for mask in 0..1usize << entry.keywords.len() {
+            table.push_str(&format!(
+                "            include_bytes!(concat!(env!(\"OUT_DIR\"), \"/shaders/{}.spv\")),\n",
+                variant_name(&entry.name, &entry.keywords, mask)
+            ));
+        }
+        table.push_str("        ],\n    },\n");
+    }
+    table.push_str("];\n");
+    
+    let path = out_dir.parent().expect("shader dir is inside OUT_DIR").join("shader_permutations.rs");
+    // Only touch the file when it changes, so the crate isn't rebuilt needlessly
+    if fs::read_to_string(&path).ok().as_deref() != Some(table.as_str()) {
+        fs::write(&path, table).expect("Failed to write shader_permutations.rs");
+    }
+}
+
+/// Inline `#include "file"` directives (each file is included at most once)
+fn preprocess(path: &Path, included: &mut HashSet<PathBuf>) -> Result<String, String> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if !included.insert(canonical) {
+        return Ok(String::new());
+    }
+    
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut output = String::with_capacity(text.len());
+    
+    for (line_number, line) in text.lines().enumerate() {
+        let Some(rest) = line.trim_start().strip_prefix("#include") else {
+            output.push_str(line);
+            output.push('\n');
+            continue;
+        };
+        
+        let name = rest.trim().trim_matches(|c| c == '"' || c == '<' || c == '>');
+        let local = path.parent().unwrap_or(Path::new(".")).join(name);
+        let include_path = if local.exists() {
+            local
+        } else {
+            Path::new(SHADER_DIR).join(name)
+        };
+        
+        if !include_path.exists() {
+            return Err(format!(
+                "{}:{}: cannot find include \"{}\"",
+                path.display(),
+                line_number + 1,
+                name
+            ));
+        }
+        
+        output.push_str(&preprocess(&include_path, included)?);
+    }
+    
+    Ok(output)
+}
+
+/// GLSL (Vulkan flavour) -> validated naga module -> SPIR-V words
+fn compile_glsl(
+    source: &str,
+    stage: naga::ShaderStage,
+    defines: &[(String, String)],
+    path: &Path,
+) -> Result<Vec<u32>, String> {
+    let mut options = naga::front::glsl::Options::from(stage);
+    options.defines.extend(defines.iter().cloned());
+    
+    let module = naga::front::glsl::Frontend::default()
+        .parse(&options, source)
+        .map_err(|e| format!("{}:\n{}", path.display(), e.emit_to_string(source)))?;
+    let info = validate_module(&module, source, path)?;
+    
+    write_entry_point(&module, &info, stage, "main", GLSL_ADJUST_COORDINATE_SPACE)
+        .map_err(|e| format!("{}: SPIR-V generation failed: {}", path.display(), e))
+}
+
+/// Entry point name and, when generated, its SPIR-V
+type WgslEntryPoint = (String, Option<Vec<u32>>);
+
+/// WGSL -> one SPIR-V module per entry point
+///
+/// WGSL has no preprocessor, so SHADER_DEFINES and keywords do not apply here.
+/// With `generate` false only the entry point names are returned.
+fn compile_wgsl(
+    source: &str,
+    path: &Path,
+    generate: bool,
+) -> Result<Vec<WgslEntryPoint>, String> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|e| e.emit_to_string_with_path(source, path.display().to_string()))?;
+    
+    if module.entry_points.is_empty() {
+        return Err(format!("{}: WGSL file has no entry points", path.display()));
+    }
+    if !generate {
+        return Ok(module.entry_points.iter().map(|e| (e.name.clone(), None)).collect());
+    }
+    
+    let info = validate_module(&module, source, path)?;
+    
+    // Flipped so shaders shared with wgpu tools behave the same here
+    module
+        .entry_points
+        .iter()
+        .map(|entry| {
+            write_entry_point(&module, &info, entry.stage, &entry.name, WGSL_ADJUST_COORDINATE_SPACE)
+                .map(|words| (entry.name.clone(), Some(words)))
+                .map_err(|e| format!("{}: SPIR-V generation failed for `{}`: {}", path.display(), entry.name, e))
+        })
+        .collect()
+}
+
+/// HLSL -> SPIR-V through DXC (naga has no HLSL frontend)
+fn compile_hlsl(
+    source: &str,
+    stage: naga::ShaderStage,
+    defines: &[(String, String)],
+    path: &Path,
+) -> Result<Vec<u32>, String> {
+    if env::var_os("CARGO_FEATURE_HLSL").is_none() {
+        return Err(format!(
+            "{}: HLSL shaders are opt-in; build with `--features hlsl` (needs `dxc` on PATH or $DXC)",
+            path.display()
+        ));
+    }
+    
+    let dxc = env::var("DXC").unwrap_or_else(|_| "dxc".to_string());
+    let profile = match stage {
+        naga::ShaderStage::Vertex => "vs_6_0",
+        naga::ShaderStage::Fragment => "ps_6_0",
+        naga::ShaderStage::Compute => "cs_6_0",
+    };
+    
+    // Includes are already inlined, so DXC compiles the preprocessed text
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set"));
+    let input = out_dir.join("hlsl_input.hlsl");
+    let output = out_dir.join("hlsl_output.spv");
+    fs::write(&input, source).map_err(|e| format!("{}: {}", input.display(), e))?;
+    
+    let mut command = Command::new(&dxc);
+    command
+        .arg("-spirv")
+        .arg(DXC_TARGET_ENV)
+        .args(["-T", profile, "-E", "main"])
+        .arg(&input)
+        .arg("-Fo")
+        .arg(&output);
+    for (name, value) in defines {
+        command.arg("-D").arg(format!("{}={}", name, value));
+    }
+    
+    let result = command.output().map_err(|e| {
+        format!(
+            "{}: HLSL needs DXC but `{}` could not be run ({}). Install it or set $DXC.",
+            path.display(),
+            dxc,
+            e
+        )
+    })?;
+    if !result.status.success() {
+        return Err(format!(
+            "{}:\n{}",
+            path.display(),
+            String::from_utf8_lossy(&result.stderr)
+        ));
+    }
+    
+    let bytes = fs::read(&output).map_err(|e| format!("{}: {}", output.display(), e))?;
+    if bytes.len() % 4 != 0 {
+        return Err(format!("{}: DXC output is not whole SPIR-V words", path.display()));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect())
+}
+
+/// Run naga's validator over a parsed module
+fn validate_module(
+    module: &naga::Module,
+    source: &str,
+    path: &Path,
+) -> Result<naga::valid::ModuleInfo, String> {
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(module)
+    .map_err(|e| e.emit_to_string_with_path(source, &path.display().to_string()))
+}
+
+/// Write one entry point of a validated module as SPIR-V for Vulkan
+fn write_entry_point(
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    stage: naga::ShaderStage,
+    entry_point: &str,
+    adjust_coordinate_space: bool,
+) -> Result<Vec<u32>, naga::back::spv::Error> {
+    let mut options = naga::back::spv::Options::default();
+    options.flags.set(
+        naga::back::spv::WriterFlags::ADJUST_COORDINATE_SPACE,
+        adjust_coordinate_space,
+    );
+    
+    let pipeline_options = naga::back::spv::PipelineOptions {
+        shader_stage: stage,
+        entry_point: entry_point.to_string(),
+    };
+    
+    naga::back::spv::write_vec(module, info, &options, Some(&pipeline_options))
+}
+
+/// spirv-val style check of a finished binary: header sanity, then a full
+/// re-parse and validation of the module it describes
+fn validate_spirv(words: &[u32]) -> Result<(), String> {
+    const SPIRV_MAGIC: u32 = 0x0723_0203;
+    if words.len() < 5 || words[0] != SPIRV_MAGIC {
+        return Err("bad magic number".to_string());
+    }
+    if words[3] == 0 {
+        return Err("id bound is zero".to_string());
+    }
+    
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    let options = naga::front::spv::Options {
+        adjust_coordinate_space: false,
+        ..Default::default()
+    };
+    let module = naga::front::spv::parse_u8_slice(&bytes, &options).map_err(|e| e.to_string())?;
+    
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map(|_| ())
+    .map_err(|e| format!("{:?}", e.into_inner()))
 }
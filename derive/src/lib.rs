@@ -0,0 +1,246 @@
+// Derive macros for GPU data layouts
+//
+// #[derive(VertexLayout)]   -> vertex binding + attribute descriptions
+// #[derive(PushConstants)]  -> std430 byte layout with compile-time offset checks
+//
+// The generated code refers to `crate::backend::layout`, so these derives
+// are meant to be used inside the renderer crate itself.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, FieldsNamed};
+
+/// Generate `VertexLayout` for a `#[repr(C)]` struct with named fields.
+///
+/// Each field becomes one attribute at consecutive shader locations, with the
+/// format taken from the field type's `VertexAttribute` impl. Matrix fields
+/// (`glam::Mat4`) take one location per column.
+///
+/// Field options:
+/// - `#[vertex(normalized)]` - read u8/u16/i8/i16 data as normalized floats
+///   (UNORM/SNORM); a type without a normalized format fails to compile
+/// - `#[vertex(skip)]` - padding field, no attribute and no location
+#[proc_macro_derive(VertexLayout, attributes(vertex))]
+pub fn derive_vertex_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_vertex_layout(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Generate a std430 `PushConstants` layout for a `#[repr(C)]` struct.
+///
+/// Field offsets follow std430 rules. The Rust `#[repr(C)]` offsets are
+/// checked against them at compile time, so a struct that would need hidden
+/// padding (e.g. a `Vec3` followed by another `Vec3`) fails to compile
+/// instead of silently uploading shifted data.
+#[proc_macro_derive(PushConstants)]
+pub fn derive_push_constants(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_push_constants(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn expand_vertex_layout(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = checked_fields(input, "VertexLayout")?;
+    let name = &input.ident;
+    
+    let mut attributes = Vec::new();
+    // Compile-time checks of the field options
+    let mut checks = Vec::new();
+    for field in &fields.named {
+        let options = VertexFieldOptions::parse(field)?;
+        if options.skip {
+            continue;
+        }
+        
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let format = if options.normalized {
+            let message = format!(
+                "field `{}` is marked #[vertex(normalized)] but its type has no normalized format",
+                ident
+            );
+            checks.push(quote! {
+                const _: () = assert!(
+                    <#ty as crate::backend::layout::VertexAttribute>::NORMALIZED_FORMAT.is_some(),
+                    #message
+                );
+            });
+            quote! {
+                match <#ty as crate::backend::layout::VertexAttribute>::NORMALIZED_FORMAT {
+                    Some(format) => format,
+                    None => unreachable!(),
+                }
+            }
+        } else {
+            quote! { <#ty as crate::backend::layout::VertexAttribute>::FORMAT }
+        };
+        
+        // Matrices take one location per column
+        attributes.push(quote! {
+            let locations = <#ty as crate::backend::layout::VertexAttribute>::LOCATIONS;
+            let column_size = (::std::mem::size_of::<#ty>() as u32) / locations;
+            for column in 0..locations {
+                attributes.push(::ash::vk::VertexInputAttributeDescription {
+                    binding,
+                    location: first_location + attributes.len() as u32,
+                    format: #format,
+                    offset: ::std::mem::offset_of!(#name, #ident) as u32 + column * column_size,
+                });
+            }
+        });
+    }
+    
+    Ok(quote! {
+        impl crate::backend::layout::VertexLayout for #name {
+            fn attributes(
+                binding: u32,
+                first_location: u32,
+            ) -> Vec<::ash::vk::VertexInputAttributeDescription> {
+                let mut attributes = Vec::new();
+                #( { #attributes } )*
+                attributes
+            }
+        }
+        
+        #( #checks )*
+    })
+}
+
+fn expand_push_constants(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = checked_fields(input, "PushConstants")?;
+    let name = &input.ident;
+    
+    let mut offset_consts = Vec::new();
+    let mut writes = Vec::new();
+    let mut prev_end = quote! { 0usize };
+    let mut aligns = Vec::new();
+    
+    for (index, field) in fields.named.iter().enumerate() {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let offset_name = format_ident!("OFFSET_{}", index);
+        let message = format!(
+            "`{}::{}` is not at its std430 offset; add explicit padding before it",
+            name, ident
+        );
+        
+        offset_consts.push(quote! {
+            const #offset_name: usize = crate::backend::layout::align_up(
+                #prev_end,
+                <#ty as crate::backend::layout::Std430>::ALIGN,
+            );
+            assert!(#offset_name == ::std::mem::offset_of!(#name, #ident), #message);
+        });
+        writes.push(quote! {
+            let offset = crate::backend::layout::align_up(
+                end,
+                <#ty as crate::backend::layout::Std430>::ALIGN,
+            );
+            end = offset + <#ty as crate::backend::layout::Std430>::SIZE;
+            crate::backend::layout::Std430::write_std430(&self.#ident, &mut out[offset..end]);
+        });
+        aligns.push(quote! { <#ty as crate::backend::layout::Std430>::ALIGN });
+        prev_end = quote! { #offset_name + <#ty as crate::backend::layout::Std430>::SIZE };
+    }
+    
+    let size_message = format!(
+        "`{}` is larger than the 128 bytes of push constants every Vulkan device guarantees",
+        name
+    );
+    
+    Ok(quote! {
+        impl crate::backend::layout::Std430 for #name {
+            const ALIGN: usize = {
+                let mut align = 4;
+                #( if #aligns > align { align = #aligns; } )*
+                align
+            };
+            const SIZE: usize = {
+                #( #offset_consts )*
+                crate::backend::layout::align_up(#prev_end, <Self as crate::backend::layout::Std430>::ALIGN)
+            };
+            
+            fn write_std430(&self, out: &mut [u8]) {
+                let mut end = 0usize;
+                #( #writes )*
+                let _ = end;
+            }
+        }
+        
+        impl crate::backend::layout::PushConstants for #name {}
+        
+        const _: () = assert!(
+            <#name as crate::backend::layout::Std430>::SIZE <= 128,
+            #size_message
+        );
+    })
+}
+
+/// Named fields of a non-generic `#[repr(C)]` struct, or a spanned error
+fn checked_fields<'a>(input: &'a DeriveInput, derive: &str) -> syn::Result<&'a FieldsNamed> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            format!("#[derive({})] does not support generic structs", derive),
+        ));
+    }
+    
+    let is_repr_c = input.attrs.iter().any(|attr| {
+        attr.path().is_ident("repr")
+            && attr
+                .parse_args_with(syn::punctuated::Punctuated::<syn::Ident, syn::Token![,]>::parse_terminated)
+                .map(|idents| idents.iter().any(|i| i == "C"))
+                .unwrap_or(false)
+    });
+    if !is_repr_c {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            format!("#[derive({})] requires #[repr(C)] so field offsets are stable", derive),
+        ));
+    }
+    
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(fields),
+            _ => Err(syn::Error::new_spanned(
+                &input.ident,
+                format!("#[derive({})] requires named fields", derive),
+            )),
+        },
+        _ => Err(syn::Error::new_spanned(
+            &input.ident,
+            format!("#[derive({})] only works on structs", derive),
+        )),
+    }
+}
+
+/// Parsed `#[vertex(...)]` options on a field
+#[derive(Default)]
+struct VertexFieldOptions {
+    normalized: bool,
+    skip: bool,
+}
+
+impl VertexFieldOptions {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("vertex")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("normalized") {
+                    options.normalized = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `normalized` or `skip`"))
+                }
+            })?;
+        }
+        Ok(options)
+    }
+}
@@ -0,0 +1,12 @@
+// Compile tests of the derives
+//
+// The fixtures in ui/ build against the renderer's own
+// src/backend/layout.rs; the failing ones check the compile-time layout
+// errors (their .stderr files hold the expected messages).
+
+#[test]
+fn layouts() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/pass.rs");
+    cases.compile_fail("tests/ui/fail_*.rs");
+}
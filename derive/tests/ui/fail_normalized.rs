@@ -0,0 +1,17 @@
+// Floats have no normalized vertex format
+#![allow(dead_code, unused_imports)]
+
+#[path = "../../../src/backend/layout.rs"]
+mod layout;
+mod backend {
+    pub(crate) use super::layout;
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, layout::VertexLayout)]
+struct Vertex {
+    #[vertex(normalized)]
+    position: glam::Vec3,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+// std430 puts the second vec3 at 16, #[repr(C)] at 12
+#![allow(dead_code, unused_imports)]
+
+#[path = "../../../src/backend/layout.rs"]
+mod layout;
+mod backend {
+    pub(crate) use super::layout;
+}
+
+#[repr(C)]
+#[derive(layout::PushConstants)]
+struct Light {
+    direction: glam::Vec3,
+    color: glam::Vec3,
+}
+
+fn main() {}
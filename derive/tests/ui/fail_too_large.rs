@@ -0,0 +1,18 @@
+// Over the 128 bytes of push constants every device guarantees
+#![allow(dead_code, unused_imports)]
+
+#[path = "../../../src/backend/layout.rs"]
+mod layout;
+mod backend {
+    pub(crate) use super::layout;
+}
+
+#[repr(C)]
+#[derive(layout::PushConstants)]
+struct Matrices {
+    view: glam::Mat4,
+    projection: glam::Mat4,
+    scale: f32,
+}
+
+fn main() {}
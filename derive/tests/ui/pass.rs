@@ -0,0 +1,75 @@
+// Layouts both derives accept, and what they generate
+#![allow(dead_code, unused_imports)]
+
+#[path = "../../../src/backend/layout.rs"]
+mod layout;
+mod backend {
+    pub(crate) use super::layout;
+}
+
+use ash::vk;
+use layout::{PushConstants, Std430, VertexLayout};
+
+#[repr(C)]
+#[derive(Clone, Copy, VertexLayout)]
+struct Vertex {
+    position: glam::Vec3,
+    #[vertex(normalized)]
+    color: [u8; 4],
+    joints: [u8; 4],
+    #[vertex(skip)]
+    padding: u32,
+    model: glam::Mat4,
+}
+
+// cube.vert's block: viewProj starts at 16 in both layouts
+#[repr(C)]
+#[derive(PushConstants)]
+struct Camera {
+    lod_fade: f32,
+    view_proj: glam::Mat4,
+}
+
+// A vec3 followed by a scalar packs into its last four bytes
+#[repr(C)]
+#[derive(PushConstants)]
+struct Light {
+    direction: glam::Vec3,
+    intensity: f32,
+    color: glam::Vec4,
+}
+
+// Exactly the guaranteed 128 bytes
+#[repr(C)]
+#[derive(PushConstants)]
+struct Matrices {
+    view: glam::Mat4,
+    projection: glam::Mat4,
+}
+
+fn main() {
+    let attributes: Vec<_> = Vertex::attributes(1, 3)
+        .iter()
+        .map(|a| (a.binding, a.location, a.format, a.offset))
+        .collect();
+    assert_eq!(attributes, [
+        (1, 3, vk::Format::R32G32B32_SFLOAT, 0),
+        (1, 4, vk::Format::R8G8B8A8_UNORM, 12),
+        (1, 5, vk::Format::R8G8B8A8_UINT, 16),
+        (1, 6, vk::Format::R32G32B32A32_SFLOAT, 32),
+        (1, 7, vk::Format::R32G32B32A32_SFLOAT, 48),
+        (1, 8, vk::Format::R32G32B32A32_SFLOAT, 64),
+        (1, 9, vk::Format::R32G32B32A32_SFLOAT, 80),
+    ]);
+    assert_eq!(Vertex::binding(1, vk::VertexInputRate::VERTEX).stride, 96);
+
+    assert_eq!((Camera::ALIGN, Camera::SIZE), (16, 80));
+    assert_eq!((Light::ALIGN, Light::SIZE), (16, 32));
+    assert_eq!(Matrices::SIZE, 128);
+
+    let light = Light { direction: glam::Vec3::new(1.0, 2.0, 3.0), intensity: 4.0, color: glam::Vec4::splat(5.0) };
+    let mut bytes = [0u8; Light::SIZE];
+    light.write_bytes(&mut bytes);
+    let floats: Vec<f32> = bytes.chunks_exact(4).map(|c| f32::from_ne_bytes([c[0], c[1], c[2], c[3]])).collect();
+    assert_eq!(floats, [1.0, 2.0, 3.0, 4.0, 5.0, 5.0, 5.0, 5.0]);
+}
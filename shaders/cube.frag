@@ -1,35 +1,88 @@
 #version 450
 
+// Variants (see backend::permutation):
+// - UNLIT: vertex color only, no lighting
+// - SHOW_NORMALS: visualize the world-space normal
+// - LOD_FADE: dithered cross-fade between mesh LOD levels
+#pragma keywords UNLIT SHOW_NORMALS LOD_FADE
+
+// Specialization constants (set per pipeline, see backend::permutation):
+// - LIGHTING_MODEL: 0 = Lambert, 1 = half-Lambert (wrapped, softer falloff)
+#pragma spec_constant LIGHTING_MODEL 0 0
+
 // Input from vertex shader
 layout(location = 0) in vec3 fragNormal;
 layout(location = 1) in vec3 fragColor;
 layout(location = 2) in vec3 fragWorldPos;
+layout(location = 3) flat in float fragLodFade;
 
 // Output color
 layout(location = 0) out vec4 outColor;
 
+// Lights from [lighting] in config.toml, rewritten every frame
+layout(set = 0, binding = 0) uniform Lighting {
+    vec4 keyLight;   // xyz = direction towards the light, w = intensity
+    vec4 fillLight;  // same for the fill light
+    float ambient;
+} lighting;
+
+#ifdef LOD_FADE
+// 2x2 Bayer matrix entry (0..3)
+uint bayer2(uint x, uint y) {
+    return (((x ^ y) & 1u) << 1) | (y & 1u);
+}
+
+// Ordered dither threshold in (0, 1) from a 4x4 Bayer matrix
+float ditherThreshold(vec2 fragCoord) {
+    uvec2 p = uvec2(fragCoord);
+    uint v = bayer2(p.x, p.y) * 4u + bayer2(p.x >> 1, p.y >> 1);
+    return (float(v) + 0.5) / 16.0;
+}
+#endif
+
 void main() {
+#ifdef LOD_FADE
+    // Levels fading in keep the pixels below their coverage, levels fading
+    // out keep the rest, so the two draws never overlap
+    if (fragLodFade != 0.0) {
+        float threshold = ditherThreshold(gl_FragCoord.xy);
+        if (fragLodFade > 0.0 ? threshold >= fragLodFade : threshold < -fragLodFade) {
+            discard;
+        }
+    }
+#endif
+
     // Normalize the interpolated normal
     vec3 normal = normalize(fragNormal);
+
+#ifdef SHOW_NORMALS
+    outColor = vec4(normal * 0.5 + 0.5, 1.0);
+    return;
+#endif
+
+#ifdef UNLIT
+    outColor = vec4(pow(fragColor, vec3(1.0 / 2.2)), 1.0);
+    return;
+#endif
+
+    // Diffuse lighting from the key light and a secondary fill light from
+    // the opposite side (x = key, y = fill)
+    vec3 lightDir = normalize(lighting.keyLight.xyz);
+    vec3 fillLightDir = normalize(lighting.fillLight.xyz);
+    vec2 nDotL = vec2(dot(normal, lightDir), dot(normal, fillLightDir));
     
-    // Light direction (from top-right-front)
-    vec3 lightDir = normalize(vec3(1.0, 1.0, 1.0));
-    
-    // Ambient light
-    float ambient = 0.15;
-    
-    // Diffuse lighting (Lambertian)
-    float diffuse = max(dot(normal, lightDir), 0.0);
-    
-    // Add a secondary fill light from the opposite side
-    vec3 fillLightDir = normalize(vec3(-0.5, 0.3, -0.5));
-    float fillDiffuse = max(dot(normal, fillLightDir), 0.0) * 0.3;
+    // A local keeps the spec constant from being folded away
+    uint lightingModel = LIGHTING_MODEL;
+    vec2 wrapped = nDotL * 0.5 + 0.5;
+    vec2 lambert = lightingModel == 1u ? wrapped * wrapped : max(nDotL, vec2(0.0));
+    float diffuse = lambert.x * lighting.keyLight.w;
+    float fillDiffuse = lambert.y * lighting.fillLight.w;
     
     // Combine lighting
-    float lighting = ambient + diffuse * 0.7 + fillDiffuse;
+    float light = lighting.ambient + diffuse + fillDiffuse;
     
     // Apply lighting to color
-    vec3 finalColor = fragColor * lighting;
+    vec3 finalColor = fragColor * light;
     
     // Slight gamma correction for better appearance
     finalColor = pow(finalColor, vec3(1.0 / 2.2));
@@ -1,26 +1,82 @@
 #version 450
 
-// Input attributes
+// Per-vertex attributes (binding 0)
 layout(location = 0) in vec3 inPosition;
 layout(location = 1) in vec3 inNormal;
 layout(location = 2) in vec3 inColor;
+layout(location = 3) in vec2 inUV;
+// Up to four joints and their weights (rigid meshes: joint 0, weight 1)
+layout(location = 4) in uvec4 inJoints;
+layout(location = 5) in vec4 inWeights;
+
+// Per-instance attributes (binding 1, VertexInputRate::INSTANCE)
+// The model matrix arrives as four column vectors
+layout(location = 6) in vec4 inModel0;
+layout(location = 7) in vec4 inModel1;
+layout(location = 8) in vec4 inModel2;
+layout(location = 9) in vec4 inModel3;
+layout(location = 10) in vec4 inTint;
 
 // Output to fragment shader
 layout(location = 0) out vec3 fragNormal;
 layout(location = 1) out vec3 fragColor;
 layout(location = 2) out vec3 fragWorldPos;
+layout(location = 3) flat out float fragLodFade;
+layout(location = 4) out vec2 fragUV;
 
-// Push constant for MVP matrix and model matrix for normals
+// Push constants: camera and LOD cross-fade, transforms come per instance
 layout(push_constant) uniform PushConstants {
-    mat4 mvp;
-    mat4 model;  // For transforming normals
+    float lodFade;  // dither coverage for this draw (see mesh::lod::LodDraw)
+    mat4 viewProj;
 } push;
 
+// This frame's joint matrices (bind pose to current pose, mesh space);
+// identity beyond the skeleton, so rigid meshes stay put
+layout(set = 1, binding = 0) readonly buffer Joints {
+    mat4 matrices[];
+} joints;
+
+// Morph target deltas of the mesh, target after target (anim::morph)
+struct MorphDelta {
+    vec4 position;
+    vec4 normal;
+    vec4 tangent;  // not used until tangents are a vertex attribute
+};
+layout(set = 1, binding = 1) readonly buffer MorphDeltas {
+    MorphDelta deltas[];
+} morphDeltas;
+
+// This frame's strongest morph weights (at most MAX_ACTIVE_MORPHS = 8)
+layout(set = 1, binding = 2) readonly buffer Morphs {
+    uint count;
+    uint vertexCount;  // deltas per target
+    uint targets[8];
+    float weights[8];
+} morphs;
+
 void main() {
-    gl_Position = push.mvp * vec4(inPosition, 1.0);
+    // Morph targets first, then skinning moves the morphed vertex
+    vec3 position = inPosition;
+    vec3 normal = inNormal;
+    for (uint i = 0u; i < min(morphs.count, 8u); i++) {
+        uint delta = morphs.targets[i] * morphs.vertexCount + uint(gl_VertexIndex);
+        position += morphs.weights[i] * morphDeltas.deltas[delta].position.xyz;
+        normal += morphs.weights[i] * morphDeltas.deltas[delta].normal.xyz;
+    }
+    
+    mat4 skin = inWeights.x * joints.matrices[inJoints.x]
+              + inWeights.y * joints.matrices[inJoints.y]
+              + inWeights.z * joints.matrices[inJoints.z]
+              + inWeights.w * joints.matrices[inJoints.w];
+    mat4 model = mat4(inModel0, inModel1, inModel2, inModel3) * skin;
+    vec4 worldPos = model * vec4(position, 1.0);
+    gl_Position = push.viewProj * worldPos;
     
-    // Transform normal to world space (using model matrix)
-    fragNormal = mat3(push.model) * inNormal;
-    fragColor = inColor;
-    fragWorldPos = (push.model * vec4(inPosition, 1.0)).xyz;
+    // Transform normal to world space (joints and instances rotate with
+    // uniform scale only, so mat3(model) is fine once renormalized)
+    fragNormal = mat3(model) * normal;
+    fragColor = inColor * inTint.rgb;
+    fragWorldPos = worldPos.xyz;
+    fragLodFade = push.lodFade;
+    fragUV = inUV;
 }
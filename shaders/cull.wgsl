@@ -0,0 +1,86 @@
+// GPU frustum culling: one invocation per object. Visible objects append a
+// VkDrawIndexedIndirectCommand and bump the draw count, which the graphics
+// pass consumes with vkCmdDrawIndexedIndirectCount. The draw uses the
+// coarsest LOD level whose projected error is under the pixel threshold
+// (see mesh::lod; the dithered cross-fade is CPU-path only).
+//
+// WGSL rather than GLSL because naga's GLSL frontend has no atomics yet.
+
+struct ObjectBounds {
+    sphere: vec4<f32>,      // xyz = center, w = radius (mesh space)
+    lod_first: u32,         // the mesh's levels in `lods`, finest first
+    lod_count: u32,
+    vertex_offset: i32,
+    padding: u32,
+}
+
+struct LodLevel {
+    first_index: u32,
+    index_count: u32,
+    error: f32,             // mesh units
+    padding: u32,
+}
+
+struct InstanceData {
+    model: mat4x4<f32>,
+    color: vec4<f32>,
+}
+
+// Matches VkDrawIndexedIndirectCommand (20 bytes)
+struct DrawCommand {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    vertex_offset: i32,
+    first_instance: u32,
+}
+
+struct PushConstants {
+    count: u32,
+    planes: array<vec4<f32>, 6>,   // world-space frustum planes, xyz = normal, w = distance
+    camera: vec4<f32>,             // xyz = position, w = LodSelector::lod_scale
+}
+
+@group(0) @binding(0) var<storage, read> bounds: array<ObjectBounds>;
+@group(0) @binding(1) var<storage, read> instances: array<InstanceData>;
+@group(0) @binding(2) var<storage, read_write> draws: array<DrawCommand>;
+@group(0) @binding(3) var<storage, read_write> draw_count: atomic<u32>;
+@group(0) @binding(4) var<storage, read> lods: array<LodLevel>;
+
+var<push_constant> push: PushConstants;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= push.count) {
+        return;
+    }
+    
+    let object = bounds[index];
+    let model = instances[index].model;
+    
+    // Bounding sphere in world space (largest axis scale for the radius)
+    let center = (model * vec4<f32>(object.sphere.xyz, 1.0)).xyz;
+    let scale = max(length(model[0].xyz), max(length(model[1].xyz), length(model[2].xyz)));
+    let radius = object.sphere.w * scale;
+    
+    for (var i = 0u; i < 6u; i++) {
+        if (dot(push.planes[i].xyz, center) + push.planes[i].w < -radius) {
+            return;
+        }
+    }
+    
+    // Coarsest level whose error stays under the threshold (errors grow per level)
+    let distance = max(length(center - push.camera.xyz) - radius, 1e-4);
+    var level = lods[object.lod_first];
+    for (var l = 1u; l < object.lod_count; l++) {
+        let candidate = lods[object.lod_first + l];
+        if (candidate.error * scale * push.camera.w > distance) {
+            break;
+        }
+        level = candidate;
+    }
+    
+    let slot = atomicAdd(&draw_count, 1u);
+    draws[slot] = DrawCommand(level.index_count, 1u, level.first_index, object.vertex_offset, index);
+}
@@ -0,0 +1,36 @@
+#version 450
+
+// Debug views (see debug::view), one keyword per view:
+// - WIREFRAME: flat edge color, drawn with PolygonMode::LINE over the lit pass
+// - DEPTH: linear view depth, near = white, fading out with distance
+// - UV_CHECKER: checkerboard over the texture coordinates, tinted by UV
+// - OVERDRAW: constant warm color, additively blended without depth test
+#pragma keywords WIREFRAME DEPTH UV_CHECKER OVERDRAW
+
+// Input from vertex shader (same locations as cube.frag)
+layout(location = 4) in vec2 fragUV;
+
+// Output color
+layout(location = 0) out vec4 outColor;
+
+void main() {
+#if defined(WIREFRAME)
+    outColor = vec4(0.02, 0.02, 0.02, 1.0);
+#elif defined(DEPTH)
+    // gl_FragCoord.w is 1 / clip w, and clip w is the view-space depth
+    float depth = 1.0 / gl_FragCoord.w;
+    float shade = exp2(-depth / 16.0);
+    outColor = vec4(vec3(shade), 1.0);
+#elif defined(UV_CHECKER)
+    vec2 cell = floor(fragUV * 8.0);
+    float checker = mod(cell.x + cell.y, 2.0);
+    vec3 tint = vec3(fragUV, 1.0 - fragUV.x * fragUV.y);
+    outColor = vec4(mix(0.25, 1.0, checker) * tint, 1.0);
+#elif defined(OVERDRAW)
+    // ~10 layers saturate red, green follows at ~25
+    outColor = vec4(0.1, 0.04, 0.01, 1.0);
+#else
+    // Not a view on its own: magenta makes a missing keyword obvious
+    outColor = vec4(1.0, 0.0, 1.0, 1.0);
+#endif
+}
@@ -0,0 +1,15 @@
+#version 450
+
+// egui textures are sRGB and premultiplied like the vertex colors
+
+layout(set = 0, binding = 0) uniform texture2D image;
+layout(set = 0, binding = 1) uniform sampler imageSampler;
+
+layout(location = 0) in vec2 fragUV;
+layout(location = 1) in vec4 fragColor;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = fragColor * texture(sampler2D(image, imageSampler), fragUV);
+}
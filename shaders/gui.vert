@@ -0,0 +1,29 @@
+#version 450
+
+// egui meshes (see ui::painter): positions in points, origin top-left
+
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec2 inUV;
+layout(location = 2) in vec4 inColor;  // sRGB, premultiplied alpha
+
+layout(location = 0) out vec2 fragUV;
+layout(location = 1) out vec4 fragColor;
+
+layout(push_constant) uniform PushConstants {
+    vec2 screenSize;  // in points
+} push;
+
+// The swapchain is sRGB, so blending happens on linear colors
+vec3 linearFromSrgb(vec3 srgb) {
+    bvec3 cutoff = lessThan(srgb, vec3(0.04045));
+    vec3 lower = srgb / 12.92;
+    vec3 higher = pow((srgb + 0.055) / 1.055, vec3(2.4));
+    return mix(higher, lower, cutoff);
+}
+
+void main() {
+    // Vulkan clip space already has Y pointing down
+    gl_Position = vec4(inPosition / push.screenSize * 2.0 - 1.0, 0.0, 1.0);
+    fragUV = inUV;
+    fragColor = vec4(linearFromSrgb(inColor.rgb), inColor.a);
+}
@@ -0,0 +1,16 @@
+#version 450
+
+// Glyph coverage (R8 atlas) scales the vertex color's alpha; solid shapes
+// sample a fully covered texel
+
+layout(set = 0, binding = 0) uniform texture2D atlas;
+layout(set = 0, binding = 1) uniform sampler atlasSampler;
+
+layout(location = 0) in vec2 fragUV;
+layout(location = 1) in vec4 fragColor;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = vec4(fragColor.rgb, fragColor.a * texture(sampler2D(atlas, atlasSampler), fragUV).r);
+}
@@ -0,0 +1,20 @@
+#version 450
+
+// Signed distance field glyphs: the atlas holds 0.5 on the outline, more
+// inside. The edge is smoothed over about one screen pixel whatever the
+// text's size or angle, using how fast the distance changes across pixels.
+
+layout(set = 0, binding = 0) uniform texture2D atlas;
+layout(set = 0, binding = 1) uniform sampler atlasSampler;
+
+layout(location = 0) in vec2 fragUV;
+layout(location = 1) in vec4 fragColor;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    float distance = texture(sampler2D(atlas, atlasSampler), fragUV).r;
+    float width = max(fwidth(distance) * 0.5, 0.001);
+    float coverage = smoothstep(0.5 - width, 0.5 + width, distance);
+    outColor = vec4(fragColor.rgb, fragColor.a * coverage);
+}
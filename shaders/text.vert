@@ -0,0 +1,21 @@
+#version 450
+
+// Text quads (see ui::text): world positions for world text, pixels for
+// screen text; the push constant maps either to clip space
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec2 inUV;
+layout(location = 2) in vec4 inColor;
+
+layout(location = 0) out vec2 fragUV;
+layout(location = 1) out vec4 fragColor;
+
+layout(push_constant) uniform PushConstants {
+    mat4 transform;
+} push;
+
+void main() {
+    gl_Position = push.transform * vec4(inPosition, 1.0);
+    fragUV = inUV;
+    fragColor = inColor;
+}
@@ -0,0 +1,261 @@
+// Animation clips
+//
+// A clip is a set of channels, each animating one property (translation,
+// rotation or scale) of one joint, or the weight of one morph target, with
+// keyframes laid out as glTF stores them: key times in seconds and, for
+// cubic splines, an in-tangent, value and out-tangent per key. glTF
+// animates all of a mesh's morph weights in one channel; an importer splits
+// it into one `Weight` channel per target. Sampling writes into a pose;
+// whatever no channel drives keeps what the pose had (normally its rest
+// transform and default weights).
+
+use anyhow::Result;
+use glam::{Quat, Vec4};
+use super::skeleton::Pose;
+
+/// How values between two keys are found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// The earlier key's value until the next key
+    Step,
+    /// Straight line (spherical for rotations)
+    Linear,
+    /// Hermite spline through the keys with per-key tangents
+    CubicSpline,
+}
+
+/// What a channel drives: part of a joint's transform, or a morph weight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Property {
+    Translation,
+    Rotation,
+    Scale,
+    /// Values in x
+    Weight,
+}
+
+/// Keyframes of one property of one joint or morph target
+#[derive(Debug, Clone)]
+pub struct Channel {
+    /// Joint index, or morph target index for `Property::Weight`
+    pub target: usize,
+    pub property: Property,
+    pub interpolation: Interpolation,
+    /// Increasing key times in seconds
+    times: Vec<f32>,
+    /// xyz (w unused), rotation quaternions as xyzw or weights in x; three
+    /// per key for cubic splines: in-tangent, value, out-tangent
+    values: Vec<Vec4>,
+}
+
+impl Channel {
+    pub fn new(
+        target: usize,
+        property: Property,
+        interpolation: Interpolation,
+        times: Vec<f32>,
+        values: Vec<Vec4>,
+    ) -> Result<Self> {
+        if times.is_empty() {
+            anyhow::bail!("Channel of target {} has no keys", target);
+        }
+        if times.windows(2).any(|pair| pair[1] <= pair[0]) {
+            anyhow::bail!("Key times of target {} don't increase", target);
+        }
+        let per_key = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+        if values.len() != times.len() * per_key {
+            anyhow::bail!(
+                "Channel of target {} has {} values for {} keys ({:?})",
+                target,
+                values.len(),
+                times.len(),
+                interpolation
+            );
+        }
+        Ok(Self { target, property, interpolation, times, values })
+    }
+    
+    /// Time of the last key
+    pub fn end(&self) -> f32 {
+        self.times[self.times.len() - 1]
+    }
+    
+    /// The value at `time`, held before the first and after the last key
+    /// (spline rotations come back unnormalized)
+    pub fn sample(&self, time: f32) -> Vec4 {
+        let times = &self.times;
+        let next = times.partition_point(|&t| t <= time);
+        if next == 0 {
+            return self.value(0);
+        }
+        if next == times.len() {
+            return self.value(times.len() - 1);
+        }
+        let previous = next - 1;
+        let duration = times[next] - times[previous];
+        let t = (time - times[previous]) / duration;
+        
+        let rotation = self.property == Property::Rotation;
+        match self.interpolation {
+            Interpolation::Step => self.value(previous),
+            Interpolation::Linear if rotation => {
+                let (a, b) = (Quat::from_vec4(self.value(previous)), Quat::from_vec4(self.value(next)));
+                Vec4::from(a.slerp(b, t))
+            }
+            Interpolation::Linear => self.value(previous).lerp(self.value(next), t),
+            Interpolation::CubicSpline => {
+                // Tangents are per second; scale them to the key interval
+                let (p0, m0) = (self.values[previous * 3 + 1], self.values[previous * 3 + 2] * duration);
+                let (p1, m1) = (self.values[next * 3 + 1], self.values[next * 3] * duration);
+                let (t2, t3) = (t * t, t * t * t);
+                p0 * (2.0 * t3 - 3.0 * t2 + 1.0)
+                    + m0 * (t3 - 2.0 * t2 + t)
+                    + p1 * (-2.0 * t3 + 3.0 * t2)
+                    + m1 * (t3 - t2)
+            }
+        }
+    }
+    
+    /// Key `k`'s value (the middle one of a cubic spline key)
+    fn value(&self, k: usize) -> Vec4 {
+        match self.interpolation {
+            Interpolation::CubicSpline => self.values[k * 3 + 1],
+            _ => self.values[k],
+        }
+    }
+}
+
+/// A named, looping set of channels
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    pub name: String,
+    /// Seconds until it loops: the last key of any channel
+    pub duration: f32,
+    pub channels: Vec<Channel>,
+}
+
+impl AnimationClip {
+    pub fn new(name: impl Into<String>, channels: Vec<Channel>) -> Self {
+        let duration = channels.iter().map(Channel::end).fold(0.0, f32::max);
+        Self { name: name.into(), duration, channels }
+    }
+    
+    /// Write the clip at `time` (wrapped into its duration) into `pose`
+    pub fn sample(&self, time: f32, pose: &mut Pose) {
+        let time = if self.duration > 0.0 { time.rem_euclid(self.duration) } else { 0.0 };
+        for channel in &self.channels {
+            let value = channel.sample(time);
+            match (channel.property, pose.locals.get_mut(channel.target)) {
+                (Property::Translation, Some(local)) => local.translation = value.truncate(),
+                (Property::Rotation, Some(local)) => {
+                    if let Some(rotation) = value.try_normalize() {
+                        local.rotation = Quat::from_vec4(rotation);
+                    }
+                }
+                (Property::Scale, Some(local)) => local.scale = value.truncate(),
+                (Property::Weight, _) => {
+                    if let Some(weight) = pose.weights.get_mut(channel.target) {
+                        *weight = value.x;
+                    }
+                }
+                (_, None) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+    
+    fn channel(property: Property, interpolation: Interpolation, times: &[f32], values: &[Vec4]) -> Channel {
+        Channel::new(0, property, interpolation, times.to_vec(), values.to_vec()).unwrap()
+    }
+    
+    fn assert_near(a: Vec4, b: Vec4) {
+        assert!(a.abs_diff_eq(b, 1e-5), "{a} != {b}");
+    }
+    
+    #[test]
+    fn step_holds_the_earlier_key() {
+        let values = [Vec4::X, Vec4::Y, Vec4::Z];
+        let step = channel(Property::Translation, Interpolation::Step, &[0.0, 1.0, 3.0], &values);
+        assert_near(step.sample(0.0), Vec4::X);
+        assert_near(step.sample(0.99), Vec4::X);
+        assert_near(step.sample(1.0), Vec4::Y);
+        assert_near(step.sample(2.5), Vec4::Y);
+        assert_near(step.sample(3.0), Vec4::Z);
+    }
+    
+    #[test]
+    fn linear_interpolates_within_the_key_interval() {
+        let values = [Vec4::ZERO, Vec4::new(2.0, 4.0, -2.0, 0.0), Vec4::ZERO];
+        let linear = channel(Property::Translation, Interpolation::Linear, &[1.0, 2.0, 6.0], &values);
+        assert_near(linear.sample(1.25), Vec4::new(0.5, 1.0, -0.5, 0.0));
+        assert_near(linear.sample(2.0), values[1]);
+        // A quarter of the way through the longer second interval
+        assert_near(linear.sample(3.0), Vec4::new(1.5, 3.0, -1.5, 0.0));
+    }
+    
+    #[test]
+    fn rotations_slerp() {
+        let half_turn = Quat::from_rotation_y(std::f32::consts::PI * 0.9);
+        let values = [Vec4::from(Quat::IDENTITY), Vec4::from(half_turn)];
+        let rotation = channel(Property::Rotation, Interpolation::Linear, &[0.0, 1.0], &values);
+        for t in [0.1, 0.25, 0.5, 0.8] {
+            let sampled = Quat::from_vec4(rotation.sample(t));
+            assert!((sampled.length() - 1.0).abs() < 1e-5, "slerp keeps unit length");
+            let expected = Quat::from_rotation_y(std::f32::consts::PI * 0.9 * t);
+            assert!(sampled.dot(expected).abs() > 1.0 - 1e-5, "constant angular speed at {t}");
+        }
+        // The shorter way round when the keys are in opposite hemispheres
+        let values = [Vec4::from(Quat::IDENTITY), -Vec4::from(Quat::from_rotation_y(0.5))];
+        let rotation = channel(Property::Rotation, Interpolation::Linear, &[0.0, 1.0], &values);
+        let sampled = Quat::from_vec4(rotation.sample(0.5));
+        assert!(sampled.dot(Quat::from_rotation_y(0.25)).abs() > 1.0 - 1e-5);
+    }
+    
+    #[test]
+    fn cubic_spline_tangents_scale_with_the_key_interval() {
+        // Values 0 and 1 with a slope of 1 per second everywhere: a straight
+        // line in time, whatever the interval between the keys
+        for duration in [0.5, 1.0, 4.0] {
+            let slope = Vec4::splat(1.0 / duration);
+            let values = [slope, Vec4::ZERO, slope, slope, Vec4::ONE, slope];
+            let spline = channel(Property::Scale, Interpolation::CubicSpline, &[1.0, 1.0 + duration], &values);
+            for t in [0.0, 0.25, 0.5, 0.75, 1.0] {
+                assert_near(spline.sample(1.0 + t * duration), Vec4::splat(t));
+            }
+        }
+        // Flat tangents ease in and out: below the line early, above it late
+        let values = [Vec4::ZERO, Vec4::ZERO, Vec4::ZERO, Vec4::ZERO, Vec4::ONE, Vec4::ZERO];
+        let spline = channel(Property::Scale, Interpolation::CubicSpline, &[0.0, 2.0], &values);
+        assert_near(spline.sample(0.5), Vec4::splat(0.15625));
+        assert_near(spline.sample(1.0), Vec4::splat(0.5));
+        assert_near(spline.sample(1.5), Vec4::splat(0.84375));
+        // The in-tangent of the first key and out-tangent of the last are unused
+        let values = [Vec4::splat(9.0), Vec4::ZERO, Vec4::ZERO, Vec4::ZERO, Vec4::ONE, Vec4::splat(9.0)];
+        let spline = channel(Property::Scale, Interpolation::CubicSpline, &[0.0, 2.0], &values);
+        assert_near(spline.sample(0.5), Vec4::splat(0.15625));
+    }
+    
+    #[test]
+    fn sampling_clamps_outside_the_keys() {
+        let values = [Vec3::ONE.extend(0.0), Vec3::splat(3.0).extend(0.0)];
+        for interpolation in [Interpolation::Step, Interpolation::Linear] {
+            let clamped = channel(Property::Translation, interpolation, &[1.0, 2.0], &values);
+            assert_near(clamped.sample(-5.0), values[0]);
+            assert_near(clamped.sample(0.999), values[0]);
+            assert_near(clamped.sample(2.0), values[1]);
+            assert_near(clamped.sample(100.0), values[1]);
+        }
+        let values = [Vec4::splat(5.0), Vec4::ONE, Vec4::splat(5.0), Vec4::splat(5.0), Vec4::splat(3.0), Vec4::splat(5.0)];
+        let spline = channel(Property::Scale, Interpolation::CubicSpline, &[1.0, 2.0], &values);
+        assert_near(spline.sample(0.0), Vec4::ONE);
+        assert_near(spline.sample(7.0), Vec4::splat(3.0));
+        let single = channel(Property::Translation, Interpolation::Linear, &[0.5], &values[..1]);
+        assert_near(single.sample(0.0), values[0]);
+        assert_near(single.sample(1.0), values[0]);
+    }
+}
@@ -0,0 +1,210 @@
+// Animated demo meshes
+//
+// `mesh = "tentacle"` in [scene]: a tapered tube on a chain of joints with
+// three clips, one per interpolation mode, played in turn with crossfades:
+// a sway travelling up the chain (linear), a slow curl with a swelling tip
+// (cubic spline) and a leaning turn in hopping quarter steps (step).
+//
+// `mesh = "blob"`: a sphere with thirteen morph targets, a bump towards
+// each icosahedron corner and a squash, alternating between a ripple
+// running round the bumps (linear) and breathing in and out (cubic spline).
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+use anyhow::Result;
+use glam::{Quat, Vec3, Vec4};
+use crate::mesh::primitives::{self, MeshData, MorphTarget};
+use crate::mesh::process::{self, NormalMode};
+use super::clip::{AnimationClip, Channel, Interpolation, Property};
+use super::player::Rig;
+use super::skeleton::{Skeleton, Transform};
+
+/// Scene mesh names of the demos
+pub const TENTACLE: &str = "tentacle";
+pub const BLOB: &str = "blob";
+
+const JOINTS: usize = 5;
+/// Length of each bone; the chain spans the tube from y = -0.5 to 0.5
+const BONE: f32 = 1.0 / JOINTS as f32;
+/// Seconds per clip, and the crossfade to the next one
+const CYCLE: f32 = 4.0;
+const FADE: f32 = 0.75;
+
+/// How far bumps push out, and where they fade out (cosine of the angle to
+/// their corner; neighbouring corners are at ~0.45)
+const BUMP_HEIGHT: f32 = 0.2;
+const BUMP_EDGE: f32 = 0.6;
+/// Morph target of the squash, after the bumps
+const SQUASH: usize = 12;
+
+/// The skinned tube and its rig; `subdivisions` are segments around (0 = 24)
+pub fn tentacle(subdivisions: u32) -> Result<(MeshData, Rig)> {
+    let segments = if subdivisions == 0 { 24 } else { subdivisions };
+    let mut mesh = primitives::tapered_tube(segments, JOINTS as u32 * 4);
+    
+    // Mid-bone vertices follow their bone only; towards a joint they blend
+    // with the neighbouring bone, half and half at the joint itself
+    for position in &mesh.positions {
+        let along = (position[1] + 0.5) / BONE - 0.5;
+        let bone = along.floor();
+        let (joints, weights) = if bone < 0.0 {
+            ([0, 0, 0, 0], [1.0, 0.0, 0.0, 0.0])
+        } else if bone as usize >= JOINTS - 1 {
+            ([JOINTS as u8 - 1, 0, 0, 0], [1.0, 0.0, 0.0, 0.0])
+        } else {
+            let t = along - bone;
+            ([bone as u8, bone as u8 + 1, 0, 0], [1.0 - t, t, 0.0, 0.0])
+        };
+        mesh.joints.push(joints);
+        mesh.weights.push(weights);
+    }
+    
+    let skeleton = Skeleton::from_rest_pose(
+        (0..JOINTS)
+            .map(|i| {
+                let (parent, offset) = match i {
+                    0 => (None, Vec3::new(0.0, -0.5, 0.0)),
+                    _ => (Some(i - 1), Vec3::new(0.0, BONE, 0.0)),
+                };
+                (format!("joint{}", i), parent, Transform::from_translation(offset))
+            })
+            .collect(),
+    )?;
+    let clips = vec![sway()?, curl()?, turn()?];
+    Ok((mesh, Rig::new(skeleton, clips, CYCLE, FADE)))
+}
+
+/// Every joint swings about Z, each a little later than its parent
+fn sway() -> Result<AnimationClip> {
+    const KEYS: usize = 9;
+    const PERIOD: f32 = 2.0;
+    let times: Vec<f32> = (0..KEYS).map(|k| k as f32 / (KEYS - 1) as f32 * PERIOD).collect();
+    let channels = (0..JOINTS)
+        .map(|joint| {
+            let values = times
+                .iter()
+                .map(|&t| {
+                    let phase = t / PERIOD * std::f32::consts::TAU - joint as f32 * 0.8;
+                    rotation_key(Quat::from_rotation_z(0.25 * phase.sin()))
+                })
+                .collect();
+            Channel::new(joint, Property::Rotation, Interpolation::Linear, times.clone(), values)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(AnimationClip::new("sway", channels))
+}
+
+/// The chain above the root bends forward and back while the tip swells,
+/// easing in and out (flat tangents at every key)
+fn curl() -> Result<AnimationClip> {
+    let times = vec![0.0, 1.5, 3.0];
+    let flat = |values: [Vec4; 3]| values.into_iter().flat_map(|value| [Vec4::ZERO, value, Vec4::ZERO]).collect();
+    let mut channels = (1..JOINTS)
+        .map(|joint| {
+            let values = [0.0, 0.45, 0.0].map(|angle| rotation_key(Quat::from_rotation_x(angle)));
+            Channel::new(joint, Property::Rotation, Interpolation::CubicSpline, times.clone(), flat(values))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let swell = [1.0, 1.6, 1.0].map(|scale| Vec3::splat(scale).extend(0.0));
+    channels.push(Channel::new(JOINTS - 1, Property::Scale, Interpolation::CubicSpline, times, flat(swell))?);
+    Ok(AnimationClip::new("curl", channels))
+}
+
+/// Leaning over from the first joint, the root turns a quarter at a time,
+/// hopping up for the first half of each
+fn turn() -> Result<AnimationClip> {
+    let lean = Channel::new(
+        1,
+        Property::Rotation,
+        Interpolation::Step,
+        vec![0.0],
+        vec![rotation_key(Quat::from_rotation_z(0.5))],
+    )?;
+    let quarters = Channel::new(
+        0,
+        Property::Rotation,
+        Interpolation::Step,
+        vec![0.0, 0.5, 1.0, 1.5, 2.0],
+        (0..5).map(|k| rotation_key(Quat::from_rotation_y(k as f32 * FRAC_PI_2))).collect(),
+    )?;
+    let hops = Channel::new(
+        0,
+        Property::Translation,
+        Interpolation::Step,
+        (0..9).map(|k| k as f32 * 0.25).collect(),
+        (0..9).map(|k| Vec3::new(0.0, if k % 2 == 0 { -0.44 } else { -0.5 }, 0.0).extend(0.0)).collect(),
+    )?;
+    Ok(AnimationClip::new("turn", vec![lean, quarters, hops]))
+}
+
+/// The morphing sphere and its rig (a single joint that stays put);
+/// `subdivisions` is the icosphere level (0 = 4)
+pub fn blob(subdivisions: u32) -> Result<(MeshData, Rig)> {
+    let mut mesh = primitives::icosphere(if subdivisions == 0 { 4 } else { subdivisions });
+    
+    for (k, corner) in icosahedron_corners().into_iter().enumerate() {
+        let positions = mesh.positions
+            .iter()
+            .map(|&p| {
+                let direction = Vec3::from(p).normalize_or_zero();
+                let t = ((direction.dot(corner) - BUMP_EDGE) / (1.0 - BUMP_EDGE)).clamp(0.0, 1.0);
+                (direction * BUMP_HEIGHT * t * t * (3.0 - 2.0 * t)).to_array()
+            })
+            .collect();
+        mesh.morph_targets.push(MorphTarget { name: format!("bump{}", k), positions, ..Default::default() });
+    }
+    let positions = mesh.positions.iter().map(|&[x, y, z]| [x * 0.2, y * -0.4, z * 0.2]).collect();
+    mesh.morph_targets.push(MorphTarget { name: "squash".to_string(), positions, ..Default::default() });
+    process::generate_morph_normals(&mut mesh, NormalMode::Smooth);
+    
+    let skeleton = Skeleton::from_rest_pose(vec![("root".to_string(), None, Transform::IDENTITY)])?;
+    let weights = vec![0.0; mesh.morph_targets.len()];
+    let rig = Rig::new(skeleton, vec![ripple()?, breathe()?], CYCLE, FADE).with_morph_weights(weights);
+    Ok((mesh, rig))
+}
+
+/// Unit directions to the corners of the icosahedron the icosphere starts
+/// from
+fn icosahedron_corners() -> [Vec3; 12] {
+    let t = (1.0 + 5.0_f32.sqrt()) * 0.5;
+    [
+        [-1.0, t, 0.0], [1.0, t, 0.0], [-1.0, -t, 0.0], [1.0, -t, 0.0],
+        [0.0, -1.0, t], [0.0, 1.0, t], [0.0, -1.0, -t], [0.0, 1.0, -t],
+        [t, 0.0, -1.0], [t, 0.0, 1.0], [-t, 0.0, -1.0], [-t, 0.0, 1.0],
+    ]
+    .map(|corner| Vec3::from(corner).normalize())
+}
+
+/// Bumps rise and fall in a wave running round the Y axis; about half are
+/// up at any time
+fn ripple() -> Result<AnimationClip> {
+    const KEYS: usize = 17;
+    const PERIOD: f32 = 2.0;
+    let times: Vec<f32> = (0..KEYS).map(|k| k as f32 / (KEYS - 1) as f32 * PERIOD).collect();
+    let channels = icosahedron_corners()
+        .into_iter()
+        .enumerate()
+        .map(|(target, corner)| {
+            let phase = corner.z.atan2(corner.x);
+            let values = times
+                .iter()
+                .map(|&t| Vec4::new((t / PERIOD * TAU - phase).sin().max(0.0).powi(2), 0.0, 0.0, 0.0))
+                .collect();
+            Channel::new(target, Property::Weight, Interpolation::Linear, times.clone(), values)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(AnimationClip::new("ripple", channels))
+}
+
+/// Squashed, then stretched (a negative weight), easing through each key
+fn breathe() -> Result<AnimationClip> {
+    let values = [0.0, 1.0, -0.6, 0.0]
+        .into_iter()
+        .flat_map(|weight| [Vec4::ZERO, Vec4::new(weight, 0.0, 0.0, 0.0), Vec4::ZERO])
+        .collect();
+    let squash = Channel::new(SQUASH, Property::Weight, Interpolation::CubicSpline, vec![0.0, 1.0, 2.0, 3.0], values)?;
+    Ok(AnimationClip::new("breathe", vec![squash]))
+}
+
+fn rotation_key(rotation: Quat) -> Vec4 {
+    Vec4::from(rotation)
+}
@@ -0,0 +1,25 @@
+// Animation
+//
+// - skeleton: joint hierarchies, poses and joint (skinning) matrices
+// - clip: keyframed channels sampled with step, linear or cubic spline
+//   interpolation
+// - player: playback with crossfades between clips
+// - morph: morph target deltas and weights laid out for cube.vert
+// - transform: keyframed node transforms with easing, looping and
+//   ping-pong, animating the instances
+// - clock: the animation time, real-time, fixed-step or manual, with
+//   pause, single steps and a time scale
+// - demo: a rigged tube and a morphing sphere to show it all ("tentacle"
+//   and "blob" as the scene mesh)
+//
+// Skinning and morphing happen in cube.vert: each vertex adds the deltas of
+// the few strongest morph targets, then blends up to four joint matrices,
+// from storage buffers rewritten every frame.
+
+pub mod clip;
+pub mod clock;
+pub mod demo;
+pub mod morph;
+pub mod player;
+pub mod skeleton;
+pub mod transform;
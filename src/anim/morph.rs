@@ -0,0 +1,105 @@
+// Morph targets on the GPU
+//
+// The deltas of every target sit in one storage buffer, target after target
+// with one `MorphDelta` per vertex. Each frame only the strongest
+// `MAX_ACTIVE_MORPHS` weights are uploaded (`ActiveMorphs`) and cube.vert
+// adds just those targets, so the cost per vertex stays bounded however many
+// targets the mesh has.
+
+use glam::{Vec3, Vec4};
+use crate::mesh::primitives::MeshData;
+
+/// Most targets blended per draw (matches the arrays of cube.vert's `Morphs`)
+pub const MAX_ACTIVE_MORPHS: usize = 8;
+
+/// Weights this close to zero leave their target out
+const MIN_WEIGHT: f32 = 1e-3;
+
+/// One vertex of one target in cube.vert's `MorphDeltas` buffer (std430:
+/// three vec4s, w unused)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MorphDelta {
+    position: Vec4,
+    normal: Vec4,
+    /// Not read until cube.vert takes tangents
+    tangent: Vec4,
+}
+
+/// The deltas of `mesh`'s targets in buffer order (empty without targets)
+pub fn pack_deltas(mesh: &MeshData) -> Vec<MorphDelta> {
+    mesh.morph_targets
+        .iter()
+        .flat_map(|target| {
+            (0..mesh.positions.len()).map(move |i| MorphDelta {
+                position: Vec3::from(target.positions[i]).extend(0.0),
+                normal: Vec3::from(target.normals[i]).extend(0.0),
+                tangent: target.tangents.get(i).map_or(Vec4::ZERO, |&t| Vec3::from(t).extend(0.0)),
+            })
+        })
+        .collect()
+}
+
+/// `Morphs` block of cube.vert (std430, which this #[repr(C)] layout
+/// matches: scalars and scalar arrays only)
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct ActiveMorphs {
+    count: u32,
+    /// Deltas per target in the `MorphDeltas` buffer
+    vertex_count: u32,
+    targets: [u32; MAX_ACTIVE_MORPHS],
+    weights: [f32; MAX_ACTIVE_MORPHS],
+}
+
+impl ActiveMorphs {
+    /// Nothing blended
+    pub const NONE: Self = Self {
+        count: 0,
+        vertex_count: 0,
+        targets: [0; MAX_ACTIVE_MORPHS],
+        weights: [0.0; MAX_ACTIVE_MORPHS],
+    };
+    
+    /// The strongest of `weights` (one per target of a mesh with
+    /// `vertex_count` vertices); the rest are dropped
+    pub fn select(weights: &[f32], vertex_count: u32) -> Self {
+        let mut order: Vec<usize> = (0..weights.len()).filter(|&i| weights[i].abs() > MIN_WEIGHT).collect();
+        order.sort_by(|&a, &b| weights[b].abs().total_cmp(&weights[a].abs()));
+        order.truncate(MAX_ACTIVE_MORPHS);
+        
+        let mut active = Self { vertex_count, ..Self::NONE };
+        for (slot, &target) in order.iter().enumerate() {
+            active.targets[slot] = target as u32;
+            active.weights[slot] = weights[target];
+        }
+        active.count = order.len() as u32;
+        active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn select_keeps_the_strongest_weights() {
+        let weights = [0.1, -0.9, 0.0005, 0.3, -0.001, 0.002, 0.5, 0.05, -0.2, 0.7, 0.01, 0.04];
+        let active = ActiveMorphs::select(&weights, 42);
+        assert_eq!(active.count as usize, MAX_ACTIVE_MORPHS);
+        assert_eq!(active.vertex_count, 42);
+        // By magnitude, keeping the sign
+        assert_eq!(active.targets, [1, 9, 6, 3, 8, 0, 7, 11]);
+        assert_eq!(active.weights, [-0.9, 0.7, 0.5, 0.3, -0.2, 0.1, 0.05, 0.04]);
+    }
+    
+    #[test]
+    fn select_drops_negligible_weights() {
+        let active = ActiveMorphs::select(&[0.0, MIN_WEIGHT, -MIN_WEIGHT, 0.0011, -0.5], 3);
+        assert_eq!(active.count, 2);
+        assert_eq!(active.targets, [4, 3, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(active.weights, [-0.5, 0.0011, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(ActiveMorphs::select(&[], 3).count, 0);
+        assert_eq!(ActiveMorphs::select(&[0.0; 20], 3).count, 0);
+    }
+}
@@ -0,0 +1,186 @@
+// Clip playback
+//
+// An `Animator` plays one clip of a rig at a time and crossfades to the
+// next: while a fade runs both clips advance and their poses are blended,
+// the new clip's weight rising linearly. A `Rig` is the skeleton, its clips
+// and their playback, turned into joint matrices and morph weights once a
+// frame.
+
+use glam::{Mat4, Vec3};
+use crate::backend::culling::{Aabb, BoundingSphere, MeshBounds};
+use crate::mesh::primitives::MeshData;
+use super::clip::AnimationClip;
+use super::skeleton::{Pose, Skeleton};
+
+/// A clip and how far into it playback is
+#[derive(Debug, Clone, Copy)]
+struct Playing {
+    clip: usize,
+    time: f32,
+}
+
+/// Which clips play and the fade between them
+#[derive(Debug, Clone)]
+pub struct Animator {
+    current: Playing,
+    /// The clip being faded out, and the fade's (elapsed, total) seconds
+    fading_out: Option<(Playing, f32, f32)>,
+}
+
+impl Animator {
+    pub fn new(clip: usize) -> Self {
+        Self { current: Playing { clip, time: 0.0 }, fading_out: None }
+    }
+    
+    /// The clip playing (or fading in)
+    pub fn clip(&self) -> usize {
+        self.current.clip
+    }
+    
+    /// Switch to `clip` from its start, blending over `fade` seconds
+    /// (a fade already running is cut short)
+    pub fn play(&mut self, clip: usize, fade: f32) {
+        let previous = std::mem::replace(&mut self.current, Playing { clip, time: 0.0 });
+        self.fading_out = (fade > 0.0).then_some((previous, 0.0, fade));
+    }
+    
+    pub fn advance(&mut self, dt: f32) {
+        self.current.time += dt;
+        if let Some((previous, elapsed, fade)) = &mut self.fading_out {
+            previous.time += dt;
+            *elapsed += dt;
+            if *elapsed >= *fade {
+                self.fading_out = None;
+            }
+        }
+    }
+    
+    /// The blended pose, starting from `rest`; `scratch` holds the
+    /// faded-out clip's pose
+    pub fn pose(&self, rest: &Pose, clips: &[AnimationClip], pose: &mut Pose, scratch: &mut Pose) {
+        pose.clone_from(rest);
+        if let Some(clip) = clips.get(self.current.clip) {
+            clip.sample(self.current.time, pose);
+        }
+        if let Some((previous, elapsed, fade)) = self.fading_out {
+            scratch.clone_from(rest);
+            if let Some(clip) = clips.get(previous.clip) {
+                clip.sample(previous.time, scratch);
+            }
+            // Blend from the old pose towards the new one
+            scratch.blend(pose, elapsed / fade);
+            std::mem::swap(pose, scratch);
+        }
+    }
+}
+
+/// A skeleton (and morph targets) with clips, playing them in turn
+#[derive(Debug, Clone)]
+pub struct Rig {
+    pub skeleton: Skeleton,
+    pub clips: Vec<AnimationClip>,
+    pub animator: Animator,
+    /// Seconds each clip plays before crossfading to the next (0 = the
+    /// first clip loops forever)
+    pub cycle: f32,
+    /// Crossfade duration in seconds
+    pub fade: f32,
+    since_switch: f32,
+    /// Rest transforms and default morph weights
+    rest: Pose,
+    pose: Pose,
+    scratch: Pose,
+    matrices: Vec<Mat4>,
+}
+
+impl Rig {
+    pub fn new(skeleton: Skeleton, clips: Vec<AnimationClip>, cycle: f32, fade: f32) -> Self {
+        let rest = skeleton.rest_pose();
+        Self {
+            animator: Animator::new(0),
+            cycle,
+            fade,
+            since_switch: 0.0,
+            pose: rest.clone(),
+            scratch: rest.clone(),
+            rest,
+            matrices: Vec::new(),
+            skeleton,
+            clips,
+        }
+    }
+    
+    /// Weights of the mesh's morph targets where no clip drives them (glTF
+    /// `mesh.weights`), one per target
+    pub fn with_morph_weights(mut self, weights: Vec<f32>) -> Self {
+        self.rest.weights = weights;
+        self.pose.clone_from(&self.rest);
+        self
+    }
+    
+    /// Advance playback by `dt` seconds and return the joint matrices
+    pub fn update(&mut self, dt: f32) -> &[Mat4] {
+        self.since_switch += dt;
+        if self.cycle > 0.0 && self.since_switch >= self.cycle && self.clips.len() > 1 {
+            self.since_switch = 0.0;
+            let next = (self.animator.clip() + 1) % self.clips.len();
+            log::debug!("Animation: crossfading to {:?}", self.clips[next].name);
+            self.animator.play(next, self.fade);
+        }
+        self.animator.advance(dt);
+        self.animator.pose(&self.rest, &self.clips, &mut self.pose, &mut self.scratch);
+        self.skeleton.joint_matrices(&self.pose, &mut self.matrices);
+        &self.matrices
+    }
+    
+    /// Morph weights of the pose from the last `update`
+    pub fn morph_weights(&self) -> &[f32] {
+        &self.pose.weights
+    }
+    
+    /// Bounds of `mesh` morphed and skinned by every clip, sampled 30 times
+    /// a second (vertices without joints follow joint 0)
+    ///
+    /// Crossfades blend poses the samples don't contain, so the box gets a
+    /// small margin.
+    pub fn bounds(&self, mesh: &MeshData) -> MeshBounds {
+        let (mut min, mut max) = (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN));
+        let mut matrices = Vec::new();
+        for clip in &self.clips {
+            let samples = (clip.duration * 30.0).ceil().max(1.0) as usize;
+            for sample in 0..=samples {
+                let mut pose = self.rest.clone();
+                clip.sample(sample as f32 / samples as f32 * clip.duration, &mut pose);
+                self.skeleton.joint_matrices(&pose, &mut matrices);
+                for (i, &position) in mesh.positions.iter().enumerate() {
+                    let morphed = mesh.morph_targets
+                        .iter()
+                        .zip(&pose.weights)
+                        .fold(Vec3::from(position), |p, (target, &weight)| {
+                            p + Vec3::from(target.positions[i]) * weight
+                        });
+                    let (joints, weights) = match (mesh.joints.get(i), mesh.weights.get(i)) {
+                        (Some(&joints), Some(&weights)) => (joints, weights),
+                        _ => ([0; 4], [1.0, 0.0, 0.0, 0.0]),
+                    };
+                    let skinned: Vec3 = joints
+                        .iter()
+                        .zip(weights)
+                        .map(|(&joint, weight)| matrices[joint as usize].transform_point3(morphed) * weight)
+                        .sum();
+                    min = min.min(skinned);
+                    max = max.max(skinned);
+                }
+            }
+        }
+        if min.x > max.x {
+            return MeshBounds::from_points(mesh.positions.iter().map(|&p| Vec3::from(p)));
+        }
+        let margin = (max - min).length() * 0.05;
+        let aabb = Aabb { min: min - margin, max: max + margin };
+        MeshBounds {
+            sphere: BoundingSphere { center: aabb.center(), radius: aabb.half_extents().length() },
+            aabb,
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// Skeletons and poses
+//
+// A skeleton is a list of joints, each with a parent, a rest transform
+// relative to that parent and an inverse bind matrix (mesh space to the
+// joint's space in the pose the mesh was modelled in). A pose holds one
+// local transform per joint, plus the mesh's morph target weights; walking
+// the hierarchy turns it into the joint matrices the vertex shader blends:
+// global transform * inverse bind.
+
+use anyhow::Result;
+use glam::{Mat4, Quat, Vec3};
+
+/// Most joints a skeleton may have: vertices address joints with a u8, and
+/// the renderer's joint buffer holds this many matrices
+pub const MAX_JOINTS: usize = 256;
+
+/// Translation, rotation and scale, applied scale first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+    
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self { translation, ..Self::IDENTITY }
+    }
+    
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+    
+    /// From `self` (t = 0) to `other` (t = 1); rotations take the shorter arc
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Transform {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Joint {
+    pub name: String,
+    /// Always an earlier joint
+    pub parent: Option<usize>,
+    /// Transform relative to the parent when no clip moves the joint
+    pub rest: Transform,
+    /// Mesh space to joint space in the bind pose
+    pub inverse_bind: Mat4,
+}
+
+/// Joints ordered parents first, so one pass computes every global transform
+#[derive(Debug, Clone)]
+pub struct Skeleton {
+    joints: Vec<Joint>,
+}
+
+impl Skeleton {
+    /// Fails when a joint comes before its parent (sort glTF skins on import)
+    /// or there are more than `MAX_JOINTS`
+    pub fn new(joints: Vec<Joint>) -> Result<Self> {
+        if joints.len() > MAX_JOINTS {
+            anyhow::bail!("Skeleton has {} joints, at most {} are supported", joints.len(), MAX_JOINTS);
+        }
+        for (i, joint) in joints.iter().enumerate() {
+            if joint.parent.is_some_and(|parent| parent >= i) {
+                anyhow::bail!("Joint {} ({:?}) comes before its parent", i, joint.name);
+            }
+        }
+        Ok(Self { joints })
+    }
+    
+    /// A skeleton bound in its rest pose: inverse bind matrices are the
+    /// inverses of the rest pose's global transforms
+    pub fn from_rest_pose(joints: Vec<(String, Option<usize>, Transform)>) -> Result<Self> {
+        let mut skeleton = Self::new(
+            joints
+                .into_iter()
+                .map(|(name, parent, rest)| Joint { name, parent, rest, inverse_bind: Mat4::IDENTITY })
+                .collect(),
+        )?;
+        let mut globals = Vec::new();
+        skeleton.global_matrices(&skeleton.rest_pose(), &mut globals);
+        for (joint, global) in skeleton.joints.iter_mut().zip(globals) {
+            joint.inverse_bind = global.inverse();
+        }
+        Ok(skeleton)
+    }
+    
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+    
+    /// Every joint at its rest transform (no morph weights)
+    pub fn rest_pose(&self) -> Pose {
+        Pose { locals: self.joints.iter().map(|joint| joint.rest).collect(), weights: Vec::new() }
+    }
+    
+    /// Mesh-space transform of every joint in `pose`
+    pub fn global_matrices(&self, pose: &Pose, out: &mut Vec<Mat4>) {
+        out.clear();
+        for (joint, local) in self.joints.iter().zip(&pose.locals) {
+            let global = match joint.parent {
+                Some(parent) => out[parent] * local.matrix(),
+                None => local.matrix(),
+            };
+            out.push(global);
+        }
+    }
+    
+    /// What the vertex shader blends: bind pose to `pose`, per joint
+    pub fn joint_matrices(&self, pose: &Pose, out: &mut Vec<Mat4>) {
+        self.global_matrices(pose, out);
+        for (matrix, joint) in out.iter_mut().zip(&self.joints) {
+            *matrix *= joint.inverse_bind;
+        }
+    }
+}
+
+/// Local transforms, one per joint of a skeleton, and morph weights, one
+/// per target of the mesh
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pose {
+    pub locals: Vec<Transform>,
+    pub weights: Vec<f32>,
+}
+
+impl Pose {
+    /// Move towards `other` by `weight` (0 = unchanged, 1 = `other`)
+    pub fn blend(&mut self, other: &Pose, weight: f32) {
+        for (local, target) in self.locals.iter_mut().zip(&other.locals) {
+            *local = local.lerp(target, weight);
+        }
+        for (morph, target) in self.weights.iter_mut().zip(&other.weights) {
+            *morph += (target - *morph) * weight;
+        }
+    }
+}
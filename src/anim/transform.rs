@@ -0,0 +1,163 @@
+// Transform animation
+//
+// Keyframed translation, rotation and scale of scene nodes (the instances),
+// with an easing curve per key and once, loop or ping-pong playback. Clips
+// come from the [animation] section of the scene config. Rotation keys are
+// Euler angles in degrees and interpolate per angle, so a track can turn
+// more than half a revolution between two keys. instances.comp samples the
+// same keys on the GPU; `TransformClip::matrix` is its CPU twin (used by CPU
+// culling), so the two must stay in step.
+
+use anyhow::Result;
+use glam::{EulerRot, Mat4, Quat, Vec3};
+
+/// How a key eases into the next one (discriminants are the ids
+/// instances.comp switches on)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    /// Hold the value until the next key
+    Step = 0,
+    Linear = 1,
+    /// Start slow (quadratic)
+    EaseIn = 2,
+    /// End slow (quadratic)
+    EaseOut = 3,
+    /// Start and end slow (smoothstep)
+    EaseInOut = 4,
+}
+
+impl Easing {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "step" => Easing::Step,
+            "linear" => Easing::Linear,
+            "ease_in" => Easing::EaseIn,
+            "ease_out" => Easing::EaseOut,
+            "ease_in_out" => Easing::EaseInOut,
+            _ => return None,
+        })
+    }
+    
+    /// Progress between two keys, `t` in [0, 1]
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Step => 0.0,
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// What happens once a clip reaches its last key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayMode {
+    /// Hold the last key
+    Once = 0,
+    /// Start over
+    Loop = 1,
+    /// Play backwards to the start, then forwards again
+    PingPong = 2,
+}
+
+impl PlayMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "once" => PlayMode::Once,
+            "loop" => PlayMode::Loop,
+            "ping_pong" | "pingpong" => PlayMode::PingPong,
+            _ => return None,
+        })
+    }
+    
+    /// Time into a clip of `duration` seconds after playing for `time`
+    pub fn clip_time(self, time: f32, duration: f32) -> f32 {
+        if duration <= 0.0 {
+            return 0.0;
+        }
+        match self {
+            PlayMode::Once => time.clamp(0.0, duration),
+            PlayMode::Loop => time.rem_euclid(duration),
+            PlayMode::PingPong => {
+                let t = time.rem_euclid(2.0 * duration);
+                if t > duration { 2.0 * duration - t } else { t }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    /// Seconds from the start of the clip
+    pub time: f32,
+    pub value: Vec3,
+    /// Curve from this key to the next
+    pub easing: Easing,
+}
+
+/// Keys of one property; an empty track leaves the property at rest
+#[derive(Debug, Clone, Default)]
+pub struct Track {
+    keys: Vec<Keyframe>,
+}
+
+impl Track {
+    /// Fails unless key times increase
+    pub fn new(keys: Vec<Keyframe>) -> Result<Self> {
+        if keys.windows(2).any(|pair| pair[1].time <= pair[0].time) {
+            anyhow::bail!("Key times don't increase");
+        }
+        Ok(Self { keys })
+    }
+    
+    pub fn keys(&self) -> &[Keyframe] {
+        &self.keys
+    }
+    
+    /// Time of the last key (0 when empty)
+    pub fn end(&self) -> f32 {
+        self.keys.last().map_or(0.0, |key| key.time)
+    }
+    
+    /// The value at `time`, held before the first and after the last key
+    /// (None when empty)
+    pub fn sample(&self, time: f32) -> Option<Vec3> {
+        let next = self.keys.partition_point(|key| key.time <= time);
+        if next == 0 || next == self.keys.len() {
+            let key = if next == 0 { self.keys.first() } else { self.keys.last() };
+            return key.map(|key| key.value);
+        }
+        let (a, b) = (&self.keys[next - 1], &self.keys[next]);
+        let t = (time - a.time) / (b.time - a.time);
+        Some(a.value.lerp(b.value, a.easing.apply(t)))
+    }
+}
+
+/// Tracks for a node's translation, rotation (Euler degrees about X, Y and
+/// Z, applied as Y * X * Z) and scale
+#[derive(Debug, Clone)]
+pub struct TransformClip {
+    pub mode: PlayMode,
+    /// Seconds to the last key of any track
+    pub duration: f32,
+    pub translation: Track,
+    pub rotation: Track,
+    pub scale: Track,
+}
+
+impl TransformClip {
+    pub fn new(mode: PlayMode, translation: Track, rotation: Track, scale: Track) -> Self {
+        let duration = translation.end().max(rotation.end()).max(scale.end());
+        Self { mode, duration, translation, rotation, scale }
+    }
+    
+    /// The clip's transform after playing for `time` seconds
+    pub fn matrix(&self, time: f32) -> Mat4 {
+        let time = self.mode.clip_time(time, self.duration);
+        let translation = self.translation.sample(time).unwrap_or(Vec3::ZERO);
+        let [x, y, z] = self.rotation.sample(time).unwrap_or(Vec3::ZERO).to_array().map(f32::to_radians);
+        let scale = self.scale.sample(time).unwrap_or(Vec3::ONE);
+        Mat4::from_scale_rotation_translation(scale, Quat::from_euler(EulerRot::YXZ, y, x, z), translation)
+    }
+}
@@ -0,0 +1,167 @@
+// Images
+//
+// PNG decoding (the png crate: every color type, bit depth and interlacing)
+// to RGBA8, plus the checker drawn in place of textures that are still
+// loading or failed to load.
+
+use std::path::Path;
+use anyhow::{Context, Result};
+
+/// Largest decoded image accepted (8192 x 8192 RGBA8), so a corrupt or
+/// hostile file can't make the decoder allocate without bound
+const MAX_IMAGE_BYTES: usize = 8192 * 8192 * 4;
+
+/// Tightly packed RGBA8 pixels, rows top to bottom
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl ImageData {
+    /// Magenta and black squares: obviously not the real texture
+    pub fn checker(size: u32, cells: u32) -> Self {
+        let cell = (size / cells.max(1)).max(1);
+        let rgba = (0..size * size)
+            .flat_map(|i| {
+                let (x, y) = (i % size / cell, i / size / cell);
+                if (x + y) % 2 == 0 { [255, 0, 255, 255] } else { [0, 0, 0, 255] }
+            })
+            .collect();
+        Self { width: size, height: size, rgba }
+    }
+}
+
+/// Read and decode a PNG file
+pub fn load(path: &Path) -> Result<ImageData> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read image: {:?}", path))?;
+    decode_png(&bytes).with_context(|| format!("Failed to decode PNG: {:?}", path))
+}
+
+/// Decode a PNG to RGBA8 (16-bit channels keep their high byte)
+pub fn decode_png(bytes: &[u8]) -> Result<ImageData> {
+    let mut decoder = png::Decoder::new_with_limits(bytes, png::Limits { bytes: MAX_IMAGE_BYTES });
+    // Palettes, transparency keys and low bit depths become 8-bit gray or
+    // color, with an alpha channel where the file has transparency
+    decoder.set_transformations(
+        png::Transformations::EXPAND | png::Transformations::STRIP_16 | png::Transformations::ALPHA,
+    );
+    let mut reader = decoder.read_info().context("Invalid PNG header")?;
+    let (width, height) = reader.info().size();
+    let rgba_size = (width as u64 * height as u64).checked_mul(4);
+    if rgba_size.is_none_or(|size| size > MAX_IMAGE_BYTES as u64) || reader.output_buffer_size() > MAX_IMAGE_BYTES {
+        anyhow::bail!("{}x{} is larger than the {} byte limit", width, height, MAX_IMAGE_BYTES);
+    }
+    
+    let mut pixels = vec![0u8; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut pixels).context("Corrupt image data")?;
+    pixels.truncate(frame.buffer_size());
+    let rgba = match frame.color_type {
+        png::ColorType::Rgba => pixels,
+        png::ColorType::Rgb => pixels.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => pixels.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|&gray| [gray, gray, gray, 255]).collect(),
+        png::ColorType::Indexed => anyhow::bail!("Palette wasn't expanded"),
+    };
+    
+    Ok(ImageData { width, height, rgba })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Encode `data` (rows packed as the color type and depth say) as a PNG
+    fn encode(
+        (width, height): (u32, u32),
+        color: png::ColorType,
+        depth: png::BitDepth,
+        setup: impl FnOnce(&mut png::Encoder<&mut Vec<u8>>),
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(color);
+        encoder.set_depth(depth);
+        setup(&mut encoder);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(data).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
+    
+    #[test]
+    fn every_row_filter_decodes() {
+        // Gradients and noise, so every predictor sees non-zero neighbours
+        let rgb: Vec<u8> = (0..4 * 3 * 3).map(|i: u32| (i * 37 % 251) as u8).collect();
+        let expected: Vec<u8> = rgb.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect();
+        for filter in [
+            png::FilterType::NoFilter,
+            png::FilterType::Sub,
+            png::FilterType::Up,
+            png::FilterType::Avg,
+            png::FilterType::Paeth,
+        ] {
+            let bytes = encode((4, 3), png::ColorType::Rgb, png::BitDepth::Eight, |e| e.set_filter(filter), &rgb);
+            let image = decode_png(&bytes).unwrap();
+            assert_eq!((image.width, image.height), (4, 3));
+            assert_eq!(image.rgba, expected, "{filter:?}");
+        }
+    }
+    
+    #[test]
+    fn palette_and_transparency_expand_to_rgba() {
+        // 2-bit indices: entry 0 fully transparent, 1 half, 2 without alpha
+        let palette = |e: &mut png::Encoder<&mut Vec<u8>>| {
+            e.set_palette(vec![10, 20, 30, 40, 50, 60, 70, 80, 90]);
+            e.set_trns(vec![0, 128]);
+        };
+        let bytes = encode((3, 1), png::ColorType::Indexed, png::BitDepth::Two, palette, &[0b0001_1000]);
+        assert_eq!(decode_png(&bytes).unwrap().rgba, [10, 20, 30, 0, 40, 50, 60, 128, 70, 80, 90, 255]);
+        
+        // A gray transparency key
+        let key = |e: &mut png::Encoder<&mut Vec<u8>>| e.set_trns(vec![0, 7]);
+        let bytes = encode((2, 1), png::ColorType::Grayscale, png::BitDepth::Eight, key, &[7, 8]);
+        assert_eq!(decode_png(&bytes).unwrap().rgba, [7, 7, 7, 0, 8, 8, 8, 255]);
+    }
+    
+    #[test]
+    fn sixteen_bit_and_low_bit_depths_become_eight_bit() {
+        let bytes = encode((2, 1), png::ColorType::GrayscaleAlpha, png::BitDepth::Sixteen, |_| {}, &[
+            0x12, 0x34, 0xFF, 0xFF,
+            0xAB, 0xCD, 0x80, 0x00,
+        ]);
+        assert_eq!(decode_png(&bytes).unwrap().rgba, [0x12, 0x12, 0x12, 0xFF, 0xAB, 0xAB, 0xAB, 0x80]);
+        
+        // 4-bit gray scales 0..15 to 0..255
+        let bytes = encode((2, 1), png::ColorType::Grayscale, png::BitDepth::Four, |_| {}, &[0xF5]);
+        assert_eq!(decode_png(&bytes).unwrap().rgba, [255, 255, 255, 255, 85, 85, 85, 255]);
+    }
+    
+    #[test]
+    fn invalid_files_are_errors() {
+        assert!(decode_png(b"GIF89a").is_err());
+        
+        let bytes = encode((4, 4), png::ColorType::Rgba, png::BitDepth::Eight, |_| {}, &[200; 64]);
+        for end in [8, 20, bytes.len() - 20] {
+            assert!(decode_png(&bytes[..end]).is_err(), "truncated to {end} bytes");
+        }
+        
+        // A header asking for far more than the limit is refused before any
+        // pixel buffer is allocated (the IHDR checksum is redone to match)
+        let mut bytes = bytes;
+        bytes[16..20].copy_from_slice(&100_000u32.to_be_bytes());
+        bytes[20..24].copy_from_slice(&100_000u32.to_be_bytes());
+        let crc = crc32(&bytes[12..29]);
+        bytes[29..33].copy_from_slice(&crc.to_be_bytes());
+        let error = format!("{:#}", decode_png(&bytes).err().unwrap());
+        assert!(error.contains("byte limit"), "{error}");
+    }
+    
+    /// CRC-32 as PNG chunks use it
+    fn crc32(bytes: &[u8]) -> u32 {
+        !bytes.iter().fold(!0u32, |crc, &byte| {
+            (0..8).fold(crc ^ byte as u32, |crc, _| (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg()))
+        })
+    }
+}
@@ -0,0 +1,157 @@
+// Background asset loader
+//
+// `AssetLoader::load` queues a job (reading files, decoding, processing
+// meshes) for a small pool of worker threads and returns a `Handle` at once.
+// The render thread calls `poll` once a frame to collect finished jobs and
+// `take`s a result when it can upload it; GPU resources are only ever
+// created on the render thread. Until then, callers draw a placeholder.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Instant;
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+
+type Job = Box<dyn FnOnce() + Send>;
+type Finished = (u64, Result<Box<dyn Any + Send>>);
+
+/// A load in progress; `AssetLoader::take` turns it into the loaded `T`
+#[derive(Debug)]
+pub struct Handle<T> {
+    id: u64,
+    _type: PhantomData<fn() -> T>,
+}
+
+// Not derived: that would require `T: Clone`
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+/// Worker threads plus the results they finished and nobody took yet
+pub struct AssetLoader {
+    jobs: Option<Sender<Job>>,
+    results_sender: Sender<Finished>,
+    results: Receiver<Finished>,
+    finished: HashMap<u64, Result<Box<dyn Any + Send>>>,
+    workers: Vec<JoinHandle<()>>,
+    next_id: u64,
+    /// Queued or running jobs
+    in_flight: usize,
+}
+
+impl AssetLoader {
+    /// Start `threads` workers (0 = one per core but one, at most 4)
+    pub fn new(threads: usize) -> Result<Self> {
+        let threads = match threads {
+            0 => std::thread::available_parallelism()
+                .map_or(1, |cores| cores.get().saturating_sub(1))
+                .clamp(1, 4),
+            n => n,
+        };
+        
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let workers = (0..threads)
+            .map(|i| {
+                let job_receiver = job_receiver.clone();
+                std::thread::Builder::new()
+                    .name(format!("asset-loader-{}", i))
+                    .spawn(move || loop {
+                        // The lock is only held while waiting for the next job
+                        let job = job_receiver.lock().recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .context("Failed to start an asset loader thread")
+            })
+            .collect::<Result<Vec<_>>>()?;
+        log::info!("Asset loader: {} worker threads", threads);
+        
+        let (results_sender, results) = mpsc::channel();
+        Ok(Self {
+            jobs: Some(jobs),
+            results_sender,
+            results,
+            finished: HashMap::new(),
+            workers,
+            next_id: 0,
+            in_flight: 0,
+        })
+    }
+    
+    /// Run `load` on a worker; `name` labels log lines and errors
+    pub fn load<T: Send + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        load: impl FnOnce() -> Result<T> + Send + 'static,
+    ) -> Handle<T> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let name = name.into();
+        let results = self.results_sender.clone();
+        let job: Job = Box::new(move || {
+            let started = Instant::now();
+            let result = match panic::catch_unwind(AssertUnwindSafe(load)) {
+                Ok(result) => result.map(|asset| Box::new(asset) as Box<dyn Any + Send>),
+                Err(_) => Err(anyhow::anyhow!("Loader panicked")),
+            };
+            let result = result.with_context(|| format!("Failed to load {}", name));
+            if result.is_ok() {
+                log::info!("Loaded {} in {:.1?}", name, started.elapsed());
+            }
+            // The loader may be gone already (shutting down)
+            let _ = results.send((id, result));
+        });
+        
+        if let Some(jobs) = &self.jobs {
+            // Workers only stop when `jobs` is dropped, so this can't fail
+            let _ = jobs.send(job);
+            self.in_flight += 1;
+        }
+        Handle { id, _type: PhantomData }
+    }
+    
+    /// Collect jobs that finished since the last call
+    pub fn poll(&mut self) {
+        while let Ok((id, result)) = self.results.try_recv() {
+            self.in_flight -= 1;
+            self.finished.insert(id, result);
+        }
+    }
+    
+    /// The result of a finished load (once), or None while it is running
+    pub fn take<T: 'static>(&mut self, handle: Handle<T>) -> Option<Result<T>> {
+        let result = self.finished.remove(&handle.id)?;
+        Some(result.and_then(|asset| {
+            asset.downcast::<T>()
+                .map(|asset| *asset)
+                .map_err(|_| anyhow::anyhow!("Asset handle {} has the wrong type", handle.id))
+        }))
+    }
+    
+    /// Loads queued or running
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+}
+
+impl Drop for AssetLoader {
+    fn drop(&mut self) {
+        // Closing the queue lets the workers run what is queued and stop
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
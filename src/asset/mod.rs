@@ -0,0 +1,8 @@
+// Asset loading
+//
+// - image: PNG decoding to RGBA8, and the magenta checker placeholder
+// - loader: worker threads that load and decode assets off the render
+//   thread, handing results back through handles
+
+pub mod image;
+pub mod loader;
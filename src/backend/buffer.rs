@@ -1,6 +1,6 @@
-// Buffer utilities for vertex, index, and uniform buffers
+// Buffer utilities for vertex, index, uniform and storage buffers
 //
-// Provides helpers for creating GPU-accessible memory buffers
+// Provides helpers for creating GPU-accessible memory buffers and images
 
 use anyhow::{Context, Result};
 use ash::vk;
@@ -12,12 +12,30 @@ pub fn create_buffer(
     size: vk::DeviceSize,
     usage: vk::BufferUsageFlags,
     memory_properties: vk::MemoryPropertyFlags,
+) -> Result<(vk::Buffer, vk::DeviceMemory)> {
+    create_buffer_shared(device, size, usage, memory_properties, &[])
+}
+
+/// Create a buffer usable from several queue families without ownership
+/// transfers (`SharingMode::CONCURRENT` when more than one family is given)
+pub fn create_buffer_shared(
+    device: &VulkanDevice,
+    size: vk::DeviceSize,
+    usage: vk::BufferUsageFlags,
+    memory_properties: vk::MemoryPropertyFlags,
+    queue_families: &[u32],
 ) -> Result<(vk::Buffer, vk::DeviceMemory)> {
     // Create buffer
     let buffer_info = vk::BufferCreateInfo::builder()
         .size(size)
-        .usage(usage)
-        .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        .usage(usage);
+    let buffer_info = if queue_families.len() > 1 {
+        buffer_info
+            .sharing_mode(vk::SharingMode::CONCURRENT)
+            .queue_family_indices(queue_families)
+    } else {
+        buffer_info.sharing_mode(vk::SharingMode::EXCLUSIVE)
+    };
     
     let buffer = unsafe {
         device.device.create_buffer(&buffer_info, None)
@@ -61,7 +79,7 @@ pub fn create_buffer_with_data<T: Copy>(
     usage: vk::BufferUsageFlags,
     data: &[T],
 ) -> Result<(vk::Buffer, vk::DeviceMemory)> {
-    let size = (std::mem::size_of::<T>() * data.len()) as vk::DeviceSize;
+    let size = std::mem::size_of_val(data) as vk::DeviceSize;
     
     let (buffer, memory) = create_buffer(
         device,
@@ -86,8 +104,80 @@ pub fn create_buffer_with_data<T: Copy>(
     Ok((buffer, memory))
 }
 
+/// Create a device-local storage buffer that compute and graphics can share
+///
+/// `extra_usage` adds e.g. `VERTEX_BUFFER` or `INDIRECT_BUFFER` for buffers
+/// a compute pass writes and a draw then reads.
+pub fn create_storage_buffer(
+    device: &VulkanDevice,
+    size: vk::DeviceSize,
+    extra_usage: vk::BufferUsageFlags,
+) -> Result<(vk::Buffer, vk::DeviceMemory)> {
+    create_buffer_shared(
+        device,
+        size,
+        vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::TRANSFER_DST | extra_usage,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        &device.queue_families(),
+    )
+}
+
+/// A host-visible buffer the CPU rewrites every frame (debug lines, UI)
+///
+/// Keep one per frame in flight: a buffer may only be rewritten once the
+/// fence of the frame that last read it has signaled.
+pub struct DynamicBuffer {
+    pub buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+    /// Size in bytes
+    pub capacity: vk::DeviceSize,
+}
+
+impl DynamicBuffer {
+    pub fn new(
+        device: &VulkanDevice,
+        capacity: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+    ) -> Result<Self> {
+        let (buffer, memory) = create_buffer(
+            device,
+            capacity,
+            usage,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        
+        Ok(Self { buffer, memory, capacity })
+    }
+    
+    /// Copy as much of `data` as fits to the start of the buffer and return
+    /// how many elements were written
+    pub fn write<T: Copy>(&self, device: &ash::Device, data: &[T]) -> Result<usize> {
+        let count = data.len().min(self.capacity as usize / std::mem::size_of::<T>());
+        if count == 0 {
+            return Ok(0);
+        }
+        
+        let size = (count * std::mem::size_of::<T>()) as vk::DeviceSize;
+        unsafe {
+            let ptr = device.map_memory(self.memory, 0, size, vk::MemoryMapFlags::empty())
+                .context("Failed to map dynamic buffer")? as *mut T;
+            ptr.copy_from_nonoverlapping(data.as_ptr(), count);
+            device.unmap_memory(self.memory);
+        }
+        
+        Ok(count)
+    }
+    
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_buffer(self.buffer, None);
+            device.free_memory(self.memory, None);
+        }
+    }
+}
+
 /// Find a suitable memory type index
-fn find_memory_type(
+pub fn find_memory_type(
     device: &VulkanDevice,
     type_filter: u32,
     properties: vk::MemoryPropertyFlags,
@@ -184,3 +274,90 @@ pub fn create_depth_buffer(
     
     Ok((image, memory, view))
 }
+
+/// Create a 2D storage image (compute writes, later passes sample or copy it)
+///
+/// The image starts in `UNDEFINED` layout; transition it to `GENERAL`
+/// before the first dispatch that writes it.
+/// Used in: compute post effects
+#[allow(dead_code)]
+pub fn create_storage_image(
+    device: &VulkanDevice,
+    extent: vk::Extent2D,
+    format: vk::Format,
+) -> Result<(vk::Image, vk::DeviceMemory, vk::ImageView)> {
+    let queue_families = device.queue_families();
+    let image_info = vk::ImageCreateInfo::builder()
+        .image_type(vk::ImageType::TYPE_2D)
+        .extent(vk::Extent3D {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        })
+        .mip_levels(1)
+        .array_layers(1)
+        .format(format)
+        .tiling(vk::ImageTiling::OPTIMAL)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .usage(
+            vk::ImageUsageFlags::STORAGE
+                | vk::ImageUsageFlags::SAMPLED
+                | vk::ImageUsageFlags::TRANSFER_SRC,
+        )
+        .samples(vk::SampleCountFlags::TYPE_1);
+    let image_info = if queue_families.len() > 1 {
+        image_info
+            .sharing_mode(vk::SharingMode::CONCURRENT)
+            .queue_family_indices(&queue_families)
+    } else {
+        image_info.sharing_mode(vk::SharingMode::EXCLUSIVE)
+    };
+    
+    let image = unsafe {
+        device.device.create_image(&image_info, None)
+            .context("Failed to create storage image")?
+    };
+    
+    let mem_requirements = unsafe {
+        device.device.get_image_memory_requirements(image)
+    };
+    
+    let memory_type_index = find_memory_type(
+        device,
+        mem_requirements.memory_type_bits,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+    
+    let alloc_info = vk::MemoryAllocateInfo::builder()
+        .allocation_size(mem_requirements.size)
+        .memory_type_index(memory_type_index);
+    
+    let memory = unsafe {
+        device.device.allocate_memory(&alloc_info, None)
+            .context("Failed to allocate storage image memory")?
+    };
+    
+    unsafe {
+        device.device.bind_image_memory(image, memory, 0)
+            .context("Failed to bind storage image memory")?;
+    }
+    
+    let view_info = vk::ImageViewCreateInfo::builder()
+        .image(image)
+        .view_type(vk::ImageViewType::TYPE_2D)
+        .format(format)
+        .subresource_range(vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        });
+    
+    let view = unsafe {
+        device.device.create_image_view(&view_info, None)
+            .context("Failed to create storage image view")?
+    };
+    
+    Ok((image, memory, view))
+}
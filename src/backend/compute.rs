@@ -0,0 +1,283 @@
+// Compute - dispatching compute shaders
+//
+// A ComputePipeline bundles the pipeline, its reflected layout and the
+// shader's workgroup size, so callers dispatch in invocations (e.g. one per
+// instance or pixel) and the group count is worked out here.
+//
+// Async compute: VulkanDevice::compute_queue is a separate hardware queue when
+// the GPU has one. Resources shared with graphics are created CONCURRENT
+// (buffer::create_storage_buffer, buffer::create_storage_image), so no queue
+// ownership transfers are needed; order the queues with semaphores
+// (AsyncCompute keeps one per frame in flight). Without such a queue,
+// dispatches are recorded into the graphics command buffer and
+// cmd_compute_barrier orders them against the draws.
+
+use anyhow::{Context, Result};
+use ash::vk;
+use super::permutation::SpecializationConstants;
+use super::pipeline;
+use super::shader::Shader;
+use super::VulkanDevice;
+
+/// A compute pipeline and everything needed to dispatch it
+pub struct ComputePipeline {
+    pub pipeline: vk::Pipeline,
+    pub layout: vk::PipelineLayout,
+    pub set_layouts: Vec<vk::DescriptorSetLayout>,
+    /// `local_size` from the shader
+    pub workgroup_size: [u32; 3],
+}
+
+impl ComputePipeline {
+    /// Create from a compute shader (the shader module can be destroyed afterwards)
+    pub fn new(
+        device: &VulkanDevice,
+        shader: &Shader,
+        specialization: &SpecializationConstants,
+    ) -> Result<Self> {
+        let workgroup_size = shader.reflection.workgroup_size
+            .context("Compute shader has no local_size")?;
+        let (pipeline, layout, set_layouts) =
+            pipeline::create_compute_pipeline(device, shader, specialization)?;
+        
+        Ok(Self { pipeline, layout, set_layouts, workgroup_size })
+    }
+    
+    /// Workgroups needed to cover `invocations` threads in each dimension
+    pub fn group_count(&self, invocations: [u32; 3]) -> [u32; 3] {
+        [
+            invocations[0].div_ceil(self.workgroup_size[0]),
+            invocations[1].div_ceil(self.workgroup_size[1]),
+            invocations[2].div_ceil(self.workgroup_size[2]),
+        ]
+    }
+    
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_pipeline(self.pipeline, None);
+            device.destroy_pipeline_layout(self.layout, None);
+            for &set_layout in &self.set_layouts {
+                device.destroy_descriptor_set_layout(set_layout, None);
+            }
+        }
+    }
+}
+
+/// Bind `pipeline` with its descriptor sets and push constants, then dispatch
+/// enough workgroups to cover `invocations` (shaders must bounds-check the rest)
+pub fn cmd_dispatch(
+    device: &ash::Device,
+    cmd: vk::CommandBuffer,
+    pipeline: &ComputePipeline,
+    descriptor_sets: &[vk::DescriptorSet],
+    push_constants: &[u8],
+    invocations: [u32; 3],
+) {
+    let [x, y, z] = pipeline.group_count(invocations);
+    
+    unsafe {
+        cmd_bind(device, cmd, pipeline, descriptor_sets, push_constants);
+        device.cmd_dispatch(cmd, x, y, z);
+    }
+}
+
+unsafe fn cmd_bind(
+    device: &ash::Device,
+    cmd: vk::CommandBuffer,
+    pipeline: &ComputePipeline,
+    descriptor_sets: &[vk::DescriptorSet],
+    push_constants: &[u8],
+) {
+    device.cmd_bind_pipeline(cmd, vk::PipelineBindPoint::COMPUTE, pipeline.pipeline);
+    if !descriptor_sets.is_empty() {
+        device.cmd_bind_descriptor_sets(
+            cmd,
+            vk::PipelineBindPoint::COMPUTE,
+            pipeline.layout,
+            0,
+            descriptor_sets,
+            &[],
+        );
+    }
+    if !push_constants.is_empty() {
+        device.cmd_push_constants(
+            cmd,
+            pipeline.layout,
+            vk::ShaderStageFlags::COMPUTE,
+            0,
+            push_constants,
+        );
+    }
+}
+
+/// Make compute shader writes visible to a later stage on the same queue
+///
+/// e.g. `dst_stage = VERTEX_INPUT, dst_access = VERTEX_ATTRIBUTE_READ` for a
+/// buffer a compute pass filled and a draw then reads as vertex data.
+pub fn cmd_compute_barrier(
+    device: &ash::Device,
+    cmd: vk::CommandBuffer,
+    dst_stage: vk::PipelineStageFlags,
+    dst_access: vk::AccessFlags,
+) {
+    let barrier = vk::MemoryBarrier::builder()
+        .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+        .dst_access_mask(dst_access)
+        .build();
+    
+    unsafe {
+        device.cmd_pipeline_barrier(
+            cmd,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            dst_stage,
+            vk::DependencyFlags::empty(),
+            &[barrier],
+            &[],
+            &[],
+        );
+    }
+}
+
+/// Transition a storage image so compute shaders can read and write it
+/// Used in: compute post effects
+#[allow(dead_code)]
+pub fn cmd_storage_image_barrier(
+    device: &ash::Device,
+    cmd: vk::CommandBuffer,
+    image: vk::Image,
+    old_layout: vk::ImageLayout,
+    src_stage: vk::PipelineStageFlags,
+    src_access: vk::AccessFlags,
+) {
+    let barrier = vk::ImageMemoryBarrier::builder()
+        .old_layout(old_layout)
+        .new_layout(vk::ImageLayout::GENERAL)
+        .src_access_mask(src_access)
+        .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        })
+        .build();
+    
+    unsafe {
+        device.cmd_pipeline_barrier(
+            cmd,
+            src_stage,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[barrier],
+        );
+    }
+}
+
+/// Submit recorded compute work to the compute queue (async when available)
+///
+/// Signal a semaphore that the graphics submit waits on at the stage that
+/// consumes the results.
+pub fn submit_compute(
+    device: &VulkanDevice,
+    cmd: vk::CommandBuffer,
+    wait_semaphores: &[vk::Semaphore],
+    signal_semaphores: &[vk::Semaphore],
+    fence: vk::Fence,
+) -> Result<()> {
+    let wait_stages = vec![vk::PipelineStageFlags::COMPUTE_SHADER; wait_semaphores.len()];
+    let command_buffers = [cmd];
+    let submit_info = vk::SubmitInfo::builder()
+        .wait_semaphores(wait_semaphores)
+        .wait_dst_stage_mask(&wait_stages)
+        .command_buffers(&command_buffers)
+        .signal_semaphores(signal_semaphores)
+        .build();
+    
+    unsafe {
+        device.device.queue_submit(device.compute_queue, &[submit_info], fence)
+            .context("Failed to submit compute work")
+    }
+}
+
+/// Command buffers and semaphores for compute work on the dedicated compute
+/// queue, one of each per frame in flight
+///
+/// Per frame: `begin`, record dispatches, `submit`, then make the graphics
+/// submit wait on `finished[frame]`. The frame's fence covers the graphics
+/// work, which can't start before the compute work ends, so once it is
+/// signaled the frame's command buffer may be recorded again.
+pub struct AsyncCompute {
+    command_pool: vk::CommandPool,
+    command_buffers: Vec<vk::CommandBuffer>,
+    /// Signaled when the frame's compute work is done
+    pub finished: Vec<vk::Semaphore>,
+}
+
+impl AsyncCompute {
+    /// None when the device has no compute family apart from graphics
+    pub fn new(device: &VulkanDevice, frames: usize) -> Result<Option<Self>> {
+        if !device.has_async_compute() {
+            return Ok(None);
+        }
+        
+        let pool_info = vk::CommandPoolCreateInfo::builder()
+            .queue_family_index(device.compute_queue_family)
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT | vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER);
+        let command_pool = unsafe {
+            device.device.create_command_pool(&pool_info, None)
+                .context("Failed to create compute command pool")?
+        };
+        
+        let alloc_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(frames as u32);
+        let semaphore_info = vk::SemaphoreCreateInfo::builder();
+        unsafe {
+            Ok(Some(Self {
+                command_pool,
+                command_buffers: device.device.allocate_command_buffers(&alloc_info)
+                    .context("Failed to allocate compute command buffers")?,
+                finished: (0..frames)
+                    .map(|_| device.device.create_semaphore(&semaphore_info, None))
+                    .collect::<Result<_, _>>()?,
+            }))
+        }
+    }
+    
+    /// Reset and begin the command buffer of `frame` (its previous submit
+    /// must be done, see above)
+    pub fn begin(&self, device: &ash::Device, frame: usize) -> Result<vk::CommandBuffer> {
+        let cmd = self.command_buffers[frame];
+        unsafe {
+            device.reset_command_buffer(cmd, vk::CommandBufferResetFlags::empty())?;
+            let begin_info = vk::CommandBufferBeginInfo::builder()
+                .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+            device.begin_command_buffer(cmd, &begin_info)?;
+        }
+        Ok(cmd)
+    }
+    
+    /// End the command buffer of `frame` and submit it, signaling
+    /// `finished[frame]`
+    pub fn submit(&self, device: &VulkanDevice, frame: usize) -> Result<()> {
+        let cmd = self.command_buffers[frame];
+        unsafe { device.device.end_command_buffer(cmd)? };
+        submit_compute(device, cmd, &[], &[self.finished[frame]], vk::Fence::null())
+    }
+    
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            for &semaphore in &self.finished {
+                device.destroy_semaphore(semaphore, None);
+            }
+            device.destroy_command_pool(self.command_pool, None);
+        }
+    }
+}
@@ -0,0 +1,337 @@
+// Culling - skipping objects the camera can't see
+//
+// Two paths share the same math:
+// - GPU: cull.wgsl tests every object's bounding sphere against the frustum
+//   and writes VkDrawIndexedIndirectCommands plus a draw count, consumed by
+//   vkCmdDrawIndexedIndirectCount (needs Vulkan 1.2 drawIndirectCount).
+// - CPU: objects' bounds are transformed per instance and tested here
+//   (sphere first, then the tighter AABB), recording one draw per run of
+//   visible objects. Used when the GPU path is unavailable or disabled.
+
+use glam::{Mat4, Vec3, Vec4};
+use std::ops::Range;
+
+/// Where (and whether) objects are culled, from `[graphics] culling`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CullingMode {
+    /// Compute pass + indirect draws
+    Gpu,
+    /// Sphere tests while recording command buffers
+    Cpu,
+    /// Draw everything
+    Off,
+}
+
+/// An axis-aligned bounding box
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn from_points(points: impl Iterator<Item = Vec3>) -> Self {
+        let (min, max) = points.fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), p| (min.min(p), max.max(p)),
+        );
+        Self { min, max }
+    }
+    
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+    
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+    
+    /// The box enclosing this one after `transform` (Arvo's method: the new
+    /// half extents are the old ones through the absolute rotation/scale)
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        let center = transform.transform_point3(self.center());
+        let half = self.half_extents();
+        let half = transform.x_axis.truncate().abs() * half.x
+            + transform.y_axis.truncate().abs() * half.y
+            + transform.z_axis.truncate().abs() * half.z;
+        
+        Self { min: center - half, max: center + half }
+    }
+}
+
+/// A bounding sphere (`center` in the same space as the object's vertices)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Sphere around the AABB center of `points` (not minimal, but cheap and tight enough)
+    pub fn from_points(points: impl Iterator<Item = Vec3> + Clone) -> Self {
+        let center = Aabb::from_points(points.clone()).center();
+        let radius = points.map(|p| p.distance(center)).fold(0.0, f32::max);
+        
+        Self { center, radius }
+    }
+    
+    /// The sphere after `transform` (radius grows by the largest axis scale)
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        let scale = transform.x_axis.truncate().length()
+            .max(transform.y_axis.truncate().length())
+            .max(transform.z_axis.truncate().length());
+        
+        Self {
+            center: transform.transform_point3(self.center),
+            radius: self.radius * scale,
+        }
+    }
+    
+    /// `[center.xyz, radius]` as stored in GPU buffers
+    pub fn to_vec4(self) -> Vec4 {
+        self.center.extend(self.radius)
+    }
+}
+
+/// Bounds of one mesh, computed once at load and transformed per instance
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MeshBounds {
+    pub aabb: Aabb,
+    pub sphere: BoundingSphere,
+}
+
+impl MeshBounds {
+    pub fn from_points(points: impl Iterator<Item = Vec3> + Clone) -> Self {
+        Self {
+            aabb: Aabb::from_points(points.clone()),
+            sphere: BoundingSphere::from_points(points),
+        }
+    }
+}
+
+/// Result of culling a set of instances on the CPU
+#[derive(Debug, Clone, Default)]
+pub struct CullResult {
+    /// Visible instances merged into contiguous runs (each run can be drawn
+    /// with one instanced draw starting at `first_instance`)
+    pub visible_ranges: Vec<Range<u32>>,
+    pub visible: u32,
+    pub culled: u32,
+}
+
+/// The six planes of a view frustum, normals pointing inward
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    /// xyz = normal, w = distance (a point p is inside when `dot(n, p) + w >= 0`)
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extract world-space planes from a view-projection matrix
+    /// (Vulkan clip space: depth 0..1, so the near plane is just row 2)
+    pub fn from_view_projection(view_proj: Mat4) -> Self {
+        let row = |i| view_proj.row(i);
+        let planes = [
+            row(3) + row(0), // left
+            row(3) - row(0), // right
+            row(3) + row(1), // bottom (top when Y is flipped; both are tested)
+            row(3) - row(1), // top
+            row(2),          // near
+            row(3) - row(2), // far
+        ];
+        
+        Self { planes: planes.map(|p| p / p.truncate().length()) }
+    }
+    
+    /// True if any part of the sphere may be inside
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(center) + plane.w >= -radius)
+    }
+    
+    /// True unless the box is entirely outside one plane (tests the corner
+    /// furthest along each plane's normal)
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+    
+    /// Cull instances of one mesh given each instance's model matrix
+    ///
+    /// The transformed sphere rejects most objects cheaply; survivors are
+    /// checked against their transformed AABB, which is tighter for long shapes.
+    pub fn cull(&self, bounds: &MeshBounds, transforms: impl Iterator<Item = Mat4>) -> CullResult {
+        let mut result = CullResult::default();
+        for (i, transform) in transforms.enumerate() {
+            let sphere = bounds.sphere.transformed(&transform);
+            let visible = self.intersects_sphere(sphere.center, sphere.radius)
+                && self.intersects_aabb(&bounds.aabb.transformed(&transform));
+            if !visible {
+                result.culled += 1;
+                continue;
+            }
+            
+            result.visible += 1;
+            let i = i as u32;
+            match result.visible_ranges.last_mut() {
+                Some(last) if last.end == i => last.end = i + 1,
+                _ => result.visible_ranges.push(i..i + 1),
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+    
+    const EPSILON: f32 = 1e-4;
+    
+    /// Camera at z = 10 looking at the origin with a 90° square view, built
+    /// like `calculate_view_projection` (depth 0..1, Y flipped), so at depth
+    /// d = 10 - z the frustum spans |x| <= d and |y| <= d, for d in 0.1..100
+    fn frustum() -> Frustum {
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO, Vec3::Y);
+        let mut proj = Mat4::perspective_rh(90f32.to_radians(), 1.0, 0.1, 100.0);
+        proj.y_axis.y *= -1.0;
+        Frustum::from_view_projection(proj * view)
+    }
+    
+    /// Indices of the planes `point` is outside of
+    fn outside(frustum: &Frustum, point: Vec3) -> Vec<usize> {
+        (0..6)
+            .filter(|&i| frustum.planes[i].truncate().dot(point) + frustum.planes[i].w < 0.0)
+            .collect()
+    }
+    
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!(a.abs_diff_eq(b, EPSILON), "{a} != {b}");
+    }
+    
+    #[test]
+    fn planes_are_normalized() {
+        for plane in frustum().planes {
+            assert!((plane.truncate().length() - 1.0).abs() < EPSILON, "{plane}");
+        }
+    }
+    
+    #[test]
+    fn points_on_each_side_of_each_plane() {
+        let frustum = frustum();
+        // (plane, a point just inside it, a point just outside it)
+        let cases = [
+            (0, Vec3::new(-9.9, 0.0, 0.0), Vec3::new(-10.1, 0.0, 0.0)), // left
+            (1, Vec3::new(9.9, 0.0, 0.0), Vec3::new(10.1, 0.0, 0.0)),   // right
+            (2, Vec3::new(0.0, 9.9, 0.0), Vec3::new(0.0, 10.1, 0.0)),   // top (Y flipped)
+            (3, Vec3::new(0.0, -9.9, 0.0), Vec3::new(0.0, -10.1, 0.0)), // bottom (Y flipped)
+            (4, Vec3::new(0.0, 0.0, 9.85), Vec3::new(0.0, 0.0, 9.95)),  // near
+            (5, Vec3::new(0.0, 0.0, -89.9), Vec3::new(0.0, 0.0, -90.1)), // far
+        ];
+        for (plane, inside, outside_point) in cases {
+            assert!(outside(&frustum, inside).is_empty(), "{inside} should be inside");
+            assert_eq!(outside(&frustum, outside_point), vec![plane], "{outside_point}");
+        }
+    }
+    
+    #[test]
+    fn spheres_and_boxes_straddling_a_plane_are_kept() {
+        let frustum = frustum();
+        assert!(frustum.intersects_sphere(Vec3::new(10.5, 0.0, 0.0), 1.0));
+        assert!(frustum.intersects_sphere(Vec3::new(0.0, 0.0, -90.5), 1.0));
+        assert!(frustum.intersects_aabb(&Aabb {
+            min: Vec3::new(9.0, -1.0, -1.0),
+            max: Vec3::new(11.0, 1.0, 1.0),
+        }));
+        assert!(frustum.intersects_aabb(&Aabb {
+            min: Vec3::new(-1.0, -1.0, 9.0),
+            max: Vec3::new(1.0, 1.0, 11.0),
+        }));
+    }
+    
+    #[test]
+    fn spheres_and_boxes_fully_outside_are_rejected() {
+        let frustum = frustum();
+        assert!(!frustum.intersects_sphere(Vec3::new(20.0, 0.0, 0.0), 1.0));
+        assert!(!frustum.intersects_sphere(Vec3::new(0.0, 0.0, 12.0), 1.0));
+        assert!(!frustum.intersects_aabb(&Aabb {
+            min: Vec3::new(19.0, -1.0, -1.0),
+            max: Vec3::new(21.0, 1.0, 1.0),
+        }));
+        assert!(!frustum.intersects_aabb(&Aabb {
+            min: Vec3::new(-1.0, -1.0, 11.0),
+            max: Vec3::new(1.0, 1.0, 13.0),
+        }));
+        assert!(!frustum.intersects_aabb(&Aabb {
+            min: Vec3::new(-1.0, -1.0, -200.0),
+            max: Vec3::new(1.0, 1.0, -150.0),
+        }));
+    }
+    
+    #[test]
+    fn cull_merges_visible_runs() {
+        let bounds = MeshBounds::from_points([Vec3::splat(-0.5), Vec3::splat(0.5)].into_iter());
+        let x = [0.0, 1.0, 50.0, 2.0, 3.0, -50.0];
+        let result = frustum().cull(&bounds, x.iter().map(|&x| Mat4::from_translation(Vec3::X * x)));
+        
+        assert_eq!(result.visible, 4);
+        assert_eq!(result.culled, 2);
+        assert_eq!(result.visible_ranges, vec![0..2, 3..5]);
+    }
+    
+    /// 45° around Z, then scaled (2, 1, 3) and moved to x = 5
+    fn rotated_scaled() -> Mat4 {
+        Mat4::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 3.0),
+            Quat::from_rotation_z(45f32.to_radians()),
+            Vec3::new(5.0, 0.0, 0.0),
+        )
+    }
+    
+    #[test]
+    fn aabb_transformed_encloses_rotated_scaled_box() {
+        let aabb = Aabb { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0) };
+        let transform = rotated_scaled();
+        let moved = aabb.transformed(&transform);
+        
+        // Scale first: x spans ±2 and y ±1, then both mix through the rotation
+        let half = 3.0 / 2f32.sqrt();
+        assert_near(moved.min, Vec3::new(5.0 - half, -half, -3.0));
+        assert_near(moved.max, Vec3::new(5.0 + half, half, 3.0));
+        
+        // Exactly the box around the transformed corners
+        let corners = (0..8).map(|i| {
+            let corner = Vec3::select(
+                glam::BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0),
+                aabb.max,
+                aabb.min,
+            );
+            transform.transform_point3(corner)
+        });
+        let expected = Aabb::from_points(corners);
+        assert_near(moved.min, expected.min);
+        assert_near(moved.max, expected.max);
+    }
+    
+    #[test]
+    fn sphere_transformed_encloses_rotated_scaled_sphere() {
+        let sphere = BoundingSphere { center: Vec3::new(1.0, 0.0, 0.0), radius: 1.0 };
+        let transform = rotated_scaled();
+        let moved = sphere.transformed(&transform);
+        
+        let diagonal = 2.0 / 2f32.sqrt();
+        assert_near(moved.center, Vec3::new(5.0 + diagonal, diagonal, 0.0));
+        assert!((moved.radius - 3.0).abs() < EPSILON);
+        
+        // Points on the original sphere stay inside the new one
+        for direction in [Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y, Vec3::Z, Vec3::NEG_Z, Vec3::ONE.normalize()] {
+            let point = transform.transform_point3(sphere.center + direction * sphere.radius);
+            assert!(point.distance(moved.center) <= moved.radius + EPSILON, "{point}");
+        }
+    }
+}
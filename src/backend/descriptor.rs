@@ -0,0 +1,177 @@
+// Descriptor sets - binding buffers and images to shaders
+//
+// Set layouts come from shader reflection (see pipeline::create_pipeline_layout);
+// this module covers the rest: pools, allocation and writing the bindings.
+
+use anyhow::{Context, Result};
+use ash::vk;
+use super::VulkanDevice;
+
+/// Create a descriptor pool for `max_sets` sets holding the given descriptors
+///
+/// `sizes` are (descriptor type, total count across all sets).
+pub fn create_descriptor_pool(
+    device: &VulkanDevice,
+    max_sets: u32,
+    sizes: &[(vk::DescriptorType, u32)],
+) -> Result<vk::DescriptorPool> {
+    let pool_sizes: Vec<vk::DescriptorPoolSize> = sizes
+        .iter()
+        .map(|&(ty, descriptor_count)| vk::DescriptorPoolSize { ty, descriptor_count })
+        .collect();
+    
+    let pool_info = vk::DescriptorPoolCreateInfo::builder()
+        .max_sets(max_sets)
+        .pool_sizes(&pool_sizes);
+    
+    unsafe {
+        device.device.create_descriptor_pool(&pool_info, None)
+            .context("Failed to create descriptor pool")
+    }
+}
+
+/// Allocate one descriptor set per layout from `pool`
+pub fn allocate_descriptor_sets(
+    device: &VulkanDevice,
+    pool: vk::DescriptorPool,
+    layouts: &[vk::DescriptorSetLayout],
+) -> Result<Vec<vk::DescriptorSet>> {
+    let alloc_info = vk::DescriptorSetAllocateInfo::builder()
+        .descriptor_pool(pool)
+        .set_layouts(layouts);
+    
+    unsafe {
+        device.device.allocate_descriptor_sets(&alloc_info)
+            .context("Failed to allocate descriptor sets")
+    }
+}
+
+/// One pending write (the infos must outlive the vk::WriteDescriptorSet)
+enum Resource {
+    Buffer(vk::DescriptorBufferInfo),
+    Image(vk::DescriptorImageInfo),
+}
+
+/// Collects bindings for one descriptor set and writes them in one call
+///
+/// ```ignore
+/// DescriptorWriter::new()
+///     .storage_buffer(0, instances, 0, vk::WHOLE_SIZE)
+///     .storage_image(1, output_view)
+///     .write(device, set);
+/// ```
+#[derive(Default)]
+pub struct DescriptorWriter {
+    writes: Vec<(u32, vk::DescriptorType, Resource)>,
+}
+
+impl DescriptorWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Bind a buffer range as `buffer` (SSBO) in the shader
+    pub fn storage_buffer(
+        self,
+        binding: u32,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+    ) -> Self {
+        self.buffer(binding, vk::DescriptorType::STORAGE_BUFFER, buffer, offset, range)
+    }
+    
+    /// Bind a buffer range as `uniform` (UBO) in the shader
+    pub fn uniform_buffer(
+        self,
+        binding: u32,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+    ) -> Self {
+        self.buffer(binding, vk::DescriptorType::UNIFORM_BUFFER, buffer, offset, range)
+    }
+    
+    /// Bind an image view as `image2D` (read/write), expected in GENERAL layout
+    /// Used in: compute post effects
+    #[allow(dead_code)]
+    pub fn storage_image(mut self, binding: u32, view: vk::ImageView) -> Self {
+        self.writes.push((
+            binding,
+            vk::DescriptorType::STORAGE_IMAGE,
+            Resource::Image(vk::DescriptorImageInfo {
+                sampler: vk::Sampler::null(),
+                image_view: view,
+                image_layout: vk::ImageLayout::GENERAL,
+            }),
+        ));
+        self
+    }
+    
+    /// Bind an image view as `texture2D`, expected in SHADER_READ_ONLY_OPTIMAL
+    /// layout (see backend::texture)
+    pub fn sampled_image(mut self, binding: u32, view: vk::ImageView) -> Self {
+        self.writes.push((
+            binding,
+            vk::DescriptorType::SAMPLED_IMAGE,
+            Resource::Image(vk::DescriptorImageInfo {
+                sampler: vk::Sampler::null(),
+                image_view: view,
+                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            }),
+        ));
+        self
+    }
+    
+    /// Bind a `sampler` (combined with a texture in the shader)
+    pub fn sampler(mut self, binding: u32, sampler: vk::Sampler) -> Self {
+        self.writes.push((
+            binding,
+            vk::DescriptorType::SAMPLER,
+            Resource::Image(vk::DescriptorImageInfo {
+                sampler,
+                image_view: vk::ImageView::null(),
+                image_layout: vk::ImageLayout::UNDEFINED,
+            }),
+        ));
+        self
+    }
+    
+    fn buffer(
+        mut self,
+        binding: u32,
+        ty: vk::DescriptorType,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+    ) -> Self {
+        self.writes.push((
+            binding,
+            ty,
+            Resource::Buffer(vk::DescriptorBufferInfo { buffer, offset, range }),
+        ));
+        self
+    }
+    
+    /// Write all collected bindings into `set`
+    pub fn write(&self, device: &VulkanDevice, set: vk::DescriptorSet) {
+        let writes: Vec<vk::WriteDescriptorSet> = self
+            .writes
+            .iter()
+            .map(|(binding, ty, resource)| {
+                let write = vk::WriteDescriptorSet::builder()
+                    .dst_set(set)
+                    .dst_binding(*binding)
+                    .descriptor_type(*ty);
+                match resource {
+                    Resource::Buffer(info) => write.buffer_info(std::slice::from_ref(info)).build(),
+                    Resource::Image(info) => write.image_info(std::slice::from_ref(info)).build(),
+                }
+            })
+            .collect();
+        
+        unsafe {
+            device.device.update_descriptor_sets(&writes, &[]);
+        }
+    }
+}
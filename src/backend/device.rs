@@ -3,7 +3,7 @@
 // Responsibilities:
 // - Instance creation with validation layers
 // - Physical device selection (prefer discrete GPU)
-// - Logical device + queue creation
+// - Logical device + queue creation (graphics, plus async compute if available)
 // - Memory allocator setup
 
 use anyhow::{Context, Result};
@@ -12,10 +12,10 @@ use std::ffi::{CStr, CString};
 use std::sync::Arc;
 
 /// Required Vulkan device features for our renderer
+/// (`wide_lines` is added when the GPU has it, see `supports_wide_lines`)
 const REQUIRED_DEVICE_FEATURES: vk::PhysicalDeviceFeatures = vk::PhysicalDeviceFeatures {
     // Phase 1: Basic requirements
     fill_mode_non_solid: vk::TRUE,
-    wide_lines: vk::TRUE,
     sampler_anisotropy: vk::TRUE,
     
     // Phase 5+: Ray tracing requirements (will enable later)
@@ -43,12 +43,26 @@ pub struct VulkanDevice {
     pub graphics_queue: vk::Queue,
     pub graphics_queue_family: u32,
     
+    /// Dedicated compute queue (async compute) when the GPU has a compute-only
+    /// family, otherwise the graphics queue
+    /// Used in: instance animation (compute::AsyncCompute)
+    pub compute_queue: vk::Queue,
+    pub compute_queue_family: u32,
+    
+    /// `multiDrawIndirect` + Vulkan 1.2 `drawIndirectCount` are enabled,
+    /// so GPU culling can decide how many indirect draws to run
+    pub supports_indirect_count: bool,
+    
+    /// `wideLines` is enabled, so line pipelines may use widths other than 1.0
+    pub supports_wide_lines: bool,
+    
+    /// VK_EXT_memory_budget is enabled, so `memory_usage` can report heap usage
+    supports_memory_budget: bool,
+    
     // Debug utils (if validation enabled)
     debug_utils: Option<(ash::extensions::ext::DebugUtils, vk::DebugUtilsMessengerEXT)>,
     
     /// GPU properties (name, limits, etc.)
-    /// Used in: Phase 2+ for capability queries and optimization
-    #[allow(dead_code)]
     pub properties: vk::PhysicalDeviceProperties,
     
     /// Memory heap info for optimal allocation placement
@@ -81,12 +95,44 @@ impl VulkanDevice {
         };
         
         // Step 4: Pick physical device (GPU)
-        let (physical_device, graphics_queue_family) = 
+        let (physical_device, graphics_queue_family, async_compute_family) = 
             Self::pick_physical_device(&instance)?;
         
-        // Step 5: Create logical device
-        let (device, graphics_queue) = 
-            Self::create_logical_device(&instance, physical_device, graphics_queue_family)?;
+        // Step 5: Create logical device (with GPU-driven draws and wide lines when supported)
+        let supports_indirect_count = Self::check_indirect_count(&instance, physical_device);
+        let supports_wide_lines = unsafe {
+            instance.get_physical_device_features(physical_device).wide_lines == vk::TRUE
+        };
+        let supports_memory_budget = Self::check_extension(
+            &instance,
+            physical_device,
+            vk::ExtMemoryBudgetFn::name(),
+        );
+        let (device, graphics_queue, async_compute_queue) = Self::create_logical_device(
+            &instance,
+            physical_device,
+            graphics_queue_family,
+            async_compute_family,
+            supports_indirect_count,
+            supports_wide_lines,
+            supports_memory_budget,
+        )?;
+        log::info!("Indirect draw count: {}",
+            if supports_indirect_count { "supported" } else { "not supported" });
+        log::info!("Wide lines: {}",
+            if supports_wide_lines { "supported" } else { "not supported" });
+        
+        // Without a dedicated family, compute work shares the graphics queue
+        let (compute_queue, compute_queue_family) = match (async_compute_queue, async_compute_family) {
+            (Some(queue), Some(family)) => {
+                log::info!("Async compute: queue family {}", family);
+                (queue, family)
+            }
+            _ => {
+                log::info!("Async compute: not available, using the graphics queue");
+                (graphics_queue, graphics_queue_family)
+            }
+        };
         
         // Step 6: Cache device properties
         let properties = unsafe { 
@@ -115,6 +161,11 @@ impl VulkanDevice {
             _entry: entry,
             graphics_queue,
             graphics_queue_family,
+            compute_queue,
+            compute_queue_family,
+            supports_indirect_count,
+            supports_wide_lines,
+            supports_memory_budget,
             debug_utils,
             properties,
             memory_properties,
@@ -137,6 +188,7 @@ impl VulkanDevice {
             .api_version(vk::API_VERSION_1_3);
         
         // Required extensions
+        #[cfg_attr(not(target_os = "windows"), allow(unused_mut))]
         let mut extensions = vec![
             ash::extensions::ext::DebugUtils::name().as_ptr(), // Debug utils
         ];
@@ -191,9 +243,11 @@ impl VulkanDevice {
         Ok((debug_utils, messenger))
     }
     
+    /// Returns the device, its graphics queue family and, if it has one,
+    /// a compute-only queue family for async compute
     fn pick_physical_device(
         instance: &ash::Instance,
-    ) -> Result<(vk::PhysicalDevice, u32)> {
+    ) -> Result<(vk::PhysicalDevice, u32, Option<u32>)> {
         let devices = unsafe { instance.enumerate_physical_devices() }?;
         
         if devices.is_empty() {
@@ -218,10 +272,24 @@ impl VulkanDevice {
                 instance.get_physical_device_queue_family_properties(device)
             };
             
+            // The cull pass is recorded next to the draws (and so is instance
+            // animation without async compute), so the queue must do both
             let graphics_family = queue_families
                 .iter()
                 .enumerate()
-                .find(|(_, props)| props.queue_flags.contains(vk::QueueFlags::GRAPHICS))
+                .find(|(_, props)| {
+                    props.queue_flags.contains(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+                })
+                .map(|(i, _)| i as u32);
+            
+            // Compute without graphics = a separate hardware queue on most GPUs
+            let compute_family = queue_families
+                .iter()
+                .enumerate()
+                .find(|(_, props)| {
+                    props.queue_flags.contains(vk::QueueFlags::COMPUTE)
+                        && !props.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+                })
                 .map(|(i, _)| i as u32);
             
             if let Some(graphics_family) = graphics_family {
@@ -234,7 +302,7 @@ impl VulkanDevice {
                 
                 if score > best_score {
                     best_score = score;
-                    best_device = Some((device, graphics_family));
+                    best_device = Some((device, graphics_family, compute_family));
                 }
             }
         }
@@ -248,27 +316,68 @@ impl VulkanDevice {
             && features.sampler_anisotropy == vk::TRUE
     }
     
+    /// Optional features for GPU-driven rendering (indirect draws with a GPU-written count)
+    fn check_indirect_count(instance: &ash::Instance, physical_device: vk::PhysicalDevice) -> bool {
+        let mut vulkan12 = vk::PhysicalDeviceVulkan12Features::default();
+        let mut features = vk::PhysicalDeviceFeatures2::builder().push_next(&mut vulkan12);
+        unsafe { instance.get_physical_device_features2(physical_device, &mut features) };
+        
+        features.features.multi_draw_indirect == vk::TRUE
+            && vulkan12.draw_indirect_count == vk::TRUE
+    }
+    
+    /// Whether the GPU offers a device extension
+    fn check_extension(instance: &ash::Instance, physical_device: vk::PhysicalDevice, name: &CStr) -> bool {
+        let extensions = unsafe {
+            instance.enumerate_device_extension_properties(physical_device)
+        }.unwrap_or_default();
+        
+        extensions.iter().any(|ext| unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) } == name)
+    }
+    
     fn create_logical_device(
         instance: &ash::Instance,
         physical_device: vk::PhysicalDevice,
         graphics_queue_family: u32,
-    ) -> Result<(ash::Device, vk::Queue)> {
+        compute_queue_family: Option<u32>,
+        enable_indirect_count: bool,
+        enable_wide_lines: bool,
+        enable_memory_budget: bool,
+    ) -> Result<(ash::Device, vk::Queue, Option<vk::Queue>)> {
         let queue_priorities = [1.0];
-        let queue_create_info = vk::DeviceQueueCreateInfo::builder()
+        let mut queue_create_infos = vec![vk::DeviceQueueCreateInfo::builder()
             .queue_family_index(graphics_queue_family)
             .queue_priorities(&queue_priorities)
-            .build();
+            .build()];
+        if let Some(family) = compute_queue_family {
+            queue_create_infos.push(vk::DeviceQueueCreateInfo::builder()
+                .queue_family_index(family)
+                .queue_priorities(&queue_priorities)
+                .build());
+        }
         
         // Required device extensions
-        let extensions = vec![
+        let mut extensions = vec![
             ash::extensions::khr::Swapchain::name().as_ptr(),
             ash::extensions::khr::DynamicRendering::name().as_ptr(), // Vulkan 1.3 dynamic rendering
         ];
+        if enable_memory_budget {
+            extensions.push(vk::ExtMemoryBudgetFn::name().as_ptr());
+        }
+        
+        let features = vk::PhysicalDeviceFeatures {
+            multi_draw_indirect: enable_indirect_count as vk::Bool32,
+            wide_lines: enable_wide_lines as vk::Bool32,
+            ..REQUIRED_DEVICE_FEATURES
+        };
+        let mut vulkan12 = vk::PhysicalDeviceVulkan12Features::builder()
+            .draw_indirect_count(enable_indirect_count);
         
         let create_info = vk::DeviceCreateInfo::builder()
-            .queue_create_infos(std::slice::from_ref(&queue_create_info))
+            .queue_create_infos(&queue_create_infos)
             .enabled_extension_names(&extensions)
-            .enabled_features(&REQUIRED_DEVICE_FEATURES);
+            .enabled_features(&features)
+            .push_next(&mut vulkan12);
         
         let device = unsafe {
             instance.create_device(physical_device, &create_info, None)
@@ -277,8 +386,11 @@ impl VulkanDevice {
         let graphics_queue = unsafe {
             device.get_device_queue(graphics_queue_family, 0)
         };
+        let compute_queue = compute_queue_family.map(|family| unsafe {
+            device.get_device_queue(family, 0)
+        });
         
-        Ok((device, graphics_queue))
+        Ok((device, graphics_queue, compute_queue))
     }
     
     fn create_allocator(
@@ -298,6 +410,55 @@ impl VulkanDevice {
         Ok(allocator)
     }
     
+    /// True when compute work can run on its own queue, overlapping graphics
+    pub fn has_async_compute(&self) -> bool {
+        self.compute_queue_family != self.graphics_queue_family
+    }
+    
+    /// The closest line width the device can rasterize: 1.0 without
+    /// `wideLines`, otherwise clamped to the `lineWidthRange` limit
+    pub fn line_width(&self, requested: f32) -> f32 {
+        if !self.supports_wide_lines {
+            return 1.0;
+        }
+        let [min, max] = self.properties.limits.line_width_range;
+        requested.clamp(min, max)
+    }
+    
+    /// Device-local memory in use by this process and the budget the driver
+    /// suggests, in bytes (None without VK_EXT_memory_budget)
+    pub fn memory_usage(&self) -> Option<(u64, u64)> {
+        if !self.supports_memory_budget {
+            return None;
+        }
+        
+        let mut budget = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+        let mut properties = vk::PhysicalDeviceMemoryProperties2::builder().push_next(&mut budget);
+        unsafe {
+            self.instance.get_physical_device_memory_properties2(self.physical_device, &mut properties);
+        }
+        
+        let memory_properties = properties.memory_properties;
+        let heaps = &memory_properties.memory_heaps[..memory_properties.memory_heap_count as usize];
+        Some(heaps
+            .iter()
+            .enumerate()
+            .filter(|(_, heap)| heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+            .fold((0, 0), |(used, total), (i, _)| {
+                (used + budget.heap_usage[i], total + budget.heap_budget[i])
+            }))
+    }
+    
+    /// Distinct queue families that may touch a resource
+    /// (for `SharingMode::CONCURRENT` when async compute is in use)
+    pub fn queue_families(&self) -> Vec<u32> {
+        let mut families = vec![self.graphics_queue_family];
+        if self.compute_queue_family != self.graphics_queue_family {
+            families.push(self.compute_queue_family);
+        }
+        families
+    }
+    
     /// Wait for device to be idle (e.g., before cleanup)
     pub fn wait_idle(&self) -> Result<()> {
         unsafe { self.device.device_wait_idle() }?;
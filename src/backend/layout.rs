@@ -0,0 +1,182 @@
+// GPU data layout traits
+//
+// Implemented by the derives in `my-renderer-derive`:
+// - VertexLayout: vertex binding/attribute descriptions from a #[repr(C)] struct
+// - PushConstants: std430 byte layout for push-constant blocks
+//
+// The per-type building blocks (VertexAttribute, Std430) are implemented
+// here for the scalar, array and glam types we upload to the GPU.
+
+use ash::vk;
+
+pub use my_renderer_derive::{PushConstants, VertexLayout};
+
+/// Round `value` up to the next multiple of `align` (a power of two)
+pub const fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+// =============================================================================
+// VERTEX LAYOUTS
+// =============================================================================
+
+/// A type that can be read by the vertex shader as one attribute
+pub trait VertexAttribute {
+    /// Format when read as-is (floats, or integers as integers)
+    const FORMAT: vk::Format;
+    /// Format when read as normalized floats (`#[vertex(normalized)]`)
+    const NORMALIZED_FORMAT: Option<vk::Format> = None;
+    /// Consecutive locations used (matrices: one per column, each `FORMAT`)
+    const LOCATIONS: u32 = 1;
+}
+
+/// A vertex struct whose layout the pipeline can describe
+pub trait VertexLayout: Copy + Sized {
+    /// Attribute descriptions for this struct at `binding`, one location per field
+    fn attributes(binding: u32, first_location: u32) -> Vec<vk::VertexInputAttributeDescription>;
+    
+    /// Binding description with the struct size as stride
+    fn binding(binding: u32, input_rate: vk::VertexInputRate) -> vk::VertexInputBindingDescription {
+        vk::VertexInputBindingDescription {
+            binding,
+            stride: std::mem::size_of::<Self>() as u32,
+            input_rate,
+        }
+    }
+    
+    /// FNV-1a hash of the stride and every attribute's location, format and
+    /// offset, so vertex data saved to disk is never read back in another layout
+    fn fingerprint() -> u64 {
+        let mut words = vec![std::mem::size_of::<Self>() as u32];
+        for attribute in Self::attributes(0, 0) {
+            words.extend([attribute.location, attribute.format.as_raw() as u32, attribute.offset]);
+        }
+        words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+    }
+}
+
+macro_rules! vertex_attribute {
+    ($ty:ty => $format:ident) => {
+        impl VertexAttribute for $ty {
+            const FORMAT: vk::Format = vk::Format::$format;
+        }
+    };
+    ($ty:ty => $format:ident, $normalized:ident) => {
+        impl VertexAttribute for $ty {
+            const FORMAT: vk::Format = vk::Format::$format;
+            const NORMALIZED_FORMAT: Option<vk::Format> = Some(vk::Format::$normalized);
+        }
+    };
+}
+
+vertex_attribute!(f32 => R32_SFLOAT);
+vertex_attribute!([f32; 2] => R32G32_SFLOAT);
+vertex_attribute!([f32; 3] => R32G32B32_SFLOAT);
+vertex_attribute!([f32; 4] => R32G32B32A32_SFLOAT);
+vertex_attribute!(glam::Vec2 => R32G32_SFLOAT);
+vertex_attribute!(glam::Vec3 => R32G32B32_SFLOAT);
+vertex_attribute!(glam::Vec4 => R32G32B32A32_SFLOAT);
+
+/// Per-instance transforms: read as four `vec4` columns in the shader
+impl VertexAttribute for glam::Mat4 {
+    const FORMAT: vk::Format = vk::Format::R32G32B32A32_SFLOAT;
+    const LOCATIONS: u32 = 4;
+}
+
+vertex_attribute!(u32 => R32_UINT);
+vertex_attribute!([u32; 2] => R32G32_UINT);
+vertex_attribute!([u32; 3] => R32G32B32_UINT);
+vertex_attribute!([u32; 4] => R32G32B32A32_UINT);
+vertex_attribute!(i32 => R32_SINT);
+vertex_attribute!([i32; 2] => R32G32_SINT);
+vertex_attribute!([i32; 3] => R32G32B32_SINT);
+vertex_attribute!([i32; 4] => R32G32B32A32_SINT);
+
+// 8/16-bit types: 3-component variants are rarely supported as vertex formats
+vertex_attribute!([u8; 2] => R8G8_UINT, R8G8_UNORM);
+vertex_attribute!([u8; 4] => R8G8B8A8_UINT, R8G8B8A8_UNORM);
+vertex_attribute!([i8; 2] => R8G8_SINT, R8G8_SNORM);
+vertex_attribute!([i8; 4] => R8G8B8A8_SINT, R8G8B8A8_SNORM);
+vertex_attribute!(u16 => R16_UINT, R16_UNORM);
+vertex_attribute!([u16; 2] => R16G16_UINT, R16G16_UNORM);
+vertex_attribute!([u16; 4] => R16G16B16A16_UINT, R16G16B16A16_UNORM);
+vertex_attribute!(i16 => R16_SINT, R16_SNORM);
+vertex_attribute!([i16; 2] => R16G16_SINT, R16G16_SNORM);
+vertex_attribute!([i16; 4] => R16G16B16A16_SINT, R16G16B16A16_SNORM);
+
+// =============================================================================
+// PUSH CONSTANTS (std430)
+// =============================================================================
+
+/// A type with a known std430 layout
+pub trait Std430 {
+    /// Base alignment in bytes
+    const ALIGN: usize;
+    /// Size in bytes (without trailing padding for vec3)
+    const SIZE: usize;
+    
+    /// Write the std430 bytes into `out` (exactly `SIZE` bytes long)
+    fn write_std430(&self, out: &mut [u8]);
+}
+
+/// A push-constant block, generated by `#[derive(PushConstants)]`
+pub trait PushConstants: Std430 {
+    /// Serialize into a fixed buffer, e.g. `[0u8; MyConstants::SIZE]`
+    fn write_bytes(&self, out: &mut [u8]) {
+        self.write_std430(&mut out[..Self::SIZE]);
+    }
+}
+
+macro_rules! std430_scalar {
+    ($($ty:ty),*) => {$(
+        impl Std430 for $ty {
+            const ALIGN: usize = 4;
+            const SIZE: usize = 4;
+            
+            fn write_std430(&self, out: &mut [u8]) {
+                out.copy_from_slice(&self.to_ne_bytes());
+            }
+        }
+    )*};
+}
+
+std430_scalar!(f32, u32, i32);
+
+macro_rules! std430_floats {
+    ($($ty:ty => align $align:expr, size $size:expr, $to_array:ident),* $(,)?) => {$(
+        impl Std430 for $ty {
+            const ALIGN: usize = $align;
+            const SIZE: usize = $size;
+            
+            fn write_std430(&self, out: &mut [u8]) {
+                let floats = self.$to_array();
+                for (chunk, value) in out.chunks_exact_mut(4).zip(floats) {
+                    chunk.copy_from_slice(&value.to_ne_bytes());
+                }
+            }
+        }
+    )*};
+}
+
+std430_floats!(
+    glam::Vec2 => align 8, size 8, to_array,
+    glam::Vec3 => align 16, size 12, to_array,
+    glam::Vec4 => align 16, size 16, to_array,
+    glam::Mat4 => align 16, size 64, to_cols_array,
+);
+
+/// Arrays: std430 element stride is the element size rounded to its alignment
+impl<T: Std430, const N: usize> Std430 for [T; N] {
+    const ALIGN: usize = T::ALIGN;
+    const SIZE: usize = align_up(T::SIZE, T::ALIGN) * N;
+    
+    fn write_std430(&self, out: &mut [u8]) {
+        let stride = align_up(T::SIZE, T::ALIGN);
+        for (i, element) in self.iter().enumerate() {
+            element.write_std430(&mut out[i * stride..i * stride + T::SIZE]);
+        }
+    }
+}
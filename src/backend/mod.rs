@@ -7,8 +7,17 @@ pub mod device;
 pub mod swapchain;
 pub mod sync;
 pub mod shader;
+pub mod reflect;
+pub mod permutation;
+pub mod layout;
 pub mod buffer;
+pub mod texture;
 pub mod pipeline;
+pub mod descriptor;
+pub mod compute;
+pub mod culling;
+pub mod query;
+pub mod pacing;
 
 pub use device::VulkanDevice;
 pub use swapchain::Swapchain;
@@ -0,0 +1,135 @@
+// Frame pacing
+//
+// Decides when the next frame may start. An fps limit spaces frame starts
+// evenly: the event loop sleeps (still handling input) until shortly before
+// the deadline, since OS sleeps overshoot by a millisecond or so, and spins
+// the rest, so frames start within microseconds of their slot. Low-latency
+// mode also holds a frame back until just before the GPU is expected to
+// finish the frames already queued, so input is sampled as late as possible
+// instead of waiting in the queue behind them. The time from the first input
+// event a frame sees to its present is averaged for the HUD.
+
+use std::time::{Duration, Instant};
+
+/// Sleeps end this long before the deadline; the rest is spun
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// Low-latency mode starts this much earlier than the estimate says
+const LATENCY_SLACK: Duration = Duration::from_micros(500);
+
+/// Weight of a new sample in the smoothed timings
+const SMOOTHING: f32 = 0.1;
+
+pub struct FramePacer {
+    /// Time between frame starts (None = unlimited)
+    interval: Option<Duration>,
+    low_latency: bool,
+    /// Earliest start of the next frame allowed by the fps limit
+    next_start: Instant,
+    /// When the GPU should be done with everything submitted so far
+    gpu_free: Instant,
+    /// Start of the frame being built
+    frame_start: Instant,
+    /// CPU time from frame start to submit, smoothed
+    cpu_ms: f32,
+    /// Oldest input event no frame has seen yet
+    pending_input: Option<Instant>,
+    /// Oldest input event seen by the frame being built
+    frame_input: Option<Instant>,
+    /// Input-to-present latency, smoothed (None before the first input)
+    latency_ms: Option<f32>,
+}
+
+impl FramePacer {
+    pub fn new(fps_limit: u32, low_latency: bool) -> Self {
+        let now = Instant::now();
+        let mut pacer = Self {
+            interval: None,
+            low_latency: false,
+            next_start: now,
+            gpu_free: now,
+            frame_start: now,
+            cpu_ms: 0.0,
+            pending_input: None,
+            frame_input: None,
+            latency_ms: None,
+        };
+        pacer.configure(fps_limit, low_latency);
+        pacer
+    }
+    
+    /// Change the limit (0 = unlimited) and the low-latency mode
+    pub fn configure(&mut self, fps_limit: u32, low_latency: bool) {
+        self.interval = (fps_limit > 0).then(|| Duration::from_secs_f64(1.0 / fps_limit as f64));
+        self.low_latency = low_latency;
+    }
+    
+    /// Earliest time the next frame should start
+    fn deadline(&self) -> Instant {
+        if !self.low_latency {
+            return self.next_start;
+        }
+        let cpu = Duration::from_secs_f32(self.cpu_ms / 1000.0) + LATENCY_SLACK;
+        let ready = self.gpu_free.checked_sub(cpu).unwrap_or(self.gpu_free);
+        self.next_start.max(ready)
+    }
+    
+    /// Spin until the next frame may start if that is close, and return
+    /// None; otherwise return when to wake up and ask again
+    pub fn wait(&self) -> Option<Instant> {
+        let deadline = self.deadline();
+        if deadline > Instant::now() + SPIN_MARGIN {
+            return Some(deadline - SPIN_MARGIN);
+        }
+        while Instant::now() < deadline {
+            std::hint::spin_loop();
+        }
+        None
+    }
+    
+    /// An input event arrived; the next frame to start will see it
+    pub fn input(&mut self) {
+        self.pending_input.get_or_insert_with(Instant::now);
+    }
+    
+    /// CPU work on a frame starts: schedule the next one and claim the
+    /// input that arrived since the last
+    pub fn begin_frame(&mut self) {
+        let now = Instant::now();
+        self.frame_start = now;
+        self.frame_input = self.pending_input.take();
+        if let Some(interval) = self.interval {
+            // Keep the cadence after a slightly late frame, but don't rush
+            // to catch up after a long stall
+            self.next_start += interval;
+            if self.next_start < now {
+                self.next_start = now + interval;
+            }
+        }
+    }
+    
+    /// The frame went to the GPU, which is expected to take `gpu_ms` for it
+    /// once it's done with the frames before (None without GPU timings, when
+    /// low-latency mode can't hold frames back)
+    pub fn submitted(&mut self, gpu_ms: Option<f32>) {
+        let now = Instant::now();
+        let cpu_ms = (now - self.frame_start).as_secs_f32() * 1000.0;
+        self.cpu_ms += (cpu_ms - self.cpu_ms) * SMOOTHING;
+        self.gpu_free = self.gpu_free.max(now) + Duration::from_secs_f32(gpu_ms.unwrap_or(0.0) / 1000.0);
+    }
+    
+    /// The frame was handed to the presentation engine
+    pub fn presented(&mut self) {
+        if let Some(input) = self.frame_input.take() {
+            let sample = input.elapsed().as_secs_f32() * 1000.0;
+            let average = self.latency_ms.get_or_insert(sample);
+            *average += (sample - *average) * SMOOTHING;
+        }
+    }
+    
+    /// Smoothed milliseconds from an input event to the present of the first
+    /// frame that saw it
+    pub fn latency_ms(&self) -> Option<f32> {
+        self.latency_ms
+    }
+}
@@ -0,0 +1,182 @@
+// Shader permutations and specialization constants
+//
+// Two ways to get variants of one shader:
+// - Keywords: a GLSL/HLSL shader declares `#pragma keywords TEXTURED SHADOWS`
+//   and build.rs compiles every combination (each enabled keyword is #defined).
+//   Use these for features that change inputs, bindings or a lot of code.
+// - Specialization constants: values baked in at pipeline creation through
+//   `PipelineShaderStageCreateInfo`, without another SPIR-V module.
+//   Use these for cheap toggles and tuning values.
+//
+// naga can't emit specialization constants, so GLSL declares them with
+// `#pragma spec_constant NAME ID DEFAULT` and build.rs patches the SPIR-V
+// (uint constants only); HLSL can use `[[vk::constant_id(N)]]` via DXC.
+
+use anyhow::{Context, Result};
+use ash::vk;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use super::reflect::ShaderReflection;
+use super::shader::{self, Shader};
+use super::VulkanDevice;
+
+/// Every keyword combination of one shader, embedded at build time
+pub struct ShaderPermutations {
+    /// Source path relative to shaders/ ("cube.frag")
+    pub name: &'static str,
+    /// Declared keywords; bit N of a mask enables `keywords[N]`
+    pub keywords: &'static [&'static str],
+    /// SPIR-V per keyword mask (index 0 = no keywords)
+    pub variants: &'static [&'static [u8]],
+}
+
+include!(concat!(env!("OUT_DIR"), "/shader_permutations.rs"));
+
+/// Look up a compiled shader by its source name
+pub fn find(name: &str) -> Result<&'static ShaderPermutations> {
+    SHADER_PERMUTATIONS
+        .iter()
+        .find(|p| p.name == name)
+        .with_context(|| format!("No compiled shader named \"{}\"", name))
+}
+
+impl ShaderPermutations {
+    /// Keyword mask for a set of enabled keywords (unknown keywords are an error)
+    pub fn mask<S: AsRef<str>>(&self, enabled: &[S]) -> Result<u32> {
+        let mut mask = 0;
+        for keyword in enabled {
+            let keyword = keyword.as_ref();
+            let bit = self.keywords.iter().position(|k| *k == keyword).with_context(|| {
+                format!(
+                    "Shader \"{}\" has no keyword {} (declared: {:?})",
+                    self.name, keyword, self.keywords
+                )
+            })?;
+            mask |= 1 << bit;
+        }
+        Ok(mask)
+    }
+    
+    /// SPIR-V for one keyword mask
+    pub fn variant(&self, mask: u32) -> Result<&'static [u8]> {
+        self.variants
+            .get(mask as usize)
+            .copied()
+            .with_context(|| format!("Keyword mask {:#b} out of range for \"{}\"", mask, self.name))
+    }
+}
+
+// =============================================================================
+// VARIANT CACHE
+// =============================================================================
+
+/// Shader modules created on first use, keyed by (shader, keyword mask)
+///
+/// Materials or draws ask for the keywords they need; each combination is
+/// turned into a `vk::ShaderModule` once and reused afterwards.
+#[derive(Default)]
+pub struct ShaderVariantCache {
+    shaders: HashMap<(&'static str, u32), Shader>,
+}
+
+impl ShaderVariantCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Get (creating if needed) the variant of `name` with `keywords` enabled
+    pub fn get<S: AsRef<str>>(
+        &mut self,
+        device: &VulkanDevice,
+        name: &str,
+        keywords: &[S],
+    ) -> Result<&Shader> {
+        let permutations = find(name)?;
+        let mask = permutations.mask(keywords)?;
+        
+        let shader = match self.shaders.entry((permutations.name, mask)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let shader = shader::create_shader(device, permutations.variant(mask)?)
+                    .with_context(|| format!("Failed to create shader variant \"{}\" {:#b}", name, mask))?;
+                log::debug!("Created shader variant {} {:?}", name,
+                    keywords.iter().map(|k| k.as_ref()).collect::<Vec<_>>());
+                entry.insert(shader)
+            }
+        };
+        
+        Ok(shader)
+    }
+    
+    /// Destroy every cached module (pipelines created from them stay valid)
+    pub fn destroy(&mut self, device: &ash::Device) {
+        for (_, shader) in self.shaders.drain() {
+            shader.destroy(device);
+        }
+    }
+}
+
+// =============================================================================
+// SPECIALIZATION CONSTANTS
+// =============================================================================
+
+/// Specialization constant values for one pipeline
+///
+/// Entries for IDs a shader doesn't declare are ignored by Vulkan, so one set
+/// can be shared by every stage of a pipeline.
+#[derive(Debug, Clone, Default)]
+pub struct SpecializationConstants {
+    entries: Vec<vk::SpecializationMapEntry>,
+    data: Vec<u8>,
+}
+
+impl SpecializationConstants {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Set a `uint` constant
+    pub fn with_u32(self, id: u32, value: u32) -> Self {
+        self.with_bytes(id, &value.to_ne_bytes())
+    }
+    
+    /// Set constant `id` to raw bytes, replacing an earlier value
+    fn with_bytes(mut self, id: u32, bytes: &[u8]) -> Self {
+        self.entries.retain(|e| e.constant_id != id);
+        self.entries.push(vk::SpecializationMapEntry {
+            constant_id: id,
+            offset: self.data.len() as u32,
+            size: bytes.len(),
+        });
+        self.data.extend_from_slice(bytes);
+        self
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    
+    /// Check the value sizes against what a shader declares
+    pub fn validate(&self, reflection: &ShaderReflection) -> Result<()> {
+        for entry in &self.entries {
+            let Some(declared) = reflection.spec_constants.iter().find(|c| c.id == entry.constant_id) else {
+                continue;
+            };
+            if declared.size as usize != entry.size {
+                anyhow::bail!(
+                    "Specialization constant {} is {} bytes in the {:?} shader but {} bytes were given",
+                    entry.constant_id, declared.size, reflection.stage, entry.size
+                );
+            }
+        }
+        Ok(())
+    }
+    
+    /// `vk::SpecializationInfo` borrowing these values
+    pub fn info(&self) -> vk::SpecializationInfo {
+        vk::SpecializationInfo::builder()
+            .map_entries(&self.entries)
+            .data(&self.data)
+            .build()
+    }
+}
@@ -1,10 +1,15 @@
-// Graphics pipeline creation and management
+// Graphics and compute pipeline creation and management
 //
 // The graphics pipeline defines how vertices are processed and rasterized.
 // It includes: vertex input, shaders, rasterization, depth/stencil, blending.
+// Compute pipelines are just a compute shader plus its layout.
 
 use anyhow::{Context, Result};
 use ash::vk;
+use super::layout::VertexLayout;
+use super::permutation::SpecializationConstants;
+use super::reflect;
+use super::shader::Shader;
 use super::VulkanDevice;
 
 /// Create a render pass for basic color attachment rendering with depth
@@ -102,79 +107,234 @@ pub fn create_framebuffers(
     }).collect()
 }
 
-/// Vertex input description for our cube vertices (position + color)
-pub fn get_vertex_input_info() -> (
-    Vec<vk::VertexInputBindingDescription>,
-    Vec<vk::VertexInputAttributeDescription>,
-) {
-    // One binding for interleaved position + normal + color data
-    let binding = vk::VertexInputBindingDescription::builder()
-        .binding(0)
-        .stride((9 * std::mem::size_of::<f32>()) as u32) // 3 pos + 3 normal + 3 color
-        .input_rate(vk::VertexInputRate::VERTEX)
-        .build();
+/// Vertex buffer bindings and attributes for a pipeline
+pub struct VertexInputInfo {
+    pub bindings: Vec<vk::VertexInputBindingDescription>,
+    pub attributes: Vec<vk::VertexInputAttributeDescription>,
+    /// Rust types the layout came from (for error messages)
+    pub description: String,
+}
+
+/// Vertex input description for a vertex type (one interleaved binding)
+///
+/// Stride, offsets and formats come from `#[derive(VertexLayout)]` on `V`.
+pub fn get_vertex_input_info<V: VertexLayout>() -> VertexInputInfo {
+    let binding = V::binding(0, vk::VertexInputRate::VERTEX);
+    let attributes = V::attributes(0, 0);
+    
+    VertexInputInfo {
+        bindings: vec![binding],
+        attributes,
+        description: std::any::type_name::<V>().to_string(),
+    }
+}
+
+/// Vertex input for instanced drawing: per-vertex `V` at binding 0 and
+/// per-instance `I` at binding 1 (`VertexInputRate::INSTANCE`)
+///
+/// Instance attributes start at the first location after the vertex ones.
+pub fn get_instanced_vertex_input_info<V: VertexLayout, I: VertexLayout>() -> VertexInputInfo {
+    let mut attributes = V::attributes(0, 0);
+    attributes.extend(I::attributes(1, attributes.len() as u32));
+    
+    VertexInputInfo {
+        bindings: vec![
+            V::binding(0, vk::VertexInputRate::VERTEX),
+            I::binding(1, vk::VertexInputRate::INSTANCE),
+        ],
+        attributes,
+        description: format!(
+            "{} + {} (per instance)",
+            std::any::type_name::<V>(),
+            std::any::type_name::<I>()
+        ),
+    }
+}
+
+/// Create a pipeline layout (and its descriptor set layouts) from shader reflection
+pub fn create_pipeline_layout(
+    device: &VulkanDevice,
+    shaders: &[&Shader],
+) -> Result<(vk::PipelineLayout, Vec<vk::DescriptorSetLayout>)> {
+    let reflections: Vec<_> = shaders.iter().map(|s| &s.reflection).collect();
+    let layout = reflect::merge_layout(&reflections)?;
+    
+    // One descriptor set layout per set index (gaps get an empty layout)
+    let set_layouts = layout.set_bindings.iter().map(|bindings| {
+        let set_layout_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(bindings);
+        
+        unsafe {
+            device.device.create_descriptor_set_layout(&set_layout_info, None)
+                .context("Failed to create descriptor set layout")
+        }
+    }).collect::<Result<Vec<_>>>()?;
     
-    // Position attribute (location 0)
-    let position_attr = vk::VertexInputAttributeDescription::builder()
-        .binding(0)
-        .location(0)
-        .format(vk::Format::R32G32B32_SFLOAT)
-        .offset(0)
-        .build();
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(&set_layouts)
+        .push_constant_ranges(&layout.push_constant_ranges);
     
-    // Normal attribute (location 1)
-    let normal_attr = vk::VertexInputAttributeDescription::builder()
-        .binding(0)
-        .location(1)
-        .format(vk::Format::R32G32B32_SFLOAT)
-        .offset(12) // After 3 floats
-        .build();
+    let pipeline_layout = unsafe {
+        device.device.create_pipeline_layout(&layout_info, None)
+            .context("Failed to create pipeline layout")?
+    };
     
-    // Color attribute (location 2)
-    let color_attr = vk::VertexInputAttributeDescription::builder()
-        .binding(0)
-        .location(2)
-        .format(vk::Format::R32G32B32_SFLOAT)
-        .offset(24) // After 6 floats
-        .build();
+    Ok((pipeline_layout, set_layouts))
+}
+
+/// How a pipeline's color output is combined with the framebuffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Overwrite
+    Opaque,
+    /// `dst + src` (e.g. overdraw counting)
+    Additive,
+    /// `src * src.a + dst * (1 - src.a)` (text, UI, sprites)
+    Alpha,
+    /// `src + dst * (1 - src.a)`, for colors already multiplied by alpha (egui)
+    Premultiplied,
+}
+
+/// Fixed-function state of a graphics pipeline
+///
+/// The default is opaque, depth-tested, back-face culled triangles (the cube);
+/// debug views and overlays change a few fields.
+#[derive(Debug, Clone, Copy)]
+pub struct PipelineState {
+    pub topology: vk::PrimitiveTopology,
+    pub polygon_mode: vk::PolygonMode,
+    pub cull_mode: vk::CullModeFlags,
+    /// Needs the `wide_lines` feature for anything but 1.0
+    pub line_width: f32,
+    pub depth_test: bool,
+    pub depth_write: bool,
+    pub depth_compare: vk::CompareOp,
+    /// (constant factor, slope factor); negative values pull toward the camera
+    pub depth_bias: Option<(f32, f32)>,
+    pub blend: BlendMode,
+    /// Scissor set with `cmd_set_scissor` before drawing (clipped UI)
+    pub dynamic_scissor: bool,
+}
+
+impl Default for PipelineState {
+    fn default() -> Self {
+        Self {
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            polygon_mode: vk::PolygonMode::FILL,
+            cull_mode: vk::CullModeFlags::BACK,
+            line_width: 1.0,
+            depth_test: true,
+            depth_write: true,
+            depth_compare: vk::CompareOp::LESS, // Closer objects win
+            depth_bias: None,
+            blend: BlendMode::Opaque,
+            dynamic_scissor: false,
+        }
+    }
+}
+
+/// A graphics pipeline with the layout it was created with
+pub struct GraphicsPipeline {
+    pub pipeline: vk::Pipeline,
+    pub layout: vk::PipelineLayout,
+    pub set_layouts: Vec<vk::DescriptorSetLayout>,
+}
+
+impl GraphicsPipeline {
+    /// See `create_graphics_pipeline`
+    pub fn new(
+        device: &VulkanDevice,
+        render_pass: vk::RenderPass,
+        extent: vk::Extent2D,
+        vertex_input: &VertexInputInfo,
+        shaders: (&Shader, &Shader),
+        specialization: &SpecializationConstants,
+        state: &PipelineState,
+    ) -> Result<Self> {
+        let (pipeline, layout, set_layouts) = create_graphics_pipeline(
+            device,
+            render_pass,
+            extent,
+            vertex_input,
+            shaders.0,
+            shaders.1,
+            specialization,
+            state,
+        )?;
+        
+        Ok(Self { pipeline, layout, set_layouts })
+    }
     
-    (vec![binding], vec![position_attr, normal_attr, color_attr])
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_pipeline(self.pipeline, None);
+            device.destroy_pipeline_layout(self.layout, None);
+            for &set_layout in &self.set_layouts {
+                device.destroy_descriptor_set_layout(set_layout, None);
+            }
+        }
+    }
 }
 
-/// Create a basic graphics pipeline for rendering the cube
+/// Create a graphics pipeline
+///
+/// The pipeline layout is derived from the shaders' reflection data, and the
+/// vertex layout (from `get_vertex_input_info` or
+/// `get_instanced_vertex_input_info`) is checked against the vertex shader's
+/// inputs before anything is created. `specialization` is applied to both
+/// stages (each stage only picks up the constant IDs it declares).
+#[allow(clippy::too_many_arguments)]
 pub fn create_graphics_pipeline(
     device: &VulkanDevice,
     render_pass: vk::RenderPass,
     extent: vk::Extent2D,
-    vert_shader: vk::ShaderModule,
-    frag_shader: vk::ShaderModule,
-) -> Result<(vk::Pipeline, vk::PipelineLayout)> {
+    vertex_input: &VertexInputInfo,
+    vert_shader: &Shader,
+    frag_shader: &Shader,
+    specialization: &SpecializationConstants,
+    state: &PipelineState,
+) -> Result<(vk::Pipeline, vk::PipelineLayout, Vec<vk::DescriptorSetLayout>)> {
+    // Vertex input (validated against what the vertex shader actually reads)
+    vert_shader.reflection.validate_vertex_input(&vertex_input.attributes)
+        .with_context(|| format!(
+            "Vertex shader does not match the Rust `{}` layout",
+            vertex_input.description
+        ))?;
+    
     // Shader stages
-    let entry_point = std::ffi::CString::new("main").unwrap();
+    specialization.validate(&vert_shader.reflection)?;
+    specialization.validate(&frag_shader.reflection)?;
+    let specialization_info = specialization.info();
+    
+    let vert_entry = std::ffi::CString::new(vert_shader.reflection.entry_point.as_str())?;
+    let frag_entry = std::ffi::CString::new(frag_shader.reflection.entry_point.as_str())?;
     
-    let vert_stage = vk::PipelineShaderStageCreateInfo::builder()
+    let mut vert_stage = vk::PipelineShaderStageCreateInfo::builder()
         .stage(vk::ShaderStageFlags::VERTEX)
-        .module(vert_shader)
-        .name(&entry_point)
+        .module(vert_shader.module)
+        .name(&vert_entry)
         .build();
     
-    let frag_stage = vk::PipelineShaderStageCreateInfo::builder()
+    let mut frag_stage = vk::PipelineShaderStageCreateInfo::builder()
         .stage(vk::ShaderStageFlags::FRAGMENT)
-        .module(frag_shader)
-        .name(&entry_point)
+        .module(frag_shader.module)
+        .name(&frag_entry)
         .build();
     
+    if !specialization.is_empty() {
+        vert_stage.p_specialization_info = &specialization_info;
+        frag_stage.p_specialization_info = &specialization_info;
+    }
+    
     let shader_stages = &[vert_stage, frag_stage];
     
-    // Vertex input
-    let (bindings, attributes) = get_vertex_input_info();
     let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::builder()
-        .vertex_binding_descriptions(&bindings)
-        .vertex_attribute_descriptions(&attributes);
+        .vertex_binding_descriptions(&vertex_input.bindings)
+        .vertex_attribute_descriptions(&vertex_input.attributes);
     
     // Input assembly
     let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::builder()
-        .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .topology(state.topology)
         .primitive_restart_enable(false);
     
     // Viewport and scissor
@@ -199,14 +359,17 @@ pub fn create_graphics_pipeline(
         .scissors(scissors);
     
     // Rasterization
+    let (bias_constant, bias_slope) = state.depth_bias.unwrap_or((0.0, 0.0));
     let rasterizer = vk::PipelineRasterizationStateCreateInfo::builder()
         .depth_clamp_enable(false)
         .rasterizer_discard_enable(false)
-        .polygon_mode(vk::PolygonMode::FILL)
-        .line_width(1.0)
-        .cull_mode(vk::CullModeFlags::BACK)
+        .polygon_mode(state.polygon_mode)
+        .line_width(state.line_width)
+        .cull_mode(state.cull_mode)
         .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
-        .depth_bias_enable(false);
+        .depth_bias_enable(state.depth_bias.is_some())
+        .depth_bias_constant_factor(bias_constant)
+        .depth_bias_slope_factor(bias_slope);
     
     // Multisampling (disabled)
     let multisampling = vk::PipelineMultisampleStateCreateInfo::builder()
@@ -215,40 +378,66 @@ pub fn create_graphics_pipeline(
     
     // Depth testing - ESSENTIAL for correct 3D rendering!
     let depth_stencil = vk::PipelineDepthStencilStateCreateInfo::builder()
-        .depth_test_enable(true)
-        .depth_write_enable(true)
-        .depth_compare_op(vk::CompareOp::LESS) // Closer objects win
+        .depth_test_enable(state.depth_test)
+        .depth_write_enable(state.depth_write)
+        .depth_compare_op(state.depth_compare)
         .depth_bounds_test_enable(false)
         .stencil_test_enable(false);
     
-    // Color blending (no blending, opaque)
-    let color_blend_attachment = vk::PipelineColorBlendAttachmentState::builder()
-        .color_write_mask(vk::ColorComponentFlags::RGBA)
-        .blend_enable(false)
-        .build();
+    // Color blending
+    let color_blend_attachment = match state.blend {
+        BlendMode::Opaque => vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(vk::ColorComponentFlags::RGBA)
+            .blend_enable(false)
+            .build(),
+        BlendMode::Additive => vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(vk::ColorComponentFlags::RGBA)
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::ONE)
+            .dst_color_blend_factor(vk::BlendFactor::ONE)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ONE)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .build(),
+        BlendMode::Alpha => vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(vk::ColorComponentFlags::RGBA)
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ONE)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .build(),
+        BlendMode::Premultiplied => vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(vk::ColorComponentFlags::RGBA)
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::ONE)
+            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_DST_ALPHA)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .build(),
+    };
     
     let color_blend_attachments = &[color_blend_attachment];
     let color_blending = vk::PipelineColorBlendStateCreateInfo::builder()
         .logic_op_enable(false)
         .attachments(color_blend_attachments);
     
-    // Push constant for MVP matrix and model matrix (128 bytes for 2x 4x4 matrices)
-    let push_constant_range = vk::PushConstantRange::builder()
-        .stage_flags(vk::ShaderStageFlags::VERTEX)
-        .offset(0)
-        .size(128)
-        .build();
-    
-    let push_constant_ranges = &[push_constant_range];
-    
-    // Pipeline layout
-    let layout_info = vk::PipelineLayoutCreateInfo::builder()
-        .push_constant_ranges(push_constant_ranges);
-    
-    let pipeline_layout = unsafe {
-        device.device.create_pipeline_layout(&layout_info, None)
-            .context("Failed to create pipeline layout")?
+    // The static scissor above is ignored when it is dynamic
+    let dynamic_states: &[vk::DynamicState] = if state.dynamic_scissor {
+        &[vk::DynamicState::SCISSOR]
+    } else {
+        &[]
     };
+    let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
+        .dynamic_states(dynamic_states);
+    
+    // Pipeline layout: push constants and descriptor sets come from reflection
+    let (pipeline_layout, set_layouts) = create_pipeline_layout(device, &[vert_shader, frag_shader])?;
     
     // Create pipeline
     let pipeline_info = vk::GraphicsPipelineCreateInfo::builder()
@@ -260,6 +449,7 @@ pub fn create_graphics_pipeline(
         .multisample_state(&multisampling)
         .depth_stencil_state(&depth_stencil)
         .color_blend_state(&color_blending)
+        .dynamic_state(&dynamic_state)
         .layout(pipeline_layout)
         .render_pass(render_pass)
         .subpass(0)
@@ -274,5 +464,50 @@ pub fn create_graphics_pipeline(
             .context("Failed to create graphics pipeline")?
     };
     
-    Ok((pipelines[0], pipeline_layout))
+    Ok((pipelines[0], pipeline_layout, set_layouts))
+}
+
+/// Create a compute pipeline from a compute shader
+///
+/// Like the graphics pipeline, the layout (storage buffers/images, push
+/// constants) is derived from reflection.
+pub fn create_compute_pipeline(
+    device: &VulkanDevice,
+    shader: &Shader,
+    specialization: &SpecializationConstants,
+) -> Result<(vk::Pipeline, vk::PipelineLayout, Vec<vk::DescriptorSetLayout>)> {
+    if shader.reflection.stage != vk::ShaderStageFlags::COMPUTE {
+        anyhow::bail!("Expected a compute shader, got {:?}", shader.reflection.stage);
+    }
+    
+    specialization.validate(&shader.reflection)?;
+    let specialization_info = specialization.info();
+    
+    let entry = std::ffi::CString::new(shader.reflection.entry_point.as_str())?;
+    let mut stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::COMPUTE)
+        .module(shader.module)
+        .name(&entry)
+        .build();
+    if !specialization.is_empty() {
+        stage.p_specialization_info = &specialization_info;
+    }
+    
+    let (pipeline_layout, set_layouts) = create_pipeline_layout(device, &[shader])?;
+    
+    let pipeline_info = vk::ComputePipelineCreateInfo::builder()
+        .stage(stage)
+        .layout(pipeline_layout)
+        .build();
+    
+    let pipelines = unsafe {
+        device.device.create_compute_pipelines(
+            vk::PipelineCache::null(),
+            &[pipeline_info],
+            None,
+        ).map_err(|(_, e)| e)
+            .context("Failed to create compute pipeline")?
+    };
+    
+    Ok((pipelines[0], pipeline_layout, set_layouts))
 }
@@ -0,0 +1,94 @@
+// GPU queries
+//
+// `GpuTimer` measures how long the GPU spends on a frame with two timestamps
+// per frame in flight: one written when the command buffer starts, one when
+// everything before it has finished. Results are read back once the frame's
+// fence has signaled, so reading never stalls.
+
+use anyhow::{Context, Result};
+use ash::vk;
+use super::VulkanDevice;
+
+/// Per-frame GPU time from timestamp queries
+pub struct GpuTimer {
+    pool: vk::QueryPool,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+    /// Frames whose queries have been written at least once (unwritten
+    /// queries must not be read)
+    written: Vec<bool>,
+}
+
+impl GpuTimer {
+    /// None when the graphics queue can't write timestamps
+    pub fn new(device: &VulkanDevice, frames_in_flight: usize) -> Result<Option<Self>> {
+        let limits = &device.properties.limits;
+        if limits.timestamp_compute_and_graphics == vk::FALSE {
+            log::info!("GPU timing: timestamps not supported");
+            return Ok(None);
+        }
+        
+        let pool_info = vk::QueryPoolCreateInfo::builder()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(2 * frames_in_flight as u32);
+        let pool = unsafe {
+            device.device.create_query_pool(&pool_info, None)
+                .context("Failed to create timestamp query pool")?
+        };
+        
+        Ok(Some(Self {
+            pool,
+            period: limits.timestamp_period,
+            written: vec![false; frames_in_flight],
+        }))
+    }
+    
+    /// Start timing frame-in-flight `frame` (first command, outside a render pass)
+    pub fn cmd_begin(&self, device: &ash::Device, cmd: vk::CommandBuffer, frame: usize) {
+        let first = 2 * frame as u32;
+        unsafe {
+            device.cmd_reset_query_pool(cmd, self.pool, first, 2);
+            device.cmd_write_timestamp(cmd, vk::PipelineStageFlags::TOP_OF_PIPE, self.pool, first);
+        }
+    }
+    
+    /// Stop timing (last command)
+    pub fn cmd_end(&self, device: &ash::Device, cmd: vk::CommandBuffer, frame: usize) {
+        unsafe {
+            device.cmd_write_timestamp(cmd, vk::PipelineStageFlags::BOTTOM_OF_PIPE, self.pool, 2 * frame as u32 + 1);
+        }
+    }
+    
+    /// Mark `frame` as submitted so its results can be read once its fence signals
+    pub fn submitted(&mut self, frame: usize) {
+        self.written[frame] = true;
+    }
+    
+    /// GPU milliseconds of the last submission of `frame` (after its fence
+    /// has signaled), or None before it was ever submitted
+    pub fn read_ms(&self, device: &ash::Device, frame: usize) -> Option<f32> {
+        if !self.written[frame] {
+            return None;
+        }
+        
+        let mut timestamps = [0u64; 2];
+        unsafe {
+            device.get_query_pool_results(
+                self.pool,
+                2 * frame as u32,
+                2,
+                &mut timestamps,
+                vk::QueryResultFlags::TYPE_64,
+            ).ok()?;
+        }
+        
+        let ticks = timestamps[1].saturating_sub(timestamps[0]);
+        Some(ticks as f32 * self.period / 1_000_000.0)
+    }
+    
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_query_pool(self.pool, None);
+        }
+    }
+}
@@ -0,0 +1,752 @@
+// SPIR-V reflection
+//
+// Parses compiled SPIR-V modules to discover what a shader expects:
+// vertex inputs, push-constant blocks, descriptor bindings and
+// specialization constants.
+// Pipeline layouts are derived from this instead of being duplicated by hand,
+// and the Rust vertex layout is checked against the shader before pipeline creation.
+
+use anyhow::{Context, Result};
+use ash::vk;
+use std::collections::HashMap;
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+// Opcodes we care about (SPIR-V spec, section 3.52)
+const OP_ENTRY_POINT: u16 = 15;
+const OP_EXECUTION_MODE: u16 = 16;
+const OP_TYPE_BOOL: u16 = 20;
+const OP_TYPE_INT: u16 = 21;
+const OP_TYPE_FLOAT: u16 = 22;
+const OP_TYPE_VECTOR: u16 = 23;
+const OP_TYPE_MATRIX: u16 = 24;
+const OP_TYPE_IMAGE: u16 = 25;
+const OP_TYPE_SAMPLER: u16 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u16 = 27;
+const OP_TYPE_ARRAY: u16 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u16 = 29;
+const OP_TYPE_STRUCT: u16 = 30;
+const OP_TYPE_POINTER: u16 = 32;
+const OP_CONSTANT: u16 = 43;
+const OP_SPEC_CONSTANT_TRUE: u16 = 48;
+const OP_SPEC_CONSTANT_FALSE: u16 = 49;
+const OP_SPEC_CONSTANT: u16 = 50;
+const OP_VARIABLE: u16 = 59;
+const OP_DECORATE: u16 = 71;
+const OP_MEMBER_DECORATE: u16 = 72;
+
+// Execution modes
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+
+// Decorations
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+// Storage classes
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+// Execution models
+const EXECUTION_MODEL_VERTEX: u32 = 0;
+const EXECUTION_MODEL_FRAGMENT: u32 = 4;
+const EXECUTION_MODEL_GL_COMPUTE: u32 = 5;
+
+/// Numeric class of a shader input, used to match it against a `vk::Format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumericType {
+    /// Floats, and integer formats read as normalized/scaled floats
+    Float,
+    SInt,
+    UInt,
+}
+
+/// A vertex shader input variable (`layout(location = N) in ...`)
+#[derive(Debug, Clone)]
+pub struct VertexInput {
+    pub location: u32,
+    pub numeric_type: NumericType,
+    pub components: u32,
+}
+
+/// A descriptor binding used by the shader
+#[derive(Debug, Clone)]
+pub struct DescriptorBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub count: u32,
+}
+
+/// A specialization constant (`layout(constant_id = N)`, `[[vk::constant_id(N)]]`)
+#[derive(Debug, Clone)]
+pub struct SpecConstant {
+    pub id: u32,
+    /// Bytes the value occupies in `vk::SpecializationInfo` data (bools are 4)
+    pub size: u32,
+}
+
+/// Everything a pipeline needs to know about one shader stage
+#[derive(Debug, Clone)]
+pub struct ShaderReflection {
+    pub stage: vk::ShaderStageFlags,
+    pub entry_point: String,
+    /// Vertex inputs, sorted by location (empty for non-vertex stages)
+    pub inputs: Vec<VertexInput>,
+    /// Size in bytes of the push-constant block, if the shader declares one
+    pub push_constant_size: Option<u32>,
+    pub descriptor_bindings: Vec<DescriptorBinding>,
+    /// Specialization constants, sorted by constant ID
+    pub spec_constants: Vec<SpecConstant>,
+    /// `local_size_x/y/z` of a compute shader
+    pub workgroup_size: Option<[u32; 3]>,
+}
+
+/// Type table entry built while walking the module
+#[derive(Debug, Clone)]
+enum SpirvType {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length_id: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+}
+
+/// Convert raw shader bytes into SPIR-V words.
+///
+/// `include_bytes!` gives no alignment guarantee, so words are decoded
+/// explicitly instead of reinterpreting the byte slice.
+pub fn spirv_words(code: &[u8]) -> Result<Vec<u32>> {
+    if !code.len().is_multiple_of(4) {
+        anyhow::bail!("SPIR-V byte length {} is not a multiple of 4", code.len());
+    }
+    
+    let words: Vec<u32> = code
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect();
+    
+    if words.len() < 5 || words[0] != SPIRV_MAGIC {
+        anyhow::bail!("Not a SPIR-V module (bad magic number)");
+    }
+    
+    Ok(words)
+}
+
+impl ShaderReflection {
+    /// Reflect a SPIR-V module from its raw bytes
+    pub fn from_spirv(code: &[u8]) -> Result<Self> {
+        Self::from_words(&spirv_words(code)?)
+    }
+    
+    /// Reflect a SPIR-V module from its words
+    pub fn from_words(words: &[u32]) -> Result<Self> {
+        let mut types: HashMap<u32, SpirvType> = HashMap::new();
+        let mut constants: HashMap<u32, u32> = HashMap::new();
+        let mut variables: Vec<(u32, u32, u32)> = Vec::new(); // (id, pointer type, storage class)
+        let mut spec_constant_ids: Vec<(u32, u32)> = Vec::new(); // (id, type)
+        let mut decorations: HashMap<u32, HashMap<u32, u32>> = HashMap::new();
+        let mut member_decorations: HashMap<(u32, u32), HashMap<u32, u32>> = HashMap::new();
+        let mut entry_point = None;
+        let mut workgroup_size = None;
+        
+        // Skip the 5-word header
+        let mut cursor = 5;
+        while cursor < words.len() {
+            let word_count = (words[cursor] >> 16) as usize;
+            let opcode = (words[cursor] & 0xffff) as u16;
+            if word_count == 0 || cursor + word_count > words.len() {
+                anyhow::bail!("Malformed SPIR-V instruction at word {}", cursor);
+            }
+            let ops = &words[cursor + 1..cursor + word_count];
+            if ops.len() < min_operands(opcode) {
+                anyhow::bail!("Truncated SPIR-V instruction (opcode {}) at word {}", opcode, cursor);
+            }
+            
+            match opcode {
+                OP_ENTRY_POINT if entry_point.is_none() => {
+                    let name = decode_string(&ops[2..]);
+                    entry_point = Some((ops[0], name));
+                }
+                OP_EXECUTION_MODE if ops.len() >= 5 && ops[1] == EXECUTION_MODE_LOCAL_SIZE => {
+                    workgroup_size = Some([ops[2], ops[3], ops[4]]);
+                }
+                OP_TYPE_BOOL => { types.insert(ops[0], SpirvType::Bool); }
+                OP_TYPE_INT => {
+                    types.insert(ops[0], SpirvType::Int { width: ops[1], signed: ops[2] != 0 });
+                }
+                OP_TYPE_FLOAT => { types.insert(ops[0], SpirvType::Float { width: ops[1] }); }
+                OP_TYPE_VECTOR => {
+                    types.insert(ops[0], SpirvType::Vector { component: ops[1], count: ops[2] });
+                }
+                OP_TYPE_MATRIX => {
+                    types.insert(ops[0], SpirvType::Matrix { column: ops[1], count: ops[2] });
+                }
+                // Operand 6 is "Sampled": 1 = used with a sampler, 2 = storage image
+                OP_TYPE_IMAGE => { types.insert(ops[0], SpirvType::Image { sampled: ops[6] }); }
+                OP_TYPE_SAMPLER => { types.insert(ops[0], SpirvType::Sampler); }
+                OP_TYPE_SAMPLED_IMAGE => { types.insert(ops[0], SpirvType::SampledImage); }
+                OP_TYPE_ARRAY => {
+                    types.insert(ops[0], SpirvType::Array { element: ops[1], length_id: ops[2] });
+                }
+                OP_TYPE_RUNTIME_ARRAY => {
+                    types.insert(ops[0], SpirvType::RuntimeArray { element: ops[1] });
+                }
+                OP_TYPE_STRUCT => {
+                    types.insert(ops[0], SpirvType::Struct { members: ops[1..].to_vec() });
+                }
+                OP_TYPE_POINTER => {
+                    types.insert(ops[0], SpirvType::Pointer { pointee: ops[2] });
+                }
+                // Only the low word matters for array lengths
+                OP_CONSTANT => { constants.insert(ops[1], ops[2]); }
+                OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE | OP_SPEC_CONSTANT => {
+                    spec_constant_ids.push((ops[1], ops[0]));
+                }
+                OP_VARIABLE => variables.push((ops[1], ops[0], ops[2])),
+                OP_DECORATE => {
+                    let value = ops.get(2).copied().unwrap_or(0);
+                    decorations.entry(ops[0]).or_default().insert(ops[1], value);
+                }
+                OP_MEMBER_DECORATE => {
+                    let value = ops.get(3).copied().unwrap_or(0);
+                    member_decorations.entry((ops[0], ops[1])).or_default().insert(ops[2], value);
+                }
+                _ => {}
+            }
+            
+            cursor += word_count;
+        }
+        
+        let (execution_model, entry_name) = entry_point
+            .context("SPIR-V module has no entry point")?;
+        let stage = match execution_model {
+            EXECUTION_MODEL_VERTEX => vk::ShaderStageFlags::VERTEX,
+            EXECUTION_MODEL_FRAGMENT => vk::ShaderStageFlags::FRAGMENT,
+            EXECUTION_MODEL_GL_COMPUTE => vk::ShaderStageFlags::COMPUTE,
+            other => anyhow::bail!("Unsupported SPIR-V execution model {}", other),
+        };
+        
+        let module = ModuleInfo { types, constants, decorations, member_decorations };
+        
+        let mut inputs = Vec::new();
+        let mut push_constant_size = None;
+        let mut descriptor_bindings = Vec::new();
+        
+        for &(id, pointer_type, storage_class) in &variables {
+            let pointee = match module.types.get(&pointer_type) {
+                Some(SpirvType::Pointer { pointee }) => *pointee,
+                _ => continue,
+            };
+            
+            match storage_class {
+                STORAGE_INPUT if stage == vk::ShaderStageFlags::VERTEX => {
+                    // Built-ins (gl_VertexIndex, ...) are not vertex attributes
+                    if module.decoration(id, DECORATION_BUILT_IN).is_some() {
+                        continue;
+                    }
+                    let Some(location) = module.decoration(id, DECORATION_LOCATION) else {
+                        continue;
+                    };
+                    let (numeric_type, components) = module.input_shape(pointee)
+                        .with_context(|| format!("Unsupported vertex input type at location {}", location))?;
+                    inputs.push(VertexInput { location, numeric_type, components });
+                }
+                STORAGE_PUSH_CONSTANT => {
+                    push_constant_size = Some(module.size_of(pointee)?);
+                }
+                STORAGE_UNIFORM | STORAGE_UNIFORM_CONSTANT | STORAGE_STORAGE_BUFFER => {
+                    let (Some(set), Some(binding)) = (
+                        module.decoration(id, DECORATION_DESCRIPTOR_SET),
+                        module.decoration(id, DECORATION_BINDING),
+                    ) else {
+                        continue;
+                    };
+                    let (descriptor_type, count) = module.descriptor_type(pointee, storage_class)
+                        .with_context(|| format!("Unsupported descriptor at set {} binding {}", set, binding))?;
+                    descriptor_bindings.push(DescriptorBinding { set, binding, descriptor_type, count });
+                }
+                _ => {}
+            }
+        }
+        
+        // Spec constants without a SpecId can't be set from the API
+        let mut spec_constants = Vec::new();
+        for &(id, type_id) in &spec_constant_ids {
+            if let Some(constant_id) = module.decoration(id, DECORATION_SPEC_ID) {
+                spec_constants.push(SpecConstant { id: constant_id, size: module.size_of(type_id)? });
+            }
+        }
+        
+        inputs.sort_by_key(|i| i.location);
+        descriptor_bindings.sort_by_key(|b| (b.set, b.binding));
+        spec_constants.sort_by_key(|c| c.id);
+        
+        Ok(Self {
+            stage,
+            entry_point: entry_name,
+            inputs,
+            push_constant_size,
+            descriptor_bindings,
+            spec_constants,
+            workgroup_size,
+        })
+    }
+    
+    /// Check the Rust-side vertex attributes against this shader's inputs.
+    ///
+    /// Every input the shader reads must be provided with a matching numeric
+    /// type and component count; anything else fails here with a readable
+    /// message instead of as a validation-layer error or garbage on screen.
+    pub fn validate_vertex_input(
+        &self,
+        attributes: &[vk::VertexInputAttributeDescription],
+    ) -> Result<()> {
+        let mut errors = Vec::new();
+        
+        for input in &self.inputs {
+            let Some(attr) = attributes.iter().find(|a| a.location == input.location) else {
+                errors.push(format!(
+                    "shader reads location {} ({} x {:?}) but the vertex layout does not provide it",
+                    input.location, input.components, input.numeric_type
+                ));
+                continue;
+            };
+            
+            match format_shape(attr.format) {
+                Some((numeric_type, components)) => {
+                    if numeric_type != input.numeric_type || components != input.components {
+                        errors.push(format!(
+                            "location {}: shader expects {} x {:?}, vertex layout provides {:?} ({} x {:?})",
+                            input.location, input.components, input.numeric_type,
+                            attr.format, components, numeric_type
+                        ));
+                    }
+                }
+                None => errors.push(format!(
+                    "location {}: vertex format {:?} is not understood by reflection",
+                    input.location, attr.format
+                )),
+            }
+        }
+        
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Vertex layout does not match shader inputs:\n  {}", errors.join("\n  "))
+        }
+    }
+}
+
+/// Pipeline layout description merged from all stages of a pipeline
+#[derive(Debug, Default)]
+pub struct PipelineLayoutInfo {
+    pub push_constant_ranges: Vec<vk::PushConstantRange>,
+    /// Bindings per descriptor set, indexed by set number
+    pub set_bindings: Vec<Vec<vk::DescriptorSetLayoutBinding>>,
+}
+
+/// Merge the reflection of several stages into one pipeline layout description
+pub fn merge_layout(stages: &[&ShaderReflection]) -> Result<PipelineLayoutInfo> {
+    let mut info = PipelineLayoutInfo::default();
+    
+    // One push-constant range covering every stage that uses the block
+    let mut push_size = 0;
+    let mut push_stages = vk::ShaderStageFlags::empty();
+    for stage in stages {
+        if let Some(size) = stage.push_constant_size {
+            push_size = push_size.max(size);
+            push_stages |= stage.stage;
+        }
+    }
+    if push_size > 0 {
+        info.push_constant_ranges.push(vk::PushConstantRange {
+            stage_flags: push_stages,
+            offset: 0,
+            size: push_size,
+        });
+    }
+    
+    for stage in stages {
+        for binding in &stage.descriptor_bindings {
+            let set = binding.set as usize;
+            if info.set_bindings.len() <= set {
+                info.set_bindings.resize_with(set + 1, Vec::new);
+            }
+            let bindings = &mut info.set_bindings[set];
+            
+            if let Some(existing) = bindings.iter_mut().find(|b| b.binding == binding.binding) {
+                if existing.descriptor_type != binding.descriptor_type {
+                    anyhow::bail!(
+                        "Set {} binding {} is {:?} in one stage and {:?} in another",
+                        binding.set, binding.binding, existing.descriptor_type, binding.descriptor_type
+                    );
+                }
+                existing.stage_flags |= stage.stage;
+            } else {
+                bindings.push(vk::DescriptorSetLayoutBinding {
+                    binding: binding.binding,
+                    descriptor_type: binding.descriptor_type,
+                    descriptor_count: binding.count,
+                    stage_flags: stage.stage,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    
+    Ok(info)
+}
+
+/// Lookup tables collected while parsing
+struct ModuleInfo {
+    types: HashMap<u32, SpirvType>,
+    constants: HashMap<u32, u32>,
+    decorations: HashMap<u32, HashMap<u32, u32>>,
+    member_decorations: HashMap<(u32, u32), HashMap<u32, u32>>,
+}
+
+impl ModuleInfo {
+    fn decoration(&self, id: u32, decoration: u32) -> Option<u32> {
+        self.decorations.get(&id)?.get(&decoration).copied()
+    }
+    
+    fn member_decoration(&self, id: u32, member: u32, decoration: u32) -> Option<u32> {
+        self.member_decorations.get(&(id, member))?.get(&decoration).copied()
+    }
+    
+    fn get(&self, id: u32) -> Result<&SpirvType> {
+        self.types.get(&id).with_context(|| format!("Unknown SPIR-V type id {}", id))
+    }
+    
+    /// Numeric type and component count of a vertex input
+    fn input_shape(&self, type_id: u32) -> Result<(NumericType, u32)> {
+        match self.get(type_id)? {
+            SpirvType::Float { width: 32 } => Ok((NumericType::Float, 1)),
+            SpirvType::Int { width: 32, signed: true } => Ok((NumericType::SInt, 1)),
+            SpirvType::Int { width: 32, signed: false } => Ok((NumericType::UInt, 1)),
+            SpirvType::Vector { component, count } => {
+                let (numeric_type, _) = self.input_shape(*component)?;
+                Ok((numeric_type, *count))
+            }
+            other => anyhow::bail!("{:?}", other),
+        }
+    }
+    
+    /// Byte size of a type as laid out in a block (uses explicit offsets/strides)
+    fn size_of(&self, type_id: u32) -> Result<u32> {
+        Ok(match self.get(type_id)? {
+            SpirvType::Bool => 4,
+            SpirvType::Int { width, .. } | SpirvType::Float { width } => width / 8,
+            SpirvType::Vector { component, count } => self.size_of(*component)? * count,
+            SpirvType::Matrix { column, count } => self.size_of(*column)? * count,
+            SpirvType::Array { element, length_id } => {
+                let length = self.constants.get(length_id).copied()
+                    .context("Array length is not a plain constant")?;
+                let stride = match self.decoration(type_id, DECORATION_ARRAY_STRIDE) {
+                    Some(stride) => stride,
+                    None => self.size_of(*element)?,
+                };
+                stride * length
+            }
+            SpirvType::RuntimeArray { .. } => 0,
+            SpirvType::Struct { members } => {
+                let mut size = 0;
+                for (index, &member) in members.iter().enumerate() {
+                    let index = index as u32;
+                    let offset = self.member_decoration(type_id, index, DECORATION_OFFSET).unwrap_or(size);
+                    let member_size = match (self.get(member)?, self.member_decoration(type_id, index, DECORATION_MATRIX_STRIDE)) {
+                        (SpirvType::Matrix { count, .. }, Some(stride)) => stride * count,
+                        _ => self.size_of(member)?,
+                    };
+                    size = size.max(offset + member_size);
+                }
+                size
+            }
+            other => anyhow::bail!("Type {:?} has no block layout size", other),
+        })
+    }
+    
+    /// Descriptor type and array count for a resource variable
+    fn descriptor_type(&self, type_id: u32, storage_class: u32) -> Result<(vk::DescriptorType, u32)> {
+        let (inner, count) = match self.get(type_id)? {
+            SpirvType::Array { element, length_id } => {
+                let length = self.constants.get(length_id).copied().unwrap_or(1);
+                (*element, length)
+            }
+            SpirvType::RuntimeArray { element } => (*element, 1),
+            _ => (type_id, 1),
+        };
+        
+        let descriptor_type = match (self.get(inner)?, storage_class) {
+            (SpirvType::Struct { .. }, STORAGE_STORAGE_BUFFER) => vk::DescriptorType::STORAGE_BUFFER,
+            (SpirvType::Struct { .. }, _) if self.decoration(inner, DECORATION_BUFFER_BLOCK).is_some() => {
+                vk::DescriptorType::STORAGE_BUFFER
+            }
+            (SpirvType::Struct { .. }, _) if self.decoration(inner, DECORATION_BLOCK).is_some() => {
+                vk::DescriptorType::UNIFORM_BUFFER
+            }
+            (SpirvType::SampledImage, _) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            (SpirvType::Image { sampled: 2 }, _) => vk::DescriptorType::STORAGE_IMAGE,
+            (SpirvType::Image { .. }, _) => vk::DescriptorType::SAMPLED_IMAGE,
+            (SpirvType::Sampler, _) => vk::DescriptorType::SAMPLER,
+            (other, _) => anyhow::bail!("{:?}", other),
+        };
+        
+        Ok((descriptor_type, count))
+    }
+}
+
+/// Numeric class and component count of a vertex attribute format
+pub fn format_shape(format: vk::Format) -> Option<(NumericType, u32)> {
+    use vk::Format as F;
+    Some(match format {
+        F::R32_SFLOAT | F::R16_SFLOAT | F::R8_UNORM | F::R8_SNORM | F::R16_UNORM | F::R16_SNORM => {
+            (NumericType::Float, 1)
+        }
+        F::R32G32_SFLOAT | F::R16G16_SFLOAT | F::R8G8_UNORM | F::R8G8_SNORM
+        | F::R16G16_UNORM | F::R16G16_SNORM => (NumericType::Float, 2),
+        F::R32G32B32_SFLOAT | F::R8G8B8_UNORM | F::R8G8B8_SNORM
+        | F::R16G16B16_UNORM | F::R16G16B16_SNORM => (NumericType::Float, 3),
+        F::R32G32B32A32_SFLOAT | F::R16G16B16A16_SFLOAT | F::R8G8B8A8_UNORM | F::R8G8B8A8_SNORM
+        | F::R16G16B16A16_UNORM | F::R16G16B16A16_SNORM => (NumericType::Float, 4),
+        F::R32_SINT | F::R16_SINT | F::R8_SINT => (NumericType::SInt, 1),
+        F::R32G32_SINT | F::R16G16_SINT | F::R8G8_SINT => (NumericType::SInt, 2),
+        F::R32G32B32_SINT | F::R16G16B16_SINT | F::R8G8B8_SINT => (NumericType::SInt, 3),
+        F::R32G32B32A32_SINT | F::R16G16B16A16_SINT | F::R8G8B8A8_SINT => (NumericType::SInt, 4),
+        F::R32_UINT | F::R16_UINT | F::R8_UINT => (NumericType::UInt, 1),
+        F::R32G32_UINT | F::R16G16_UINT | F::R8G8_UINT => (NumericType::UInt, 2),
+        F::R32G32B32_UINT | F::R16G16B16_UINT | F::R8G8B8_UINT => (NumericType::UInt, 3),
+        F::R32G32B32A32_UINT | F::R16G16B16A16_UINT | F::R8G8B8A8_UINT => (NumericType::UInt, 4),
+        _ => return None,
+    })
+}
+
+/// Fewest operands of the instructions we read (everything up to the last
+/// operand we index)
+fn min_operands(opcode: u16) -> usize {
+    match opcode {
+        OP_TYPE_BOOL | OP_TYPE_SAMPLER | OP_TYPE_STRUCT => 1,
+        OP_EXECUTION_MODE | OP_TYPE_FLOAT | OP_TYPE_SAMPLED_IMAGE | OP_TYPE_RUNTIME_ARRAY
+        | OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE | OP_DECORATE => 2,
+        OP_ENTRY_POINT | OP_TYPE_INT | OP_TYPE_VECTOR | OP_TYPE_MATRIX | OP_TYPE_ARRAY
+        | OP_TYPE_POINTER | OP_CONSTANT | OP_SPEC_CONSTANT | OP_VARIABLE | OP_MEMBER_DECORATE => 3,
+        OP_TYPE_IMAGE => 7,
+        _ => 0,
+    }
+}
+
+/// Decode a nul-terminated SPIR-V literal string
+fn decode_string(words: &[u32]) -> String {
+    let bytes: Vec<u8> = words
+        .iter()
+        .flat_map(|w| w.to_le_bytes())
+        .take_while(|&b| b != 0)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    macro_rules! reflect {
+        ($name:expr) => {
+            ShaderReflection::from_spirv(include_bytes!(concat!(env!("OUT_DIR"), "/shaders/", $name, ".spv"))).unwrap()
+        };
+    }
+    
+    fn binding(set: u32, binding: u32, descriptor_type: vk::DescriptorType) -> (u32, u32, vk::DescriptorType, u32) {
+        (set, binding, descriptor_type, 1)
+    }
+    
+    fn bindings(reflection: &ShaderReflection) -> Vec<(u32, u32, vk::DescriptorType, u32)> {
+        reflection.descriptor_bindings.iter().map(|b| (b.set, b.binding, b.descriptor_type, b.count)).collect()
+    }
+    
+    /// The attributes cube.vert expects: one per input, in the shader's shape
+    fn cube_attributes() -> Vec<vk::VertexInputAttributeDescription> {
+        use vk::Format as F;
+        let formats = [
+            F::R32G32B32_SFLOAT,
+            F::R32G32B32_SFLOAT,
+            F::R32G32B32_SFLOAT,
+            F::R32G32_SFLOAT,
+            F::R8G8B8A8_UINT,
+            F::R32G32B32A32_SFLOAT,
+            F::R32G32B32A32_SFLOAT,
+            F::R32G32B32A32_SFLOAT,
+            F::R32G32B32A32_SFLOAT,
+            F::R32G32B32A32_SFLOAT,
+            F::R8G8B8A8_UNORM,
+        ];
+        formats
+            .iter()
+            .enumerate()
+            .map(|(location, &format)| vk::VertexInputAttributeDescription { location: location as u32, format, ..Default::default() })
+            .collect()
+    }
+    
+    /// A module header followed by `instructions`
+    fn module(instructions: &[u32]) -> Vec<u32> {
+        let mut words = vec![SPIRV_MAGIC, 0x0001_0000, 0, 16, 0];
+        words.extend_from_slice(instructions);
+        words
+    }
+    
+    #[test]
+    fn vertex_shader() {
+        let vert = reflect!("cube.vert");
+        assert_eq!(vert.stage, vk::ShaderStageFlags::VERTEX);
+        assert_eq!(vert.entry_point, "main");
+        let inputs: Vec<_> = vert.inputs.iter().map(|i| (i.location, i.numeric_type, i.components)).collect();
+        assert_eq!(inputs[..6], [
+            (0, NumericType::Float, 3),
+            (1, NumericType::Float, 3),
+            (2, NumericType::Float, 3),
+            (3, NumericType::Float, 2),
+            (4, NumericType::UInt, 4),
+            (5, NumericType::Float, 4),
+        ]);
+        assert_eq!(inputs.len(), 11);
+        // lodFade, then viewProj aligned to 16 bytes
+        assert_eq!(vert.push_constant_size, Some(16 + 64));
+        assert_eq!(bindings(&vert), [
+            binding(1, 0, vk::DescriptorType::STORAGE_BUFFER),
+            binding(1, 1, vk::DescriptorType::STORAGE_BUFFER),
+            binding(1, 2, vk::DescriptorType::STORAGE_BUFFER),
+        ]);
+        assert!(vert.spec_constants.is_empty());
+        assert_eq!(vert.workgroup_size, None);
+    }
+    
+    #[test]
+    fn fragment_shaders() {
+        let frag = reflect!("cube.frag");
+        assert_eq!(frag.stage, vk::ShaderStageFlags::FRAGMENT);
+        assert_eq!(frag.push_constant_size, None);
+        assert_eq!(bindings(&frag), [binding(0, 0, vk::DescriptorType::UNIFORM_BUFFER)]);
+        // LIGHTING_MODEL, a uint
+        let constants: Vec<_> = frag.spec_constants.iter().map(|c| (c.id, c.size)).collect();
+        assert_eq!(constants, [(0, 4)]);
+        
+        let gui = reflect!("gui.frag");
+        assert_eq!(bindings(&gui), [
+            binding(0, 0, vk::DescriptorType::SAMPLED_IMAGE),
+            binding(0, 1, vk::DescriptorType::SAMPLER),
+        ]);
+    }
+    
+    #[test]
+    fn compute_shaders() {
+        let cull = reflect!("cull.wgsl.main");
+        assert_eq!(cull.stage, vk::ShaderStageFlags::COMPUTE);
+        assert_eq!(cull.workgroup_size, Some([64, 1, 1]));
+        // count, six planes from offset 16 and the camera after them
+        assert_eq!(cull.push_constant_size, Some(16 + 6 * 16 + 16));
+        assert_eq!(bindings(&cull), (0..5).map(|b| binding(0, b, vk::DescriptorType::STORAGE_BUFFER)).collect::<Vec<_>>());
+        
+        let instances = reflect!("instances.comp");
+        assert_eq!(instances.workgroup_size, Some([64, 1, 1]));
+        assert_eq!(instances.descriptor_bindings.len(), 3);
+    }
+    
+    #[test]
+    fn merged_layout() {
+        let (vert, frag) = (reflect!("cube.vert"), reflect!("cube.frag"));
+        let layout = merge_layout(&[&vert, &frag]).unwrap();
+        // Only the vertex stage reads the push constants
+        assert_eq!(layout.push_constant_ranges.len(), 1);
+        let range = layout.push_constant_ranges[0];
+        assert_eq!((range.stage_flags, range.offset, range.size), (vk::ShaderStageFlags::VERTEX, 0, 80));
+        assert_eq!(layout.set_bindings.len(), 2);
+        let shape = |b: &vk::DescriptorSetLayoutBinding| (b.binding, b.descriptor_type, b.descriptor_count, b.stage_flags);
+        assert_eq!(layout.set_bindings[0].iter().map(shape).collect::<Vec<_>>(), [
+            (0, vk::DescriptorType::UNIFORM_BUFFER, 1, vk::ShaderStageFlags::FRAGMENT),
+        ]);
+        assert_eq!(layout.set_bindings[1].len(), 3);
+        assert!(layout.set_bindings[1].iter().all(|b| b.stage_flags == vk::ShaderStageFlags::VERTEX));
+        
+        // A binding both stages use gets both stage flags; the push range
+        // covers the larger block
+        let mut shared = frag.clone();
+        shared.descriptor_bindings.push(vert.descriptor_bindings[0].clone());
+        shared.push_constant_size = Some(96);
+        let layout = merge_layout(&[&vert, &shared]).unwrap();
+        let range = layout.push_constant_ranges[0];
+        assert_eq!((range.stage_flags, range.size), (vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT, 96));
+        assert_eq!(layout.set_bindings[1][0].stage_flags, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
+        assert_eq!(layout.set_bindings[1].len(), 3);
+    }
+    
+    #[test]
+    fn conflicting_bindings_are_an_error() {
+        let (vert, mut frag) = (reflect!("cube.vert"), reflect!("cube.frag"));
+        frag.descriptor_bindings.push(DescriptorBinding {
+            set: 1,
+            binding: 2,
+            descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+            count: 1,
+        });
+        let error = merge_layout(&[&vert, &frag]).unwrap_err().to_string();
+        assert!(error.contains("Set 1 binding 2"), "{error}");
+    }
+    
+    #[test]
+    fn vertex_input_validation() {
+        let vert = reflect!("cube.vert");
+        vert.validate_vertex_input(&cube_attributes()).unwrap();
+        
+        // Wrong component count, and floats where the shader reads uints
+        let mut attributes = cube_attributes();
+        attributes[0].format = vk::Format::R32G32_SFLOAT;
+        attributes[4].format = vk::Format::R32G32B32A32_SFLOAT;
+        let error = vert.validate_vertex_input(&attributes).unwrap_err().to_string();
+        assert!(error.contains("location 0: shader expects 3 x Float"), "{error}");
+        assert!(error.contains("location 4: shader expects 4 x UInt"), "{error}");
+        
+        let mut attributes = cube_attributes();
+        attributes.remove(10);
+        let error = vert.validate_vertex_input(&attributes).unwrap_err().to_string();
+        assert!(error.contains("shader reads location 10"), "{error}");
+    }
+    
+    #[test]
+    fn malformed_modules_are_errors() {
+        // OpTypeVector with only its result id
+        let error = ShaderReflection::from_words(&module(&[2 << 16 | OP_TYPE_VECTOR as u32, 1])).unwrap_err();
+        assert!(error.to_string().contains("Truncated"), "{error}");
+        // An entry point without its name
+        let error = ShaderReflection::from_words(&module(&[3 << 16 | OP_ENTRY_POINT as u32, 0, 1])).unwrap_err();
+        assert!(error.to_string().contains("Truncated"), "{error}");
+        // Word counts of zero or past the end of the module
+        for instructions in [&[OP_TYPE_BOOL as u32, 1][..], &[5 << 16 | OP_TYPE_INT as u32, 1, 32]] {
+            let error = ShaderReflection::from_words(&module(instructions)).unwrap_err();
+            assert!(error.to_string().contains("Malformed"), "{error}");
+        }
+        // Every prefix of a real module fails cleanly or parses
+        let words = spirv_words(include_bytes!(concat!(env!("OUT_DIR"), "/shaders/cube.vert.spv"))).unwrap();
+        for end in 5..words.len() {
+            let _ = ShaderReflection::from_words(&words[..end]);
+        }
+        assert!(spirv_words(&[0; 7]).is_err());
+        assert!(spirv_words(&[0; 20]).is_err());
+    }
+}
@@ -5,21 +5,32 @@
 
 use anyhow::{Context, Result};
 use ash::vk;
+use super::reflect::{self, ShaderReflection};
 use super::VulkanDevice;
 
+/// A shader module together with what reflection found inside it
+pub struct Shader {
+    pub module: vk::ShaderModule,
+    pub reflection: ShaderReflection,
+}
+
+impl Shader {
+    /// Destroy the shader module (safe once all pipelines using it are created)
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_shader_module(self.module, None);
+        }
+    }
+}
+
 /// Load SPIR-V shader from bytes and create a shader module
 pub fn create_shader_module(device: &VulkanDevice, code: &[u8]) -> Result<vk::ShaderModule> {
-    // SPIR-V uses 4-byte words, so we need to convert bytes to u32s
-    // Safety: We trust that the shader compiler produces valid aligned data
-    let code_aligned = unsafe {
-        std::slice::from_raw_parts(
-            code.as_ptr() as *const u32,
-            code.len() / 4,
-        )
-    };
+    // SPIR-V uses 4-byte words; decode them explicitly since embedded
+    // bytes are not guaranteed to be 4-byte aligned
+    let words = reflect::spirv_words(code)?;
     
     let create_info = vk::ShaderModuleCreateInfo::builder()
-        .code(code_aligned);
+        .code(&words);
     
     unsafe {
         device.device.create_shader_module(&create_info, None)
@@ -27,11 +38,24 @@ pub fn create_shader_module(device: &VulkanDevice, code: &[u8]) -> Result<vk::Sh
     }
 }
 
+/// Reflect SPIR-V bytes and create a shader module from them
+pub fn create_shader(device: &VulkanDevice, code: &[u8]) -> Result<Shader> {
+    let reflection = ShaderReflection::from_spirv(code)
+        .context("Failed to reflect shader")?;
+    let module = create_shader_module(device, code)?;
+    
+    Ok(Shader { module, reflection })
+}
+
 /// Helper to load shader from embedded bytes at compile time
+///
+/// `$name` is the source path relative to shaders/ (e.g. "cube.vert" or
+/// "post.frag.hlsl"); build.rs compiles it to `$OUT_DIR/shaders/<name>.spv`.
+/// WGSL files produce one module per entry point: "sky.wgsl.vs_main".
 #[macro_export]
 macro_rules! load_shader {
-    ($device:expr, $path:expr) => {{
-        let bytes = include_bytes!($path);
-        $crate::backend::shader::create_shader_module($device, bytes)
+    ($device:expr, $name:expr) => {{
+        let bytes = include_bytes!(concat!(env!("OUT_DIR"), "/shaders/", $name, ".spv"));
+        $crate::backend::shader::create_shader($device, bytes)
     }};
 }
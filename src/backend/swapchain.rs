@@ -24,6 +24,9 @@ pub struct Swapchain {
     #[allow(dead_code)]
     pub extent: vk::Extent2D,
     
+    /// Chosen present mode (shown in the stats overlay)
+    pub present_mode: vk::PresentModeKHR,
+    
     device: Arc<VulkanDevice>,
 }
 
@@ -175,6 +178,7 @@ impl Swapchain {
             image_views: image_views?,
             format: surface_format.format,
             extent,
+            present_mode,
             device,
         })
     }
@@ -0,0 +1,297 @@
+// Textures - sampled images uploaded from the CPU
+//
+// `Texture::new` copies pixels through a staging buffer with a one-time
+// command buffer and leaves the image in SHADER_READ_ONLY_OPTIMAL, ready to
+// be bound as a `texture2D` plus `sampler` (see DescriptorWriter::sampled_image).
+
+use anyhow::{Context, Result};
+use ash::vk;
+use super::VulkanDevice;
+use super::buffer::{create_buffer_with_data, find_memory_type};
+
+/// A sampled 2D image with its view and sampler
+pub struct Texture {
+    pub image: vk::Image,
+    memory: vk::DeviceMemory,
+    pub view: vk::ImageView,
+    pub sampler: vk::Sampler,
+    pub extent: vk::Extent2D,
+}
+
+impl Texture {
+    /// Upload tightly packed rows of `format` pixels (e.g. `R8_UNORM` glyph
+    /// coverage, `R8G8B8A8_SRGB` colors)
+    ///
+    /// `filter` is used for both magnification and minification; text and
+    /// pixel art want `NEAREST`, everything else `LINEAR`.
+    pub fn new(
+        device: &VulkanDevice,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        pixels: &[u8],
+        filter: vk::Filter,
+    ) -> Result<Self> {
+        let image_info = vk::ImageCreateInfo::builder()
+            .image_type(vk::ImageType::TYPE_2D)
+            .extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(1)
+            .format(format)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .usage(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        
+        let image = unsafe {
+            device.device.create_image(&image_info, None)
+                .context("Failed to create texture image")?
+        };
+        
+        let mem_requirements = unsafe {
+            device.device.get_image_memory_requirements(image)
+        };
+        
+        let memory_type_index = find_memory_type(
+            device,
+            mem_requirements.memory_type_bits,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        )?;
+        
+        let alloc_info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(mem_requirements.size)
+            .memory_type_index(memory_type_index);
+        
+        let memory = unsafe {
+            device.device.allocate_memory(&alloc_info, None)
+                .context("Failed to allocate texture memory")?
+        };
+        
+        unsafe {
+            device.device.bind_image_memory(image, memory, 0)
+                .context("Failed to bind texture memory")?;
+        }
+        
+        // Upload: staging buffer -> image, then make it readable by shaders
+        let upload_result = upload_region(
+            device,
+            image,
+            vk::Offset2D { x: 0, y: 0 },
+            extent,
+            pixels,
+            vk::ImageLayout::UNDEFINED,
+        );
+        if let Err(e) = upload_result {
+            unsafe {
+                device.device.destroy_image(image, None);
+                device.device.free_memory(memory, None);
+            }
+            return Err(e);
+        }
+        
+        // ─────────────────────────────────────────────────────────────────────
+        // View and sampler
+        // ─────────────────────────────────────────────────────────────────────
+        let subresource_range = color_subresource_range();
+        let view_info = vk::ImageViewCreateInfo::builder()
+            .image(image)
+            .view_type(vk::ImageViewType::TYPE_2D)
+            .format(format)
+            .subresource_range(subresource_range);
+        
+        let view = unsafe {
+            device.device.create_image_view(&view_info, None)
+                .context("Failed to create texture view")?
+        };
+        
+        let sampler_info = vk::SamplerCreateInfo::builder()
+            .mag_filter(filter)
+            .min_filter(filter)
+            .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+            .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .max_lod(0.0);
+        
+        let sampler = unsafe {
+            device.device.create_sampler(&sampler_info, None)
+                .context("Failed to create texture sampler")?
+        };
+        
+        Ok(Self { image, memory, view, sampler, extent })
+    }
+    
+    /// Overwrite the `extent` pixels at `offset` (e.g. a patch of a growing
+    /// font atlas)
+    ///
+    /// Nothing in flight may be sampling the texture; callers wait for the
+    /// device first.
+    pub fn update(
+        &self,
+        device: &VulkanDevice,
+        offset: vk::Offset2D,
+        extent: vk::Extent2D,
+        pixels: &[u8],
+    ) -> Result<()> {
+        upload_region(
+            device,
+            self.image,
+            offset,
+            extent,
+            pixels,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        )
+    }
+    
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_sampler(self.sampler, None);
+            device.destroy_image_view(self.view, None);
+            device.destroy_image(self.image, None);
+            device.free_memory(self.memory, None);
+        }
+    }
+}
+
+/// Record commands into a temporary command buffer, run them on the graphics
+/// queue and wait for them to finish (uploads at load time, not per frame)
+pub fn submit_one_time(device: &VulkanDevice, record: impl FnOnce(vk::CommandBuffer)) -> Result<()> {
+    let pool_info = vk::CommandPoolCreateInfo::builder()
+        .queue_family_index(device.graphics_queue_family)
+        .flags(vk::CommandPoolCreateFlags::TRANSIENT);
+    
+    let pool = unsafe {
+        device.device.create_command_pool(&pool_info, None)
+            .context("Failed to create one-time command pool")?
+    };
+    
+    let result = (|| unsafe {
+        let alloc_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+        let cmd = device.device.allocate_command_buffers(&alloc_info)
+            .context("Failed to allocate one-time command buffer")?[0];
+        
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        device.device.begin_command_buffer(cmd, &begin_info)?;
+        record(cmd);
+        device.device.end_command_buffer(cmd)?;
+        
+        let command_buffers = [cmd];
+        let submit_info = vk::SubmitInfo::builder().command_buffers(&command_buffers);
+        device.device.queue_submit(device.graphics_queue, &[submit_info.build()], vk::Fence::null())
+            .context("Failed to submit one-time commands")?;
+        device.device.queue_wait_idle(device.graphics_queue)?;
+        anyhow::Ok(())
+    })();
+    
+    unsafe {
+        device.device.destroy_command_pool(pool, None);
+    }
+    result
+}
+
+/// The single mip level and layer of a color texture
+fn color_subresource_range() -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level: 0,
+        level_count: 1,
+        base_array_layer: 0,
+        layer_count: 1,
+    }
+}
+
+/// Copy `pixels` into a region of `image` through a staging buffer, moving
+/// it from `old_layout` to SHADER_READ_ONLY_OPTIMAL
+fn upload_region(
+    device: &VulkanDevice,
+    image: vk::Image,
+    offset: vk::Offset2D,
+    extent: vk::Extent2D,
+    pixels: &[u8],
+    old_layout: vk::ImageLayout,
+) -> Result<()> {
+    let (staging_buffer, staging_memory) = create_buffer_with_data(
+        device,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        pixels,
+    )?;
+    
+    let subresource_range = color_subresource_range();
+    let upload_result = submit_one_time(device, |cmd| unsafe {
+        let to_transfer = vk::ImageMemoryBarrier::builder()
+            .old_layout(old_layout)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .src_access_mask(vk::AccessFlags::empty())
+            .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .build();
+        device.device.cmd_pipeline_barrier(
+            cmd,
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[to_transfer],
+        );
+        
+        let region = vk::BufferImageCopy::builder()
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_offset(vk::Offset3D { x: offset.x, y: offset.y, z: 0 })
+            .image_extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .build();
+        device.device.cmd_copy_buffer_to_image(
+            cmd,
+            staging_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[region],
+        );
+        
+        let to_shader = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ)
+            .build();
+        device.device.cmd_pipeline_barrier(
+            cmd,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[to_shader],
+        );
+    });
+    
+    unsafe {
+        device.device.destroy_buffer(staging_buffer, None);
+        device.device.free_memory(staging_memory, None);
+    }
+    upload_result
+}
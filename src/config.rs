@@ -4,23 +4,37 @@
 //
 // This module handles loading and parsing configuration from config.toml.
 // Provides sensible defaults if config file is missing or has errors.
+// Settings edited at runtime (ui::inspector) are written back with `save`,
+// which keeps the file's comments and layout.
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use crate::anim::clock::{Clock, ClockMode};
+use crate::anim::transform::{Easing, Keyframe, PlayMode, Track, TransformClip};
+use crate::backend::culling::CullingMode;
+use crate::backend::permutation::SpecializationConstants;
+use crate::mesh::process::NormalMode;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+/// Where `load` reads and `save` writes the configuration
+pub const CONFIG_PATH: &str = "config.toml";
+
 /// Root configuration structure
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct Config {
     pub window: WindowConfig,
     pub graphics: GraphicsConfig,
     pub debug: DebugConfig,
     pub controls: ControlsConfig,
+    pub scene: SceneConfig,
+    pub animation: AnimationConfig,
+    pub camera: CameraConfig,
+    pub lighting: LightingConfig,
 }
 
 /// Window settings
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct WindowConfig {
     pub title: String,
@@ -41,32 +55,65 @@ impl Default for WindowConfig {
 }
 
 /// Graphics settings
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct GraphicsConfig {
     pub present_mode: String,
+    /// Frames per second at most (0 = unlimited)
+    pub fps_limit: u32,
+    /// Start each frame just before the GPU is expected to be free (see
+    /// backend::pacing)
+    pub low_latency: bool,
     pub clear_color: [f32; 4],
     pub max_frames_in_flight: usize,
+    /// Keywords enabled for the cube's fragment shader variant
+    pub shader_keywords: Vec<String>,
+    /// "gpu", "cpu" or "off" (see backend::culling)
+    pub culling: String,
+    /// Largest mesh LOD error allowed on screen, in pixels
+    pub lod_threshold: f32,
+    /// Fraction of the threshold over which LOD levels cross-fade (0 = off)
+    pub lod_fade_range: f32,
+    /// TTF/OTF file for labels drawn with ui::text (empty = the bundled font)
+    pub text_font: String,
 }
 
 impl Default for GraphicsConfig {
     fn default() -> Self {
         Self {
             present_mode: "immediate".to_string(),
+            fps_limit: 0,
+            low_latency: false,
             clear_color: [0.1, 0.2, 0.8, 1.0],
             max_frames_in_flight: 2,
+            shader_keywords: Vec::new(),
+            culling: "gpu".to_string(),
+            lod_threshold: 1.0,
+            lod_fade_range: 0.25,
+            text_font: String::new(),
         }
     }
 }
 
 /// Debug settings
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct DebugConfig {
     pub validation_layers: bool,
     pub log_to_file: bool,
     pub log_file: String,
+    /// Start with the stats HUD shown; F8 toggles it
     pub show_fps: bool,
+    /// Debug line width in pixels (1.0 on GPUs without wide lines)
+    pub line_width: f32,
+    /// Start with debug shapes (grid, axes, bounds) shown; F7 toggles them
+    pub show_shapes: bool,
+    /// Start with the inspector open; F9 toggles it
+    pub show_inspector: bool,
+    /// Start with the sprite layer demo shown; F10 toggles it
+    pub show_sprites: bool,
+    /// PNG drawn in the sprite demo, loaded in the background (empty = none)
+    pub sprite_image: String,
 }
 
 impl Default for DebugConfig {
@@ -76,12 +123,17 @@ impl Default for DebugConfig {
             log_to_file: true,
             log_file: "vulkan_debug.log".to_string(),
             show_fps: true,
+            line_width: 2.0,
+            show_shapes: false,
+            show_inspector: false,
+            show_sprites: false,
+            sprite_image: String::new(),
         }
     }
 }
 
 /// Control key bindings
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ControlsConfig {
     pub fullscreen_key: String,
@@ -99,10 +151,165 @@ impl Default for ControlsConfig {
     }
 }
 
+/// Scene settings
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneConfig {
+    pub cube_count: u32,
+    pub cube_spacing: f32,
+    /// Mesh drawn for every instance: a primitive name (see
+    /// `Primitive::from_name`), the animated `"tentacle"` and `"blob"`
+    /// demos, an `.obj` file or a `.mesh` cache
+    pub mesh: String,
+    /// Detail of the shape (0 = the shape's default)
+    pub subdivisions: u32,
+    /// Regenerate normals: "keep", "smooth" or "flat"
+    pub normals: String,
+    /// Asset loader threads (0 = one per core but one, at most 4)
+    pub loader_threads: u32,
+}
+
+impl Default for SceneConfig {
+    fn default() -> Self {
+        Self {
+            cube_count: 1,
+            cube_spacing: 2.0,
+            mesh: "cube".to_string(),
+            subdivisions: 0,
+            normals: "keep".to_string(),
+            loader_threads: 0,
+        }
+    }
+}
+
+/// Keyframed transform animation of every instance (see anim::transform)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnimationConfig {
+    /// "real_time", "fixed" or "manual" (see anim::clock)
+    pub clock: String,
+    /// Seconds per fixed step and per single step
+    pub fixed_step: f32,
+    /// Animation seconds per clock second
+    pub time_scale: f32,
+    /// "once", "loop" or "ping_pong"
+    pub mode: String,
+    pub translation: Vec<KeyframeConfig>,
+    /// Euler angles in degrees about X, Y and Z (applied as Y * X * Z)
+    pub rotation: Vec<KeyframeConfig>,
+    pub scale: Vec<KeyframeConfig>,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        // The original tumble (0.3 rad/s about X, 0.5 rad/s about Y), which
+        // comes back round after 20π seconds
+        let turns = |time: f32, value: [f32; 3]| KeyframeConfig { time, value, easing: default_easing() };
+        Self {
+            clock: "real_time".to_string(),
+            fixed_step: 1.0 / 60.0,
+            time_scale: 1.0,
+            mode: "loop".to_string(),
+            translation: Vec::new(),
+            rotation: vec![
+                turns(0.0, [0.0; 3]),
+                turns(20.0 * std::f32::consts::PI, [1080.0, 1800.0, 0.0]),
+            ],
+            scale: Vec::new(),
+        }
+    }
+}
+
+/// One key of an [animation] track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyframeConfig {
+    /// Seconds from the start of the clip
+    pub time: f32,
+    pub value: [f32; 3],
+    /// Curve to the next key: "step", "linear", "ease_in", "ease_out" or
+    /// "ease_in_out"
+    #[serde(default = "default_easing")]
+    pub easing: String,
+}
+
+fn default_easing() -> String {
+    "linear".to_string()
+}
+
+/// Camera orbiting the origin
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraConfig {
+    /// Vertical field of view in degrees
+    pub fov_y: f32,
+    /// Orbit angles in degrees; 0/0 looks down -Z from the +Z axis
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Distance from the origin (0 = back off until the whole scene fits)
+    pub distance: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            fov_y: 45.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            distance: 0.0,
+        }
+    }
+}
+
+/// Directional key and fill lights of the lit cube shader
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightingConfig {
+    /// Direction towards the light (normalized by the shader)
+    pub key_direction: [f32; 3],
+    pub key_intensity: f32,
+    pub fill_direction: [f32; 3],
+    pub fill_intensity: f32,
+    pub ambient: f32,
+    /// Diffuse falloff: "lambert" or "half_lambert" (baked into the cube
+    /// pipeline as a specialization constant)
+    pub model: String,
+}
+
+impl Default for LightingConfig {
+    fn default() -> Self {
+        Self {
+            key_direction: [1.0, 1.0, 1.0],
+            key_intensity: 0.7,
+            fill_direction: [-0.5, 0.3, -0.5],
+            fill_intensity: 0.3,
+            ambient: 0.15,
+            model: "lambert".to_string(),
+        }
+    }
+}
+
+/// Diffuse falloff of cube.frag (its LIGHTING_MODEL specialization constant)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightingModel {
+    /// max(N.L, 0)
+    Lambert = 0,
+    /// (N.L * 0.5 + 0.5)^2: light wraps past the terminator
+    HalfLambert = 1,
+}
+
+impl LightingModel {
+    /// SpecId of LIGHTING_MODEL (`#pragma spec_constant` in cube.frag)
+    const SPEC_ID: u32 = 0;
+    
+    pub fn specialization(self) -> SpecializationConstants {
+        SpecializationConstants::new().with_u32(Self::SPEC_ID, self as u32)
+    }
+}
+
 impl Config {
     /// Load configuration from file, falling back to defaults if not found
     pub fn load() -> Self {
-        Self::load_from_path("config.toml").unwrap_or_else(|e| {
+        Self::load_from_path(CONFIG_PATH).unwrap_or_else(|e| {
             log::warn!("Failed to load config.toml: {}. Using defaults.", e);
             Config::default()
         })
@@ -129,6 +336,64 @@ impl Config {
         Ok(config)
     }
     
+    /// Write the configuration to `path`
+    ///
+    /// Values in an existing file are replaced in place, so its comments and
+    /// ordering survive; settings it does not mention yet are appended.
+    pub fn save_to_path<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        
+        let updated: toml_edit::DocumentMut = toml::to_string(self)
+            .context("Failed to serialize config")?
+            .parse()
+            .context("Failed to re-parse serialized config")?;
+        
+        let mut document: toml_edit::DocumentMut = if path.exists() {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config file: {:?}", path))?
+                .parse()
+                .with_context(|| format!("Failed to parse config file: {:?}", path))?
+        } else {
+            toml_edit::DocumentMut::new()
+        };
+        
+        for (section, item) in updated.iter() {
+            let Some(table) = item.as_table() else {
+                continue;
+            };
+            let target = document
+                .entry(section)
+                .or_insert_with(toml_edit::table)
+                .as_table_mut()
+                .with_context(|| format!("[{}] in {:?} is not a table", section, path))?;
+            
+            for (key, value) in table.iter() {
+                let Some(value) = value.as_value() else {
+                    continue;
+                };
+                let mut value = value.clone();
+                shortest_floats(&mut value);
+                match target.get_mut(key).and_then(|existing| existing.as_value_mut()) {
+                    // Keep the comment and spacing around the old value
+                    Some(existing) => {
+                        let decor = existing.decor().clone();
+                        *existing = value;
+                        *existing.decor_mut() = decor;
+                    }
+                    None => {
+                        target.insert(key, toml_edit::value(value));
+                    }
+                }
+            }
+        }
+        
+        std::fs::write(path, document.to_string())
+            .with_context(|| format!("Failed to write config file: {:?}", path))?;
+        
+        log::info!("Saved configuration to {:?}", path);
+        Ok(())
+    }
+    
     /// Get present mode as Vulkan enum
     pub fn get_present_mode(&self) -> ash::vk::PresentModeKHR {
         match self.graphics.present_mode.to_lowercase().as_str() {
@@ -145,4 +410,104 @@ impl Config {
             }
         }
     }
+    
+    /// Get the culling mode (GPU-driven, CPU fallback or off)
+    pub fn get_culling_mode(&self) -> CullingMode {
+        match self.graphics.culling.to_lowercase().as_str() {
+            "gpu" => CullingMode::Gpu,
+            "cpu" => CullingMode::Cpu,
+            "off" | "none" => CullingMode::Off,
+            _ => {
+                log::warn!(
+                    "Unknown culling mode '{}', defaulting to GPU",
+                    self.graphics.culling
+                );
+                CullingMode::Gpu
+            }
+        }
+    }
+    
+    /// Get the normals to regenerate for the scene mesh (None keeps its own)
+    pub fn get_normal_mode(&self) -> Option<NormalMode> {
+        match self.scene.normals.to_lowercase().as_str() {
+            "keep" => None,
+            "smooth" => Some(NormalMode::Smooth),
+            "flat" => Some(NormalMode::Flat),
+            _ => {
+                log::warn!("Unknown normal mode '{}', keeping the mesh's normals", self.scene.normals);
+                None
+            }
+        }
+    }
+    
+    /// Get the diffuse lighting model of the cube shader
+    pub fn get_lighting_model(&self) -> LightingModel {
+        match self.lighting.model.to_lowercase().as_str() {
+            "lambert" => LightingModel::Lambert,
+            "half_lambert" => LightingModel::HalfLambert,
+            _ => {
+                log::warn!("Unknown lighting model '{}', defaulting to lambert", self.lighting.model);
+                LightingModel::Lambert
+            }
+        }
+    }
+    
+    /// The animation clock of the [animation] section
+    pub fn get_clock(&self) -> Clock {
+        let animation = &self.animation;
+        let mode = ClockMode::from_name(&animation.clock).unwrap_or_else(|| {
+            log::warn!("Unknown clock '{}', defaulting to real_time", animation.clock);
+            ClockMode::RealTime
+        });
+        Clock::new(mode, animation.fixed_step as f64, animation.time_scale as f64)
+    }
+    
+    /// The [animation] clip; an invalid track falls back to the default clip
+    pub fn get_transform_clip(&self) -> TransformClip {
+        let animation = &self.animation;
+        let mode = PlayMode::from_name(&animation.mode).unwrap_or_else(|| {
+            log::warn!("Unknown animation mode '{}', defaulting to loop", animation.mode);
+            PlayMode::Loop
+        });
+        let track = |name: &str, keys: &[KeyframeConfig]| {
+            let keys = keys
+                .iter()
+                .map(|key| Keyframe {
+                    time: key.time,
+                    value: key.value.into(),
+                    easing: Easing::from_name(&key.easing).unwrap_or_else(|| {
+                        log::warn!("Unknown easing '{}' in the {} track, using linear", key.easing, name);
+                        Easing::Linear
+                    }),
+                })
+                .collect();
+            Track::new(keys).with_context(|| format!("Invalid {} track", name))
+        };
+        let tracks = track("translation", &animation.translation).and_then(|translation| {
+            Ok((translation, track("rotation", &animation.rotation)?, track("scale", &animation.scale)?))
+        });
+        match tracks {
+            Ok((translation, rotation, scale)) => TransformClip::new(mode, translation, rotation, scale),
+            Err(e) => {
+                log::warn!("{:#} in [animation], using the default clip", e);
+                TransformClip { mode, ..Config::default().get_transform_clip() }
+            }
+        }
+    }
+}
+
+/// Write floats with the shortest spelling that reads back as the same f32
+/// (0.7 rather than 0.699999988079071), since every setting is stored as f32
+fn shortest_floats(value: &mut toml_edit::Value) {
+    match value {
+        toml_edit::Value::Float(float) => {
+            let single = *float.value() as f32;
+            let decor = float.decor().clone();
+            let mut shortest = toml_edit::Formatted::new(single.to_string().parse().unwrap_or(single as f64));
+            *shortest.decor_mut() = decor;
+            *float = shortest;
+        }
+        toml_edit::Value::Array(array) => array.iter_mut().for_each(shortest_floats),
+        _ => {}
+    }
 }
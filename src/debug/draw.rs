@@ -0,0 +1,329 @@
+// Immediate-mode debug drawing
+//
+// Anything can queue lines and shapes on a `DebugDraw` during a frame; the
+// renderer copies them into this frame-in-flight's vertex buffer and draws
+// them after the scene with a LINE_LIST pipeline. Shapes are either depth
+// tested against the scene or drawn as an overlay on top of everything
+// (`set_overlay`). The list is cleared every frame, so shapes that should
+// stay visible are simply queued again.
+
+use anyhow::Result;
+use ash::vk;
+use glam::{Mat4, Vec3};
+use crate::backend::buffer::DynamicBuffer;
+use crate::backend::culling::Aabb;
+use crate::backend::layout::{PushConstants, Std430, VertexLayout};
+use crate::backend::permutation::SpecializationConstants;
+use crate::backend::pipeline::{GraphicsPipeline, PipelineState};
+use crate::backend::VulkanDevice;
+
+/// Vertices per frame in flight (1 MB at 28 bytes each); more are dropped
+const MAX_VERTICES: usize = 1 << 15;
+
+/// Segments per circle of `DebugDraw::sphere`
+const CIRCLE_SEGMENTS: usize = 24;
+
+pub const RED: [f32; 4] = [1.0, 0.2, 0.2, 1.0];
+pub const GREEN: [f32; 4] = [0.2, 1.0, 0.2, 1.0];
+pub const BLUE: [f32; 4] = [0.3, 0.4, 1.0, 1.0];
+pub const YELLOW: [f32; 4] = [1.0, 0.9, 0.2, 1.0];
+pub const GRAY: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+
+/// One line endpoint (locations 0/1 in debug_line.vert)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, VertexLayout)]
+pub struct DebugVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+/// Push constants for debug_line.vert
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PushConstants)]
+struct DebugLinePushConstants {
+    view_proj: Mat4,
+}
+
+/// Lines queued for the current frame
+#[derive(Debug, Default)]
+pub struct DebugDraw {
+    depth_tested: Vec<DebugVertex>,
+    overlay: Vec<DebugVertex>,
+    /// Where shapes added from now on go
+    overlay_mode: bool,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Drop everything queued (and go back to depth-tested shapes)
+    pub fn clear(&mut self) {
+        self.depth_tested.clear();
+        self.overlay.clear();
+        self.overlay_mode = false;
+    }
+    
+    /// Draw shapes added from now on over the scene instead of depth testing them
+    pub fn set_overlay(&mut self, overlay: bool) {
+        self.overlay_mode = overlay;
+    }
+    
+    pub fn line(&mut self, a: Vec3, b: Vec3, color: [f32; 4]) {
+        let list = if self.overlay_mode { &mut self.overlay } else { &mut self.depth_tested };
+        list.push(DebugVertex { position: a.to_array(), color });
+        list.push(DebugVertex { position: b.to_array(), color });
+    }
+    
+    /// The 12 edges of a box
+    pub fn aabb(&mut self, aabb: &Aabb, color: [f32; 4]) {
+        let corner = |i: usize| Vec3::new(
+            if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+            if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+            if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+        );
+        self.box_edges(corner, color);
+    }
+    
+    /// Three axis-aligned circles
+    /// Used in: bounding sphere and light radius visualization
+    #[allow(dead_code)]
+    pub fn sphere(&mut self, center: Vec3, radius: f32, color: [f32; 4]) {
+        let point = |axis: usize, i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            let (sin, cos) = angle.sin_cos();
+            let offset = match axis {
+                0 => Vec3::new(cos, sin, 0.0),
+                1 => Vec3::new(0.0, cos, sin),
+                _ => Vec3::new(sin, 0.0, cos),
+            };
+            center + offset * radius
+        };
+        for axis in 0..3 {
+            for i in 0..CIRCLE_SEGMENTS {
+                self.line(point(axis, i), point(axis, i + 1), color);
+            }
+        }
+    }
+    
+    /// The volume a view-projection matrix sees (Vulkan depth 0..1)
+    /// Used in: camera and shadow cascade visualization
+    #[allow(dead_code)]
+    pub fn frustum(&mut self, view_proj: Mat4, color: [f32; 4]) {
+        let inverse = view_proj.inverse();
+        let corner = |i: usize| inverse.project_point3(Vec3::new(
+            if i & 1 == 0 { -1.0 } else { 1.0 },
+            if i & 2 == 0 { -1.0 } else { 1.0 },
+            if i & 4 == 0 { 0.0 } else { 1.0 },
+        ));
+        self.box_edges(corner, color);
+    }
+    
+    /// X/Y/Z arrows (red/green/blue) of a transform, `size` long
+    pub fn axes(&mut self, transform: Mat4, size: f32) {
+        let origin = transform.transform_point3(Vec3::ZERO);
+        for (axis, color) in [(Vec3::X, RED), (Vec3::Y, GREEN), (Vec3::Z, BLUE)] {
+            self.arrow(origin, transform.transform_point3(axis * size), color);
+        }
+    }
+    
+    /// A square grid on the XZ plane through `center`, `cells` cells per side
+    pub fn grid(&mut self, center: Vec3, half_size: f32, cells: u32, color: [f32; 4]) {
+        let cells = cells.max(1);
+        let step = 2.0 * half_size / cells as f32;
+        for i in 0..=cells {
+            let offset = -half_size + i as f32 * step;
+            self.line(
+                center + Vec3::new(offset, 0.0, -half_size),
+                center + Vec3::new(offset, 0.0, half_size),
+                color,
+            );
+            self.line(
+                center + Vec3::new(-half_size, 0.0, offset),
+                center + Vec3::new(half_size, 0.0, offset),
+                color,
+            );
+        }
+    }
+    
+    /// A line with a four-sided head at `to`
+    pub fn arrow(&mut self, from: Vec3, to: Vec3, color: [f32; 4]) {
+        self.line(from, to, color);
+        
+        let length = from.distance(to);
+        if length <= f32::EPSILON {
+            return;
+        }
+        let direction = (to - from) / length;
+        let (u, v) = direction.any_orthonormal_pair();
+        let head = length.min(1.0) * 0.2;
+        let base = to - direction * head;
+        for side in [u, -u, v, -v] {
+            self.line(to, base + side * head * 0.5, color);
+        }
+    }
+    
+    /// Edges of a box given its corners (bit 0 = +x, bit 1 = +y, bit 2 = +z)
+    fn box_edges(&mut self, corner: impl Fn(usize) -> Vec3, color: [f32; 4]) {
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.line(corner(i), corner(i | bit), color);
+                }
+            }
+        }
+    }
+}
+
+// =============================================================================
+// RENDERING
+// =============================================================================
+
+/// Pipelines and per-frame vertex buffers that draw a `DebugDraw`
+pub struct DebugDrawRenderer {
+    depth_tested: GraphicsPipeline,
+    overlay: GraphicsPipeline,
+    /// One vertex buffer per frame in flight
+    buffers: Vec<DynamicBuffer>,
+    /// Vertices uploaded per frame: depth tested first, then overlay
+    counts: Vec<(u32, u32)>,
+    warned_overflow: bool,
+}
+
+impl DebugDrawRenderer {
+    /// `line_width` is clamped to what the device supports (1.0 without `wideLines`)
+    pub fn new(
+        device: &VulkanDevice,
+        render_pass: vk::RenderPass,
+        extent: vk::Extent2D,
+        frames_in_flight: usize,
+        line_width: f32,
+    ) -> Result<Self> {
+        let vert_shader = crate::load_shader!(device, "debug_line.vert")?;
+        let shader_push_size = vert_shader.reflection.push_constant_size.unwrap_or(0) as usize;
+        if shader_push_size != DebugLinePushConstants::SIZE {
+            vert_shader.destroy(&device.device);
+            anyhow::bail!(
+                "debug_line.vert declares {} bytes of push constants, DebugLinePushConstants is {} bytes",
+                shader_push_size,
+                DebugLinePushConstants::SIZE
+            );
+        }
+        let frag_shader = match crate::load_shader!(device, "debug_line.frag") {
+            Ok(shader) => shader,
+            Err(e) => {
+                vert_shader.destroy(&device.device);
+                return Err(e);
+            }
+        };
+        
+        // Lines are tested against the scene but never hide each other or it
+        let depth_tested_state = PipelineState {
+            topology: vk::PrimitiveTopology::LINE_LIST,
+            cull_mode: vk::CullModeFlags::NONE,
+            line_width: device.line_width(line_width),
+            depth_write: false,
+            depth_compare: vk::CompareOp::LESS_OR_EQUAL,
+            ..Default::default()
+        };
+        let overlay_state = PipelineState { depth_test: false, ..depth_tested_state };
+        
+        let vertex_input = crate::backend::pipeline::get_vertex_input_info::<DebugVertex>();
+        let create = |state: &PipelineState| GraphicsPipeline::new(
+            device,
+            render_pass,
+            extent,
+            &vertex_input,
+            (&vert_shader, &frag_shader),
+            &SpecializationConstants::new(),
+            state,
+        );
+        let pipelines = create(&depth_tested_state).and_then(|depth_tested| {
+            match create(&overlay_state) {
+                Ok(overlay) => Ok((depth_tested, overlay)),
+                Err(e) => {
+                    depth_tested.destroy(&device.device);
+                    Err(e)
+                }
+            }
+        });
+        
+        vert_shader.destroy(&device.device);
+        frag_shader.destroy(&device.device);
+        
+        let (depth_tested, overlay) = pipelines?;
+        
+        let capacity = (MAX_VERTICES * std::mem::size_of::<DebugVertex>()) as vk::DeviceSize;
+        let buffers = (0..frames_in_flight)
+            .map(|_| DynamicBuffer::new(device, capacity, vk::BufferUsageFlags::VERTEX_BUFFER))
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(Self {
+            depth_tested,
+            overlay,
+            buffers,
+            counts: vec![(0, 0); frames_in_flight],
+            warned_overflow: false,
+        })
+    }
+    
+    /// Copy this frame's lines into the buffer of frame-in-flight `frame`
+    /// (its fence must have signaled)
+    pub fn upload(&mut self, device: &ash::Device, frame: usize, draw: &DebugDraw) -> Result<()> {
+        let total = draw.depth_tested.len() + draw.overlay.len();
+        if total > MAX_VERTICES && !self.warned_overflow {
+            log::warn!("Debug draw: {} vertices queued, only {} are drawn", total, MAX_VERTICES);
+            self.warned_overflow = true;
+        }
+        
+        let mut vertices = Vec::with_capacity(total.min(MAX_VERTICES));
+        vertices.extend_from_slice(&draw.depth_tested);
+        vertices.extend_from_slice(&draw.overlay);
+        let written = self.buffers[frame].write(device, &vertices)?;
+        
+        let depth_tested = written.min(draw.depth_tested.len());
+        self.counts[frame] = (depth_tested as u32, (written - depth_tested) as u32);
+        Ok(())
+    }
+    
+    /// Draw calls `cmd_draw` records for frame `frame` (0-2)
+    pub fn draw_count(&self, frame: usize) -> u32 {
+        let (depth_tested, overlay) = self.counts[frame];
+        (depth_tested > 0) as u32 + (overlay > 0) as u32
+    }
+    
+    /// Draw frame `frame`'s lines (inside the render pass, after the scene)
+    pub fn cmd_draw(&self, device: &ash::Device, cmd: vk::CommandBuffer, frame: usize, view_proj: Mat4) {
+        let (depth_tested, overlay) = self.counts[frame];
+        if depth_tested + overlay == 0 {
+            return;
+        }
+        
+        let mut push_bytes = [0u8; DebugLinePushConstants::SIZE];
+        DebugLinePushConstants { view_proj }.write_bytes(&mut push_bytes);
+        
+        unsafe {
+            device.cmd_bind_vertex_buffers(cmd, 0, &[self.buffers[frame].buffer], &[0]);
+            for (pipeline, first, count) in [
+                (&self.depth_tested, 0, depth_tested),
+                (&self.overlay, depth_tested, overlay),
+            ] {
+                if count == 0 {
+                    continue;
+                }
+                device.cmd_bind_pipeline(cmd, vk::PipelineBindPoint::GRAPHICS, pipeline.pipeline);
+                device.cmd_push_constants(cmd, pipeline.layout, vk::ShaderStageFlags::VERTEX, 0, &push_bytes);
+                device.cmd_draw(cmd, count, 1, first, 0);
+            }
+        }
+    }
+    
+    pub fn destroy(&self, device: &ash::Device) {
+        self.depth_tested.destroy(device);
+        self.overlay.destroy(device);
+        for buffer in &self.buffers {
+            buffer.destroy(device);
+        }
+    }
+}
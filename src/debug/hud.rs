@@ -0,0 +1,192 @@
+// Stats HUD
+//
+// Frame times of the last few seconds are kept so the HUD can show a smoothed
+// FPS and a frame-time graph next to renderer counters. It is laid out into a
+// ui::overlay::Overlay in the top-left corner every frame it is visible.
+
+use ash::vk;
+use glam::Vec2;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use crate::ui::overlay::Overlay;
+
+/// How much frame-time history the graph shows
+const HISTORY: Duration = Duration::from_secs(5);
+/// FPS and frame time are averaged over this window
+const AVERAGE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Graph bars (each covers HISTORY / GRAPH_COLUMNS) and their pixel size
+const GRAPH_COLUMNS: usize = 120;
+const GRAPH_BAR_WIDTH: f32 = 2.0;
+const GRAPH_HEIGHT: f32 = 60.0;
+/// The graph's vertical range is at least this (2 frames at 60 Hz)
+const GRAPH_MIN_RANGE_MS: f32 = 33.3;
+
+const MARGIN: f32 = 8.0;
+const PADDING: f32 = 6.0;
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const TEXT: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+const GOOD: [f32; 4] = [0.2, 0.8, 0.2, 1.0];
+const SLOW: [f32; 4] = [0.9, 0.7, 0.1, 1.0];
+const BAD: [f32; 4] = [0.9, 0.2, 0.1, 1.0];
+
+/// Renderer counters for one frame, gathered by the app
+pub struct HudStats {
+    /// Last measured GPU frame time (None without timestamp support)
+    pub gpu_ms: Option<f32>,
+    pub draw_calls: u32,
+    /// None when the GPU decides what is drawn (indirect draws)
+    pub triangles: Option<u64>,
+    /// Device-local bytes (used, budget)
+    pub memory: Option<(u64, u64)>,
+    pub present_mode: vk::PresentModeKHR,
+    pub view: &'static str,
+    pub culling: String,
+    /// Animation clock state
+    pub clock: String,
+    /// Smoothed input-to-present latency (None before any input)
+    pub latency_ms: Option<f32>,
+    /// Assets still loading in the background
+    pub loading: usize,
+}
+
+/// Frame-time history and visibility of the stats overlay
+pub struct Hud {
+    pub visible: bool,
+    /// (end of frame, CPU frame time in ms), oldest first
+    frame_times: VecDeque<(Instant, f32)>,
+}
+
+impl Hud {
+    pub fn new(visible: bool) -> Self {
+        Self { visible, frame_times: VecDeque::new() }
+    }
+    
+    /// Record a finished frame (kept while hidden, so the graph is full when shown)
+    pub fn record_frame(&mut self, now: Instant, frame_ms: f32) {
+        self.frame_times.push_back((now, frame_ms));
+        while let Some(&(time, _)) = self.frame_times.front() {
+            if now.duration_since(time) <= HISTORY {
+                break;
+            }
+            self.frame_times.pop_front();
+        }
+    }
+    
+    /// Average frame time in ms over the last second
+    fn average_ms(&self) -> Option<f32> {
+        let (latest, _) = *self.frame_times.back()?;
+        let recent: Vec<f32> = self.frame_times
+            .iter()
+            .rev()
+            .take_while(|(time, _)| latest.duration_since(*time) <= AVERAGE_WINDOW)
+            .map(|&(_, ms)| ms)
+            .collect();
+        Some(recent.iter().sum::<f32>() / recent.len() as f32)
+    }
+    
+    /// Lay out the HUD into `overlay` (top-left corner)
+    pub fn build(&self, overlay: &mut Overlay, stats: &HudStats) {
+        if !self.visible {
+            return;
+        }
+        
+        let average_ms = self.average_ms().unwrap_or(0.0);
+        let fps = if average_ms > 0.0 { 1000.0 / average_ms } else { 0.0 };
+        let gpu = match stats.gpu_ms {
+            Some(ms) => format!("{:.2} ms", ms),
+            None => "n/a".to_string(),
+        };
+        let triangles = match stats.triangles {
+            Some(count) => format_count(count),
+            None => "GPU-driven".to_string(),
+        };
+        let memory = match stats.memory {
+            Some((used, budget)) => format!("{} / {} MB", used >> 20, budget >> 20),
+            None => "n/a".to_string(),
+        };
+        let mut text = format!(
+            "FPS {:.0} ({:.2} ms)\nGPU {}\nDraws {}  Tris {}\nVRAM {}\nPresent {:?}\nView {}\n{}\n{}",
+            fps,
+            average_ms,
+            gpu,
+            stats.draw_calls,
+            triangles,
+            memory,
+            stats.present_mode,
+            stats.view,
+            stats.culling,
+            stats.clock,
+        );
+        if let Some(latency) = stats.latency_ms {
+            text += &format!("\nLatency {:.1} ms (input to present)", latency);
+        }
+        if stats.loading > 0 {
+            text += &format!("\nLoading {} asset(s)", stats.loading);
+        }
+        
+        let text_size = overlay.text_size(&text);
+        let graph_width = GRAPH_COLUMNS as f32 * GRAPH_BAR_WIDTH;
+        let panel_size = Vec2::new(
+            text_size.x.max(graph_width),
+            text_size.y + PADDING + GRAPH_HEIGHT,
+        ) + Vec2::splat(2.0 * PADDING);
+        let origin = Vec2::splat(MARGIN);
+        
+        overlay.rect(origin, panel_size, BACKGROUND);
+        overlay.text(origin + Vec2::splat(PADDING), &text, TEXT);
+        self.build_graph(
+            overlay,
+            origin + Vec2::new(PADDING, PADDING * 2.0 + text_size.y),
+            graph_width,
+        );
+    }
+    
+    /// Frame-time bars, newest on the right, with a 60 Hz reference line
+    fn build_graph(&self, overlay: &mut Overlay, origin: Vec2, width: f32) {
+        overlay.rect(origin, Vec2::new(width, GRAPH_HEIGHT), [0.1, 0.1, 0.1, 0.6]);
+        
+        let Some(&(latest, _)) = self.frame_times.back() else {
+            return;
+        };
+        
+        // Worst frame per column, so single spikes stay visible
+        let mut columns = [0.0f32; GRAPH_COLUMNS];
+        let column_span = HISTORY.as_secs_f32() / GRAPH_COLUMNS as f32;
+        for &(time, ms) in &self.frame_times {
+            let age = latest.duration_since(time).as_secs_f32();
+            let column = GRAPH_COLUMNS - 1 - ((age / column_span) as usize).min(GRAPH_COLUMNS - 1);
+            columns[column] = columns[column].max(ms);
+        }
+        
+        let range = columns.iter().copied().fold(GRAPH_MIN_RANGE_MS, f32::max);
+        for (i, &ms) in columns.iter().enumerate() {
+            if ms <= 0.0 {
+                continue;
+            }
+            let height = (ms / range * GRAPH_HEIGHT).max(1.0);
+            let color = match ms {
+                ms if ms <= 1000.0 / 60.0 => GOOD,
+                ms if ms <= 1000.0 / 30.0 => SLOW,
+                _ => BAD,
+            };
+            overlay.rect(
+                origin + Vec2::new(i as f32 * GRAPH_BAR_WIDTH, GRAPH_HEIGHT - height),
+                Vec2::new(GRAPH_BAR_WIDTH, height),
+                color,
+            );
+        }
+        
+        let target_y = GRAPH_HEIGHT - (1000.0 / 60.0) / range * GRAPH_HEIGHT;
+        overlay.rect(origin + Vec2::new(0.0, target_y), Vec2::new(width, 1.0), [1.0, 1.0, 1.0, 0.4]);
+    }
+}
+
+/// 1234 -> "1234", 12345 -> "12.3k", 1234567 -> "1.23M"
+fn format_count(count: u64) -> String {
+    match count {
+        0..=9_999 => count.to_string(),
+        10_000..=999_999 => format!("{:.1}k", count as f32 / 1e3),
+        _ => format!("{:.2}M", count as f32 / 1e6),
+    }
+}
@@ -0,0 +1,12 @@
+// Debug visualization
+//
+// - draw: immediate-mode lines and shapes (boxes, spheres, axes, grids...)
+// - hud: stats overlay (FPS, frame-time graph, GPU time, counters)
+// - sprites: procedural atlas and scene exercising the 2D sprite layer
+// - view: full-screen debug views (wireframe, normals, depth, UVs, overdraw),
+//   each a variant of the cube pipeline selected at runtime
+
+pub mod draw;
+pub mod hud;
+pub mod sprites;
+pub mod view;
@@ -0,0 +1,122 @@
+// Sprite layer demo
+//
+// A procedural atlas (a framed panel, a disc and a diamond) and a small
+// scene that exercises the sprite batch: a nine-slice panel in the
+// bottom-right corner with tinted, rotating sprites circling inside it on
+// two layers, plus the `[debug] sprite_image` (if any) left of the panel.
+// Shown with F10.
+
+use std::f32::consts::TAU;
+use anyhow::Result;
+use ash::vk;
+use glam::Vec2;
+use crate::sprite::atlas::{AtlasBuilder, AtlasImage, SpriteAtlas};
+use crate::sprite::batch::{NineSlice, Sprite, SpriteBatch, TextureId};
+use crate::sprite::camera::Camera2D;
+
+const PANEL_SIZE: Vec2 = Vec2::new(300.0, 160.0);
+const MARGIN: f32 = 8.0;
+/// Corner radius plus frame of the panel image, kept unstretched
+const PANEL_BORDER: f32 = 8.0;
+const ORBITERS: usize = 6;
+const TINTS: [[f32; 4]; ORBITERS] = [
+    [1.0, 0.3, 0.3, 1.0],
+    [1.0, 0.7, 0.2, 1.0],
+    [0.9, 1.0, 0.3, 1.0],
+    [0.3, 1.0, 0.5, 1.0],
+    [0.3, 0.7, 1.0, 1.0],
+    [0.8, 0.4, 1.0, 1.0],
+];
+
+/// The demo's images packed into one atlas
+pub fn atlas_image() -> Result<AtlasImage> {
+    let mut builder = AtlasBuilder::new(128);
+    builder
+        .add("panel", 32, 32, panel_pixels(32))?
+        .add("disc", 32, 32, shape_pixels(32, |p| p.length()))?
+        .add("diamond", 32, 32, shape_pixels(32, |p| p.x.abs() + p.y.abs()))?;
+    builder.build()
+}
+
+/// Queue the demo for a viewport of `extent` pixels, `time` seconds in;
+/// `image` is a whole texture and its size in texels
+pub fn queue(
+    batch: &mut SpriteBatch,
+    atlas: &SpriteAtlas,
+    image: Option<(TextureId, Vec2)>,
+    extent: vk::Extent2D,
+    time: f32,
+) -> Result<()> {
+    batch.camera = Camera2D::screen(extent);
+    let corner = Vec2::new(extent.width as f32, extent.height as f32) - MARGIN;
+    let center = corner - PANEL_SIZE * 0.5;
+    
+    // Discs go on top of diamonds whatever order they are queued in
+    for (i, tint) in TINTS.iter().enumerate() {
+        let angle = time + i as f32 / ORBITERS as f32 * TAU;
+        let offset = Vec2::new(angle.cos() * 110.0, angle.sin() * 45.0);
+        let (name, layer) = if i % 2 == 0 { ("disc", 2) } else { ("diamond", 1) };
+        batch.draw(
+            atlas.sprite(name)?
+                .at(center + offset)
+                .rotation(angle * 2.0)
+                .tint(*tint)
+                .layer(layer),
+        );
+    }
+    
+    batch.draw(
+        atlas.sprite("panel")?
+            .at(corner)
+            .pivot(Vec2::ONE)
+            .size(PANEL_SIZE)
+            .nine_slice(NineSlice::uniform(PANEL_BORDER)),
+    );
+    
+    // Scaled to the panel's height, aspect kept
+    if let Some((texture, size)) = image {
+        batch.draw(
+            Sprite::new(texture, [0.0, 0.0, 1.0, 1.0], size)
+                .at(corner - Vec2::new(PANEL_SIZE.x + MARGIN, 0.0))
+                .pivot(Vec2::ONE)
+                .size(size * (PANEL_SIZE.y / size.y.max(1.0))),
+        );
+    }
+    Ok(())
+}
+
+/// RGBA8 image of `size`² texels from a function of the texel center
+/// (-1..1 across the image)
+fn pixels(size: u32, texel: impl Fn(Vec2) -> [u8; 4]) -> Vec<u8> {
+    (0..size * size)
+        .flat_map(|i| {
+            let center = Vec2::new((i % size) as f32 + 0.5, (i / size) as f32 + 0.5);
+            texel(center / size as f32 * 2.0 - 1.0)
+        })
+        .collect()
+}
+
+/// White shape where `distance` (0 at the center) is under 1, antialiased
+fn shape_pixels(size: u32, distance: impl Fn(Vec2) -> f32) -> Vec<u8> {
+    let texels_per_unit = size as f32 * 0.5;
+    pixels(size, |p| {
+        let coverage = ((1.0 - distance(p)) * texels_per_unit + 0.5).clamp(0.0, 1.0);
+        [255, 255, 255, (coverage * 255.0).round() as u8]
+    })
+}
+
+/// Translucent dark rounded rectangle with a light 2-texel frame
+fn panel_pixels(size: u32) -> Vec<u8> {
+    let half = size as f32 * 0.5;
+    let radius = 6.0;
+    pixels(size, |p| {
+        // Signed distance to the rounded rectangle, in texels
+        let q = (p.abs() * half - Vec2::splat(half - radius)).max(Vec2::ZERO);
+        let distance = q.length() - radius;
+        let coverage = (0.5 - distance).clamp(0.0, 1.0);
+        let frame = (distance + 2.5).clamp(0.0, 1.0);
+        let shade = (40.0 + frame * 180.0) as u8;
+        let alpha = 0.75 + frame * 0.25;
+        [shade, shade, shade + 8, (coverage * alpha * 255.0).round() as u8]
+    })
+}
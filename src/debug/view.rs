@@ -0,0 +1,116 @@
+// Debug views
+//
+// Each view is a pipeline variant of the cube pass: a different fragment
+// shader permutation (cube.frag / debug.frag keywords) and/or different
+// fixed-function state. All variants are created up front, so switching is
+// just binding another pipeline when the frame is recorded.
+
+use ash::vk;
+use crate::backend::pipeline::{BlendMode, PipelineState};
+use winit::keyboard::KeyCode;
+
+/// What the cube pass shows, selected with F1-F6
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DebugView {
+    /// Normal shading
+    #[default]
+    Lit,
+    /// Shaded, with triangle edges drawn on top
+    Wireframe,
+    /// World-space normals as colors
+    Normals,
+    /// Linear view depth, near = white
+    Depth,
+    /// Checkerboard over the texture coordinates
+    UvChecker,
+    /// Additive heatmap of how often each pixel is shaded
+    Overdraw,
+}
+
+impl DebugView {
+    pub const ALL: [DebugView; 6] = [
+        DebugView::Lit,
+        DebugView::Wireframe,
+        DebugView::Normals,
+        DebugView::Depth,
+        DebugView::UvChecker,
+        DebugView::Overdraw,
+    ];
+    
+    /// Shown in the window title
+    pub fn name(self) -> &'static str {
+        match self {
+            DebugView::Lit => "Lit",
+            DebugView::Wireframe => "Wireframe",
+            DebugView::Normals => "Normals",
+            DebugView::Depth => "Depth",
+            DebugView::UvChecker => "UV checker",
+            DebugView::Overdraw => "Overdraw",
+        }
+    }
+    
+    /// F1-F6 in declaration order
+    pub fn from_key(key: KeyCode) -> Option<Self> {
+        let index = match key {
+            KeyCode::F1 => 0,
+            KeyCode::F2 => 1,
+            KeyCode::F3 => 2,
+            KeyCode::F4 => 3,
+            KeyCode::F5 => 4,
+            KeyCode::F6 => 5,
+            _ => return None,
+        };
+        Some(Self::ALL[index])
+    }
+    
+    /// Fragment shader and keywords of the extra pipeline this view draws
+    /// with, or None for the plain lit pipeline
+    ///
+    /// The wireframe view draws the lit pass first and this on top.
+    pub fn fragment_variant(self) -> Option<(&'static str, &'static [&'static str])> {
+        match self {
+            DebugView::Lit => None,
+            DebugView::Wireframe => Some(("debug.frag", &["WIREFRAME"])),
+            DebugView::Normals => Some(("cube.frag", &["SHOW_NORMALS"])),
+            DebugView::Depth => Some(("debug.frag", &["DEPTH"])),
+            DebugView::UvChecker => Some(("debug.frag", &["UV_CHECKER"])),
+            DebugView::Overdraw => Some(("debug.frag", &["OVERDRAW"])),
+        }
+    }
+    
+    /// Fixed-function state of this view's pipeline
+    pub fn pipeline_state(self) -> PipelineState {
+        match self {
+            // Edges only, pulled slightly toward the camera so they win the
+            // depth test against the shaded faces they lie on
+            DebugView::Wireframe => PipelineState {
+                polygon_mode: vk::PolygonMode::LINE,
+                depth_write: false,
+                depth_compare: vk::CompareOp::LESS_OR_EQUAL,
+                depth_bias: Some((-1.0, -1.0)),
+                ..Default::default()
+            },
+            // Every fragment counts, hidden or not
+            DebugView::Overdraw => PipelineState {
+                depth_test: false,
+                depth_write: false,
+                blend: BlendMode::Additive,
+                ..Default::default()
+            },
+            _ => PipelineState::default(),
+        }
+    }
+    
+    /// Whether the cube pass also draws with the lit pipeline
+    pub fn draws_lit(self) -> bool {
+        matches!(self, DebugView::Lit | DebugView::Wireframe)
+    }
+    
+    /// Views that accumulate color need a black background
+    pub fn clear_color(self, configured: [f32; 4]) -> [f32; 4] {
+        match self {
+            DebugView::Overdraw => [0.0, 0.0, 0.0, 1.0],
+            _ => configured,
+        }
+    }
+}
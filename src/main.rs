@@ -24,103 +24,532 @@
 //
 // =============================================================================
 
+mod anim;
+mod asset;
 mod backend;
 mod config;
+mod debug;
+mod mesh;
+mod sprite;
+mod ui;
 #[cfg(feature = "bevy")]
 mod bevy_integration;
 
 use anyhow::{Context, Result};
 use ash::vk;
+use anim::clock::Clock;
+use anim::morph::{ActiveMorphs, MorphDelta};
+use anim::player::Rig;
+use anim::skeleton::MAX_JOINTS;
+use anim::transform::TransformClip;
+use asset::image::{self as image_asset, ImageData};
+use asset::loader::{AssetLoader, Handle};
 use backend::{VulkanDevice, Swapchain};
+use backend::buffer::DynamicBuffer;
+use backend::layout::{PushConstants, Std430, VertexLayout};
+use backend::compute::{AsyncCompute, ComputePipeline};
+use backend::culling::{CullResult, CullingMode, Frustum, MeshBounds};
+use backend::descriptor::DescriptorWriter;
+use backend::permutation::{ShaderVariantCache, SpecializationConstants};
+use backend::pipeline::{GraphicsPipeline, PipelineState};
+use backend::pacing::FramePacer;
+use backend::query::GpuTimer;
 use config::Config;
+use debug::draw::{self as debug_draw, DebugDraw, DebugDrawRenderer};
+use debug::hud::{Hud, HudStats};
+use debug::view::DebugView;
+use mesh::lod::{self, LodDraw, LodLevel, LodSelector, LodSettings, MeshLods};
+use mesh::cache::{self as mesh_cache, MappedMesh, MeshAsset, MeshView};
+use mesh::gltf;
+use mesh::obj;
+use mesh::primitives::{MeshData, Primitive};
+use mesh::process::{self as mesh_process, Indices, NormalMode};
+use sprite::atlas::SpriteAtlas;
+use sprite::batch::{SpriteBatch, SpriteRenderer, TextureId};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
 use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
 use std::io::Write;
+use ui::font::BitmapFont;
+use ui::gui::Gui;
+use ui::inspector::Inspector;
+use ui::overlay::{Overlay, OverlayRenderer};
+use ui::painter::GuiRenderer;
+use ui::sdf::SdfFont;
+use ui::text::{Align, TextBatch, TextRenderer, TextStyle};
 use winit::{
     application::ApplicationHandler,
     event::WindowEvent,
-    event_loop::{ActiveEventLoop, EventLoop},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     window::{Window, WindowAttributes, Fullscreen},
 };
 
 // =============================================================================
-// VERTEX DATA & CUBE GEOMETRY
+// VERTEX DATA
 // =============================================================================
 
-/// Vertex structure with position, normal, and color
+/// Vertex structure with position, normal, color, texture coordinates and
+/// skinning joints and weights (locations 0-5 in cube.vert, in field order)
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, VertexLayout)]
 struct Vertex {
     position: [f32; 3],
     normal: [f32; 3],
     color: [f32; 3],
+    uv: [f32; 2],
+    joints: [u8; 4],
+    #[vertex(normalized)]
+    weights: [u16; 4],
 }
 
-/// Cube vertices with proper normals for lighting (24 vertices - 4 per face)
-/// Each face has its own vertices so normals are correct for flat shading
-const CUBE_VERTICES: &[Vertex] = &[
-    // Front face (Z+) - Red
-    Vertex { position: [-0.5, -0.5,  0.5], normal: [0.0, 0.0, 1.0], color: [0.9, 0.2, 0.2] },
-    Vertex { position: [ 0.5, -0.5,  0.5], normal: [0.0, 0.0, 1.0], color: [0.9, 0.2, 0.2] },
-    Vertex { position: [ 0.5,  0.5,  0.5], normal: [0.0, 0.0, 1.0], color: [0.9, 0.2, 0.2] },
-    Vertex { position: [-0.5,  0.5,  0.5], normal: [0.0, 0.0, 1.0], color: [0.9, 0.2, 0.2] },
-    // Back face (Z-) - Green
-    Vertex { position: [ 0.5, -0.5, -0.5], normal: [0.0, 0.0, -1.0], color: [0.2, 0.9, 0.2] },
-    Vertex { position: [-0.5, -0.5, -0.5], normal: [0.0, 0.0, -1.0], color: [0.2, 0.9, 0.2] },
-    Vertex { position: [-0.5,  0.5, -0.5], normal: [0.0, 0.0, -1.0], color: [0.2, 0.9, 0.2] },
-    Vertex { position: [ 0.5,  0.5, -0.5], normal: [0.0, 0.0, -1.0], color: [0.2, 0.9, 0.2] },
-    // Right face (X+) - Blue
-    Vertex { position: [ 0.5, -0.5,  0.5], normal: [1.0, 0.0, 0.0], color: [0.2, 0.2, 0.9] },
-    Vertex { position: [ 0.5, -0.5, -0.5], normal: [1.0, 0.0, 0.0], color: [0.2, 0.2, 0.9] },
-    Vertex { position: [ 0.5,  0.5, -0.5], normal: [1.0, 0.0, 0.0], color: [0.2, 0.2, 0.9] },
-    Vertex { position: [ 0.5,  0.5,  0.5], normal: [1.0, 0.0, 0.0], color: [0.2, 0.2, 0.9] },
-    // Left face (X-) - Yellow
-    Vertex { position: [-0.5, -0.5, -0.5], normal: [-1.0, 0.0, 0.0], color: [0.9, 0.9, 0.2] },
-    Vertex { position: [-0.5, -0.5,  0.5], normal: [-1.0, 0.0, 0.0], color: [0.9, 0.9, 0.2] },
-    Vertex { position: [-0.5,  0.5,  0.5], normal: [-1.0, 0.0, 0.0], color: [0.9, 0.9, 0.2] },
-    Vertex { position: [-0.5,  0.5, -0.5], normal: [-1.0, 0.0, 0.0], color: [0.9, 0.9, 0.2] },
-    // Top face (Y+) - Cyan
-    Vertex { position: [-0.5,  0.5,  0.5], normal: [0.0, 1.0, 0.0], color: [0.2, 0.9, 0.9] },
-    Vertex { position: [ 0.5,  0.5,  0.5], normal: [0.0, 1.0, 0.0], color: [0.2, 0.9, 0.9] },
-    Vertex { position: [ 0.5,  0.5, -0.5], normal: [0.0, 1.0, 0.0], color: [0.2, 0.9, 0.9] },
-    Vertex { position: [-0.5,  0.5, -0.5], normal: [0.0, 1.0, 0.0], color: [0.2, 0.9, 0.9] },
-    // Bottom face (Y-) - Magenta
-    Vertex { position: [-0.5, -0.5, -0.5], normal: [0.0, -1.0, 0.0], color: [0.9, 0.2, 0.9] },
-    Vertex { position: [ 0.5, -0.5, -0.5], normal: [0.0, -1.0, 0.0], color: [0.9, 0.2, 0.9] },
-    Vertex { position: [ 0.5, -0.5,  0.5], normal: [0.0, -1.0, 0.0], color: [0.9, 0.2, 0.9] },
-    Vertex { position: [-0.5, -0.5,  0.5], normal: [0.0, -1.0, 0.0], color: [0.9, 0.2, 0.9] },
+/// Face colors of the original cube, by outward axis: +Z red, -Z green,
+/// +X blue, -X yellow, +Y cyan, -Y magenta
+const FACE_COLORS: [[[f32; 3]; 2]; 3] = [
+    [[0.2, 0.2, 0.9], [0.9, 0.9, 0.2]],
+    [[0.2, 0.9, 0.9], [0.9, 0.2, 0.9]],
+    [[0.9, 0.2, 0.2], [0.2, 0.9, 0.2]],
 ];
 
-/// Cube indices: 12 triangles (2 per face, 6 faces)
-const CUBE_INDICES: &[u16] = &[
-    0,  1,  2,   2,  3,  0,  // Front
-    4,  5,  6,   6,  7,  4,  // Back
-    8,  9,  10,  10, 11, 8,  // Right
-    12, 13, 14,  14, 15, 12, // Left
-    16, 17, 18,  18, 19, 16, // Top
-    20, 21, 22,  22, 23, 20, // Bottom
-];
+/// Attribute differences below this are noise; such vertices are welded
+const WELD_TOLERANCE: f32 = 1e-5;
+
+impl Vertex {
+    /// Interleave a generated mesh, colored by blending the face colors with
+    /// the squared normal components (exactly the face color on axis-aligned
+    /// faces); rigid meshes follow joint 0 only
+    fn from_mesh(mesh: &MeshData) -> Vec<Vertex> {
+        let mut vertices = mesh.positions
+            .iter()
+            .zip(&mesh.normals)
+            .zip(&mesh.uvs)
+            .map(|((&position, &normal), &uv)| {
+                let mut color = [0.0; 3];
+                for (axis, &n) in normal.iter().enumerate() {
+                    let face = FACE_COLORS[axis][(n < 0.0) as usize];
+                    for (c, f) in color.iter_mut().zip(face) {
+                        *c += f * n * n;
+                    }
+                }
+                Vertex { position, normal, color, uv, joints: [0; 4], weights: [u16::MAX, 0, 0, 0] }
+            })
+            .collect::<Vec<_>>();
+        for ((vertex, &joints), &weights) in vertices.iter_mut().zip(&mesh.joints).zip(&mesh.weights) {
+            vertex.joints = joints;
+            vertex.weights = quantize_weights(weights);
+        }
+        vertices
+    }
+}
+
+/// Skin weights as 16-bit unorm, rounded so they still sum to exactly 1
+fn quantize_weights(weights: [f32; 4]) -> [u16; 4] {
+    let total: f32 = weights.iter().sum();
+    if total <= 0.0 {
+        return [u16::MAX, 0, 0, 0];
+    }
+    let mut quantized = weights.map(|w| (w / total * u16::MAX as f32).round() as u16);
+    // Rounding error goes to the heaviest joint
+    let sum: i32 = quantized.iter().map(|&w| w as i32).sum();
+    let heaviest = (0..4).max_by_key(|&i| quantized[i]).unwrap_or(0);
+    quantized[heaviest] = (quantized[heaviest] as i32 + u16::MAX as i32 - sum) as u16;
+    quantized
+}
 
 // =============================================================================
-// HELPER FUNCTIONS
+// MESH ASSETS
 // =============================================================================
 
-/// Convert MVP and Model matrices to bytes for push constants
-fn matrices_to_bytes(mvp: &glam::Mat4, model: &glam::Mat4) -> [u8; 128] {
-    let mut bytes = [0u8; 128];
-    let mvp_cols = mvp.to_cols_array();
-    let model_cols = model.to_cols_array();
-    
-    for (i, &val) in mvp_cols.iter().enumerate() {
-        let val_bytes = val.to_ne_bytes();
-        bytes[i * 4..(i + 1) * 4].copy_from_slice(&val_bytes);
+/// The scene mesh, mapped from a `.mesh` file or built at startup
+enum SceneMesh {
+    Mapped(MappedMesh),
+    Built(MeshAsset),
+}
+
+impl SceneMesh {
+    fn view(&self) -> MeshView<'_> {
+        match self {
+            SceneMesh::Mapped(mesh) => mesh.view(),
+            SceneMesh::Built(mesh) => mesh.view(),
+        }
+    }
+}
+
+/// The scene mesh and what animates it, as the asset loader hands it over
+struct LoadedMesh {
+    mesh: SceneMesh,
+    /// Skeleton, morph weights and clips (animated sources only)
+    rig: Option<Rig>,
+    /// Morph target deltas in the mesh's final vertex order
+    morph_deltas: Vec<MorphDelta>,
+}
+
+/// A source mesh: a primitive name, an `.obj`, `.gltf` or `.glb` file or
+/// one of the animated demos (skinned glTF meshes and the demos have a rig)
+fn load_source_mesh(source: &str, subdivisions: u32) -> Result<(MeshData, Option<Rig>)> {
+    let lower = source.to_lowercase();
+    if lower.ends_with(".obj") {
+        return Ok((obj::load(Path::new(source))?, None));
+    }
+    if lower.ends_with(".gltf") || lower.ends_with(".glb") {
+        return gltf::load(Path::new(source));
+    }
+    if source.eq_ignore_ascii_case(anim::demo::TENTACLE) {
+        let (mesh, rig) = anim::demo::tentacle(subdivisions)?;
+        return Ok((mesh, Some(rig)));
+    }
+    if source.eq_ignore_ascii_case(anim::demo::BLOB) {
+        let (mesh, rig) = anim::demo::blob(subdivisions)?;
+        return Ok((mesh, Some(rig)));
+    }
+    let primitive = Primitive::from_name(source).with_context(|| format!(
+        "Unknown mesh '{}' (expected a primitive name, an .obj, .gltf, .glb or .mesh file)",
+        source
+    ))?;
+    Ok((primitive.generate(subdivisions), None))
+}
+
+/// Clean up a source mesh and lay it out for upload: weld, optionally
+/// regenerate normals, optimize, build the LOD chain, compact the indices;
+/// morph deltas come back separately
+fn build_mesh_asset(mut mesh: MeshData, normals: Option<NormalMode>) -> Result<(MeshAsset, Vec<MorphDelta>)> {
+    mesh_process::weld(&mut mesh, WELD_TOLERANCE);
+    if let Some(mode) = normals {
+        mesh_process::generate_normals(&mut mesh, mode);
+        mesh_process::generate_tangents(&mut mesh);
+    }
+    mesh_process::optimize(&mut mesh);
+    
+    // Simplifying would mix the triangles of different materials
+    let lods = if mesh.submeshes.len() > 1 {
+        MeshLods::single(&mesh.indices)
+    } else {
+        MeshLods::build(&mesh.positions, &mesh.indices, &LodSettings::default())?
+    };
+    let vertices = Vertex::from_mesh(&mesh);
+    let morph_deltas = anim::morph::pack_deltas(&mesh);
+    
+    let asset = MeshAsset {
+        vertex_stride: std::mem::size_of::<Vertex>() as u32,
+        vertex_layout: Vertex::fingerprint(),
+        vertices: meshopt::typed_to_bytes(&vertices).to_vec(),
+        // u16 unless finely subdivided or imported meshes pass 65535 vertices
+        indices: Indices::compact(&lods.indices, vertices.len()),
+        lods: lods.levels,
+        bounds: MeshBounds::from_points(mesh.positions.iter().map(|&p| glam::Vec3::from(p))),
+        submeshes: mesh.submeshes,
+    };
+    Ok((asset, morph_deltas))
+}
+
+/// The `[scene] mesh`: a `.mesh` file is mapped, anything else is built;
+/// the rig and morph targets come along for animated sources
+///
+/// Runs on an asset loader thread.
+fn load_scene_mesh(source: &str, subdivisions: u32, normals: Option<NormalMode>) -> Result<LoadedMesh> {
+    if source.to_lowercase().ends_with(".mesh") {
+        let mapped = MappedMesh::open(Path::new(source))?;
+        if mapped.view().vertex_layout != Vertex::fingerprint() {
+            anyhow::bail!("{:?} was converted for another vertex format; convert it again", source);
+        }
+        return Ok(LoadedMesh { mesh: SceneMesh::Mapped(mapped), rig: None, morph_deltas: Vec::new() });
+    }
+    let (mesh, rig) = load_source_mesh(source, subdivisions)?;
+    // Culling bounds must hold in every pose, not just the bind pose
+    let posed_bounds = rig.as_ref().map(|rig| rig.bounds(&mesh));
+    let (mut asset, morph_deltas) = build_mesh_asset(mesh, normals)?;
+    if let Some(bounds) = posed_bounds {
+        asset.bounds = bounds;
     }
-    for (i, &val) in model_cols.iter().enumerate() {
-        let val_bytes = val.to_ne_bytes();
-        bytes[64 + i * 4..64 + (i + 1) * 4].copy_from_slice(&val_bytes);
+    Ok(LoadedMesh { mesh: SceneMesh::Built(asset), rig, morph_deltas })
+}
+
+/// `convert <input> <output.mesh>`: bake a primitive or a model file into
+/// a mesh cache (`[scene] subdivisions` and `normals` apply)
+fn convert_command(config: &Config, args: &[String]) -> Result<()> {
+    let [input, output] = args else {
+        anyhow::bail!("Usage: my-renderer convert <primitive name, .obj, .gltf or .glb file> <output.mesh>");
+    };
+    let started = Instant::now();
+    let (mesh, rig) = load_source_mesh(input, config.scene.subdivisions)?;
+    if rig.is_some() {
+        log::warn!("Mesh files don't store skeletons or morph targets: {} will stay in its bind pose", output);
     }
-    bytes
+    let (asset, _) = build_mesh_asset(mesh, config.get_normal_mode())?;
+    let view = asset.view();
+    mesh_cache::write(Path::new(output), &view)?;
+    log::info!(
+        "Converted {} to {} in {:.1?}: {} vertices, LODs of {:?} triangles",
+        input,
+        output,
+        started.elapsed(),
+        view.vertex_count(),
+        view.lods.iter().map(|l| l.index_count / 3).collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+// =============================================================================
+// PUSH CONSTANTS
+// =============================================================================
+
+/// Per-draw push constants for cube.vert (camera; transforms are per instance)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PushConstants)]
+struct CubePushConstants {
+    /// LOD dither coverage (`LodDraw::fade`), pushed again per fading draw
+    lod_fade: f32,
+    view_proj: glam::Mat4,
+}
+
+/// Push constants for instances.comp
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PushConstants)]
+struct AnimatePushConstants {
+    time: f32,
+    count: u32,
+    /// The transform clip's length and `PlayMode`
+    duration: f32,
+    mode: u32,
+    /// (first key, key count) of the translation, rotation and scale tracks
+    tracks: [u32; 6],
+}
+
+/// Push constants for cull.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PushConstants)]
+struct CullPushConstants {
+    count: u32,
+    planes: [glam::Vec4; 6],
+    /// xyz = camera position, w = `LodSelector::lod_scale`
+    camera: glam::Vec4,
+}
+
+/// Descriptor set of cube.vert's joint matrices and morph targets (set 0 is
+/// cube.frag's lighting)
+const DEFORM_SET: u32 = 1;
+
+/// Morph delta buffer for `deltas`, with one zero delta when there are none
+/// (storage buffers can't be empty)
+fn create_morph_delta_buffer(device: &VulkanDevice, deltas: &[MorphDelta]) -> Result<(vk::Buffer, vk::DeviceMemory)> {
+    let empty = [MorphDelta::default()];
+    let data = if deltas.is_empty() { &empty[..] } else { deltas };
+    backend::buffer::create_buffer_with_data(device, vk::BufferUsageFlags::STORAGE_BUFFER, data)
+}
+
+/// Whether a cube.frag/debug.frag variant declares the lighting block (set 0);
+/// variants without it get an empty set 0 in their pipeline layout
+fn reads_lighting(frag_shader: &backend::shader::Shader) -> bool {
+    frag_shader.reflection.descriptor_bindings.iter().any(|binding| binding.set == 0)
+}
+
+/// `Lighting` uniform block of cube.frag (std140, which this #[repr(C)]
+/// layout matches: vec4s first, then the scalar padded to 16 bytes)
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct LightingUniforms {
+    key_light: glam::Vec4,
+    fill_light: glam::Vec4,
+    ambient: f32,
+    _padding: [f32; 3],
+}
+
+impl LightingUniforms {
+    fn from_config(lighting: &config::LightingConfig) -> Self {
+        Self {
+            key_light: glam::Vec3::from(lighting.key_direction).extend(lighting.key_intensity),
+            fill_light: glam::Vec3::from(lighting.fill_direction).extend(lighting.fill_intensity),
+            ambient: lighting.ambient,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+// =============================================================================
+// INSTANCES
+// =============================================================================
+
+/// Static placement of one cube (std430 `InstanceSource` in instances.comp)
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct InstanceSource {
+    /// xyz = position, w = uniform scale
+    position_scale: [f32; 4],
+    color: [f32; 4],
+    /// x = playback speed of the transform clip, y = seconds into it at
+    /// time 0
+    playback: [f32; 4],
+}
+
+impl InstanceSource {
+    /// Model matrix at `time`: the transform instances.comp writes, for CPU culling
+    fn model(&self, time: f32, clip: &TransformClip) -> glam::Mat4 {
+        use glam::{Mat4, Vec3};
+        
+        let [x, y, z, scale] = self.position_scale;
+        let [speed, offset, ..] = self.playback;
+        Mat4::from_translation(Vec3::new(x, y, z))
+            * clip.matrix(time * speed + offset)
+            * Mat4::from_scale(Vec3::splat(scale))
+    }
+}
+
+/// One key of the transform clip (std430 `Key` in instances.comp); tracks
+/// sit back to back
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct AnimationKey {
+    value: [f32; 3],
+    time: f32,
+    easing: u32,
+    _padding: [u32; 3],
+}
+
+/// Keys of `clip` for instances.comp, and each track's (first key, key
+/// count) in translation, rotation, scale order
+fn pack_animation_keys(clip: &TransformClip) -> (Vec<AnimationKey>, [u32; 6]) {
+    let mut keys = Vec::new();
+    let mut tracks = [0; 6];
+    for (i, track) in [&clip.translation, &clip.rotation, &clip.scale].into_iter().enumerate() {
+        tracks[i * 2] = keys.len() as u32;
+        tracks[i * 2 + 1] = track.keys().len() as u32;
+        keys.extend(track.keys().iter().map(|key| AnimationKey {
+            value: key.value.to_array(),
+            time: key.time,
+            easing: key.easing as u32,
+            _padding: [0; 3],
+        }));
+    }
+    (keys, tracks)
+}
+
+/// Per-instance vertex data written by instances.comp each frame
+/// (locations 6-10 in cube.vert, binding 1 with `VertexInputRate::INSTANCE`)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, VertexLayout)]
+struct InstanceData {
+    model: glam::Mat4,
+    color: [f32; 4],
+}
+
+/// Mesh-space bounds and LOD levels of one object (`ObjectBounds` in cull.wgsl)
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct ObjectBounds {
+    /// xyz = center, w = radius
+    sphere: [f32; 4],
+    /// First of the mesh's levels in the LOD buffer, and how many
+    lod_first: u32,
+    lod_count: u32,
+    vertex_offset: i32,
+    _padding: u32,
+}
+
+/// One LOD level's index range (`LodLevel` in cull.wgsl)
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct GpuLodLevel {
+    first_index: u32,
+    index_count: u32,
+    error: f32,
+    _padding: u32,
+}
+
+/// The cull pass's per-object bounds (the same for every instance) and LOD
+/// table, in that order
+fn create_cull_mesh_buffers(
+    device: &VulkanDevice,
+    bounds: &MeshBounds,
+    lods: &[LodLevel],
+    instance_count: u32,
+) -> Result<[(vk::Buffer, vk::DeviceMemory); 2]> {
+    let object_bounds = vec![
+        ObjectBounds {
+            sphere: bounds.sphere.to_vec4().to_array(),
+            lod_first: 0,
+            lod_count: lods.len() as u32,
+            vertex_offset: 0,
+            _padding: 0,
+        };
+        instance_count as usize
+    ];
+    let object_bounds = backend::buffer::create_buffer_with_data(
+        device,
+        vk::BufferUsageFlags::STORAGE_BUFFER,
+        &object_bounds,
+    )?;
+    
+    let gpu_lods: Vec<GpuLodLevel> = lods
+        .iter()
+        .map(|level| GpuLodLevel {
+            first_index: level.first_index,
+            index_count: level.index_count,
+            error: level.error,
+            _padding: 0,
+        })
+        .collect();
+    let lod_table = match backend::buffer::create_buffer_with_data(
+        device,
+        vk::BufferUsageFlags::STORAGE_BUFFER,
+        &gpu_lods,
+    ) {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            unsafe {
+                device.device.destroy_buffer(object_bounds.0, None);
+                device.device.free_memory(object_bounds.1, None);
+            }
+            return Err(e);
+        }
+    };
+    Ok([object_bounds, lod_table])
+}
+
+/// Place `count` cubes on a centered grid, `spacing` apart.
+///
+/// A single cube sits at the origin and plays the transform clip (of
+/// `duration` seconds) as authored; larger counts get varied tints, speeds
+/// and offsets into it so stress scenes don't look uniform.
+fn build_instance_sources(count: u32, spacing: f32, duration: f32) -> Vec<InstanceSource> {
+    if count == 1 {
+        return vec![InstanceSource {
+            position_scale: [0.0, 0.0, 0.0, 1.0],
+            color: [1.0; 4],
+            playback: [1.0, 0.0, 0.0, 0.0],
+        }];
+    }
+    
+    let side = (count as f32).cbrt().ceil() as u32;
+    let half = (side - 1) as f32 * spacing * 0.5;
+    
+    (0..count).map(|i| {
+        let (x, y, z) = (i % side, (i / side) % side, i / (side * side));
+        
+        // Cheap integer hash (murmur3 finalizer) -> values in [0, 1)
+        let mut counter = i.wrapping_mul(8);
+        let mut next = || {
+            counter = counter.wrapping_add(1);
+            let mut h = counter.wrapping_mul(0x9E37_79B9);
+            h = (h ^ (h >> 16)).wrapping_mul(0x85EB_CA6B);
+            h = (h ^ (h >> 13)).wrapping_mul(0xC2B2_AE35);
+            h ^= h >> 16;
+            (h >> 8) as f32 / (1 << 24) as f32
+        };
+        let tint = [0.5 + 0.5 * next(), 0.5 + 0.5 * next(), 0.5 + 0.5 * next(), 1.0];
+        let playback = [0.5 + next(), next() * duration, 0.0, 0.0];
+        
+        InstanceSource {
+            position_scale: [
+                x as f32 * spacing - half,
+                y as f32 * spacing - half,
+                z as f32 * spacing - half,
+                1.0,
+            ],
+            color: tint,
+            playback,
+        }
+    }).collect()
 }
 
 // =============================================================================
@@ -133,6 +562,13 @@ fn main() -> Result<()> {
     
     // Initialize logging
     init_logging(&config);
+    
+    // `convert <input> <output.mesh>` bakes a mesh cache instead of rendering
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("convert") {
+        return convert_command(&config, &args[1..]);
+    }
+    
     log::info!("Starting Vulkan renderer");
     log::info!("Window: {}x{} ({})", 
         config.window.width, 
@@ -140,7 +576,7 @@ fn main() -> Result<()> {
         if config.window.fullscreen { "fullscreen" } else { "windowed" }
     );
     log::info!("Present mode: {}", config.graphics.present_mode);
-
+    
     // OPTION 1: Run with Bevy (ECS integration)
     #[cfg(feature = "bevy")]
     {
@@ -220,6 +656,47 @@ pub struct App {
     framebuffers: Vec<vk::Framebuffer>,
     pipeline: Option<vk::Pipeline>,
     pipeline_layout: Option<vk::PipelineLayout>,
+    /// Descriptor set layouts derived from shader reflection
+    descriptor_set_layouts: Vec<vk::DescriptorSetLayout>,
+    
+    /// Shader variants created so far (keyword permutations)
+    shader_variants: ShaderVariantCache,
+    
+    /// Active debug view (F1-F6) and the pipeline variant each view draws
+    /// with, plus whether that variant reads the lighting block
+    debug_view: DebugView,
+    debug_pipelines: HashMap<DebugView, (GraphicsPipeline, bool)>,
+    
+    /// Lines and shapes queued this frame, drawn after the scene
+    debug_draw: DebugDraw,
+    debug_draw_renderer: Option<DebugDrawRenderer>,
+    /// Grid, origin axes and cube bounds (F7)
+    show_debug_shapes: bool,
+    
+    /// 2D layer over the scene, and the atlas of its demo (F10)
+    sprite_batch: SpriteBatch,
+    sprite_renderer: Option<SpriteRenderer>,
+    sprite_demo_atlas: Option<SpriteAtlas>,
+    /// `[debug] sprite_image`: a checker until it has loaded
+    sprite_image: Option<TextureId>,
+    show_sprite_demo: bool,
+    
+    /// Labels in the scene (axis names) and on screen (the debug view banner)
+    text: Option<TextBatch>,
+    text_renderer: Option<TextRenderer>,
+    
+    /// Stats HUD (F8), laid out into the overlay every frame it is visible
+    hud: Hud,
+    overlay: Option<Overlay>,
+    overlay_renderer: Option<OverlayRenderer>,
+    /// GPU frame time from timestamp queries, read back once a frame's fence signals
+    gpu_timer: Option<GpuTimer>,
+    gpu_ms: Option<f32>,
+    
+    /// egui: input state, painter and the config inspector (F9)
+    gui: Option<Gui>,
+    gui_renderer: Option<GuiRenderer>,
+    inspector: Inspector,
     
     // ─────────────────────────────────────────────────────────────────────────
     // DEPTH BUFFER
@@ -235,6 +712,79 @@ pub struct App {
     vertex_buffer_memory: Option<vk::DeviceMemory>,
     index_buffer: Option<vk::Buffer>,
     index_buffer_memory: Option<vk::DeviceMemory>,
+    index_type: vk::IndexType,
+    /// Morph target deltas of cube.vert (set 1, binding 1), and the vertex
+    /// count they were packed for
+    morph_delta_buffer: Option<vk::Buffer>,
+    morph_delta_memory: Option<vk::DeviceMemory>,
+    morph_vertex_count: u32,
+    
+    // ─────────────────────────────────────────────────────────────────────────
+    // ASSET LOADING (worker threads; placeholders are drawn meanwhile)
+    // ─────────────────────────────────────────────────────────────────────────
+    asset_loader: Option<AssetLoader>,
+    /// The scene mesh, replacing the placeholder cube when it arrives
+    pending_mesh: Option<Handle<LoadedMesh>>,
+    pending_sprite_image: Option<Handle<ImageData>>,
+    
+    // ─────────────────────────────────────────────────────────────────────────
+    // INSTANCES (animated by a compute pass, drawn in one instanced draw)
+    // ─────────────────────────────────────────────────────────────────────────
+    instance_count: u32,
+    instance_source_buffer: Option<vk::Buffer>,
+    instance_source_memory: Option<vk::DeviceMemory>,
+    /// Animated instances, one buffer and animation set per frame in flight
+    /// so async compute can animate a frame while the previous one draws
+    instance_buffers: Vec<vk::Buffer>,
+    instance_buffer_memory: Vec<vk::DeviceMemory>,
+    animate_pipeline: Option<ComputePipeline>,
+    descriptor_pool: Option<vk::DescriptorPool>,
+    animate_descriptor_sets: Vec<vk::DescriptorSet>,
+    /// The [animation] clip every instance plays, its keys as instances.comp
+    /// reads them and where each track starts in them
+    transform_clip: TransformClip,
+    animation_key_buffer: Option<vk::Buffer>,
+    animation_key_memory: Option<vk::DeviceMemory>,
+    animation_tracks: [u32; 6],
+    
+    /// Lighting uniforms of cube.frag, one buffer and set per frame in flight
+    /// (no sets when the fragment variant has no lighting block)
+    lighting_buffers: Vec<DynamicBuffer>,
+    lighting_descriptor_sets: Vec<vk::DescriptorSet>,
+    
+    /// Joint matrices and active morph targets of cube.vert (set 1), one
+    /// buffer each and a set per frame in flight, and the rig posing them
+    /// (None: rigid mesh, identity joints, no morphs)
+    joint_buffers: Vec<DynamicBuffer>,
+    morph_buffers: Vec<DynamicBuffer>,
+    deform_descriptor_sets: Vec<vk::DescriptorSet>,
+    rig: Option<Rig>,
+    
+    // ─────────────────────────────────────────────────────────────────────────
+    // CULLING (GPU: compute pass + indirect draws, CPU: sphere tests)
+    // ─────────────────────────────────────────────────────────────────────────
+    culling: CullingMode,
+    /// Cube bounds (mesh space), transformed per instance for CPU culling
+    mesh_bounds: MeshBounds,
+    /// CPU copy of the instance placements (animated here too for CPU culling)
+    instance_sources: Vec<InstanceSource>,
+    /// This frame's visible runs and counts (CPU culling and Off)
+    cull_result: CullResult,
+    /// Cube LOD levels (index ranges in the index buffer)
+    mesh_lods: Vec<LodLevel>,
+    /// This frame's draws after LOD selection (CPU culling and Off)
+    lod_draws: Vec<LodDraw>,
+    lod_buffer: Option<vk::Buffer>,
+    lod_buffer_memory: Option<vk::DeviceMemory>,
+    object_bounds_buffer: Option<vk::Buffer>,
+    object_bounds_memory: Option<vk::DeviceMemory>,
+    draw_command_buffer: Option<vk::Buffer>,
+    draw_command_memory: Option<vk::DeviceMemory>,
+    draw_count_buffer: Option<vk::Buffer>,
+    draw_count_memory: Option<vk::DeviceMemory>,
+    cull_pipeline: Option<ComputePipeline>,
+    /// One per frame in flight (each reads that frame's instance buffer)
+    cull_descriptor_sets: Vec<vk::DescriptorSet>,
     
     // ─────────────────────────────────────────────────────────────────────────
     // COMMANDS
@@ -250,11 +800,15 @@ pub struct App {
     frame_sync: Vec<backend::sync::FrameSync>,
     /// Which sync slot we're currently using (0 to MAX_FRAMES_IN_FLIGHT-1)
     current_frame: usize,
+    /// Instance animation on the dedicated compute queue (None: the GPU has
+    /// no separate compute family, so it is recorded with the draws)
+    async_compute: Option<AsyncCompute>,
     
     // ─────────────────────────────────────────────────────────────────────────
     // OPTIMIZATION: Pre-allocated arrays to avoid per-frame heap allocations
     // ─────────────────────────────────────────────────────────────────────────
-    wait_stages: [vk::PipelineStageFlags; 1],
+    /// Stages waiting on the acquired image and on async compute
+    wait_stages: [vk::PipelineStageFlags; 2],
     
     // ─────────────────────────────────────────────────────────────────────────
     // STATE FLAGS
@@ -269,19 +823,28 @@ pub struct App {
     // ─────────────────────────────────────────────────────────────────────────
     // FPS TRACKING
     // ─────────────────────────────────────────────────────────────────────────
-    frame_count: u32,
-    last_fps_update: Instant,
     last_frame_time: Instant,
+    /// fps limit, low-latency scheduling and input-to-present latency
+    pacer: FramePacer,
     
     // ─────────────────────────────────────────────────────────────────────────
     // ANIMATION
     // ─────────────────────────────────────────────────────────────────────────
-    start_time: Instant,
+    clock: Clock,
+    /// Clock time, sampled once per frame (GPU animation and CPU culling agree)
+    animation_time: f32,
 }
 
 impl App {
     pub fn new(config: Config) -> Self {
         let is_fullscreen = config.window.fullscreen;
+        let show_debug_shapes = config.debug.show_shapes;
+        let show_hud = config.debug.show_fps;
+        let show_inspector = config.debug.show_inspector;
+        let show_sprite_demo = config.debug.show_sprites;
+        let transform_clip = config.get_transform_clip();
+        let clock = config.get_clock();
+        let pacer = FramePacer::new(config.graphics.fps_limit, config.graphics.low_latency);
         let now = Instant::now();
         Self {
             config,
@@ -295,6 +858,28 @@ impl App {
             framebuffers: Vec::new(),
             pipeline: None,
             pipeline_layout: None,
+            descriptor_set_layouts: Vec::new(),
+            shader_variants: ShaderVariantCache::new(),
+            debug_view: DebugView::default(),
+            debug_pipelines: HashMap::new(),
+            debug_draw: DebugDraw::new(),
+            debug_draw_renderer: None,
+            show_debug_shapes,
+            sprite_batch: SpriteBatch::new(),
+            sprite_renderer: None,
+            sprite_demo_atlas: None,
+            sprite_image: None,
+            show_sprite_demo,
+            text: None,
+            text_renderer: None,
+            hud: Hud::new(show_hud),
+            overlay: None,
+            overlay_renderer: None,
+            gpu_timer: None,
+            gpu_ms: None,
+            gui: None,
+            gui_renderer: None,
+            inspector: Inspector::new(show_inspector),
             depth_image: None,
             depth_image_memory: None,
             depth_image_view: None,
@@ -302,18 +887,63 @@ impl App {
             vertex_buffer_memory: None,
             index_buffer: None,
             index_buffer_memory: None,
+            index_type: vk::IndexType::UINT16,
+            morph_delta_buffer: None,
+            morph_delta_memory: None,
+            morph_vertex_count: 0,
+            asset_loader: None,
+            pending_mesh: None,
+            pending_sprite_image: None,
+            instance_count: 0,
+            instance_source_buffer: None,
+            instance_source_memory: None,
+            transform_clip,
+            animation_key_buffer: None,
+            animation_key_memory: None,
+            animation_tracks: [0; 6],
+            instance_buffers: Vec::new(),
+            instance_buffer_memory: Vec::new(),
+            animate_pipeline: None,
+            descriptor_pool: None,
+            animate_descriptor_sets: Vec::new(),
+            lighting_buffers: Vec::new(),
+            lighting_descriptor_sets: Vec::new(),
+            joint_buffers: Vec::new(),
+            morph_buffers: Vec::new(),
+            deform_descriptor_sets: Vec::new(),
+            rig: None,
+            culling: CullingMode::Off,
+            mesh_bounds: MeshBounds::default(),
+            instance_sources: Vec::new(),
+            cull_result: CullResult::default(),
+            mesh_lods: Vec::new(),
+            lod_draws: Vec::new(),
+            lod_buffer: None,
+            lod_buffer_memory: None,
+            object_bounds_buffer: None,
+            object_bounds_memory: None,
+            draw_command_buffer: None,
+            draw_command_memory: None,
+            draw_count_buffer: None,
+            draw_count_memory: None,
+            cull_pipeline: None,
+            cull_descriptor_sets: Vec::new(),
             command_pool: None,
             command_buffers: Vec::new(),
             frame_sync: Vec::new(),
             current_frame: 0,
-            wait_stages: [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT],
+            async_compute: None,
+            wait_stages: [
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::PipelineStageFlags::COMPUTE_SHADER | vk::PipelineStageFlags::VERTEX_INPUT,
+            ],
             needs_resize: false,
             is_minimized: false,
             needs_sync: false,
-            frame_count: 0,
-            last_fps_update: now,
             last_frame_time: now,
-            start_time: now,
+            pacer,
+            clock,
+            animation_time: 0.0,
         }
     }
     
@@ -329,6 +959,11 @@ impl App {
     /// 3. Swapchain (image buffers)
     /// 4. Command pool & buffers
     /// 5. Synchronization primitives
+    // Surfaces are only created on Windows; elsewhere the surface step bails
+    #[cfg_attr(
+        not(target_os = "windows"),
+        allow(unused_imports, unused_variables, unused_unsafe, unreachable_code)
+    )]
     fn init_vulkan(&mut self, window: Arc<Window>) -> Result<()> {
         log::info!("Initializing Vulkan...");
         
@@ -403,6 +1038,8 @@ impl App {
         // STEP 3.5: Create rendering pipeline and geometry buffers
         // ─────────────────────────────────────────────────────────────────────
         self.create_rendering_resources()?;
+        let max_texture_side = device.properties.limits.max_image_dimension2_d as usize;
+        self.gui = Some(Gui::new(&window, max_texture_side));
         
         // ─────────────────────────────────────────────────────────────────────
         // STEP 4: Create synchronization primitives
@@ -414,6 +1051,7 @@ impl App {
             .collect::<Result<Vec<_>>>()?;
         
         self.frame_sync = frame_sync;
+        self.async_compute = AsyncCompute::new(&device, max_frames)?;
         
         log::info!("Vulkan initialized successfully!");
         Ok(())
@@ -517,8 +1155,22 @@ impl App {
         // ─────────────────────────────────────────────────────────────────────
         // Load shaders
         // ─────────────────────────────────────────────────────────────────────
-        let vert_shader = load_shader!(device, "../shaders/cube.vert.spv")?;
-        let frag_shader = load_shader!(device, "../shaders/cube.frag.spv")?;
+        let vert_shader = load_shader!(device, "cube.vert")?;
+        
+        let mut culling = self.config.get_culling_mode();
+        if culling == CullingMode::Gpu && !device.supports_indirect_count {
+            log::warn!("GPU culling needs drawIndirectCount, falling back to CPU culling");
+            culling = CullingMode::Cpu;
+        }
+        
+        // Fragment variant from [graphics] shader_keywords (e.g. ["UNLIT"]),
+        // plus dithered LOD cross-fade where draws are recorded on the CPU
+        let mut keywords = self.config.graphics.shader_keywords.clone();
+        if culling != CullingMode::Gpu && self.config.graphics.lod_fade_range > 0.0 {
+            keywords.push("LOD_FADE".to_string());
+        }
+        let frag_shader = self.shader_variants.get(device, "cube.frag", &keywords)?;
+        let lit_reads_lighting = reads_lighting(frag_shader);
         
         // ─────────────────────────────────────────────────────────────────────
         // Create render pass
@@ -550,48 +1202,385 @@ impl App {
         // ─────────────────────────────────────────────────────────────────────
         // Create graphics pipeline
         // ─────────────────────────────────────────────────────────────────────
-        let (pipeline, pipeline_layout) = backend::pipeline::create_graphics_pipeline(
+        // The Rust push-constant block must match what the shaders declare
+        let shader_push_size = vert_shader.reflection.push_constant_size.unwrap_or(0) as usize;
+        if shader_push_size != CubePushConstants::SIZE {
+            anyhow::bail!(
+                "cube.vert declares {} bytes of push constants, CubePushConstants is {} bytes",
+                shader_push_size,
+                CubePushConstants::SIZE
+            );
+        }
+        
+        // Per-vertex cube data at binding 0, per-instance transforms at binding 1
+        let vertex_input = backend::pipeline::get_instanced_vertex_input_info::<Vertex, InstanceData>();
+        let specialization = self.config.get_lighting_model().specialization();
+        let pipeline_result = backend::pipeline::create_graphics_pipeline(
             device,
             render_pass,
             swapchain.extent,
-            vert_shader,
+            &vertex_input,
+            &vert_shader,
             frag_shader,
+            &specialization,
+            &PipelineState::default(),
+        );
+        
+        // Debug views: same vertex stage, their own fragment variant and state
+        let mut debug_pipelines = HashMap::new();
+        let debug_result = DebugView::ALL.iter().try_for_each(|&view| {
+            let Some((frag_name, frag_keywords)) = view.fragment_variant() else {
+                return Ok(());
+            };
+            let frag_shader = self.shader_variants.get(device, frag_name, frag_keywords)?;
+            let uses_lighting = reads_lighting(frag_shader);
+            let debug_pipeline = GraphicsPipeline::new(
+                device,
+                render_pass,
+                swapchain.extent,
+                &vertex_input,
+                (&vert_shader, frag_shader),
+                &specialization,
+                &view.pipeline_state(),
+            ).with_context(|| format!("Failed to create the {} debug view pipeline", view.name()))?;
+            debug_pipelines.insert(view, (debug_pipeline, uses_lighting));
+            anyhow::Ok(())
+        });
+        
+        // Clean up shader modules (no longer needed after pipeline creation);
+        // fragment variants stay cached for other materials
+        vert_shader.destroy(&device.device);
+        
+        let (pipeline, pipeline_layout, descriptor_set_layouts) = pipeline_result?;
+        self.debug_pipelines = debug_pipelines;
+        debug_result?;
+        
+        let debug_draw_renderer = DebugDrawRenderer::new(
+            device,
+            render_pass,
+            swapchain.extent,
+            self.config.graphics.max_frames_in_flight,
+            self.config.debug.line_width,
         )?;
         
-        // Clean up shader modules (no longer needed after pipeline creation)
-        unsafe {
-            device.device.destroy_shader_module(vert_shader, None);
-            device.device.destroy_shader_module(frag_shader, None);
+        // Stats HUD: font atlas and overlay pipeline, plus GPU timestamps
+        let (font, atlas_pixels) = BitmapFont::rasterize(Self::HUD_FONT_PX)?;
+        let overlay_renderer = OverlayRenderer::new(
+            device,
+            render_pass,
+            swapchain.extent,
+            self.config.graphics.max_frames_in_flight,
+            &font,
+            &atlas_pixels,
+        )?;
+        let gpu_timer = GpuTimer::new(device, self.config.graphics.max_frames_in_flight)?;
+        if gpu_timer.is_none() && self.config.graphics.low_latency {
+            log::warn!("Low-latency mode needs GPU timestamps; frames will start as soon as allowed");
         }
         
+        // 2D layer, with the demo's atlas as its first texture
+        let mut sprite_renderer = SpriteRenderer::new(
+            device,
+            render_pass,
+            swapchain.extent,
+            self.config.graphics.max_frames_in_flight,
+        )?;
+        let sprite_demo_atlas = sprite_renderer.add_atlas(device, debug::sprites::atlas_image()?)?;
+        
+        // Background loads: the scene mesh and the demo's image
+        let mut asset_loader = AssetLoader::new(self.config.scene.loader_threads as usize)?;
+        let pending_mesh = {
+            let (source, subdivisions) = (self.config.scene.mesh.clone(), self.config.scene.subdivisions);
+            let normals = self.config.get_normal_mode();
+            asset_loader.load(format!("mesh {:?}", source), move || load_scene_mesh(&source, subdivisions, normals))
+        };
+        let (sprite_image, pending_sprite_image) = if self.config.debug.sprite_image.is_empty() {
+            (None, None)
+        } else {
+            let checker = ImageData::checker(64, 8);
+            let texture = sprite_renderer.add_texture(
+                device,
+                vk::Extent2D { width: checker.width, height: checker.height },
+                &checker.rgba,
+            )?;
+            let path = PathBuf::from(&self.config.debug.sprite_image);
+            let handle = asset_loader.load(format!("image {:?}", path), move || image_asset::load(&path));
+            (Some(texture), Some(handle))
+        };
+        
+        // Labels: the configured font rasterized into a distance field atlas
+        let (text_font, text_atlas) = self.load_text_font()?;
+        let text_renderer = TextRenderer::new(
+            device,
+            render_pass,
+            swapchain.extent,
+            self.config.graphics.max_frames_in_flight,
+            &text_font,
+            &text_atlas,
+        )?;
+        
+        // egui painter (the font atlas arrives with the first frame's textures)
+        let gui_renderer = GuiRenderer::new(
+            device,
+            render_pass,
+            swapchain.extent,
+            self.config.graphics.max_frames_in_flight,
+        )?;
+        
         // ─────────────────────────────────────────────────────────────────────
-        // Create vertex buffer
+        // Create vertex and index buffers for the placeholder cube, drawn
+        // until the scene mesh loads (the index buffer holds every LOD
+        // level, each an index range)
         // ─────────────────────────────────────────────────────────────────────
+        let (placeholder, placeholder_deltas) = build_mesh_asset(Primitive::Cube.generate(0), None)?;
+        let mesh = placeholder.view();
         let (vertex_buffer, vertex_buffer_memory) = backend::buffer::create_buffer_with_data(
             device,
             vk::BufferUsageFlags::VERTEX_BUFFER,
-            CUBE_VERTICES,
+            mesh.vertices,
         )?;
+        let (index_buffer, index_buffer_memory) = backend::buffer::create_buffer_with_data(
+            device,
+            vk::BufferUsageFlags::INDEX_BUFFER,
+            mesh.indices,
+        )?;
+        let (morph_delta_buffer, morph_delta_memory) = create_morph_delta_buffer(device, &placeholder_deltas)?;
+        let index_type = mesh.index_type;
+        let lods = mesh.lods.to_vec();
+        let mesh_bounds = mesh.bounds;
         
         // ─────────────────────────────────────────────────────────────────────
-        // Create index buffer
+        // Create instance buffers and the compute pass that animates them
         // ─────────────────────────────────────────────────────────────────────
-        let (index_buffer, index_buffer_memory) = backend::buffer::create_buffer_with_data(
+        let instance_count = self.config.scene.cube_count.max(1);
+        let sources = build_instance_sources(
+            instance_count,
+            self.config.scene.cube_spacing,
+            self.transform_clip.duration,
+        );
+        
+        let (instance_source_buffer, instance_source_memory) = backend::buffer::create_buffer_with_data(
             device,
-            vk::BufferUsageFlags::INDEX_BUFFER,
-            CUBE_INDICES,
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            &sources,
+        )?;
+        
+        // Keys of the transform clip (one unused key when every track is
+        // empty, as storage buffers can't be)
+        let (mut animation_keys, animation_tracks) = pack_animation_keys(&self.transform_clip);
+        if animation_keys.is_empty() {
+            animation_keys.push(AnimationKey { value: [0.0; 3], time: 0.0, easing: 0, _padding: [0; 3] });
+        }
+        let (animation_key_buffer, animation_key_memory) = backend::buffer::create_buffer_with_data(
+            device,
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            &animation_keys,
+        )?;
+        
+        // Written by the compute pass, then read as a per-instance vertex
+        // buffer (shared with the compute queue when there is one)
+        let max_frames = self.config.graphics.max_frames_in_flight;
+        let (instance_buffers, instance_buffer_memory): (Vec<_>, Vec<_>) = (0..max_frames)
+            .map(|_| backend::buffer::create_storage_buffer(
+                device,
+                (instance_count as usize * std::mem::size_of::<InstanceData>()) as vk::DeviceSize,
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+            ))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        
+        let animate_shader = load_shader!(device, "instances.comp")?;
+        let animate_result = ComputePipeline::new(device, &animate_shader, &SpecializationConstants::new());
+        animate_shader.destroy(&device.device);
+        let animate_pipeline = animate_result?;
+        
+        // Per-frame sets for the animation pass (3 storage buffers), the cull
+        // pass (5), lighting (1 uniform buffer) and deformation (3)
+        let descriptor_pool = backend::descriptor::create_descriptor_pool(
+            device,
+            4 * max_frames as u32,
+            &[
+                (vk::DescriptorType::STORAGE_BUFFER, 11 * max_frames as u32),
+                (vk::DescriptorType::UNIFORM_BUFFER, max_frames as u32),
+            ],
+        )?;
+        let animate_descriptor_sets = backend::descriptor::allocate_descriptor_sets(
+            device,
+            descriptor_pool,
+            &vec![*animate_pipeline.set_layouts.first().context("instances.comp declares no buffers")?; max_frames],
+        )?;
+        for (&instance_buffer, &set) in instance_buffers.iter().zip(&animate_descriptor_sets) {
+            DescriptorWriter::new()
+                .storage_buffer(0, instance_source_buffer, 0, vk::WHOLE_SIZE)
+                .storage_buffer(1, instance_buffer, 0, vk::WHOLE_SIZE)
+                .storage_buffer(2, animation_key_buffer, 0, vk::WHOLE_SIZE)
+                .write(device, set);
+        }
+        
+        // ─────────────────────────────────────────────────────────────────────
+        // Create lighting uniforms (set 0 of cube.frag)
+        // ─────────────────────────────────────────────────────────────────────
+        let lighting_size = std::mem::size_of::<LightingUniforms>() as vk::DeviceSize;
+        let lighting_buffers = (0..max_frames)
+            .map(|_| DynamicBuffer::new(device, lighting_size, vk::BufferUsageFlags::UNIFORM_BUFFER))
+            .collect::<Result<Vec<_>>>()?;
+        let lighting_descriptor_sets = match descriptor_set_layouts.first() {
+            Some(&set_layout) if lit_reads_lighting => backend::descriptor::allocate_descriptor_sets(
+                device,
+                descriptor_pool,
+                &vec![set_layout; max_frames],
+            )?,
+            _ => Vec::new(),
+        };
+        for (buffer, &set) in lighting_buffers.iter().zip(&lighting_descriptor_sets) {
+            DescriptorWriter::new()
+                .uniform_buffer(0, buffer.buffer, 0, vk::WHOLE_SIZE)
+                .write(device, set);
+        }
+        
+        // ─────────────────────────────────────────────────────────────────────
+        // Create joint matrix and active morph buffers (set 1 of cube.vert),
+        // identity and none until a rig poses them
+        // ─────────────────────────────────────────────────────────────────────
+        let deform_layout = *descriptor_set_layouts.get(DEFORM_SET as usize)
+            .context("cube.vert declares no joint and morph buffers (set 1)")?;
+        let joint_size = (MAX_JOINTS * std::mem::size_of::<glam::Mat4>()) as vk::DeviceSize;
+        let joint_buffers = (0..max_frames)
+            .map(|_| DynamicBuffer::new(device, joint_size, vk::BufferUsageFlags::STORAGE_BUFFER))
+            .collect::<Result<Vec<_>>>()?;
+        let morph_size = std::mem::size_of::<ActiveMorphs>() as vk::DeviceSize;
+        let morph_buffers = (0..max_frames)
+            .map(|_| DynamicBuffer::new(device, morph_size, vk::BufferUsageFlags::STORAGE_BUFFER))
+            .collect::<Result<Vec<_>>>()?;
+        let deform_descriptor_sets = backend::descriptor::allocate_descriptor_sets(
+            device,
+            descriptor_pool,
+            &vec![deform_layout; max_frames],
         )?;
+        for ((joints, morphs), &set) in joint_buffers.iter().zip(&morph_buffers).zip(&deform_descriptor_sets) {
+            joints.write(&device.device, &[glam::Mat4::IDENTITY; MAX_JOINTS])?;
+            morphs.write(&device.device, &[ActiveMorphs::NONE])?;
+            DescriptorWriter::new()
+                .storage_buffer(0, joints.buffer, 0, vk::WHOLE_SIZE)
+                .storage_buffer(1, morph_delta_buffer, 0, vk::WHOLE_SIZE)
+                .storage_buffer(2, morphs.buffer, 0, vk::WHOLE_SIZE)
+                .write(device, set);
+        }
+        
+        // ─────────────────────────────────────────────────────────────────────
+        // Create culling resources
+        // ─────────────────────────────────────────────────────────────────────
+        if culling == CullingMode::Gpu {
+            let [(object_bounds_buffer, object_bounds_memory), (lod_buffer, lod_buffer_memory)] =
+                create_cull_mesh_buffers(device, &mesh_bounds, &lods, instance_count)?;
+            
+            // Worst case every object is visible: one command each
+            let (draw_command_buffer, draw_command_memory) = backend::buffer::create_storage_buffer(
+                device,
+                (instance_count as usize * std::mem::size_of::<vk::DrawIndexedIndirectCommand>()) as vk::DeviceSize,
+                vk::BufferUsageFlags::INDIRECT_BUFFER,
+            )?;
+            let (draw_count_buffer, draw_count_memory) = backend::buffer::create_storage_buffer(
+                device,
+                std::mem::size_of::<u32>() as vk::DeviceSize,
+                vk::BufferUsageFlags::INDIRECT_BUFFER,
+            )?;
+            
+            let cull_shader = load_shader!(device, "cull.wgsl.main")?;
+            let shader_push_size = cull_shader.reflection.push_constant_size.unwrap_or(0) as usize;
+            if shader_push_size != CullPushConstants::SIZE {
+                cull_shader.destroy(&device.device);
+                anyhow::bail!(
+                    "cull.wgsl declares {} bytes of push constants, CullPushConstants is {} bytes",
+                    shader_push_size,
+                    CullPushConstants::SIZE
+                );
+            }
+            let cull_result = ComputePipeline::new(device, &cull_shader, &SpecializationConstants::new());
+            cull_shader.destroy(&device.device);
+            let cull_pipeline = cull_result?;
+            
+            let cull_descriptor_sets = backend::descriptor::allocate_descriptor_sets(
+                device,
+                descriptor_pool,
+                &vec![*cull_pipeline.set_layouts.first().context("cull.wgsl declares no buffers")?; max_frames],
+            )?;
+            for (&instance_buffer, &set) in instance_buffers.iter().zip(&cull_descriptor_sets) {
+                DescriptorWriter::new()
+                    .storage_buffer(0, object_bounds_buffer, 0, vk::WHOLE_SIZE)
+                    .storage_buffer(1, instance_buffer, 0, vk::WHOLE_SIZE)
+                    .storage_buffer(2, draw_command_buffer, 0, vk::WHOLE_SIZE)
+                    .storage_buffer(3, draw_count_buffer, 0, vk::WHOLE_SIZE)
+                    .storage_buffer(4, lod_buffer, 0, vk::WHOLE_SIZE)
+                    .write(device, set);
+            }
+            
+            self.object_bounds_buffer = Some(object_bounds_buffer);
+            self.object_bounds_memory = Some(object_bounds_memory);
+            self.lod_buffer = Some(lod_buffer);
+            self.lod_buffer_memory = Some(lod_buffer_memory);
+            self.draw_command_buffer = Some(draw_command_buffer);
+            self.draw_command_memory = Some(draw_command_memory);
+            self.draw_count_buffer = Some(draw_count_buffer);
+            self.draw_count_memory = Some(draw_count_memory);
+            self.cull_pipeline = Some(cull_pipeline);
+            self.cull_descriptor_sets = cull_descriptor_sets;
+        }
+        
+        log::info!("Scene: {} cube(s), culling: {:?}", instance_count, culling);
         
         self.pipeline = Some(pipeline);
         self.pipeline_layout = Some(pipeline_layout);
+        self.descriptor_set_layouts = descriptor_set_layouts;
         self.vertex_buffer = Some(vertex_buffer);
         self.vertex_buffer_memory = Some(vertex_buffer_memory);
         self.index_buffer = Some(index_buffer);
         self.index_buffer_memory = Some(index_buffer_memory);
+        self.index_type = index_type;
+        self.morph_delta_buffer = Some(morph_delta_buffer);
+        self.morph_delta_memory = Some(morph_delta_memory);
+        self.morph_vertex_count = mesh.vertex_count() as u32;
+        self.instance_count = instance_count;
+        self.instance_source_buffer = Some(instance_source_buffer);
+        self.instance_source_memory = Some(instance_source_memory);
+        self.animation_key_buffer = Some(animation_key_buffer);
+        self.animation_key_memory = Some(animation_key_memory);
+        self.animation_tracks = animation_tracks;
+        self.instance_buffers = instance_buffers;
+        self.instance_buffer_memory = instance_buffer_memory;
+        self.animate_pipeline = Some(animate_pipeline);
+        self.descriptor_pool = Some(descriptor_pool);
+        self.animate_descriptor_sets = animate_descriptor_sets;
+        self.lighting_buffers = lighting_buffers;
+        self.lighting_descriptor_sets = lighting_descriptor_sets;
+        self.joint_buffers = joint_buffers;
+        self.morph_buffers = morph_buffers;
+        self.deform_descriptor_sets = deform_descriptor_sets;
+        self.debug_draw_renderer = Some(debug_draw_renderer);
+        self.sprite_renderer = Some(sprite_renderer);
+        self.sprite_demo_atlas = Some(sprite_demo_atlas);
+        self.sprite_image = sprite_image;
+        self.asset_loader = Some(asset_loader);
+        self.pending_mesh = Some(pending_mesh);
+        self.pending_sprite_image = pending_sprite_image;
+        self.text = Some(TextBatch::new(text_font));
+        self.text_renderer = Some(text_renderer);
+        self.overlay = Some(Overlay::new(font));
+        self.overlay_renderer = Some(overlay_renderer);
+        self.gpu_timer = gpu_timer;
+        self.gui_renderer = Some(gui_renderer);
+        self.culling = culling;
+        self.mesh_bounds = mesh_bounds;
+        self.mesh_lods = lods;
+        self.instance_sources = sources;
         
         // ─────────────────────────────────────────────────────────────────────
         // Now record command buffers with the rendering pipeline
         // ─────────────────────────────────────────────────────────────────────
+        self.update_culling();
+        let device = self.device.as_ref()
+            .context("Device not initialized")?;
         if let Some(swapchain) = &self.swapchain {
             log::info!("Recording command buffers: {} buffers, {} framebuffers", 
                 self.command_buffers.len(), self.framebuffers.len());
@@ -700,9 +1689,29 @@ impl App {
         let pipeline_layout = self.pipeline_layout.context("Pipeline layout not initialized")?;
         let vertex_buffer = self.vertex_buffer.context("Vertex buffer not initialized")?;
         let index_buffer = self.index_buffer.context("Index buffer not initialized")?;
+        let instance_buffer = *self.instance_buffers.get(self.current_frame)
+            .context("Instance buffer not initialized")?;
+        
+        // Camera is the same for every command buffer
+        let view_proj = self.calculate_view_projection(swapchain.extent);
+        
+        let mut push_bytes = [0u8; CubePushConstants::SIZE];
+        CubePushConstants { lod_fade: 0.0, view_proj }.write_bytes(&mut push_bytes);
+        
+        // GPU culling only needs the frustum planes and LOD parameters; CPU
+        // culling already produced the draws (update_culling)
+        let frustum = Frustum::from_view_projection(view_proj);
+        let (eye, _) = self.camera_framing();
+        let lod_scale = self.lod_selector(swapchain.extent).lod_scale;
+        let mut cull_bytes = [0u8; CullPushConstants::SIZE];
+        CullPushConstants {
+            count: self.instance_count,
+            planes: frustum.planes,
+            camera: eye.extend(lod_scale),
+        }.write_bytes(&mut cull_bytes);
         
         // Clear values: color and depth
-        let color = self.config.graphics.clear_color;
+        let color = self.debug_view.clear_color(self.config.graphics.clear_color);
         let clear_values = [
             vk::ClearValue {
                 color: vk::ClearColorValue {
@@ -724,6 +1733,70 @@ impl App {
                 // Begin recording
                 let begin_info = vk::CommandBufferBeginInfo::builder();
                 device.begin_command_buffer(cmd, &begin_info)?;
+                if let Some(gpu_timer) = &self.gpu_timer {
+                    gpu_timer.cmd_begin(device, cmd, self.current_frame);
+                }
+                
+                // Animate instances (compute), unless the compute queue already
+                // did. The previous frame may still be reading the draw buffers,
+                // so wait for it first.
+                device.cmd_pipeline_barrier(
+                    cmd,
+                    vk::PipelineStageFlags::VERTEX_INPUT | vk::PipelineStageFlags::DRAW_INDIRECT,
+                    vk::PipelineStageFlags::COMPUTE_SHADER | vk::PipelineStageFlags::TRANSFER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[],
+                );
+                if self.async_compute.is_none() {
+                    self.cmd_animate_instances(device, cmd)?;
+                }
+                
+                // GPU culling: reset the draw count, then test every animated
+                // instance and append an indirect draw for each visible one
+                if self.culling == CullingMode::Gpu {
+                    let cull_pipeline = self.cull_pipeline.as_ref()
+                        .context("Cull pipeline not initialized")?;
+                    let draw_count_buffer = self.draw_count_buffer
+                        .context("Draw count buffer not initialized")?;
+                    
+                    device.cmd_fill_buffer(cmd, draw_count_buffer, 0, vk::WHOLE_SIZE, 0);
+                    let reset_barrier = vk::MemoryBarrier::builder()
+                        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                        .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE)
+                        .build();
+                    device.cmd_pipeline_barrier(
+                        cmd,
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::PipelineStageFlags::COMPUTE_SHADER,
+                        vk::DependencyFlags::empty(),
+                        &[reset_barrier],
+                        &[],
+                        &[],
+                    );
+                    backend::compute::cmd_compute_barrier(
+                        device,
+                        cmd,
+                        vk::PipelineStageFlags::COMPUTE_SHADER,
+                        vk::AccessFlags::SHADER_READ,
+                    );
+                    backend::compute::cmd_dispatch(
+                        device,
+                        cmd,
+                        cull_pipeline,
+                        &[self.cull_descriptor_sets[self.current_frame]],
+                        &cull_bytes,
+                        [self.instance_count, 1, 1],
+                    );
+                }
+                
+                backend::compute::cmd_compute_barrier(
+                    device,
+                    cmd,
+                    vk::PipelineStageFlags::VERTEX_INPUT | vk::PipelineStageFlags::DRAW_INDIRECT,
+                    vk::AccessFlags::VERTEX_ATTRIBUTE_READ | vk::AccessFlags::INDIRECT_COMMAND_READ,
+                );
                 
                 // Begin render pass
                 let render_pass_info = vk::RenderPassBeginInfo::builder()
@@ -737,41 +1810,85 @@ impl App {
                 
                 device.cmd_begin_render_pass(cmd, &render_pass_info, vk::SubpassContents::INLINE);
                 
-                // Bind pipeline
-                device.cmd_bind_pipeline(cmd, vk::PipelineBindPoint::GRAPHICS, pipeline);
-                
-                // Bind vertex buffer
-                device.cmd_bind_vertex_buffers(cmd, 0, &[vertex_buffer], &[0]);
+                // Bind vertex buffer (binding 0) and instance buffer (binding 1)
+                device.cmd_bind_vertex_buffers(cmd, 0, &[vertex_buffer, instance_buffer], &[0, 0]);
                 
                 // Bind index buffer
-                device.cmd_bind_index_buffer(cmd, index_buffer, 0, vk::IndexType::UINT16);
+                device.cmd_bind_index_buffer(cmd, index_buffer, 0, self.index_type);
                 
-                // Calculate matrices (animated)
-                let time = self.start_time.elapsed().as_secs_f32();
-                let (mvp, model) = self.calculate_matrices(time, swapchain.extent);
+                // The same draws once per pipeline of the active view
+                // (the wireframe overlay goes on top of the lit pass)
+                let lit_pass = self.debug_view.draws_lit()
+                    .then_some((pipeline, pipeline_layout, true));
+                let debug_pass = self.debug_pipelines
+                    .get(&self.debug_view)
+                    .map(|(debug, uses_lighting)| (debug.pipeline, debug.layout, *uses_lighting));
+                for (pass_pipeline, pass_layout, uses_lighting) in lit_pass.into_iter().chain(debug_pass) {
+                    device.cmd_bind_pipeline(cmd, vk::PipelineBindPoint::GRAPHICS, pass_pipeline);
+                    
+                    // Variants of cube.frag read the lighting block (set 0)
+                    if let Some(&lighting_set) = self.lighting_descriptor_sets.get(self.current_frame) {
+                        if uses_lighting {
+                            device.cmd_bind_descriptor_sets(
+                                cmd,
+                                vk::PipelineBindPoint::GRAPHICS,
+                                pass_layout,
+                                0,
+                                &[lighting_set],
+                                &[],
+                            );
+                        }
+                    }
+                    if let Some(&deform_set) = self.deform_descriptor_sets.get(self.current_frame) {
+                        device.cmd_bind_descriptor_sets(
+                            cmd,
+                            vk::PipelineBindPoint::GRAPHICS,
+                            pass_layout,
+                            DEFORM_SET,
+                            &[deform_set],
+                            &[],
+                        );
+                    }
+                    
+                    // Push the camera matrix (no LOD fade)
+                    device.cmd_push_constants(
+                        cmd,
+                        pass_layout,
+                        vk::ShaderStageFlags::VERTEX,
+                        0,
+                        &push_bytes,
+                    );
+                    self.cmd_draw_cubes(device, cmd, pass_layout)?;
+                }
                 
-                // Push MVP and model matrices
-                let matrix_bytes = matrices_to_bytes(&mvp, &model);
-                device.cmd_push_constants(
-                    cmd,
-                    pipeline_layout,
-                    vk::ShaderStageFlags::VERTEX,
-                    0,
-                    &matrix_bytes,
-                );
+                // Debug lines on top of the scene
+                if let Some(debug_draw_renderer) = &self.debug_draw_renderer {
+                    debug_draw_renderer.cmd_draw(device, cmd, self.current_frame, view_proj);
+                }
                 
-                // Draw indexed
-                device.cmd_draw_indexed(
-                    cmd,
-                    CUBE_INDICES.len() as u32,
-                    1,  // instance count
-                    0,  // first index
-                    0,  // vertex offset
-                    0,  // first instance
-                );
+                // 2D layer over the scene
+                if let Some(sprite_renderer) = &self.sprite_renderer {
+                    sprite_renderer.cmd_draw(device, cmd, self.current_frame);
+                }
+                
+                // Labels: world text against the scene, screen text over it
+                if let Some(text_renderer) = &self.text_renderer {
+                    text_renderer.cmd_draw(device, cmd, self.current_frame, view_proj, swapchain.extent);
+                }
+                
+                // HUD, then egui windows over everything
+                if let Some(overlay_renderer) = &self.overlay_renderer {
+                    overlay_renderer.cmd_draw(device, cmd, self.current_frame, swapchain.extent);
+                }
+                if let Some(gui_renderer) = &self.gui_renderer {
+                    gui_renderer.cmd_draw(device, cmd, self.current_frame);
+                }
                 
                 // End render pass
                 device.cmd_end_render_pass(cmd);
+                if let Some(gpu_timer) = &self.gpu_timer {
+                    gpu_timer.cmd_end(device, cmd, self.current_frame);
+                }
                 
                 // End recording
                 device.end_command_buffer(cmd)?;
@@ -781,34 +1898,589 @@ impl App {
         Ok(())
     }
     
-    /// Calculate Model-View-Projection and Model matrices for the cube
-    fn calculate_matrices(&self, time: f32, extent: vk::Extent2D) -> (glam::Mat4, glam::Mat4) {
+    /// Record the compute pass animating this frame's instance buffer
+    fn cmd_animate_instances(&self, device: &ash::Device, cmd: vk::CommandBuffer) -> Result<()> {
+        let animate_pipeline = self.animate_pipeline.as_ref()
+            .context("Animation pipeline not initialized")?;
+        let &animate_set = self.animate_descriptor_sets.get(self.current_frame)
+            .context("Animation descriptor sets not initialized")?;
+        
+        let mut animate_bytes = [0u8; AnimatePushConstants::SIZE];
+        AnimatePushConstants {
+            time: self.animation_time,
+            count: self.instance_count,
+            duration: self.transform_clip.duration,
+            mode: self.transform_clip.mode as u32,
+            tracks: self.animation_tracks,
+        }.write_bytes(&mut animate_bytes);
+        backend::compute::cmd_dispatch(
+            device,
+            cmd,
+            animate_pipeline,
+            &[animate_set],
+            &animate_bytes,
+            [self.instance_count, 1, 1],
+        );
+        Ok(())
+    }
+    
+    /// Animate this frame's instances on the compute queue; the graphics
+    /// submit waits on `finished[current_frame]`
+    ///
+    /// The instance buffer was last read by this slot's previous frame,
+    /// whose fence render_frame has just waited on, so no barrier is needed.
+    fn submit_async_compute(&self, device: &VulkanDevice, compute: &AsyncCompute) -> Result<()> {
+        let cmd = compute.begin(&device.device, self.current_frame)?;
+        self.cmd_animate_instances(&device.device, cmd)?;
+        compute.submit(device, self.current_frame)
+    }
+    
+    /// Record this frame's cube draws (pipeline, buffers and camera are bound)
+    ///
+    /// GPU culling draws whatever the cull pass wrote; otherwise the draws
+    /// come from update_culling, pushing the LOD fade where it changes.
+    fn cmd_draw_cubes(
+        &self,
+        device: &ash::Device,
+        cmd: vk::CommandBuffer,
+        pipeline_layout: vk::PipelineLayout,
+    ) -> Result<()> {
+        unsafe {
+            if self.culling == CullingMode::Gpu {
+                // The cull pass wrote the commands and how many there are
+                let draw_command_buffer = self.draw_command_buffer
+                    .context("Draw command buffer not initialized")?;
+                let draw_count_buffer = self.draw_count_buffer
+                    .context("Draw count buffer not initialized")?;
+                device.cmd_draw_indexed_indirect_count(
+                    cmd,
+                    draw_command_buffer,
+                    0,
+                    draw_count_buffer,
+                    0,
+                    self.instance_count,  // max draws
+                    std::mem::size_of::<vk::DrawIndexedIndirectCommand>() as u32,
+                );
+            } else {
+                // One instanced draw per run of visible cubes at one LOD;
+                // fading draws only change the dither coverage
+                let mut fade = 0.0;
+                for draw in &self.lod_draws {
+                    if draw.fade != fade {
+                        fade = draw.fade;
+                        device.cmd_push_constants(
+                            cmd,
+                            pipeline_layout,
+                            vk::ShaderStageFlags::VERTEX,
+                            0,
+                            &fade.to_ne_bytes(),
+                        );
+                    }
+                    let level = &self.mesh_lods[draw.level];
+                    device.cmd_draw_indexed(
+                        cmd,
+                        level.index_count,
+                        draw.instances.end - draw.instances.start,  // instance count
+                        level.first_index,  // first index
+                        0,  // vertex offset
+                        draw.instances.start,  // first instance
+                    );
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Queue this frame's debug shapes and upload them for the current frame
+    fn update_debug_draw(&mut self) -> Result<()> {
+        self.debug_draw.clear();
+        if self.show_debug_shapes {
+            self.queue_debug_shapes();
+        }
+        
+        if let (Some(renderer), Some(device)) = (self.debug_draw_renderer.as_mut(), self.device.as_ref()) {
+            renderer.upload(&device.device, self.current_frame, &self.debug_draw)?;
+        }
+        Ok(())
+    }
+    
+    /// Swap in assets the loader finished; failed loads keep their placeholder
+    fn update_assets(&mut self) -> Result<()> {
+        let Some(loader) = self.asset_loader.as_mut() else {
+            return Ok(());
+        };
+        loader.poll();
+        let mesh = self.pending_mesh.and_then(|handle| loader.take(handle));
+        let image = self.pending_sprite_image.and_then(|handle| loader.take(handle));
+        
+        if let Some(result) = mesh {
+            self.pending_mesh = None;
+            match result {
+                Ok(loaded) => {
+                    self.replace_scene_mesh(&loaded.mesh.view(), &loaded.morph_deltas)?;
+                    self.set_rig(loaded.rig)?;
+                }
+                Err(e) => log::warn!("{:#}; keeping the placeholder cube", e),
+            }
+        }
+        if let Some(result) = image {
+            self.pending_sprite_image = None;
+            match result {
+                Ok(image) => {
+                    if let (Some(renderer), Some(device), Some(texture)) =
+                        (self.sprite_renderer.as_mut(), self.device.as_ref(), self.sprite_image)
+                    {
+                        let extent = vk::Extent2D { width: image.width, height: image.height };
+                        renderer.replace_texture(device, texture, extent, &image.rgba)?;
+                    }
+                }
+                Err(e) => log::warn!("{:#}; keeping the checker", e),
+            }
+        }
+        Ok(())
+    }
+    
+    /// Upload `mesh` in place of the current scene mesh (geometry buffers,
+    /// morph deltas, LOD levels, bounds and, with GPU culling, the cull
+    /// pass's copies)
+    ///
+    /// Waits for the device, as frames in flight still read the old buffers.
+    fn replace_scene_mesh(&mut self, mesh: &MeshView, morph_deltas: &[MorphDelta]) -> Result<()> {
+        let device = self.device.clone().context("Device not initialized")?;
+        log::info!("Mesh: {} ({} vertices, LODs of {:?} triangles, {} materials)",
+            self.config.scene.mesh,
+            mesh.vertex_count(),
+            mesh.lods.iter().map(|l| l.index_count / 3).collect::<Vec<_>>(),
+            mesh.submeshes.len().max(1));
+        let lods = mesh.lods.to_vec();
+        
+        // Everything new is created before anything old is destroyed
+        let vertex_buffer = backend::buffer::create_buffer_with_data(
+            &device,
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+            mesh.vertices,
+        )?;
+        let index_buffer = backend::buffer::create_buffer_with_data(
+            &device,
+            vk::BufferUsageFlags::INDEX_BUFFER,
+            mesh.indices,
+        )?;
+        let morph_delta_buffer = create_morph_delta_buffer(&device, morph_deltas)?;
+        let cull_buffers = match self.culling {
+            CullingMode::Gpu => Some(create_cull_mesh_buffers(&device, &mesh.bounds, &lods, self.instance_count)?),
+            _ => None,
+        };
+        
+        device.wait_idle()?;
+        unsafe {
+            for (buffer, memory) in [
+                (self.vertex_buffer.replace(vertex_buffer.0), self.vertex_buffer_memory.replace(vertex_buffer.1)),
+                (self.index_buffer.replace(index_buffer.0), self.index_buffer_memory.replace(index_buffer.1)),
+                (
+                    self.morph_delta_buffer.replace(morph_delta_buffer.0),
+                    self.morph_delta_memory.replace(morph_delta_buffer.1),
+                ),
+            ] {
+                if let Some(buffer) = buffer {
+                    device.device.destroy_buffer(buffer, None);
+                }
+                if let Some(memory) = memory {
+                    device.device.free_memory(memory, None);
+                }
+            }
+        }
+        
+        for &set in &self.deform_descriptor_sets {
+            DescriptorWriter::new()
+                .storage_buffer(1, morph_delta_buffer.0, 0, vk::WHOLE_SIZE)
+                .write(&device, set);
+        }
+        
+        if let Some([object_bounds, lod_table]) = cull_buffers {
+            unsafe {
+                for (buffer, memory) in [
+                    (self.object_bounds_buffer.replace(object_bounds.0), self.object_bounds_memory.replace(object_bounds.1)),
+                    (self.lod_buffer.replace(lod_table.0), self.lod_buffer_memory.replace(lod_table.1)),
+                ] {
+                    if let Some(buffer) = buffer {
+                        device.device.destroy_buffer(buffer, None);
+                    }
+                    if let Some(memory) = memory {
+                        device.device.free_memory(memory, None);
+                    }
+                }
+            }
+            for &set in &self.cull_descriptor_sets {
+                DescriptorWriter::new()
+                    .storage_buffer(0, object_bounds.0, 0, vk::WHOLE_SIZE)
+                    .storage_buffer(4, lod_table.0, 0, vk::WHOLE_SIZE)
+                    .write(&device, set);
+            }
+        }
+        
+        self.index_type = mesh.index_type;
+        self.morph_vertex_count = mesh.vertex_count() as u32;
+        self.mesh_bounds = mesh.bounds;
+        self.mesh_lods = lods;
+        Ok(())
+    }
+    
+    /// Pose the joints with `rig`, or reset them to identity for a rigid mesh
+    fn set_rig(&mut self, rig: Option<Rig>) -> Result<()> {
+        if let Some(rig) = &rig {
+            log::info!(
+                "Rig: {} joints, {} morph targets, clips {:?}",
+                rig.skeleton.joints().len(),
+                rig.morph_weights().len(),
+                rig.clips.iter().map(|clip| clip.name.as_str()).collect::<Vec<_>>()
+            );
+        } else if let Some(device) = self.device.as_ref() {
+            for buffer in &self.joint_buffers {
+                buffer.write(&device.device, &[glam::Mat4::IDENTITY; MAX_JOINTS])?;
+            }
+            for buffer in &self.morph_buffers {
+                buffer.write(&device.device, &[ActiveMorphs::NONE])?;
+            }
+        }
+        self.rig = rig;
+        Ok(())
+    }
+    
+    /// Advance the rig by `dt` seconds and upload its joint matrices and
+    /// strongest morph weights for the current frame
+    fn update_rig(&mut self, dt: f32) -> Result<()> {
+        let (Some(rig), Some(device)) = (self.rig.as_mut(), self.device.as_ref()) else {
+            return Ok(());
+        };
+        if let Some(buffer) = self.joint_buffers.get(self.current_frame) {
+            buffer.write(&device.device, rig.update(dt))?;
+        }
+        if let Some(buffer) = self.morph_buffers.get(self.current_frame) {
+            let active = ActiveMorphs::select(rig.morph_weights(), self.morph_vertex_count);
+            buffer.write(&device.device, &[active])?;
+        }
+        Ok(())
+    }
+    
+    /// Queue this frame's sprites and upload them for the current frame
+    fn update_sprites(&mut self) -> Result<()> {
+        let Some(extent) = self.swapchain.as_ref().map(|swapchain| swapchain.extent) else {
+            return Ok(());
+        };
+        
+        self.sprite_batch.clear();
+        if let (true, Some(atlas)) = (self.show_sprite_demo, self.sprite_demo_atlas.as_ref()) {
+            let image = self.sprite_image.zip(self.sprite_renderer.as_ref())
+                .map(|(texture, renderer)| (texture, renderer.texture_size(texture)));
+            debug::sprites::queue(&mut self.sprite_batch, atlas, image, extent, self.animation_time)?;
+        }
+        
+        if let (Some(renderer), Some(device)) = (self.sprite_renderer.as_mut(), self.device.as_ref()) {
+            renderer.upload(&device.device, self.current_frame, &self.sprite_batch, extent)?;
+        }
+        Ok(())
+    }
+    
+    /// Queue this frame's labels and upload them for the current frame
+    fn update_text(&mut self) -> Result<()> {
+        use glam::{Vec2, Vec3};
+        
+        let Some(extent) = self.swapchain.as_ref().map(|swapchain| swapchain.extent) else {
+            return Ok(());
+        };
+        let view = self.calculate_view();
+        let Some(text) = self.text.as_mut() else {
+            return Ok(());
+        };
+        text.clear();
+        
+        // Axis names just past the arrow heads, facing the camera
+        if self.show_debug_shapes {
+            for (axis, name, color) in [
+                (Vec3::X, "X", debug_draw::RED),
+                (Vec3::Y, "Y", debug_draw::GREEN),
+                (Vec3::Z, "Z", debug_draw::BLUE),
+            ] {
+                text.billboard(axis * 1.25, view, name, &TextStyle::new(0.3, color).align(Align::Center));
+            }
+        }
+        
+        // Which debug view is on, centered at the top
+        if self.debug_view != DebugView::Lit {
+            text.screen(
+                Vec2::new(extent.width as f32 * 0.5, 12.0),
+                self.debug_view.name(),
+                &TextStyle::new(32.0, [1.0, 1.0, 1.0, 0.9]).align(Align::Center),
+            );
+        }
+        
+        if let (Some(renderer), Some(device)) = (self.text_renderer.as_mut(), self.device.as_ref()) {
+            renderer.upload(&device.device, self.current_frame, text)?;
+        }
+        Ok(())
+    }
+    
+    /// Read back GPU timing, lay out the HUD and upload it for the current frame
+    fn update_hud(&mut self) -> Result<()> {
+        let Some(device) = self.device.clone() else {
+            return Ok(());
+        };
+        
+        if let Some(ms) = self.gpu_timer.as_ref().and_then(|t| t.read_ms(&device.device, self.current_frame)) {
+            self.gpu_ms = Some(ms);
+        }
+        
+        let stats = self.hud_stats(&device);
+        if let (Some(overlay), Some(renderer)) = (self.overlay.as_mut(), self.overlay_renderer.as_mut()) {
+            overlay.clear();
+            self.hud.build(overlay, &stats);
+            renderer.upload(&device.device, self.current_frame, overlay)?;
+        }
+        Ok(())
+    }
+    
+    /// Run this frame's egui UI (the inspector) and upload it for the current frame
+    fn update_gui(&mut self) -> Result<()> {
+        let (Some(gui), Some(window), Some(device)) = (self.gui.as_mut(), self.window.clone(), self.device.clone()) else {
+            return Ok(());
+        };
+        let Some(extent) = self.swapchain.as_ref().map(|swapchain| swapchain.extent) else {
+            return Ok(());
+        };
+        
+        let inspector = &mut self.inspector;
+        let config = &mut self.config;
+        let frame = gui.run(&window, |context| {
+            if inspector.visible {
+                inspector.show(context, config);
+            }
+        });
+        
+        if let Some(renderer) = self.gui_renderer.as_mut() {
+            renderer.update_textures(&device, &frame.textures_delta)?;
+            renderer.upload(&device.device, self.current_frame, &frame.primitives, frame.pixels_per_point, extent)?;
+        }
+        Ok(())
+    }
+    
+    /// Counters shown by the HUD for the frame about to be recorded
+    fn hud_stats(&self, device: &VulkanDevice) -> HudStats {
+        // Every pass of the debug view repeats the cube draws
+        let passes = self.debug_view.draws_lit() as u32
+            + self.debug_pipelines.contains_key(&self.debug_view) as u32;
+        let (cube_draws, triangles, culling) = match self.culling {
+            // One indirect draw; what it expands to stays on the GPU
+            CullingMode::Gpu => (1, None, "GPU culling".to_string()),
+            CullingMode::Cpu | CullingMode::Off => (
+                self.lod_draws.len() as u32,
+                Some(self.lod_draws.iter().map(|draw| {
+                    let triangles = (self.mesh_lods[draw.level].index_count / 3) as u64;
+                    triangles * (draw.instances.end - draw.instances.start) as u64
+                }).sum::<u64>()),
+                format!(
+                    "{}/{} visible, {} culled",
+                    self.cull_result.visible,
+                    self.instance_count,
+                    self.cull_result.culled
+                ),
+            ),
+        };
+        let line_draws = self.debug_draw_renderer
+            .as_ref()
+            .map_or(0, |renderer| renderer.draw_count(self.current_frame));
+        let text_draws = self.text_renderer
+            .as_ref()
+            .map_or(0, |renderer| renderer.draw_count(self.current_frame));
+        let sprite_draws = self.sprite_renderer
+            .as_ref()
+            .map_or(0, |renderer| renderer.draw_count(self.current_frame));
+        
+        HudStats {
+            gpu_ms: self.gpu_ms,
+            draw_calls: cube_draws * passes + line_draws + sprite_draws + text_draws + self.hud.visible as u32,
+            triangles: triangles.map(|t| t * passes as u64),
+            memory: device.memory_usage(),
+            present_mode: self.swapchain.as_ref().map_or(vk::PresentModeKHR::FIFO, |s| s.present_mode),
+            view: self.debug_view.name(),
+            culling,
+            clock: self.clock.to_string(),
+            latency_ms: self.pacer.latency_ms(),
+            loading: self.asset_loader.as_ref().map_or(0, AssetLoader::in_flight),
+        }
+    }
+    
+    /// Ground grid, origin axes and the world bounds of the drawn cubes
+    fn queue_debug_shapes(&mut self) {
+        use backend::culling::Aabb;
         use glam::{Mat4, Vec3};
         
-        // Model matrix: rotate cube over time
-        let model = Mat4::from_rotation_y(time * 0.5)
-            * Mat4::from_rotation_x(time * 0.3);
+        // Grid just below the lowest cube, covering the whole scene
+        let scene = Aabb::from_points(
+            self.instance_sources.iter().map(|s| Vec3::from_slice(&s.position_scale[..3])),
+        );
+        let half_size = (scene.half_extents().max_element() + self.config.scene.cube_spacing).ceil();
+        let center = scene.center();
+        self.debug_draw.grid(
+            Vec3::new(center.x, scene.min.y - 1.0, center.z),
+            half_size,
+            (half_size * 2.0) as u32,
+            debug_draw::GRAY,
+        );
+        
+        // CPU culling knows what is visible; GPU culling results stay on the GPU
+        let all = 0..self.instance_count;
+        let ranges = match self.culling {
+            CullingMode::Gpu => std::slice::from_ref(&all),
+            CullingMode::Cpu | CullingMode::Off => &self.cull_result.visible_ranges[..],
+        };
+        for i in ranges.iter().cloned().flatten() {
+            let model = self.instance_sources[i as usize].model(self.animation_time, &self.transform_clip);
+            self.debug_draw.aabb(&self.mesh_bounds.aabb.transformed(&model), debug_draw::YELLOW);
+        }
         
-        // View matrix: camera positioned at (0, 0, 3) looking at origin
-        let view = Mat4::look_at_rh(
-            Vec3::new(0.0, 0.0, 3.0),  // eye
-            Vec3::ZERO,                 // center
-            Vec3::Y,                    // up
+        self.debug_draw.set_overlay(true);
+        self.debug_draw.axes(Mat4::IDENTITY, 1.0);
+    }
+    
+    /// Decide which instances to draw this frame (CPU culling and Off)
+    ///
+    /// Each instance's transform is recomputed from its source, the mesh
+    /// bounds are moved with it and tested against the camera frustum.
+    fn update_culling(&mut self) {
+        let Some(extent) = self.swapchain.as_ref().map(|s| s.extent) else {
+            return;
+        };
+        
+        self.lod_draws.clear();
+        if self.culling == CullingMode::Gpu {
+            self.cull_result = CullResult::default();
+            return;
+        }
+        
+        let time = self.animation_time;
+        let transforms: Vec<glam::Mat4> = self.instance_sources
+            .iter()
+            .map(|source| source.model(time, &self.transform_clip))
+            .collect();
+        
+        self.cull_result = match self.culling {
+            CullingMode::Cpu => {
+                let frustum = Frustum::from_view_projection(self.calculate_view_projection(extent));
+                frustum.cull(&self.mesh_bounds, transforms.iter().copied())
+            }
+            _ => CullResult {
+                visible_ranges: std::iter::once(0..self.instance_count).collect(),
+                visible: self.instance_count,
+                culled: 0,
+            },
+        };
+        
+        // Pick a LOD per visible instance from its distance to the camera
+        let selector = self.lod_selector(extent);
+        let (eye, _) = self.camera_framing();
+        for i in self.cull_result.visible_ranges.iter().flat_map(|range| range.clone()) {
+            let transform = &transforms[i as usize];
+            let sphere = self.mesh_bounds.sphere.transformed(transform);
+            // Largest axis scale, the instance's and the clip's together
+            let scale = transform.x_axis.truncate().length()
+                .max(transform.y_axis.truncate().length())
+                .max(transform.z_axis.truncate().length());
+            let selection = selector.select(
+                &self.mesh_lods,
+                scale,
+                sphere.center.distance(eye) - sphere.radius,
+            );
+            lod::push_lod_draws(&mut self.lod_draws, i, selection);
+        }
+    }
+    
+    /// LOD selection for the current viewport and `[graphics]` LOD settings
+    fn lod_selector(&self, extent: vk::Extent2D) -> LodSelector {
+        LodSelector::new(
+            extent.height as f32,
+            self.config.camera.fov_y.to_radians(),
+            self.config.graphics.lod_threshold,
+            self.config.graphics.lod_fade_range,
+        )
+    }
+    
+    /// Pixel size of the HUD font
+    const HUD_FONT_PX: f32 = 14.0;
+    
+    /// `[graphics] text_font`, or the bundled font when it is empty or fails to load
+    fn load_text_font(&self) -> Result<(SdfFont, Vec<u8>)> {
+        let path = &self.config.graphics.text_font;
+        if !path.is_empty() {
+            match SdfFont::load(path) {
+                Ok(font) => return Ok(font),
+                Err(e) => log::warn!("{:#}; using the bundled font", e),
+            }
+        }
+        SdfFont::bundled()
+    }
+    
+    /// Camera position and far plane distance
+    ///
+    /// The camera orbits the origin at `[camera]` yaw/pitch; without a set
+    /// distance it backs off until the whole instance grid fits the FOV.
+    fn camera_framing(&self) -> (glam::Vec3, f32) {
+        let camera = &self.config.camera;
+        
+        // Bounding radius of the instance grid plus one mesh
+        let side = (self.instance_count.max(1) as f32).cbrt().ceil();
+        let half_extent = (side - 1.0) * self.config.scene.cube_spacing * 0.5;
+        let sphere = self.mesh_bounds.sphere;
+        let radius = half_extent * 3.0_f32.sqrt() + sphere.center.length() + sphere.radius;
+        
+        // Back off until the grid fits the FOV (3 units for a single cube)
+        let distance = if camera.distance > 0.0 {
+            camera.distance
+        } else {
+            (radius / (camera.fov_y * 0.5).to_radians().sin()).max(3.0)
+        };
+        
+        // Yaw around Y, then pitch up; short of the poles so "up" stays valid
+        let yaw = camera.yaw.to_radians();
+        let pitch = camera.pitch.clamp(-89.0, 89.0).to_radians();
+        let direction = glam::Vec3::new(
+            yaw.sin() * pitch.cos(),
+            pitch.sin(),
+            yaw.cos() * pitch.cos(),
         );
+        (direction * distance, (distance + radius * 2.0).max(100.0))
+    }
+    
+    /// View matrix: the camera looking at the origin
+    fn calculate_view(&self) -> glam::Mat4 {
+        let (eye, _) = self.camera_framing();
+        glam::Mat4::look_at_rh(
+            eye,              // eye
+            glam::Vec3::ZERO, // center
+            glam::Vec3::Y,    // up
+        )
+    }
+    
+    /// Calculate the View-Projection matrix, framing the whole instance grid
+    fn calculate_view_projection(&self, extent: vk::Extent2D) -> glam::Mat4 {
+        use glam::Mat4;
+        
+        let (_, far) = self.camera_framing();
+        let view = self.calculate_view();
         
-        // Projection matrix: perspective with 45° FOV
+        // Projection matrix: perspective with the configured FOV
         let aspect = extent.width as f32 / extent.height as f32;
         let mut proj = Mat4::perspective_rh(
-            45.0_f32.to_radians(),
+            self.config.camera.fov_y.to_radians(),
             aspect,
-            0.1,   // near plane
-            100.0, // far plane
+            0.1, // near plane
+            far, // far plane
         );
         
         // Vulkan has Y pointing down in clip space, flip it
         proj.y_axis.y *= -1.0;
         
-        (proj * view * model, model)
+        proj * view
     }
     
     // =========================================================================
@@ -941,8 +2613,28 @@ impl App {
         }
         
         // ─────────────────────────────────────────────────────────────────────
-        // STEP 2.5: Re-record command buffer with updated time for animation
+        // STEP 2.5: Cull, then re-record command buffer with updated time
         // ─────────────────────────────────────────────────────────────────────
+        self.pacer.begin_frame();
+        let dt = self.clock.tick();
+        self.animation_time = self.clock.time();
+        self.update_assets()?;
+        self.update_rig(dt)?;
+        self.update_culling();
+        self.update_debug_draw()?;
+        self.update_sprites()?;
+        self.update_text()?;
+        self.update_hud()?;
+        self.update_gui()?;
+        self.pacer.configure(self.config.graphics.fps_limit, self.config.graphics.low_latency);
+        let device = self.device.as_ref()
+            .context("Device not initialized")?;
+        if let Some(buffer) = self.lighting_buffers.get(self.current_frame) {
+            buffer.write(&device.device, &[LightingUniforms::from_config(&self.config.lighting)])?;
+        }
+        let swapchain = self.swapchain.as_ref()
+            .context("Swapchain not initialized")?;
+        let sync = &self.frame_sync[self.current_frame];
         self.record_command_buffers_with_config(&device.device, swapchain, &self.command_buffers)?;
         
         // ─────────────────────────────────────────────────────────────────────
@@ -950,13 +2642,25 @@ impl App {
         // ─────────────────────────────────────────────────────────────────────
         let cmd = self.command_buffers[image_index as usize];
         
-        let wait_semaphores = [sync.image_available];
+        // With async compute, the instances are animated on the compute queue
+        // and the draws also wait for that
+        let wait_count = match &self.async_compute {
+            Some(compute) => {
+                self.submit_async_compute(device, compute)?;
+                2
+            }
+            None => 1,
+        };
+        let wait_semaphores = [
+            sync.image_available,
+            self.async_compute.as_ref().map_or(vk::Semaphore::null(), |c| c.finished[self.current_frame]),
+        ];
         let signal_semaphores = [sync.render_finished];
         let command_buffers = [cmd];
         
         let submit_info = vk::SubmitInfo::builder()
-            .wait_semaphores(&wait_semaphores)      // Wait for image to be available
-            .wait_dst_stage_mask(&self.wait_stages) // Which stage waits
+            .wait_semaphores(&wait_semaphores[..wait_count])      // Wait for image (and compute)
+            .wait_dst_stage_mask(&self.wait_stages[..wait_count]) // Which stages wait
             .command_buffers(&command_buffers)      // Commands to execute
             .signal_semaphores(&signal_semaphores); // Signal when done
         
@@ -967,6 +2671,10 @@ impl App {
                 sync.in_flight_fence,  // Signal this fence when GPU is done
             )?;
         }
+        if let Some(gpu_timer) = self.gpu_timer.as_mut() {
+            gpu_timer.submitted(self.current_frame);
+        }
+        self.pacer.submitted(self.gpu_ms);
         
         // ─────────────────────────────────────────────────────────────────────
         // STEP 4: Present the image
@@ -982,6 +2690,7 @@ impl App {
                 if suboptimal {
                     self.needs_resize = true;
                 }
+                self.pacer.presented();
             }
             Err(_) => {
                 self.needs_resize = true;
@@ -1022,35 +2731,12 @@ impl App {
     // FPS TRACKING
     // =========================================================================
     
+    /// Record the frame that was just presented for the HUD's FPS and graph
     pub fn update_fps(&mut self) {
-        if !self.config.debug.show_fps {
-            return;
-        }
-        
         let now = Instant::now();
         let frame_time = now.duration_since(self.last_frame_time).as_secs_f32();
         self.last_frame_time = now;
-        self.frame_count += 1;
-        
-        // Update title every second
-        if now.duration_since(self.last_fps_update).as_secs_f32() >= 1.0 {
-            let elapsed = now.duration_since(self.last_fps_update).as_secs_f32();
-            let fps = self.frame_count as f32 / elapsed;
-            
-            if let Some(ref window) = self.window {
-                let mode = if self.is_fullscreen { "fullscreen" } else { "windowed" };
-                window.set_title(&format!(
-                    "{} - {:.0} FPS ({:.2}ms) [{}]",
-                    self.config.window.title,
-                    fps,
-                    frame_time * 1000.0,
-                    mode
-                ));
-            }
-            
-            self.frame_count = 0;
-            self.last_fps_update = now;
-        }
+        self.hud.record_frame(now, frame_time * 1000.0);
     }
 }
 
@@ -1104,6 +2790,22 @@ impl ApplicationHandler for App {
         _id: winit::window::WindowId,
         event: WindowEvent,
     ) {
+        // egui sees every event first; keys it used (e.g. typing into a
+        // field) do not reach the shortcuts below
+        let gui_consumed = match (self.gui.as_mut(), self.window.as_ref()) {
+            (Some(gui), Some(window)) => gui.on_window_event(window, &event),
+            _ => false,
+        };
+        if matches!(
+            event,
+            WindowEvent::KeyboardInput { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::CursorMoved { .. }
+        ) {
+            self.pacer.input();
+        }
+        
         match event {
             // ─────────────────────────────────────────────────────────────────
             // CLOSE REQUEST
@@ -1149,7 +2851,7 @@ impl ApplicationHandler for App {
             // ─────────────────────────────────────────────────────────────────
             // KEYBOARD INPUT
             // ─────────────────────────────────────────────────────────────────
-            WindowEvent::KeyboardInput { event, .. } => {
+            WindowEvent::KeyboardInput { event, .. } if !gui_consumed => {
                 use winit::keyboard::{KeyCode, PhysicalKey};
                 
                 if event.state.is_pressed() {
@@ -1164,7 +2866,51 @@ impl ApplicationHandler for App {
                             KeyCode::F11 => {
                                 self.toggle_fullscreen();
                             }
-                            _ => {}
+                            // F7 - Toggle debug shapes
+                            KeyCode::F7 => {
+                                self.show_debug_shapes = !self.show_debug_shapes;
+                            }
+                            // F8 - Toggle the stats HUD
+                            KeyCode::F8 => {
+                                self.hud.visible = !self.hud.visible;
+                            }
+                            // F9 - Toggle the inspector
+                            KeyCode::F9 => {
+                                self.inspector.visible = !self.inspector.visible;
+                            }
+                            // F10 - Toggle the sprite layer demo
+                            KeyCode::F10 => {
+                                self.show_sprite_demo = !self.show_sprite_demo;
+                            }
+                            // Space - Pause the animation clock
+                            KeyCode::Space => {
+                                self.clock.toggle_pause();
+                                log::info!("{}", self.clock);
+                            }
+                            // . - Advance the animation one step
+                            KeyCode::Period => {
+                                self.clock.single_step();
+                            }
+                            // [ / ] - Halve / double the animation speed, Backspace - Reset it
+                            KeyCode::BracketLeft | KeyCode::BracketRight | KeyCode::Backspace => {
+                                let scale = match key {
+                                    KeyCode::BracketLeft => self.clock.scale() / 2.0,
+                                    KeyCode::BracketRight => self.clock.scale() * 2.0,
+                                    _ => 1.0,
+                                };
+                                self.clock.set_scale(scale);
+                                log::info!("{}", self.clock);
+                            }
+                            // F1-F6 - Debug views (picked up when the next frame is recorded)
+                            _ => {
+                                if let Some(view) = DebugView::from_key(key) {
+                                    log::info!("Debug view: {}", view.name());
+                                    self.debug_view = view;
+                                    if let Some(ref window) = self.window {
+                                        window.set_title(&format!("{} [{}]", self.config.window.title, view.name()));
+                                    }
+                                }
+                            }
                         }
                     }
                 }
@@ -1173,6 +2919,7 @@ impl ApplicationHandler for App {
             // ─────────────────────────────────────────────────────────────────
             // FOCUS CHANGE
             // ─────────────────────────────────────────────────────────────────
+            #[allow(clippy::collapsible_match)]
             WindowEvent::Focused(focused) => {
                 if focused {
                     // Window regained focus - might need to sync GPU state
@@ -1188,7 +2935,13 @@ impl ApplicationHandler for App {
     
     /// Called when the event loop is about to block waiting for events.
     /// We use this to request continuous redraws for maximum FPS.
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Sleep while the next frame isn't due (input is still handled)
+        if let Some(wake) = self.pacer.wait() {
+            event_loop.set_control_flow(ControlFlow::WaitUntil(wake));
+            return;
+        }
+        event_loop.set_control_flow(ControlFlow::Wait);
         if let Some(ref window) = self.window {
             window.request_redraw();
         }
@@ -1219,6 +2972,9 @@ impl Drop for App {
                 if let Some(pool) = self.command_pool {
                     device.device.destroy_command_pool(pool, None);
                 }
+                if let Some(compute) = &self.async_compute {
+                    compute.destroy(&device.device);
+                }
                 
                 // 3. Geometry buffers
                 if let Some(buffer) = self.index_buffer {
@@ -1227,6 +2983,12 @@ impl Drop for App {
                 if let Some(memory) = self.index_buffer_memory {
                     device.device.free_memory(memory, None);
                 }
+                if let Some(buffer) = self.morph_delta_buffer {
+                    device.device.destroy_buffer(buffer, None);
+                }
+                if let Some(memory) = self.morph_delta_memory {
+                    device.device.free_memory(memory, None);
+                }
                 if let Some(buffer) = self.vertex_buffer {
                     device.device.destroy_buffer(buffer, None);
                 }
@@ -1234,13 +2996,82 @@ impl Drop for App {
                     device.device.free_memory(memory, None);
                 }
                 
+                for &buffer in &self.instance_buffers {
+                    device.device.destroy_buffer(buffer, None);
+                }
+                for &memory in &self.instance_buffer_memory {
+                    device.device.free_memory(memory, None);
+                }
+                if let Some(buffer) = self.instance_source_buffer {
+                    device.device.destroy_buffer(buffer, None);
+                }
+                if let Some(memory) = self.instance_source_memory {
+                    device.device.free_memory(memory, None);
+                }
+                if let Some(buffer) = self.animation_key_buffer {
+                    device.device.destroy_buffer(buffer, None);
+                }
+                if let Some(memory) = self.animation_key_memory {
+                    device.device.free_memory(memory, None);
+                }
+                for (buffer, memory) in [
+                    (self.object_bounds_buffer, self.object_bounds_memory),
+                    (self.lod_buffer, self.lod_buffer_memory),
+                    (self.draw_command_buffer, self.draw_command_memory),
+                    (self.draw_count_buffer, self.draw_count_memory),
+                ] {
+                    if let Some(buffer) = buffer {
+                        device.device.destroy_buffer(buffer, None);
+                    }
+                    if let Some(memory) = memory {
+                        device.device.free_memory(memory, None);
+                    }
+                }
+                
                 // 4. Pipeline
+                if let Some(animate_pipeline) = self.animate_pipeline.take() {
+                    animate_pipeline.destroy(&device.device);
+                }
+                if let Some(cull_pipeline) = self.cull_pipeline.take() {
+                    cull_pipeline.destroy(&device.device);
+                }
+                if let Some(pool) = self.descriptor_pool {
+                    device.device.destroy_descriptor_pool(pool, None);
+                }
+                for buffer in self.lighting_buffers.iter().chain(&self.joint_buffers).chain(&self.morph_buffers) {
+                    buffer.destroy(&device.device);
+                }
+                if let Some(gui_renderer) = self.gui_renderer.take() {
+                    gui_renderer.destroy(&device.device);
+                }
+                if let Some(overlay_renderer) = self.overlay_renderer.take() {
+                    overlay_renderer.destroy(&device.device);
+                }
+                if let Some(gpu_timer) = self.gpu_timer.take() {
+                    gpu_timer.destroy(&device.device);
+                }
+                if let Some(text_renderer) = self.text_renderer.take() {
+                    text_renderer.destroy(&device.device);
+                }
+                if let Some(sprite_renderer) = self.sprite_renderer.take() {
+                    sprite_renderer.destroy(&device.device);
+                }
+                if let Some(debug_draw_renderer) = self.debug_draw_renderer.take() {
+                    debug_draw_renderer.destroy(&device.device);
+                }
+                for (_, (debug_pipeline, _)) in self.debug_pipelines.drain() {
+                    debug_pipeline.destroy(&device.device);
+                }
                 if let Some(pipeline) = self.pipeline {
                     device.device.destroy_pipeline(pipeline, None);
                 }
                 if let Some(layout) = self.pipeline_layout {
                     device.device.destroy_pipeline_layout(layout, None);
                 }
+                for &set_layout in &self.descriptor_set_layouts {
+                    device.device.destroy_descriptor_set_layout(set_layout, None);
+                }
+                self.shader_variants.destroy(&device.device);
                 
                 // 5. Framebuffers
                 for &framebuffer in &self.framebuffers {
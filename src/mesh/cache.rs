@@ -0,0 +1,417 @@
+// Binary mesh cache
+//
+// `.mesh` files hold a mesh exactly as the renderer uploads it, so loading is
+// a memory map, a header check and one copy per GPU buffer. Written by the
+// `convert` command from OBJ files or primitives.
+//
+// Layout (little endian, every section starts 16-byte aligned):
+//   header     HEADER_SIZE bytes, see `Header`
+//   vertices   vertex_count * vertex_stride bytes in the renderer's vertex
+//              layout (`VertexLayout::fingerprint` must match)
+//   indices    index_count u16 or u32: every LOD level back to back
+//   lods       lod_count * (first_index u32, index_count u32, error f32)
+//   materials  material_count * (first_index u32, index_count u32,
+//              name_offset u32, name_len u32), ranges of level 0
+//   strings    UTF-8 material names
+
+use std::fs::File;
+use std::path::Path;
+use anyhow::{Context, Result};
+use ash::vk;
+use glam::Vec3;
+use memmap2::Mmap;
+use crate::backend::culling::{Aabb, BoundingSphere, MeshBounds};
+use super::lod::LodLevel;
+use super::primitives::Submesh;
+use super::process::Indices;
+
+const MAGIC: [u8; 8] = *b"MESHCACH";
+/// Bump when the layout changes; older files then have to be converted again
+const FORMAT_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 128;
+const SECTION_ALIGN: usize = 16;
+const LOD_SIZE: usize = 12;
+const MATERIAL_SIZE: usize = 16;
+
+/// A mesh ready to upload, built in memory
+pub struct MeshAsset {
+    pub vertex_stride: u32,
+    pub vertex_layout: u64,
+    /// Interleaved vertices as raw bytes
+    pub vertices: Vec<u8>,
+    /// Every LOD level back to back
+    pub indices: Indices,
+    pub lods: Vec<LodLevel>,
+    pub bounds: MeshBounds,
+    pub submeshes: Vec<Submesh>,
+}
+
+impl MeshAsset {
+    pub fn view(&self) -> MeshView<'_> {
+        MeshView {
+            vertex_stride: self.vertex_stride,
+            vertex_layout: self.vertex_layout,
+            vertices: &self.vertices,
+            index_type: self.indices.index_type(),
+            indices: self.indices.as_bytes(),
+            lods: &self.lods,
+            bounds: self.bounds,
+            submeshes: &self.submeshes,
+        }
+    }
+}
+
+/// A mesh ready to upload, borrowed from a `MeshAsset` or a mapped file
+pub struct MeshView<'a> {
+    pub vertex_stride: u32,
+    pub vertex_layout: u64,
+    pub vertices: &'a [u8],
+    pub index_type: vk::IndexType,
+    pub indices: &'a [u8],
+    pub lods: &'a [LodLevel],
+    pub bounds: MeshBounds,
+    pub submeshes: &'a [Submesh],
+}
+
+impl MeshView<'_> {
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len() / self.vertex_stride.max(1) as usize
+    }
+    
+    pub fn index_count(&self) -> usize {
+        self.indices.len() / index_size(self.index_type)
+    }
+}
+
+// =============================================================================
+// WRITING
+// =============================================================================
+
+/// Save a mesh as a `.mesh` file
+pub fn write(path: &Path, mesh: &MeshView) -> Result<()> {
+    let mut strings = Vec::new();
+    let mut materials = Vec::with_capacity(mesh.submeshes.len() * MATERIAL_SIZE);
+    for submesh in mesh.submeshes {
+        for value in [submesh.first_index, submesh.index_count, strings.len() as u32, submesh.material.len() as u32] {
+            materials.extend_from_slice(&value.to_le_bytes());
+        }
+        strings.extend_from_slice(submesh.material.as_bytes());
+    }
+    let mut lods = Vec::with_capacity(mesh.lods.len() * LOD_SIZE);
+    for level in mesh.lods {
+        lods.extend_from_slice(&level.first_index.to_le_bytes());
+        lods.extend_from_slice(&level.index_count.to_le_bytes());
+        lods.extend_from_slice(&level.error.to_le_bytes());
+    }
+    
+    // Sections follow the header in this order, each aligned
+    let sections: [&[u8]; 5] = [mesh.vertices, mesh.indices, &lods, &materials, &strings];
+    let mut offsets = [0u64; 5];
+    let mut end = HEADER_SIZE;
+    for (offset, section) in offsets.iter_mut().zip(sections) {
+        end = end.next_multiple_of(SECTION_ALIGN);
+        *offset = end as u64;
+        end += section.len();
+    }
+    
+    let header = Header {
+        vertex_stride: mesh.vertex_stride,
+        vertex_layout: mesh.vertex_layout,
+        vertex_count: mesh.vertex_count() as u32,
+        index_size: index_size(mesh.index_type) as u32,
+        index_count: mesh.index_count() as u32,
+        lod_count: mesh.lods.len() as u32,
+        material_count: mesh.submeshes.len() as u32,
+        strings_size: strings.len() as u32,
+        offsets,
+        bounds: mesh.bounds,
+    };
+    let mut bytes = Vec::with_capacity(end);
+    header.write(&mut bytes);
+    for (offset, section) in offsets.iter().zip(sections) {
+        bytes.resize(*offset as usize, 0);
+        bytes.extend_from_slice(section);
+    }
+    
+    std::fs::write(path, bytes).with_context(|| format!("Failed to write mesh file: {:?}", path))
+}
+
+// =============================================================================
+// READING
+// =============================================================================
+
+/// A `.mesh` file mapped into memory, its sections checked against its size
+pub struct MappedMesh {
+    map: Mmap,
+    header: Header,
+    lods: Vec<LodLevel>,
+    submeshes: Vec<Submesh>,
+}
+
+impl MappedMesh {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open mesh file: {:?}", path))?;
+        // SAFETY: the file must not be truncated while mapped; the cache is
+        // only written by the convert command, not while the renderer runs
+        let map = unsafe { Mmap::map(&file) }
+            .with_context(|| format!("Failed to map mesh file: {:?}", path))?;
+        Self::parse(map).with_context(|| format!("Invalid mesh file: {:?}", path))
+    }
+    
+    fn parse(map: Mmap) -> Result<Self> {
+        let header = Header::read(&map)?;
+        if header.lod_count == 0 {
+            anyhow::bail!("No LOD levels (level 0 is the full mesh)");
+        }
+        let [_, _, lods_offset, materials_offset, strings_offset] = header.offsets;
+        // Offsets and sizes come from the file, so no arithmetic on them may wrap
+        let section = |offset: u64, len: u64| -> Result<&[u8]> {
+            offset.checked_add(len)
+                .and_then(|end| Some(usize::try_from(offset).ok()?..usize::try_from(end).ok()?))
+                .and_then(|range| map.get(range))
+                .context("Section runs past the end of the file")
+        };
+        section(header.offsets[0], header.vertex_count as u64 * header.vertex_stride as u64)?;
+        let indices = section(header.offsets[1], header.index_count as u64 * header.index_size as u64)?;
+        // An index past the vertices would read outside the vertex buffer
+        let largest = match header.index_size {
+            2 => indices.chunks_exact(2).map(|i| u16::from_le_bytes([i[0], i[1]]) as u32).max(),
+            _ => indices.chunks_exact(4).map(|i| u32_at(i, 0)).max(),
+        };
+        if let Some(largest) = largest.filter(|&i| i >= header.vertex_count) {
+            anyhow::bail!("Index {} outside the {} vertices", largest, header.vertex_count);
+        }
+        
+        let lods = section(lods_offset, header.lod_count as u64 * LOD_SIZE as u64)?
+            .chunks_exact(LOD_SIZE)
+            .map(|entry| LodLevel {
+                first_index: u32_at(entry, 0),
+                index_count: u32_at(entry, 4),
+                error: f32::from_bits(u32_at(entry, 8)),
+            })
+            .collect::<Vec<_>>();
+        if lods.iter().any(|level| level.first_index as u64 + level.index_count as u64 > header.index_count as u64) {
+            anyhow::bail!("LOD level outside the index list");
+        }
+        
+        let strings = section(strings_offset, header.strings_size as u64)?;
+        let submeshes = section(materials_offset, header.material_count as u64 * MATERIAL_SIZE as u64)?
+            .chunks_exact(MATERIAL_SIZE)
+            .map(|entry| {
+                let (name_offset, name_len) = (u32_at(entry, 8) as usize, u32_at(entry, 12) as usize);
+                let name = name_offset.checked_add(name_len)
+                    .and_then(|end| strings.get(name_offset..end))
+                    .context("Material name outside the string table")?;
+                Ok(Submesh {
+                    material: String::from_utf8(name.to_vec()).context("Material name isn't UTF-8")?,
+                    first_index: u32_at(entry, 0),
+                    index_count: u32_at(entry, 4),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if submeshes.iter().any(|submesh| submesh.first_index as u64 + submesh.index_count as u64 > header.index_count as u64) {
+            anyhow::bail!("Submesh outside the index list");
+        }
+        
+        Ok(Self { map, header, lods, submeshes })
+    }
+    
+    /// The mapped data; vertex and index bytes point straight into the file
+    pub fn view(&self) -> MeshView<'_> {
+        let bytes = |offset: u64, len: usize| &self.map[offset as usize..offset as usize + len];
+        let header = &self.header;
+        MeshView {
+            vertex_stride: header.vertex_stride,
+            vertex_layout: header.vertex_layout,
+            vertices: bytes(header.offsets[0], header.vertex_count as usize * header.vertex_stride as usize),
+            index_type: if header.index_size == 2 { vk::IndexType::UINT16 } else { vk::IndexType::UINT32 },
+            indices: bytes(header.offsets[1], header.index_count as usize * header.index_size as usize),
+            lods: &self.lods,
+            bounds: header.bounds,
+            submeshes: &self.submeshes,
+        }
+    }
+}
+
+/// Fixed-size start of a `.mesh` file (byte offsets)
+///
+///   0   magic, version
+///   12  vertex_stride, vertex_layout (u64), vertex_count
+///   28  index_size (2 or 4), index_count, lod_count
+///   40  material_count, strings_size
+///   48  section offsets (u64): vertices, indices, lods, materials, strings
+///   88  AABB min, AABB max, bounding sphere center and radius (f32)
+struct Header {
+    vertex_stride: u32,
+    vertex_layout: u64,
+    vertex_count: u32,
+    index_size: u32,
+    index_count: u32,
+    lod_count: u32,
+    material_count: u32,
+    strings_size: u32,
+    offsets: [u64; 5],
+    bounds: MeshBounds,
+}
+
+impl Header {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.vertex_stride.to_le_bytes());
+        out.extend_from_slice(&self.vertex_layout.to_le_bytes());
+        for value in [
+            self.vertex_count,
+            self.index_size,
+            self.index_count,
+            self.lod_count,
+            self.material_count,
+            self.strings_size,
+        ] {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        for offset in self.offsets {
+            out.extend_from_slice(&offset.to_le_bytes());
+        }
+        let bounds = &self.bounds;
+        for value in bounds.aabb.min.to_array()
+            .into_iter()
+            .chain(bounds.aabb.max.to_array())
+            .chain(bounds.sphere.center.to_array())
+            .chain([bounds.sphere.radius])
+        {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out.resize(HEADER_SIZE, 0);
+    }
+    
+    fn read(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_SIZE || bytes[..8] != MAGIC {
+            anyhow::bail!("Not a mesh cache file");
+        }
+        let version = u32_at(bytes, 8);
+        if version != FORMAT_VERSION {
+            anyhow::bail!("Format version {} (expected {}); convert the source again", version, FORMAT_VERSION);
+        }
+        let index_size = u32_at(bytes, 28);
+        if index_size != 2 && index_size != 4 {
+            anyhow::bail!("Invalid index size {}", index_size);
+        }
+        let offsets = [0, 1, 2, 3, 4].map(|i| u64_at(bytes, 48 + i * 8));
+        let vec3_at = |offset: usize| Vec3::new(f32_at(bytes, offset), f32_at(bytes, offset + 4), f32_at(bytes, offset + 8));
+        Ok(Self {
+            vertex_stride: u32_at(bytes, 12),
+            vertex_layout: u64_at(bytes, 16),
+            vertex_count: u32_at(bytes, 24),
+            index_size,
+            index_count: u32_at(bytes, 32),
+            lod_count: u32_at(bytes, 36),
+            material_count: u32_at(bytes, 40),
+            strings_size: u32_at(bytes, 44),
+            offsets,
+            bounds: MeshBounds {
+                aabb: Aabb { min: vec3_at(88), max: vec3_at(100) },
+                sphere: BoundingSphere { center: vec3_at(112), radius: f32_at(bytes, 124) },
+            },
+        })
+    }
+}
+
+fn index_size(index_type: vk::IndexType) -> usize {
+    if index_type == vk::IndexType::UINT16 { 2 } else { 4 }
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+    f32::from_bits(u32_at(bytes, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// One triangle (positions only) in a single LOD level and material
+    fn triangle(indices: Vec<u16>, lods: Vec<LodLevel>) -> MeshAsset {
+        let positions: [[f32; 3]; 3] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        MeshAsset {
+            vertex_stride: 12,
+            vertex_layout: 0x1234_5678_9abc_def0,
+            vertices: positions.iter().flatten().flat_map(|v| v.to_le_bytes()).collect(),
+            indices: Indices::U16(indices),
+            lods,
+            bounds: MeshBounds {
+                aabb: Aabb { min: Vec3::ZERO, max: Vec3::new(1.0, 1.0, 0.0) },
+                sphere: BoundingSphere { center: Vec3::new(0.5, 0.5, 0.0), radius: 0.75 },
+            },
+            submeshes: vec![Submesh { material: "stone".to_string(), first_index: 0, index_count: 3 }],
+        }
+    }
+    
+    fn level(first_index: u32, index_count: u32) -> LodLevel {
+        LodLevel { first_index, index_count, error: 0.0 }
+    }
+    
+    /// Write `mesh`, optionally edit the bytes, and open the file again
+    fn round_trip(name: &str, mesh: &MeshAsset, edit: impl FnOnce(&mut Vec<u8>)) -> Result<MappedMesh> {
+        let path = std::env::temp_dir().join(format!("mesh-cache-{}-{}.mesh", std::process::id(), name));
+        write(&path, &mesh.view()).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        edit(&mut bytes);
+        std::fs::write(&path, bytes).unwrap();
+        let mapped = MappedMesh::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        mapped
+    }
+    
+    fn error(result: Result<MappedMesh>) -> String {
+        format!("{:#}", result.err().expect("the file should be rejected"))
+    }
+    
+    #[test]
+    fn written_mesh_opens_unchanged() {
+        let mesh = triangle(vec![0, 1, 2, 2, 1, 0], vec![level(0, 3), level(3, 3)]);
+        let mapped = round_trip("valid", &mesh, |_| {}).unwrap();
+        let (view, original) = (mapped.view(), mesh.view());
+        assert_eq!(view.vertex_stride, 12);
+        assert_eq!(view.vertex_layout, original.vertex_layout);
+        assert_eq!(view.vertices, original.vertices);
+        assert_eq!(view.index_type, vk::IndexType::UINT16);
+        assert_eq!(view.indices, original.indices);
+        assert_eq!(view.lods.len(), 2);
+        assert_eq!((view.lods[1].first_index, view.lods[1].index_count), (3, 3));
+        assert_eq!(view.submeshes, original.submeshes);
+        assert_eq!(view.bounds.aabb.max, Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(view.bounds.sphere.radius, 0.75);
+    }
+    
+    #[test]
+    fn invalid_files_are_rejected() {
+        let valid = triangle(vec![0, 1, 2], vec![level(0, 3)]);
+        
+        let no_lods = triangle(vec![0, 1, 2], Vec::new());
+        assert!(error(round_trip("no-lods", &no_lods, |_| {})).contains("No LOD levels"));
+        
+        let past_vertices = triangle(vec![0, 1, 3], vec![level(0, 3)]);
+        assert!(error(round_trip("index", &past_vertices, |_| {})).contains("Index 3 outside the 3 vertices"));
+        
+        let past_indices = triangle(vec![0, 1, 2], vec![level(0, 3), level(2, 3)]);
+        assert!(error(round_trip("lod", &past_indices, |_| {})).contains("LOD level outside"));
+        
+        let truncated = error(round_trip("truncated", &valid, |bytes| bytes.truncate(bytes.len() - 1)));
+        assert!(truncated.contains("past the end"), "{truncated}");
+        
+        // A section offset near u64::MAX must not wrap around
+        let wrapped = error(round_trip("offset", &valid, |bytes| bytes[56..64].copy_from_slice(&u64::MAX.to_le_bytes())));
+        assert!(wrapped.contains("past the end"), "{wrapped}");
+        
+        let version = error(round_trip("version", &valid, |bytes| bytes[8] += 1));
+        assert!(version.contains("Format version"), "{version}");
+    }
+}
@@ -0,0 +1,962 @@
+// glTF import
+//
+// Reads the mesh of one node from a .gltf file (buffers in .bin files or
+// base64 data URIs) or a .glb file: the first node with a skin, otherwise
+// the first with a mesh. The `gltf` crate parses and validates the
+// document; accessor data is read here, checked against its buffer view
+// and buffer. Triangle primitives are merged and grouped by material into
+// submeshes, with positions, normals, UVs (flipped to v up), tangents,
+// morph targets and, when skinned, JOINTS_0/WEIGHTS_0. Vertices stay in the
+// mesh's own space; node transforms only shape the skeleton.
+//
+// The skin becomes a `Skeleton`: its joints plus the nodes above them,
+// sorted parents first, with the file's inverse bind matrices; a mesh with
+// morph targets but no skin gets a single root joint. Each animation
+// becomes an `AnimationClip` of the channels driving those joints, plus one
+// `Weight` channel per target from the mesh node's weights channel. The
+// mesh's `weights` hold wherever no clip drives them. Not supported: sparse
+// accessors, primitives other than triangle lists, more than four joints
+// per vertex (JOINTS_1) and node animation of meshes without a skin.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use glam::{Mat4, Quat, Vec3, Vec4};
+use gltf::accessor::DataType;
+use gltf::buffer::Source;
+use gltf::mesh::{Mode, Semantic};
+use serde::Deserialize;
+use crate::anim::clip::{AnimationClip, Channel, Interpolation, Property};
+use crate::anim::player::Rig;
+use crate::anim::skeleton::{Joint, Skeleton, Transform};
+use super::primitives::{MeshData, MorphTarget, Submesh};
+use super::process::{self, NormalMode};
+
+/// Crossfade between imported clips, in seconds
+const FADE: f32 = 0.5;
+
+/// Load a .gltf or .glb file: the mesh, and its rig when it is skinned
+pub fn load(path: &Path) -> Result<(MeshData, Option<Rig>)> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read glTF file: {:?}", path))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    parse(&bytes, base).with_context(|| format!("Failed to import glTF file: {:?}", path))
+}
+
+/// Import from the contents of a .gltf or .glb file; external buffers are
+/// read relative to `base`
+pub fn parse(bytes: &[u8], base: &Path) -> Result<(MeshData, Option<Rig>)> {
+    let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(bytes).context("Invalid glTF document")?;
+    let document = Document::new(document, blob.as_deref(), base)?;
+    
+    let meshes = || document.gltf.nodes().filter_map(|node| Some((node.mesh()?, node)));
+    let (gltf_mesh, node) = meshes()
+        .find(|(_, node)| node.skin().is_some())
+        .or_else(|| meshes().next())
+        .context("No node with a mesh")?;
+    let skin = node.skin().map(|skin| import_skin(&document, &skin)).transpose()?;
+    let mesh = import_mesh(&document, &gltf_mesh, skin.as_ref().map(|skin| &skin.slots[..]))?;
+    let morphs = mesh.morph_targets.len();
+    if skin.is_none() && morphs == 0 {
+        if document.gltf.animations().len() > 0 {
+            log::warn!("Animations of meshes without a skin or morph targets aren't imported");
+        }
+        return Ok((mesh, None));
+    }
+    
+    let weights = morph_weights(&gltf_mesh, morphs)?;
+    let (skeleton, node_joints) = match skin {
+        Some(skin) => (skin.skeleton, skin.node_joints),
+        None => (Skeleton::from_rest_pose(vec![("root".to_string(), None, Transform::IDENTITY)])?, HashMap::new()),
+    };
+    let clips = import_clips(&document, &Targets { node_joints, mesh_node: node.index(), morphs })?;
+    // Every clip plays for as long as the longest before fading to the next
+    let cycle = clips.iter().map(|clip| clip.duration).fold(0.0, f32::max);
+    Ok((mesh, Some(Rig::new(skeleton, clips, cycle, FADE).with_morph_weights(weights))))
+}
+
+// =============================================================================
+// MESH
+// =============================================================================
+
+/// What Blender and most exporters put in a mesh's `extras`
+#[derive(Default, Deserialize)]
+struct MeshExtras {
+    #[serde(default, rename = "targetNames")]
+    target_names: Vec<String>,
+}
+
+/// Merge the triangle primitives of a mesh; `slots` maps the skin's joint
+/// list to skeleton joints (None: not skinned)
+fn import_mesh(document: &Document, gltf_mesh: &gltf::Mesh, slots: Option<&[usize]>) -> Result<MeshData> {
+    let index = gltf_mesh.index();
+    let mut mesh = MeshData::default();
+    // Triangles per material, in order of first use
+    let mut groups: Vec<(String, Vec<u32>)> = Vec::new();
+    let (mut missing_normals, mut missing_tangents) = (false, false);
+    // Every primitive has the same number of morph targets
+    let mut morphs = None;
+    // Extras are free-form, so names that don't parse are left out
+    let names = gltf_mesh.extras()
+        .as_ref()
+        .and_then(|extras| serde_json::from_str::<MeshExtras>(extras.get()).ok())
+        .unwrap_or_default()
+        .target_names;
+    
+    for primitive in gltf_mesh.primitives() {
+        let p = primitive.index();
+        if primitive.mode() != Mode::Triangles {
+            log::warn!("Skipping primitive {} of mesh {}: mode {:?} isn't a triangle list", p, index, primitive.mode());
+            continue;
+        }
+        let at_primitive = || format!("Primitive {} of mesh {}", p, index);
+        let first = mesh.positions.len() as u32;
+        let positions = primitive.get(&Semantic::Positions)
+            .context("No positions")
+            .and_then(|accessor| document.vectors::<3>(&accessor))
+            .with_context(at_primitive)?;
+        let count = positions.len();
+        mesh.positions.extend(positions);
+        let attribute = |semantic: Semantic| per_vertex(primitive.get(&semantic), semantic, count).with_context(at_primitive);
+        
+        match attribute(Semantic::Normals)? {
+            Some(accessor) => mesh.normals.extend(
+                document.vectors::<3>(&accessor)?.into_iter().map(|n| Vec3::from(n).normalize_or_zero().to_array()),
+            ),
+            None => {
+                missing_normals = true;
+                mesh.normals.resize(mesh.positions.len(), [0.0; 3]);
+            }
+        }
+        match attribute(Semantic::TexCoords(0))? {
+            Some(accessor) => mesh.uvs.extend(document.vectors::<2>(&accessor)?.into_iter().map(|[u, v]| [u, 1.0 - v])),
+            None => mesh.uvs.resize(mesh.positions.len(), [0.0; 2]),
+        }
+        match attribute(Semantic::Tangents)? {
+            Some(accessor) => mesh.tangents.extend(
+                document.vectors::<4>(&accessor)?
+                    .into_iter()
+                    .map(|[x, y, z, w]| [x, y, z, if w < 0.0 { -1.0 } else { 1.0 }]),
+            ),
+            None => {
+                missing_tangents = true;
+                mesh.tangents.resize(mesh.positions.len(), [1.0, 0.0, 0.0, 1.0]);
+            }
+        }
+        
+        let targets: Vec<_> = primitive.morph_targets().collect();
+        let expected = *morphs.get_or_insert(targets.len());
+        if targets.len() != expected {
+            anyhow::bail!("{} has {} morph targets where the first has {}", at_primitive(), targets.len(), expected);
+        }
+        if mesh.morph_targets.is_empty() {
+            mesh.morph_targets = (0..targets.len())
+                .map(|t| MorphTarget {
+                    name: names.get(t).cloned().unwrap_or_else(|| format!("target{}", t)),
+                    ..Default::default()
+                })
+                .collect();
+        }
+        // Attributes a target leaves out don't morph
+        for (target, morph) in targets.iter().zip(&mut mesh.morph_targets) {
+            let deltas = |accessor, semantic| -> Result<Vec<[f32; 3]>> {
+                match per_vertex(accessor, semantic, count).with_context(at_primitive)? {
+                    Some(accessor) => document.vectors::<3>(&accessor),
+                    None => Ok(vec![[0.0; 3]; count]),
+                }
+            };
+            morph.positions.extend(deltas(target.positions(), Semantic::Positions)?);
+            morph.normals.extend(deltas(target.normals(), Semantic::Normals)?);
+            morph.tangents.extend(deltas(target.tangents(), Semantic::Tangents)?);
+        }
+        if let Some(slots) = slots {
+            import_influences(document, &mut mesh, slots, attribute(Semantic::Joints(0))?, attribute(Semantic::Weights(0))?)
+                .with_context(at_primitive)?;
+            if primitive.get(&Semantic::Joints(1)).is_some() {
+                log::warn!("{}: only the first four joints per vertex are used", at_primitive());
+            }
+        }
+        
+        let indices = match primitive.indices() {
+            Some(accessor) => document.integers(&accessor)?,
+            None => (0..count as u32).collect(),
+        };
+        if indices.len() % 3 != 0 || indices.iter().any(|&i| i as usize >= count) {
+            anyhow::bail!("{}: indices don't form triangles of its {} vertices", at_primitive(), count);
+        }
+        let material = primitive.material();
+        let material = match material.index() {
+            Some(i) => material.name().map_or_else(|| format!("material{}", i), str::to_string),
+            None => String::new(),
+        };
+        let group = match groups.iter().position(|(name, _)| *name == material) {
+            Some(existing) => existing,
+            None => {
+                groups.push((material, Vec::new()));
+                groups.len() - 1
+            }
+        };
+        groups[group].1.extend(indices.into_iter().map(|i| first + i));
+    }
+    
+    groups.retain(|(_, indices)| !indices.is_empty());
+    if groups.is_empty() {
+        anyhow::bail!("Mesh {} has no triangles", index);
+    }
+    // A single group needs no submeshes unless it names a material
+    let named = groups.len() > 1 || !groups[0].0.is_empty();
+    for (material, indices) in groups {
+        if named {
+            mesh.submeshes.push(Submesh {
+                material,
+                first_index: mesh.indices.len() as u32,
+                index_count: indices.len() as u32,
+            });
+        }
+        mesh.indices.extend(indices);
+    }
+    
+    // Generating normals or tangents redoes those of the morph targets too
+    if missing_normals {
+        process::generate_normals(&mut mesh, NormalMode::Smooth);
+    }
+    if missing_normals || missing_tangents {
+        process::generate_tangents(&mut mesh);
+    }
+    Ok(mesh)
+}
+
+/// An optional attribute of a primitive or morph target, which needs one
+/// value per vertex
+fn per_vertex<'a>(accessor: Option<gltf::Accessor<'a>>, semantic: Semantic, count: usize) -> Result<Option<gltf::Accessor<'a>>> {
+    match accessor {
+        Some(accessor) if accessor.count() != count => {
+            anyhow::bail!("{} has {} values for {} positions", semantic.to_string(), accessor.count(), count)
+        }
+        accessor => Ok(accessor),
+    }
+}
+
+/// The mesh's default morph weights (zeros when it has none)
+fn morph_weights(gltf_mesh: &gltf::Mesh, morphs: usize) -> Result<Vec<f32>> {
+    match gltf_mesh.weights().unwrap_or_default() {
+        [] => Ok(vec![0.0; morphs]),
+        weights if weights.len() == morphs => Ok(weights.to_vec()),
+        weights => anyhow::bail!("Mesh has {} weights for {} morph targets", weights.len(), morphs),
+    }
+}
+
+/// Append the joints and weights of a primitive's vertices: joint indices
+/// go from skin slots to skeleton joints and weights are normalized.
+/// Without JOINTS_0/WEIGHTS_0 vertices follow the first joint.
+fn import_influences(
+    document: &Document,
+    mesh: &mut MeshData,
+    slots: &[usize],
+    joints: Option<gltf::Accessor>,
+    weights: Option<gltf::Accessor>,
+) -> Result<()> {
+    let (Some(joints), Some(weights)) = (joints, weights) else {
+        mesh.joints.resize(mesh.positions.len(), [0; 4]);
+        mesh.weights.resize(mesh.positions.len(), [1.0, 0.0, 0.0, 0.0]);
+        return Ok(());
+    };
+    let joints = document.integers(&joints)?;
+    let weights = document.vectors::<4>(&weights)?;
+    if joints.len() != weights.len() * 4 {
+        anyhow::bail!("JOINTS_0 isn't a VEC4 per vertex");
+    }
+    for (vertex, weights) in joints.chunks_exact(4).zip(weights) {
+        let mut remapped = [0u8; 4];
+        for (joint, &slot) in remapped.iter_mut().zip(vertex) {
+            let skeleton_joint = *slots.get(slot as usize)
+                .with_context(|| format!("Joint {} isn't in the skin", slot))?;
+            // The skeleton has at most MAX_JOINTS (256) joints
+            *joint = skeleton_joint as u8;
+        }
+        let sum: f32 = weights.iter().sum();
+        let weights = if sum > 0.0 { weights.map(|w| w / sum) } else { [1.0, 0.0, 0.0, 0.0] };
+        mesh.joints.push(remapped);
+        mesh.weights.push(weights);
+    }
+    Ok(())
+}
+
+// =============================================================================
+// SKIN AND ANIMATION
+// =============================================================================
+
+/// A skin as a skeleton
+struct ImportedSkin {
+    skeleton: Skeleton,
+    /// Skeleton joint of each node in it
+    node_joints: HashMap<usize, usize>,
+    /// Skeleton joint of each entry of the skin's joint list
+    slots: Vec<usize>,
+}
+
+/// The joints of a skin and every node above them, parents first; nodes
+/// outside the skin's list keep an identity inverse bind matrix
+fn import_skin(document: &Document, skin: &gltf::Skin) -> Result<ImportedSkin> {
+    let index = skin.index();
+    let nodes: Vec<gltf::Node> = document.gltf.nodes().collect();
+    let parents = node_parents(&nodes)?;
+    let skin_nodes: Vec<usize> = skin.joints().map(|joint| joint.index()).collect();
+    if skin_nodes.is_empty() {
+        anyhow::bail!("Skin {} has no joints", index);
+    }
+    
+    let mut members = Vec::new();
+    let mut seen = HashSet::new();
+    for &joint in &skin_nodes {
+        let mut node = Some(joint);
+        while let Some(current) = node.filter(|&current| seen.insert(current)) {
+            members.push(current);
+            node = parents[current];
+        }
+    }
+    // Parents are one level up, so sorting by depth puts them first
+    let depths = members.iter()
+        .map(|&node| Ok((node, node_depth(&parents, node)?)))
+        .collect::<Result<HashMap<_, _>>>()?;
+    members.sort_by_key(|node| (depths[node], *node));
+    let node_joints: HashMap<usize, usize> = members.iter().enumerate().map(|(joint, &node)| (node, joint)).collect();
+    
+    let mut joints: Vec<Joint> = members
+        .iter()
+        .map(|&node| Joint {
+            name: nodes[node].name().map_or_else(|| format!("node{}", node), str::to_string),
+            parent: parents[node].map(|parent| node_joints[&parent]),
+            rest: local_transform(&nodes[node]),
+            inverse_bind: Mat4::IDENTITY,
+        })
+        .collect();
+    if let Some(accessor) = skin.inverse_bind_matrices() {
+        let matrices = document.vectors::<16>(&accessor)?;
+        if matrices.len() < skin_nodes.len() {
+            anyhow::bail!("Skin {} has {} inverse bind matrices for {} joints", index, matrices.len(), skin_nodes.len());
+        }
+        for (node, matrix) in skin_nodes.iter().zip(matrices) {
+            joints[node_joints[node]].inverse_bind = Mat4::from_cols_array(&matrix);
+        }
+    }
+    
+    Ok(ImportedSkin {
+        skeleton: Skeleton::new(joints).with_context(|| format!("Skin {}", index))?,
+        slots: skin_nodes.iter().map(|node| node_joints[node]).collect(),
+        node_joints,
+    })
+}
+
+/// Parent of every node (None for roots)
+fn node_parents(nodes: &[gltf::Node]) -> Result<Vec<Option<usize>>> {
+    let mut parents = vec![None; nodes.len()];
+    for node in nodes {
+        for child in node.children() {
+            if parents[child.index()].replace(node.index()).is_some() {
+                anyhow::bail!("Node {} has more than one parent", child.index());
+            }
+        }
+    }
+    Ok(parents)
+}
+
+/// Number of ancestors of `node`
+fn node_depth(parents: &[Option<usize>], node: usize) -> Result<usize> {
+    let mut depth = 0;
+    let mut current = node;
+    while let Some(parent) = parents[current] {
+        depth += 1;
+        if depth > parents.len() {
+            anyhow::bail!("Node {} is its own ancestor", node);
+        }
+        current = parent;
+    }
+    Ok(depth)
+}
+
+/// A node's `matrix`, or its translation, rotation and scale
+fn local_transform(node: &gltf::Node) -> Transform {
+    match node.transform() {
+        gltf::scene::Transform::Matrix { matrix } => {
+            let (scale, rotation, translation) = Mat4::from_cols_array_2d(&matrix).to_scale_rotation_translation();
+            Transform { translation, rotation, scale }
+        }
+        gltf::scene::Transform::Decomposed { translation, rotation, scale } => Transform {
+            translation: Vec3::from(translation),
+            rotation: Quat::from_array(rotation).normalize(),
+            scale: Vec3::from(scale),
+        },
+    }
+}
+
+/// What animation channels may drive
+struct Targets {
+    /// Skeleton joint of each node in the skin
+    node_joints: HashMap<usize, usize>,
+    /// Node of the imported mesh, whose weights drive its morph targets
+    mesh_node: usize,
+    morphs: usize,
+}
+
+/// One clip per animation, with the channels that drive `targets`
+fn import_clips(document: &Document, targets: &Targets) -> Result<Vec<AnimationClip>> {
+    let mut clips = Vec::new();
+    for animation in document.gltf.animations() {
+        let name = animation.name().map_or_else(|| format!("animation{}", animation.index()), str::to_string);
+        let mut channels = Vec::new();
+        for channel in animation.channels() {
+            let imported = import_channels(document, &channel, targets)
+                .with_context(|| format!("Channel {} of animation {:?}", channel.index(), name))?;
+            channels.extend(imported);
+        }
+        if channels.is_empty() {
+            log::debug!("Skipping animation {:?}: it drives neither the skeleton nor the morph targets", name);
+            continue;
+        }
+        clips.push(AnimationClip::new(name, channels));
+    }
+    Ok(clips)
+}
+
+/// The channel driving the translation, rotation or scale of a skeleton
+/// node, or one channel per target for the mesh node's weights (none for
+/// anything else)
+fn import_channels(document: &Document, channel: &gltf::animation::Channel, targets: &Targets) -> Result<Vec<Channel>> {
+    let node = channel.target().node().index();
+    let property = match channel.target().property() {
+        gltf::animation::Property::Translation => Property::Translation,
+        gltf::animation::Property::Rotation => Property::Rotation,
+        gltf::animation::Property::Scale => Property::Scale,
+        gltf::animation::Property::MorphTargetWeights => Property::Weight,
+    };
+    let joint = targets.node_joints.get(&node);
+    let driven = match property {
+        Property::Weight => node == targets.mesh_node && targets.morphs > 0,
+        _ => joint.is_some(),
+    };
+    if !driven {
+        return Ok(Vec::new());
+    }
+    let sampler = channel.sampler();
+    let interpolation = match sampler.interpolation() {
+        gltf::animation::Interpolation::Step => Interpolation::Step,
+        gltf::animation::Interpolation::Linear => Interpolation::Linear,
+        gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+    };
+    let times: Vec<f32> = document.vectors::<1>(&sampler.input())?.into_iter().map(|[t]| t).collect();
+    let output = sampler.output();
+    let values = match property {
+        Property::Rotation => document.vectors::<4>(&output)?.into_iter().map(Vec4::from).collect(),
+        Property::Weight => return weight_channels(interpolation, times, &document.vectors::<1>(&output)?, targets.morphs),
+        _ => document.vectors::<3>(&output)?.into_iter().map(|v| Vec3::from(v).extend(0.0)).collect(),
+    };
+    let joint = *joint.context("Channel of a node outside the skeleton")?;
+    Ok(vec![Channel::new(joint, property, interpolation, times, values)?])
+}
+
+/// Split a weights channel into one channel per morph target
+///
+/// glTF stores every target's weight for a key together (for cubic
+/// splines: all in-tangents, then all values, then all out-tangents).
+fn weight_channels(interpolation: Interpolation, times: Vec<f32>, values: &[[f32; 1]], morphs: usize) -> Result<Vec<Channel>> {
+    let parts = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+    if values.len() != times.len() * parts * morphs {
+        anyhow::bail!("{} weights for {} keys of {} morph targets", values.len(), times.len(), morphs);
+    }
+    (0..morphs)
+        .map(|target| {
+            let values = (0..times.len() * parts)
+                .map(|k| Vec4::new(values[k * morphs + target][0], 0.0, 0.0, 0.0))
+                .collect();
+            Channel::new(target, Property::Weight, interpolation, times.clone(), values)
+        })
+        .collect()
+}
+
+// =============================================================================
+// DOCUMENT
+// =============================================================================
+
+/// The validated document and the buffers its accessors read from
+struct Document {
+    gltf: gltf::Document,
+    buffers: Vec<Vec<u8>>,
+}
+
+/// Elements of one accessor
+struct Accessor<'a> {
+    /// From the first element on (None: no buffer view, all zeros)
+    data: Option<&'a [u8]>,
+    count: usize,
+    components: usize,
+    data_type: DataType,
+    normalized: bool,
+    stride: usize,
+}
+
+impl Document {
+    /// Load every buffer: the .glb's binary chunk, a data URI or a file
+    /// next to the document
+    fn new(gltf: gltf::Document, bin: Option<&[u8]>, base: &Path) -> Result<Self> {
+        let buffers = gltf.buffers()
+            .map(|buffer| {
+                let data = match buffer.source() {
+                    Source::Uri(uri) => match uri.strip_prefix("data:") {
+                        Some(data) => {
+                            let (_, encoded) = data.split_once(";base64,").context("Data URI isn't base64")?;
+                            decode_base64(encoded)?
+                        }
+                        None => {
+                            let path = base.join(uri_path(uri));
+                            std::fs::read(&path).with_context(|| format!("Failed to read buffer {:?}", path))?
+                        }
+                    },
+                    Source::Bin => bin.context("Buffer without a URI outside a .glb")?.to_vec(),
+                };
+                if data.len() < buffer.length() {
+                    anyhow::bail!("Buffer {} has {} of its {} bytes", buffer.index(), data.len(), buffer.length());
+                }
+                Ok(data)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { gltf, buffers })
+    }
+    
+    /// An accessor's elements, checked to lie within its buffer view and
+    /// buffer
+    fn accessor(&self, accessor: &gltf::Accessor) -> Result<Accessor<'_>> {
+        let index = accessor.index();
+        if accessor.sparse().is_some() {
+            anyhow::bail!("Sparse accessors aren't supported (accessor {})", index);
+        }
+        let (count, data_type, normalized) = (accessor.count(), accessor.data_type(), accessor.normalized());
+        let components = accessor.dimensions().multiplicity();
+        let Some(view) = accessor.view() else {
+            return Ok(Accessor { data: None, count, components, data_type, normalized, stride: 0 });
+        };
+        
+        // Sizes come from the file, so no arithmetic on them may wrap
+        let buffer = &self.buffers[view.buffer().index()];
+        let view_data = view.offset()
+            .checked_add(view.length())
+            .and_then(|end| buffer.get(view.offset()..end))
+            .context("Buffer view runs past the end of its buffer")?;
+        let element = components * data_type.size();
+        let stride = view.stride().unwrap_or(element);
+        if stride < element {
+            anyhow::bail!("Accessor {} has elements of {} bytes {} bytes apart", index, element, stride);
+        }
+        let start = accessor.offset();
+        let end = match count {
+            0 => Some(start),
+            _ => stride.checked_mul(count - 1)
+                .and_then(|span| span.checked_add(element))
+                .and_then(|span| span.checked_add(start)),
+        };
+        let data = end
+            .and_then(|end| view_data.get(start..end))
+            .with_context(|| format!("Accessor {} runs past the end of its buffer view", index))?;
+        Ok(Accessor { data: Some(data), count, components, data_type, normalized, stride })
+    }
+    
+    /// An accessor as `N`-component vectors of floats
+    fn vectors<const N: usize>(&self, accessor: &gltf::Accessor) -> Result<Vec<[f32; N]>> {
+        let elements = self.accessor(accessor)?;
+        if elements.components != N {
+            anyhow::bail!("Accessor {} has {} components, expected {}", accessor.index(), elements.components, N);
+        }
+        Ok((0..elements.count)
+            .map(|element| std::array::from_fn(|component| elements.float(element, component)))
+            .collect())
+    }
+    
+    /// An accessor's components as unsigned integers, in order
+    fn integers(&self, accessor: &gltf::Accessor) -> Result<Vec<u32>> {
+        let elements = self.accessor(accessor)?;
+        if !matches!(elements.data_type, DataType::U8 | DataType::U16 | DataType::U32) || elements.normalized {
+            anyhow::bail!("Accessor {} doesn't hold unsigned integers", accessor.index());
+        }
+        Ok((0..elements.count)
+            .flat_map(|element| (0..elements.components).map(move |component| (element, component)))
+            .map(|(element, component)| elements.integer(element, component))
+            .collect())
+    }
+}
+
+impl Accessor<'_> {
+    fn bytes(&self, element: usize, component: usize) -> Option<&[u8]> {
+        let size = self.data_type.size();
+        let at = element * self.stride + component * size;
+        self.data.map(|data| &data[at..at + size])
+    }
+    
+    fn integer(&self, element: usize, component: usize) -> u32 {
+        match self.bytes(element, component) {
+            Some(&[byte]) => byte as u32,
+            Some(&[low, high]) => u16::from_le_bytes([low, high]) as u32,
+            Some(&[a, b, c, d]) => u32::from_le_bytes([a, b, c, d]),
+            _ => 0,
+        }
+    }
+    
+    /// A component as a float; normalized integers map to 0..1 (or -1..1)
+    fn float(&self, element: usize, component: usize) -> f32 {
+        let Some(bytes) = self.bytes(element, component) else {
+            return 0.0;
+        };
+        let value = match self.data_type {
+            DataType::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            DataType::I8 => bytes[0] as i8 as f32,
+            DataType::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+            _ => self.integer(element, component) as f32,
+        };
+        if !self.normalized {
+            return value;
+        }
+        match self.data_type {
+            DataType::I8 => (value / 127.0).max(-1.0),
+            DataType::U8 => value / 255.0,
+            DataType::I16 => (value / 32767.0).max(-1.0),
+            DataType::U16 => value / 65535.0,
+            _ => value,
+        }
+    }
+}
+
+// =============================================================================
+// URIS
+// =============================================================================
+
+/// Bytes of base64 text (either alphabet, padding optional)
+fn decode_base64(text: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut bits, mut count) = (0u32, 0);
+    for byte in text.bytes().filter(|byte| !byte.is_ascii_whitespace()) {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => anyhow::bail!("Invalid base64 character {:?}", byte as char),
+        };
+        bits = (bits << 6 | value as u32) & 0xFFFF;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+/// A relative URI as a path (%XX escapes resolved)
+fn uri_path(uri: &str) -> PathBuf {
+    let mut bytes = Vec::with_capacity(uri.len());
+    let mut rest = uri.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const CHUNK_JSON: u32 = 0x4E4F_534A;
+    const CHUNK_BIN: u32 = 0x004E_4942;
+    
+    /// Accessor component types
+    const UNSIGNED_BYTE: u32 = 5121;
+    const UNSIGNED_SHORT: u32 = 5123;
+    const FLOAT: u32 = 5126;
+    
+    /// Binary data and the buffer views and accessors that describe it
+    #[derive(Clone, Default)]
+    struct Builder {
+        bin: Vec<u8>,
+        views: Vec<String>,
+        accessors: Vec<String>,
+    }
+    
+    impl Builder {
+        /// An accessor over `bytes` (in a buffer view of its own)
+        fn add(&mut self, bytes: &[u8], component_type: u32, kind: &str, count: usize) -> usize {
+            self.bin.resize(self.bin.len().next_multiple_of(4), 0);
+            self.views.push(format!(
+                r#"{{"buffer": 0, "byteOffset": {}, "byteLength": {}}}"#,
+                self.bin.len(),
+                bytes.len()
+            ));
+            self.bin.extend_from_slice(bytes);
+            self.accessors.push(format!(
+                r#"{{"bufferView": {}, "componentType": {}, "type": "{}", "count": {}}}"#,
+                self.views.len() - 1,
+                component_type,
+                kind,
+                count
+            ));
+            self.accessors.len() - 1
+        }
+        
+        fn floats(&mut self, values: &[f32], kind: &str, components: usize) -> usize {
+            let bytes: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+            self.add(&bytes, FLOAT, kind, values.len() / components)
+        }
+        
+        /// The document: `rest` holds its nodes, meshes, skins and so on
+        fn json(&self, buffer_uri: &str, rest: &str) -> String {
+            format!(
+                r#"{{"asset": {{"version": "2.0"}}, "buffers": [{{{}"byteLength": {}}}],
+                    "bufferViews": [{}], "accessors": [{}], {}}}"#,
+                buffer_uri,
+                self.bin.len(),
+                self.views.join(", "),
+                self.accessors.join(", "),
+                rest
+            )
+        }
+        
+        fn glb(&self, rest: &str) -> Vec<u8> {
+            let mut json = self.json("", rest).into_bytes();
+            let mut bin = self.bin.clone();
+            json.resize(json.len().next_multiple_of(4), b' ');
+            bin.resize(bin.len().next_multiple_of(4), 0);
+            let total = 12 + 8 + json.len() + 8 + bin.len();
+            let mut glb = Vec::new();
+            glb.extend_from_slice(b"glTF");
+            for word in [2, total as u32, json.len() as u32, CHUNK_JSON] {
+                glb.extend_from_slice(&word.to_le_bytes());
+            }
+            glb.extend_from_slice(&json);
+            glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+            glb.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+            glb.extend_from_slice(&bin);
+            glb
+        }
+    }
+    
+    /// Positions of a triangle, with the bounds POSITION accessors must give
+    fn triangle(builder: &mut Builder) -> usize {
+        let positions = builder.floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0], "VEC3", 3);
+        let accessor = &mut builder.accessors[positions];
+        accessor.insert_str(accessor.len() - 1, r#", "min": [0, 0, 0], "max": [1, 1, 0]"#);
+        positions
+    }
+    
+    fn encode_base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut text = String::new();
+        for chunk in bytes.chunks(3) {
+            let word = chunk.iter().enumerate().fold(0u32, |word, (i, &byte)| word | (byte as u32) << (16 - 8 * i));
+            for i in 0..4 {
+                let digit = if i <= chunk.len() { ALPHABET[(word >> (18 - 6 * i) & 63) as usize] } else { b'=' };
+                text.push(digit as char);
+            }
+        }
+        text
+    }
+    
+    #[test]
+    fn skin_joints_sort_parents_first() {
+        let mut builder = Builder::default();
+        let positions = triangle(&mut builder);
+        let uvs = builder.floats(&[0.0, 0.0, 1.0, 0.0, 0.0, 1.0], "VEC2", 2);
+        // Skin slots 0 and 1 are the spine and hips
+        let joints = builder.add(&[0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0], UNSIGNED_BYTE, "VEC4", 3);
+        let weights = builder.floats(&[1.0, 0.0, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0, 2.0, 2.0, 0.0, 0.0], "VEC4", 4);
+        let indices = builder.add(&[0, 0, 1, 0, 2, 0], UNSIGNED_SHORT, "SCALAR", 3);
+        let spine_bind = Mat4::from_translation(Vec3::new(0.0, -2.0, 0.0));
+        let hips_bind = Mat4::from_translation(Vec3::new(0.0, -1.0, 0.0));
+        let matrices: Vec<f32> = [spine_bind, hips_bind].iter().flat_map(|m| m.to_cols_array()).collect();
+        let inverse_binds = builder.floats(&matrices, "MAT4", 16);
+        let times = builder.floats(&[0.0, 2.0], "SCALAR", 1);
+        let turn = Quat::from_rotation_z(1.0);
+        let rotations = builder.floats(&[0.0, 0.0, 0.0, 1.0, turn.x, turn.y, turn.z, turn.w], "VEC4", 4);
+        let offsets = builder.floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0], "VEC3", 3);
+        let glb = builder.glb(&format!(
+            r#""nodes": [
+                {{"name": "armature", "children": [1, 3], "scale": [2, 2, 2]}},
+                {{"name": "hips", "children": [2], "translation": [0, 1, 0]}},
+                {{"name": "spine", "translation": [0, 1, 0]}},
+                {{"mesh": 0, "skin": 0}}
+            ],
+            "meshes": [{{"primitives": [{{
+                "attributes": {{"POSITION": {positions}, "TEXCOORD_0": {uvs}, "JOINTS_0": {joints}, "WEIGHTS_0": {weights}}},
+                "indices": {indices}, "material": 0
+            }}]}}],
+            "materials": [{{"name": "skin"}}],
+            "skins": [{{"joints": [2, 1], "inverseBindMatrices": {inverse_binds}}}],
+            "animations": [{{
+                "name": "bend",
+                "samplers": [
+                    {{"input": {times}, "output": {rotations}}},
+                    {{"input": {times}, "output": {offsets}, "interpolation": "STEP"}}
+                ],
+                "channels": [
+                    {{"sampler": 0, "target": {{"node": 2, "path": "rotation"}}}},
+                    {{"sampler": 1, "target": {{"node": 3, "path": "translation"}}}},
+                    {{"sampler": 1, "target": {{"node": 0, "path": "translation"}}}}
+                ]
+            }}]"#
+        ));
+        
+        let (mesh, rig) = parse(&glb, Path::new("")).unwrap();
+        let rig = rig.expect("a skinned mesh has a rig");
+        
+        // The armature above the skin's joints is part of the skeleton
+        let joints = rig.skeleton.joints();
+        let names: Vec<&str> = joints.iter().map(|joint| joint.name.as_str()).collect();
+        assert_eq!(names, ["armature", "hips", "spine"]);
+        assert_eq!(joints.iter().map(|joint| joint.parent).collect::<Vec<_>>(), [None, Some(0), Some(1)]);
+        assert_eq!(joints[0].rest.scale, Vec3::splat(2.0));
+        assert_eq!(joints[2].rest.translation, Vec3::Y);
+        assert_eq!(joints[0].inverse_bind, Mat4::IDENTITY);
+        assert_eq!(joints[1].inverse_bind, hips_bind);
+        assert_eq!(joints[2].inverse_bind, spine_bind);
+        
+        // Skin slots become skeleton joints; weights sum to one
+        assert_eq!(mesh.joints, [[2, 2, 2, 2], [1, 2, 2, 2], [2, 1, 2, 2]]);
+        assert_eq!(mesh.weights, [[1.0, 0.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0], [0.5, 0.5, 0.0, 0.0]]);
+        assert_eq!(mesh.uvs, [[0.0, 1.0], [1.0, 1.0], [0.0, 0.0]]);
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        assert_eq!(mesh.submeshes.len(), 1);
+        assert_eq!(mesh.submeshes[0].material, "skin");
+        // Missing normals and tangents are generated
+        assert!(mesh.normals.iter().all(|&n| Vec3::from(n).abs_diff_eq(Vec3::Z, 1e-6)));
+        assert_eq!(mesh.tangents.len(), 3);
+        
+        // The channel on the mesh node isn't a joint's and is dropped
+        assert_eq!(rig.clips.len(), 1);
+        let clip = &rig.clips[0];
+        assert_eq!(clip.name, "bend");
+        assert_eq!(clip.duration, 2.0);
+        let targets: Vec<_> = clip.channels.iter().map(|channel| (channel.target, channel.property)).collect();
+        assert_eq!(targets, [(2, Property::Rotation), (0, Property::Translation)]);
+        assert!(Quat::from_vec4(clip.channels[0].sample(2.0)).abs_diff_eq(turn, 1e-6));
+        assert_eq!(clip.channels[1].interpolation, Interpolation::Step);
+        assert_eq!(rig.cycle, 2.0);
+    }
+    
+    #[test]
+    fn morph_targets_and_weight_channels() {
+        let mut builder = Builder::default();
+        let positions = triangle(&mut builder);
+        let normals = builder.floats(&[0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0], "VEC3", 3);
+        let tangents = builder.floats(&[1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0], "VEC4", 4);
+        let raise = builder.floats(&[0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], "VEC3", 3);
+        let tilt = builder.floats(&[0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], "VEC3", 3);
+        let times = builder.floats(&[0.0, 1.0], "SCALAR", 1);
+        // Per key: both in-tangents, both values, both out-tangents
+        let keys = builder.floats(&[
+            9.0, 9.0, 0.1, 0.2, 1.6, 0.0,
+            0.0, 0.8, 0.9, 0.8, 9.0, 9.0,
+        ], "SCALAR", 1);
+        let glb = builder.glb(&format!(
+            r#""nodes": [{{"mesh": 0}}],
+            "meshes": [{{
+                "primitives": [{{
+                    "attributes": {{"POSITION": {positions}, "NORMAL": {normals}, "TANGENT": {tangents}}},
+                    "targets": [{{"POSITION": {raise}, "NORMAL": {tilt}}}, {{"POSITION": {tilt}}}]
+                }}],
+                "weights": [0.25, 0.5],
+                "extras": {{"targetNames": ["raise"]}}
+            }}],
+            "animations": [{{
+                "samplers": [{{"input": {times}, "output": {keys}, "interpolation": "CUBICSPLINE"}}],
+                "channels": [{{"sampler": 0, "target": {{"node": 0, "path": "weights"}}}}]
+            }}]"#
+        ));
+        
+        let (mesh, rig) = parse(&glb, Path::new("")).unwrap();
+        let names: Vec<&str> = mesh.morph_targets.iter().map(|target| target.name.as_str()).collect();
+        assert_eq!(names, ["raise", "target1"]);
+        assert_eq!(mesh.morph_targets[0].positions[0], [0.0, 0.0, 1.0]);
+        // Deltas a target leaves out are zero
+        assert_eq!(mesh.morph_targets[0].normals[0], [0.0, 0.5, 0.0]);
+        assert_eq!(mesh.morph_targets[1].normals, [[0.0; 3]; 3]);
+        assert_eq!(mesh.morph_targets[1].tangents, [[0.0; 3]; 3]);
+        
+        // Morphs without a skin get a single joint to hang the clips on
+        let rig = rig.expect("morph targets come with a rig");
+        assert_eq!(rig.skeleton.joints().len(), 1);
+        assert_eq!(rig.morph_weights(), [0.25, 0.5]);
+        let clip = &rig.clips[0];
+        assert_eq!(clip.name, "animation0");
+        let targets: Vec<_> = clip.channels.iter().map(|channel| (channel.target, channel.property)).collect();
+        assert_eq!(targets, [(0, Property::Weight), (1, Property::Weight)]);
+        let sample = |channel: usize, time: f32| clip.channels[channel].sample(time).x;
+        assert_eq!((sample(0, 0.0), sample(0, 1.0)), (0.1, 0.9));
+        assert_eq!((sample(1, 0.0), sample(1, 1.0)), (0.2, 0.8));
+        // The first key's out-tangent and the second's in-tangent shape the
+        // curve between them
+        assert!((sample(0, 0.5) - 0.7).abs() < 1e-5);
+        assert!((sample(1, 0.5) - 0.4).abs() < 1e-5);
+    }
+    
+    #[test]
+    fn embedded_buffers_decode() {
+        let mut builder = Builder::default();
+        let positions = triangle(&mut builder);
+        let rest = format!(
+            r#""nodes": [{{"mesh": 0}}], "meshes": [{{"primitives": [{{"attributes": {{"POSITION": {positions}}}}}]}}]"#
+        );
+        let uri = format!(r#""uri": "data:application/octet-stream;base64,{}", "#, encode_base64(&builder.bin));
+        let (mesh, rig) = parse(builder.json(&uri, &rest).as_bytes(), Path::new("")).unwrap();
+        assert!(rig.is_none());
+        assert_eq!(mesh.positions, [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        assert!(mesh.submeshes.is_empty());
+        
+        for bytes in [&b""[..], b"M", b"Ma", b"Man", b"any carnal pleas"] {
+            assert_eq!(decode_base64(&encode_base64(bytes)).unwrap(), bytes);
+        }
+        assert_eq!(decode_base64("_-8").unwrap(), [0xFF, 0xEF]);
+        assert!(decode_base64("TW*u").is_err());
+    }
+    
+    #[test]
+    fn out_of_range_data_is_an_error() {
+        let mut builder = Builder::default();
+        let positions = triangle(&mut builder);
+        let mesh = |indices: &str| {
+            format!(
+                r#""nodes": [{{"mesh": 0}}],
+                "meshes": [{{"primitives": [{{"attributes": {{"POSITION": {positions}}}{indices}}}]}}]"#
+            )
+        };
+        assert!(parse(&builder.glb(&mesh("")), Path::new("")).is_ok());
+        
+        // An index past the last vertex
+        let mut bad = builder.clone();
+        let indices = bad.add(&[0, 0, 1, 0, 3, 0], UNSIGNED_SHORT, "SCALAR", 3);
+        assert!(parse(&bad.glb(&mesh(&format!(r#", "indices": {indices}"#))), Path::new("")).is_err());
+        
+        // An accessor longer than its buffer view, and a view past its buffer
+        let mut bad = builder.clone();
+        bad.accessors[positions] = bad.accessors[positions].replace(r#""count": 3"#, r#""count": 4"#);
+        assert!(parse(&bad.glb(&mesh("")), Path::new("")).is_err());
+        let mut bad = builder.clone();
+        bad.views[0] = bad.views[0].replace(r#""byteOffset": 0"#, r#""byteOffset": 18446744073709551615"#);
+        assert!(parse(&bad.glb(&mesh("")), Path::new("")).is_err());
+        
+        // A truncated .glb
+        let glb = builder.glb(&mesh(""));
+        assert!(parse(&glb[..glb.len() - 4], Path::new("")).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+// Mesh level of detail
+//
+// Import: `MeshLods::build` simplifies a mesh into a chain of coarser index
+// lists (meshoptimizer edge collapse). Every level references the original
+// vertices, so a mesh keeps one vertex buffer and one index buffer with an
+// index range per level.
+//
+// Draw: `LodSelector` picks, per instance, the coarsest level whose
+// simplification error projected to the screen stays under a pixel threshold.
+// Close to the switch point the finer level can be faded in with a dither
+// pattern (cube.frag LOD_FADE) so levels don't visibly pop.
+
+use anyhow::Result;
+use std::ops::Range;
+
+/// One level of detail: a range of the combined index list
+#[derive(Debug, Clone, Copy)]
+pub struct LodLevel {
+    pub first_index: u32,
+    pub index_count: u32,
+    /// Worst-case deviation from the full mesh, in mesh units (0 for level 0)
+    pub error: f32,
+}
+
+/// Simplification targets for the LOD chain
+#[derive(Debug, Clone, Copy)]
+pub struct LodSettings {
+    /// Levels including the full mesh
+    pub max_levels: usize,
+    /// Target triangle ratio between consecutive levels
+    pub reduction: f32,
+    /// Allowed error for the first simplified level, relative to the mesh
+    /// size; doubles for every further attempt
+    pub base_error: f32,
+    /// Stop once the allowed relative error would exceed this
+    pub max_error: f32,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            max_levels: 5,
+            reduction: 0.5,
+            base_error: 0.01,
+            max_error: 0.25,
+        }
+    }
+}
+
+/// A mesh's index list with every LOD level appended (finest first)
+#[derive(Debug, Clone, Default)]
+pub struct MeshLods {
+    pub indices: Vec<u32>,
+    pub levels: Vec<LodLevel>,
+}
+
+impl MeshLods {
+    /// Just the full mesh as level 0
+    pub fn single(indices: &[u32]) -> Self {
+        Self {
+            indices: indices.to_vec(),
+            levels: vec![LodLevel { first_index: 0, index_count: indices.len() as u32, error: 0.0 }],
+        }
+    }
+    
+    /// Simplify a triangle list into a LOD chain
+    ///
+    /// Attribute seams (e.g. flat-shaded faces with split vertices) are kept
+    /// intact, so such meshes may not simplify at all and end up with only
+    /// level 0.
+    pub fn build(positions: &[[f32; 3]], indices: &[u32], settings: &LodSettings) -> Result<Self> {
+        let vertices = meshopt::VertexDataAdapter::new(
+            meshopt::typed_to_bytes(positions),
+            std::mem::size_of::<[f32; 3]>(),
+            0,
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid vertex data for simplification: {}", e))?;
+        
+        // meshoptimizer measures error relative to the largest extent
+        let (min, max) = positions.iter().fold(
+            ([f32::MAX; 3], [f32::MIN; 3]),
+            |(min, max), p| (
+                [min[0].min(p[0]), min[1].min(p[1]), min[2].min(p[2])],
+                [max[0].max(p[0]), max[1].max(p[1]), max[2].max(p[2])],
+            ),
+        );
+        let extent = (0..3).map(|i| max[i] - min[i]).fold(0.0, f32::max);
+        
+        let mut lods = Self {
+            indices: indices.to_vec(),
+            levels: vec![LodLevel { first_index: 0, index_count: indices.len() as u32, error: 0.0 }],
+        };
+        
+        let mut current = indices.to_vec();
+        let mut error = 0.0;
+        let mut relative_error = settings.base_error;
+        while lods.levels.len() < settings.max_levels && relative_error <= settings.max_error {
+            let target_count = ((current.len() / 3) as f32 * settings.reduction) as usize * 3;
+            let simplified = meshopt::simplify(&current, &vertices, target_count, relative_error);
+            
+            // Each level simplifies the previous one, so errors add up.
+            // Levels that barely shrink aren't worth a switch; allow more error.
+            let attempt_error = relative_error * extent;
+            relative_error *= 2.0;
+            if simplified.is_empty() || simplified.len() as f32 > current.len() as f32 * 0.9 {
+                continue;
+            }
+            
+            error += attempt_error;
+            lods.levels.push(LodLevel {
+                first_index: lods.indices.len() as u32,
+                index_count: simplified.len() as u32,
+                error,
+            });
+            lods.indices.extend_from_slice(&simplified);
+            current = simplified;
+        }
+        
+        Ok(lods)
+    }
+}
+
+// =============================================================================
+// SELECTION
+// =============================================================================
+
+/// Level chosen for one instance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LodSelection {
+    pub level: usize,
+    /// Finer level being faded in and how much of it is drawn (0..1);
+    /// `level` covers the remaining pixels
+    pub fade_in: Option<(usize, f32)>,
+}
+
+/// Screen-space error based LOD selection
+#[derive(Debug, Clone, Copy)]
+pub struct LodSelector {
+    /// Converts `world error / distance` into multiples of the pixel threshold
+    pub lod_scale: f32,
+    /// Fraction of the threshold over which the finer level fades in (0 = off)
+    pub fade_range: f32,
+}
+
+impl LodSelector {
+    /// `fov_y` in radians; `threshold_px` is the largest error allowed on screen
+    pub fn new(viewport_height: f32, fov_y: f32, threshold_px: f32, fade_range: f32) -> Self {
+        let pixels_per_unit = viewport_height / (2.0 * (fov_y * 0.5).tan());
+        Self {
+            lod_scale: pixels_per_unit / threshold_px.max(f32::EPSILON),
+            fade_range: fade_range.clamp(0.0, 1.0),
+        }
+    }
+    
+    /// Projected error of `level` in threshold units (1.0 = exactly at the threshold)
+    fn projected_error(&self, level: &LodLevel, scale: f32, distance: f32) -> f32 {
+        level.error * scale * self.lod_scale / distance.max(f32::EPSILON)
+    }
+    
+    /// Pick a level for an instance with uniform `scale` whose bounds are
+    /// `distance` away from the camera (level 0 when there are no levels)
+    pub fn select(&self, levels: &[LodLevel], scale: f32, distance: f32) -> LodSelection {
+        if levels.is_empty() {
+            return LodSelection { level: 0, fade_in: None };
+        }
+        
+        // Errors grow with the level, so the acceptable levels form a prefix
+        let level = levels
+            .iter()
+            .rposition(|l| self.projected_error(l, scale, distance) <= 1.0)
+            .unwrap_or(0);
+        
+        let fade_start = 1.0 - self.fade_range;
+        let error = self.projected_error(&levels[level], scale, distance);
+        let fade_in = (level > 0 && self.fade_range > 0.0 && error > fade_start)
+            .then(|| (level - 1, ((error - fade_start) / self.fade_range).min(1.0)));
+        
+        LodSelection { level, fade_in }
+    }
+}
+
+/// One draw of consecutive instances at one level
+#[derive(Debug, Clone, PartialEq)]
+pub struct LodDraw {
+    pub level: usize,
+    pub instances: Range<u32>,
+    /// Dither coverage: 0 = opaque, > 0 = draw this fraction of pixels,
+    /// < 0 = draw the complementary pixels (the level being faded out)
+    pub fade: f32,
+}
+
+/// Build draws from per-instance selections, merging runs of instances that
+/// share an opaque level into one instanced draw
+pub fn push_lod_draws(draws: &mut Vec<LodDraw>, instance: u32, selection: LodSelection) {
+    match selection.fade_in {
+        Some((finer, coverage)) => {
+            draws.push(LodDraw { level: finer, instances: instance..instance + 1, fade: coverage });
+            draws.push(LodDraw { level: selection.level, instances: instance..instance + 1, fade: -coverage });
+        }
+        None => match draws.last_mut() {
+            Some(last) if last.level == selection.level && last.fade == 0.0 && last.instances.end == instance => {
+                last.instances.end += 1;
+            }
+            _ => draws.push(LodDraw { level: selection.level, instances: instance..instance + 1, fade: 0.0 }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn select_picks_the_coarsest_acceptable_level() {
+        // One threshold unit per mesh unit of error at distance 1
+        let selector = LodSelector { lod_scale: 1.0, fade_range: 0.5 };
+        let levels = [0.0, 1.0, 4.0].map(|error| LodLevel { first_index: 0, index_count: 3, error });
+        
+        assert_eq!(selector.select(&levels, 1.0, 0.5).level, 0);
+        assert_eq!(selector.select(&levels, 1.0, 2.0), LodSelection { level: 1, fade_in: None });
+        assert_eq!(selector.select(&levels, 1.0, 100.0).level, 2);
+        // Just under the threshold, the finer level fades in
+        assert_eq!(selector.select(&levels, 1.0, 1.25), LodSelection { level: 1, fade_in: Some((0, 0.6)) });
+        assert_eq!(selector.select(&[], 1.0, 1.0), LodSelection { level: 0, fade_in: None });
+    }
+}
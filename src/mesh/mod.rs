@@ -0,0 +1,11 @@
+// Mesh module - CPU-side mesh processing done at import
+//
+// Works on plain positions and u32 index lists so it stays independent of the
+// renderer's vertex format; results are index ranges into the same vertices.
+
+pub mod cache;
+pub mod gltf;
+pub mod lod;
+pub mod obj;
+pub mod primitives;
+pub mod process;
@@ -0,0 +1,140 @@
+// Wavefront OBJ import
+//
+// Reads positions, UVs, normals and polygon faces (fan-triangulated, any of
+// the v, v/vt, v//vn and v/vt/vn forms, negative indices counting back from
+// the end). Triangles are grouped by their `usemtl` material into submeshes;
+// the material libraries themselves aren't read. Files with faces that lack
+// normals get smooth generated normals throughout.
+
+use std::collections::HashMap;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::{Vec2, Vec3};
+use super::primitives::{MeshData, Submesh};
+use super::process::{self, NormalMode};
+
+/// Load an OBJ file as one mesh
+pub fn load(path: &Path) -> Result<MeshData> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read OBJ file: {:?}", path))?;
+    parse(&text).with_context(|| format!("Failed to parse OBJ file: {:?}", path))
+}
+
+/// Parse OBJ source text
+pub fn parse(text: &str) -> Result<MeshData> {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut uvs: Vec<Vec2> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    
+    let mut mesh = MeshData::default();
+    // One corner of a face (position, uv, normal) becomes one vertex
+    let mut vertices: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+    // Triangles per material, in order of first use
+    let mut groups: Vec<(String, Vec<u32>)> = vec![(String::new(), Vec::new())];
+    let mut group = 0;
+    let mut missing_normals = false;
+    
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let at_line = || format!("line {}: {:?}", line_number + 1, line);
+        match keyword {
+            "v" => positions.push(Vec3::from(floats::<3>(words).with_context(at_line)?)),
+            "vt" => uvs.push(Vec2::from(floats::<2>(words).with_context(at_line)?)),
+            "vn" => normals.push(Vec3::from(floats::<3>(words).with_context(at_line)?)),
+            "usemtl" => {
+                let name = words.collect::<Vec<_>>().join(" ");
+                group = match groups.iter().position(|(material, _)| *material == name) {
+                    Some(existing) => existing,
+                    None => {
+                        groups.push((name, Vec::new()));
+                        groups.len() - 1
+                    }
+                };
+            }
+            "f" => {
+                let mut corners = Vec::new();
+                for word in words {
+                    let corner = face_corner(word, positions.len(), uvs.len(), normals.len())
+                        .with_context(at_line)?;
+                    missing_normals |= corner.2.is_none();
+                    let index = *vertices.entry(corner).or_insert_with(|| {
+                        let (position, uv, normal) = corner;
+                        mesh.vertex(
+                            positions[position],
+                            normal.map_or(Vec3::ZERO, |n| normals[n].normalize_or_zero()),
+                            uv.map_or(Vec2::ZERO, |t| uvs[t]),
+                        )
+                    });
+                    corners.push(index);
+                }
+                if corners.len() < 3 {
+                    anyhow::bail!("Face with fewer than 3 corners at {}", at_line());
+                }
+                for i in 1..corners.len() - 1 {
+                    groups[group].1.extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            // Objects, groups, smoothing groups, material libraries, lines...
+            _ => {}
+        }
+    }
+    
+    groups.retain(|(_, indices)| !indices.is_empty());
+    if groups.is_empty() {
+        anyhow::bail!("No faces");
+    }
+    // A single group needs no submeshes unless it names a material
+    let named = groups.len() > 1 || !groups[0].0.is_empty();
+    for (material, indices) in groups {
+        if named {
+            mesh.submeshes.push(Submesh {
+                material,
+                first_index: mesh.indices.len() as u32,
+                index_count: indices.len() as u32,
+            });
+        }
+        mesh.indices.extend(indices);
+    }
+    
+    if missing_normals {
+        process::generate_normals(&mut mesh, NormalMode::Smooth);
+    }
+    process::generate_tangents(&mut mesh);
+    Ok(mesh)
+}
+
+/// The first `N` numbers after a keyword (extra ones, like a w, are ignored)
+fn floats<'a, const N: usize>(mut words: impl Iterator<Item = &'a str>) -> Result<[f32; N]> {
+    let mut values = [0.0; N];
+    for value in &mut values {
+        let word = words.next().context("Too few numbers")?;
+        *value = word.parse().with_context(|| format!("Invalid number {:?}", word))?;
+    }
+    Ok(values)
+}
+
+/// Zero-based (position, uv, normal) indices of a face corner like `3/1/2`
+fn face_corner(word: &str, positions: usize, uvs: usize, normals: usize) -> Result<(usize, Option<usize>, Option<usize>)> {
+    let mut parts = word.split('/');
+    let position = resolve(parts.next(), positions)?.context("Face corner without a position")?;
+    let uv = resolve(parts.next(), uvs)?;
+    let normal = resolve(parts.next(), normals)?;
+    Ok((position, uv, normal))
+}
+
+/// 1-based or negative (relative) OBJ index into a list of `count` elements
+fn resolve(part: Option<&str>, count: usize) -> Result<Option<usize>> {
+    let Some(part) = part.filter(|part| !part.is_empty()) else {
+        return Ok(None);
+    };
+    let index: i64 = part.parse().with_context(|| format!("Invalid index {:?}", part))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if resolved < 0 || resolved >= count as i64 {
+        anyhow::bail!("Index {} out of range (1..={})", index, count);
+    }
+    Ok(Some(resolved as usize))
+}
@@ -0,0 +1,729 @@
+// Procedural meshes
+//
+// Generators for the basic shapes, selected by name from the [scene] config.
+// Every shape is centered on the origin and fits the unit cube, triangles
+// wind counter-clockwise seen from outside, and every vertex has a unit
+// normal, UVs (v up) and a MikkTSpace tangent. Where UVs or
+// normals jump (texture seams, hard edges, poles) vertices are duplicated,
+// but their positions match exactly, so closed shapes are watertight once
+// positions are welded.
+
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+use std::ops::Range;
+use glam::{Vec2, Vec3};
+use super::process;
+
+/// Triangle list with per-vertex attributes in separate arrays
+#[derive(Debug, Clone, Default)]
+pub struct MeshData {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    /// xyz along +u, w = ±1 so that cross(normal, tangent) * w points along +v
+    /// Used in: normal mapping
+    #[allow(dead_code)]
+    pub tangents: Vec<[f32; 4]>,
+    /// Up to four joints per vertex and their weights (summing to 1); both
+    /// empty for rigid meshes
+    pub joints: Vec<[u8; 4]>,
+    pub weights: Vec<[f32; 4]>,
+    /// Blend shapes, each with a delta per vertex
+    pub morph_targets: Vec<MorphTarget>,
+    pub indices: Vec<u32>,
+    /// Index ranges drawn with different materials (empty: one material)
+    pub submeshes: Vec<Submesh>,
+}
+
+/// A range of a mesh's indices that uses one material
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submesh {
+    pub material: String,
+    pub first_index: u32,
+    pub index_count: u32,
+}
+
+/// Offsets a blend shape adds to every vertex, scaled by its weight
+#[derive(Debug, Clone, Default)]
+pub struct MorphTarget {
+    pub name: String,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// Tangent direction only (the handedness doesn't morph); empty when
+    /// the mesh has no tangents
+    pub tangents: Vec<[f32; 3]>,
+}
+
+impl MorphTarget {
+    /// Same name, no deltas yet
+    pub(super) fn empty_like(&self) -> Self {
+        Self { name: self.name.clone(), ..Default::default() }
+    }
+    
+    fn push_from(&mut self, source: &MorphTarget, i: usize) {
+        self.positions.push(source.positions[i]);
+        self.normals.push(source.normals[i]);
+        if let Some(&tangent) = source.tangents.get(i) {
+            self.tangents.push(tangent);
+        }
+    }
+}
+
+impl MeshData {
+    /// Index ranges of the submeshes, or the whole list when there are none
+    pub(super) fn index_ranges(&self) -> Vec<Range<usize>> {
+        if self.submeshes.is_empty() {
+            return std::iter::once(0..self.indices.len()).collect();
+        }
+        self.submeshes
+            .iter()
+            .map(|submesh| submesh.first_index as usize..(submesh.first_index + submesh.index_count) as usize)
+            .collect()
+    }
+    
+    pub(super) fn vertex(&mut self, position: Vec3, normal: Vec3, uv: Vec2) -> u32 {
+        self.positions.push(position.to_array());
+        self.normals.push(normal.to_array());
+        self.uvs.push(uv.to_array());
+        self.positions.len() as u32 - 1
+    }
+    
+    /// Append a copy of vertex `i` of `source` (without its tangent);
+    /// skinning data and morph deltas come along (`self` needs the same
+    /// morph targets)
+    pub(super) fn copy_vertex(&mut self, source: &MeshData, i: usize) -> u32 {
+        let index = self.vertex(source.positions[i].into(), source.normals[i].into(), source.uvs[i].into());
+        if !source.joints.is_empty() {
+            self.joints.push(source.joints[i]);
+            self.weights.push(source.weights[i]);
+        }
+        for (target, source) in self.morph_targets.iter_mut().zip(&source.morph_targets) {
+            target.push_from(source, i);
+        }
+        index
+    }
+    
+    /// Append a copy of vertex `i` (without its tangent)
+    pub(super) fn clone_vertex(&mut self, i: usize) -> u32 {
+        let index = self.vertex(self.positions[i].into(), self.normals[i].into(), self.uvs[i].into());
+        if !self.joints.is_empty() {
+            self.joints.push(self.joints[i]);
+            self.weights.push(self.weights[i]);
+        }
+        for target in &mut self.morph_targets {
+            target.positions.push(target.positions[i]);
+            target.normals.push(target.normals[i]);
+            if let Some(&tangent) = target.tangents.get(i) {
+                target.tangents.push(tangent);
+            }
+        }
+        index
+    }
+    
+    /// Two triangles; corners counter-clockwise seen from the front
+    fn quad(&mut self, bottom_left: u32, bottom_right: u32, top_right: u32, top_left: u32) {
+        self.indices.extend_from_slice(&[
+            bottom_left, bottom_right, top_right,
+            top_right, top_left, bottom_left,
+        ]);
+    }
+    
+    /// Fill in tangents once all triangles are in
+    fn with_tangents(mut self) -> Self {
+        process::generate_tangents(&mut self);
+        self
+    }
+}
+
+/// The generated shapes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    Cube,
+    UvSphere,
+    Icosphere,
+    Plane,
+    Cylinder,
+    Cone,
+    Torus,
+    Capsule,
+}
+
+impl Primitive {
+    /// Config spelling (`[scene] mesh`); "sphere" also means the UV sphere
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "cube" => Primitive::Cube,
+            "uv_sphere" | "sphere" => Primitive::UvSphere,
+            "icosphere" => Primitive::Icosphere,
+            "plane" => Primitive::Plane,
+            "cylinder" => Primitive::Cylinder,
+            "cone" => Primitive::Cone,
+            "torus" => Primitive::Torus,
+            "capsule" => Primitive::Capsule,
+            _ => return None,
+        })
+    }
+    
+    /// Detail used for `subdivisions = 0`
+    fn default_subdivisions(self) -> u32 {
+        match self {
+            Primitive::Cube => 1,
+            Primitive::Plane => 8,
+            Primitive::Icosphere => 3,
+            Primitive::UvSphere | Primitive::Cylinder | Primitive::Cone
+            | Primitive::Torus | Primitive::Capsule => 32,
+        }
+    }
+    
+    /// Build the shape with `subdivisions` of detail (0 = the shape's default)
+    ///
+    /// Subdivisions are grid cells per side for the cube's faces and the
+    /// plane, recursion depth for the icosphere (at most 6) and segments
+    /// around the axis for everything round.
+    pub fn generate(self, subdivisions: u32) -> MeshData {
+        let n = if subdivisions == 0 { self.default_subdivisions() } else { subdivisions };
+        match self {
+            Primitive::Cube => cube(n),
+            Primitive::UvSphere => uv_sphere(n, n / 2),
+            Primitive::Icosphere => icosphere(n),
+            Primitive::Plane => plane(n),
+            Primitive::Cylinder => cylinder(n),
+            Primitive::Cone => cone(n),
+            Primitive::Torus => torus(n, n / 2),
+            Primitive::Capsule => capsule(n, n / 4),
+        }
+    }
+}
+
+// =============================================================================
+// SHAPES
+// =============================================================================
+
+/// Unit cube, each face a grid of `cells`² quads with its own vertices
+pub fn cube(cells: u32) -> MeshData {
+    let cells = cells.max(1);
+    let mut mesh = MeshData::default();
+    // Outward normal, then the face's +u and +v directions (right x up = normal)
+    let faces = [
+        (Vec3::Z, Vec3::X, Vec3::Y),
+        (Vec3::NEG_Z, Vec3::NEG_X, Vec3::Y),
+        (Vec3::X, Vec3::NEG_Z, Vec3::Y),
+        (Vec3::NEG_X, Vec3::Z, Vec3::Y),
+        (Vec3::Y, Vec3::X, Vec3::NEG_Z),
+        (Vec3::NEG_Y, Vec3::X, Vec3::Z),
+    ];
+    for (normal, right, up) in faces {
+        grid(&mut mesh, cells, |p| normal * 0.5 + right * p.x + up * p.y, normal);
+    }
+    mesh.with_tangents()
+}
+
+/// Unit square on the XZ plane facing +Y, `cells`² quads (open, one-sided)
+pub fn plane(cells: u32) -> MeshData {
+    let mut mesh = MeshData::default();
+    grid(&mut mesh, cells.max(1), |p| Vec3::new(p.x, 0.0, -p.y), Vec3::Y);
+    mesh.with_tangents()
+}
+
+/// Sphere of radius 0.5 in `segments` longitudes and `rings` latitudes
+pub fn uv_sphere(segments: u32, rings: u32) -> MeshData {
+    let rings = rings.max(2);
+    let profile: Vec<ProfilePoint> = (0..=rings)
+        .map(|j| {
+            let latitude = j as f32 / rings as f32 * PI - PI * 0.5;
+            let normal = Vec2::new(latitude.cos(), latitude.sin());
+            ProfilePoint::new(normal * 0.5, normal, j as f32 / rings as f32)
+        })
+        .collect();
+    
+    let mut mesh = MeshData::default();
+    lathe(&mut mesh, &profile, segments);
+    mesh.with_tangents()
+}
+
+/// Sphere of radius 0.5 from an icosahedron split `level` times (4^level x 20
+/// triangles, plus a few where the UV seam cuts them)
+pub fn icosphere(level: u32) -> MeshData {
+    // Icosahedron: three orthogonal golden rectangles
+    let t = (1.0 + 5.0_f32.sqrt()) * 0.5;
+    let mut points: Vec<Vec3> = [
+        [-1.0, t, 0.0], [1.0, t, 0.0], [-1.0, -t, 0.0], [1.0, -t, 0.0],
+        [0.0, -1.0, t], [0.0, 1.0, t], [0.0, -1.0, -t], [0.0, 1.0, -t],
+        [t, 0.0, -1.0], [t, 0.0, 1.0], [-t, 0.0, -1.0], [-t, 0.0, 1.0],
+    ]
+    .iter()
+    .map(|&p| Vec3::from(p).normalize())
+    .collect();
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+    
+    // Each split replaces a triangle by four, sharing edge midpoints
+    for _ in 0..level.min(6) {
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, points: &mut Vec<Vec3>| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                points.push((points[a as usize] + points[b as usize]).normalize());
+                points.len() as u32 - 1
+            })
+        };
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let ab = midpoint(a, b, &mut points);
+                let bc = midpoint(b, c, &mut points);
+                let ca = midpoint(c, a, &mut points);
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+    
+    // Spherical UVs: the u = 0/1 seam runs down the -Z side of x = 0, where
+    // triangles are split so none spans it. Corners on the seam take u = 1
+    // in triangles east of it and 0 west of it, pole corners the u of the
+    // triangle they are in
+    let triangles = split_at_seam(&mut points, triangles);
+    let mut mesh = MeshData::default();
+    let mut vertices: HashMap<(u32, u32), u32> = HashMap::new();
+    for triangle in triangles {
+        let east = triangle.iter().any(|&i| points[i as usize].x > 0.0);
+        let mut uvs = triangle.map(|i| {
+            let p = points[i as usize];
+            let u = if p.x == 0.0 && p.z < 0.0 {
+                if east { 1.0 } else { 0.0 }
+            } else {
+                p.x.atan2(p.z) / TAU + 0.5
+            };
+            Vec2::new(u, p.y.asin() / PI + 0.5)
+        });
+        for corner in 0..3 {
+            if points[triangle[corner] as usize].y.abs() > 0.999_999 {
+                uvs[corner].x = (uvs[(corner + 1) % 3].x + uvs[(corner + 2) % 3].x) * 0.5;
+            }
+        }
+        
+        for (&i, uv) in triangle.iter().zip(uvs) {
+            let index = *vertices.entry((i, uv.x.to_bits())).or_insert_with(|| {
+                let p = points[i as usize];
+                mesh.vertex(p * 0.5, p, uv)
+            });
+            mesh.indices.push(index);
+        }
+    }
+    mesh.with_tangents()
+}
+
+/// Cylinder of radius 0.5 and height 1 along Y, with caps
+pub fn cylinder(segments: u32) -> MeshData {
+    let profile = [
+        ProfilePoint::new(Vec2::new(0.5, -0.5), Vec2::X, 0.0),
+        ProfilePoint::new(Vec2::new(0.5, 0.5), Vec2::X, 1.0),
+    ];
+    let mut mesh = MeshData::default();
+    lathe(&mut mesh, &profile, segments);
+    disk(&mut mesh, 0.5, 0.5, segments, true);
+    disk(&mut mesh, -0.5, 0.5, segments, false);
+    mesh.with_tangents()
+}
+
+/// Tube along Y from -0.5 to 0.5 narrowing from a radius of 0.2 to 0.05,
+/// capped at both ends, with `rings` rows along its length so it can bend
+pub fn tapered_tube(segments: u32, rings: u32) -> MeshData {
+    const BOTTOM: f32 = 0.2;
+    const TOP: f32 = 0.05;
+    let rings = rings.max(1);
+    // The side leans in by (BOTTOM - TOP) over its height of 1
+    let normal = Vec2::new(1.0, BOTTOM - TOP).normalize();
+    let profile: Vec<ProfilePoint> = (0..=rings)
+        .map(|j| {
+            let v = j as f32 / rings as f32;
+            // Lands exactly on both radii, so the caps meet the side
+            ProfilePoint::new(Vec2::new(BOTTOM * (1.0 - v) + TOP * v, v - 0.5), normal, v)
+        })
+        .collect();
+    let mut mesh = MeshData::default();
+    lathe(&mut mesh, &profile, segments);
+    disk(&mut mesh, 0.5, TOP, segments, true);
+    disk(&mut mesh, -0.5, BOTTOM, segments, false);
+    mesh.with_tangents()
+}
+
+/// Cone with a base of radius 0.5 at y = -0.5 and its apex at y = 0.5
+pub fn cone(segments: u32) -> MeshData {
+    // The side normal leans up by the slope (radius over height)
+    let normal = Vec2::new(1.0, 0.5).normalize();
+    let profile = [
+        ProfilePoint::new(Vec2::new(0.5, -0.5), normal, 0.0),
+        ProfilePoint::new(Vec2::new(0.0, 0.5), normal, 1.0),
+    ];
+    let mut mesh = MeshData::default();
+    lathe(&mut mesh, &profile, segments);
+    disk(&mut mesh, -0.5, 0.5, segments, false);
+    mesh.with_tangents()
+}
+
+/// Torus around Y: ring radius 0.35, tube radius 0.15 in `sides` steps
+pub fn torus(segments: u32, sides: u32) -> MeshData {
+    const RING: f32 = 0.35;
+    const TUBE: f32 = 0.15;
+    let sides = sides.max(3);
+    // Around the tube starting on the inside, under the ring
+    let profile: Vec<ProfilePoint> = (0..=sides)
+        .map(|j| {
+            let angle = (j % sides) as f32 / sides as f32 * TAU - PI;
+            let normal = Vec2::new(angle.cos(), angle.sin());
+            ProfilePoint::new(Vec2::new(RING, 0.0) + normal * TUBE, normal, j as f32 / sides as f32)
+        })
+        .collect();
+    let mut mesh = MeshData::default();
+    lathe(&mut mesh, &profile, segments);
+    mesh.with_tangents()
+}
+
+/// Capsule of radius 0.25 and height 1 along Y; `rings` per hemisphere
+pub fn capsule(segments: u32, rings: u32) -> MeshData {
+    const RADIUS: f32 = 0.25;
+    const HALF_CYLINDER: f32 = 0.25;
+    let rings = rings.max(1);
+    
+    // Bottom pole to top pole; v follows the length of the profile
+    let mut points = Vec::new();
+    for (center, start) in [(-HALF_CYLINDER, -PI * 0.5), (HALF_CYLINDER, 0.0)] {
+        for j in 0..=rings {
+            let latitude = start + j as f32 / rings as f32 * PI * 0.5;
+            let normal = Vec2::new(latitude.cos(), latitude.sin());
+            points.push((Vec2::new(0.0, center) + normal * RADIUS, normal));
+        }
+    }
+    let length = PI * RADIUS + 2.0 * HALF_CYLINDER;
+    let mut travelled = 0.0;
+    let profile: Vec<ProfilePoint> = points
+        .iter()
+        .enumerate()
+        .map(|(j, &(point, normal))| {
+            if j > 0 {
+                travelled += point.distance(points[j - 1].0);
+            }
+            ProfilePoint::new(point, normal, travelled / length)
+        })
+        .collect();
+    
+    let mut mesh = MeshData::default();
+    lathe(&mut mesh, &profile, segments);
+    mesh.with_tangents()
+}
+
+// =============================================================================
+// BUILDING BLOCKS
+// =============================================================================
+
+/// `cells`² quads over a flat face; `place` maps a point of the face
+/// (-0.5..0.5 along u and v) to a position
+fn grid(mesh: &mut MeshData, cells: u32, place: impl Fn(Vec2) -> Vec3, normal: Vec3) {
+    let first = mesh.positions.len() as u32;
+    for row in 0..=cells {
+        for column in 0..=cells {
+            let uv = Vec2::new(column as f32, row as f32) / cells as f32;
+            // From whole steps, so faces meeting at an edge agree exactly
+            let centered = Vec2::new(
+                (2 * column) as f32 - cells as f32,
+                (2 * row) as f32 - cells as f32,
+            ) / (2 * cells) as f32;
+            mesh.vertex(place(centered), normal, uv);
+        }
+    }
+    let index = |column: u32, row: u32| first + row * (cells + 1) + column;
+    for row in 0..cells {
+        for column in 0..cells {
+            mesh.quad(index(column, row), index(column + 1, row), index(column + 1, row + 1), index(column, row + 1));
+        }
+    }
+}
+
+/// Split the triangles of a unit sphere along the -Z half of the x = 0 plane
+///
+/// Each crossing edge is cut once, at a new point on the sphere shared by
+/// both triangles along it, so the surface stays watertight.
+fn split_at_seam(points: &mut Vec<Vec3>, triangles: Vec<[u32; 3]>) -> Vec<[u32; 3]> {
+    let mut cuts: HashMap<(u32, u32), u32> = HashMap::new();
+    for triangle in &triangles {
+        for k in 0..3 {
+            let (a, b) = (triangle[k], triangle[(k + 1) % 3]);
+            let edge = (a.min(b), a.max(b));
+            let (pa, pb) = (points[edge.0 as usize], points[edge.1 as usize]);
+            if pa.x * pb.x >= 0.0 {
+                continue;
+            }
+            // Edges crossing the +Z half stay (u is 0.5 there); z = 0 is a pole
+            let crossing = pa + (pb - pa) * (pa.x / (pa.x - pb.x));
+            if crossing.z > 0.0 {
+                continue;
+            }
+            cuts.entry(edge).or_insert_with(|| {
+                points.push(Vec3::new(0.0, crossing.y, crossing.z).normalize());
+                points.len() as u32 - 1
+            });
+        }
+    }
+    
+    let cut = |a: u32, b: u32| cuts.get(&(a.min(b), a.max(b))).copied();
+    triangles
+        .into_iter()
+        .flat_map(|[a, b, c]| match (cut(a, b), cut(b, c), cut(c, a)) {
+            (None, None, None) => vec![[a, b, c]],
+            (Some(p), None, None) => vec![[a, p, c], [p, b, c]],
+            (None, Some(p), None) => vec![[b, p, a], [p, c, a]],
+            (None, None, Some(p)) => vec![[c, p, b], [p, a, b]],
+            // One corner alone on its side: a triangle there, a quad on the other
+            (Some(p), Some(q), None) => vec![[b, q, p], [p, q, c], [p, c, a]],
+            (None, Some(p), Some(q)) => vec![[c, q, p], [p, q, a], [p, a, b]],
+            (Some(q), None, Some(p)) => vec![[a, q, p], [q, b, c], [q, c, p]],
+            (Some(_), Some(_), Some(_)) => unreachable!("a plane cuts at most two edges of a triangle"),
+        })
+        .collect()
+}
+
+/// A point of a profile revolved around Y, from bottom to top
+#[derive(Debug, Clone, Copy)]
+struct ProfilePoint {
+    /// Distance from the axis, height
+    position: Vec2,
+    /// Outward normal as (radial, up)
+    normal: Vec2,
+    v: f32,
+}
+
+impl ProfilePoint {
+    fn new(position: Vec2, normal: Vec2, v: f32) -> Self {
+        // Points meant to be on the axis land exactly on it (sphere poles)
+        let position = if position.x.abs() < 1e-6 { Vec2::new(0.0, position.y) } else { position };
+        Self { position, normal, v }
+    }
+}
+
+/// Surface of revolution: the profile swept around Y in `segments` steps
+///
+/// The seam column is duplicated for u = 1; quads that touch the axis
+/// become single triangles.
+fn lathe(mesh: &mut MeshData, profile: &[ProfilePoint], segments: u32) {
+    let segments = segments.max(3);
+    let first = mesh.positions.len() as u32;
+    for column in 0..=segments {
+        // The seam reuses angle 0 exactly, so its positions match
+        let angle = (column % segments) as f32 / segments as f32 * TAU;
+        let (sin, cos) = angle.sin_cos();
+        for point in profile {
+            let radial = Vec3::new(sin, 0.0, cos);
+            mesh.vertex(
+                radial * point.position.x + Vec3::Y * point.position.y,
+                radial * point.normal.x + Vec3::Y * point.normal.y,
+                Vec2::new(column as f32 / segments as f32, point.v),
+            );
+        }
+    }
+    
+    let rows = profile.len() as u32;
+    let index = |column: u32, row: u32| first + column * rows + row;
+    for column in 0..segments {
+        for row in 0..rows - 1 {
+            let (bottom_left, bottom_right) = (index(column, row), index(column + 1, row));
+            let (top_right, top_left) = (index(column + 1, row + 1), index(column, row + 1));
+            if profile[row as usize].position.x == 0.0 {
+                mesh.indices.extend_from_slice(&[bottom_left, top_right, top_left]);
+            } else if profile[row as usize + 1].position.x == 0.0 {
+                mesh.indices.extend_from_slice(&[bottom_left, bottom_right, top_right]);
+            } else {
+                mesh.quad(bottom_left, bottom_right, top_right, top_left);
+            }
+        }
+    }
+}
+
+/// Flat cap at height `y` facing +Y (`up`) or -Y, with planar UVs
+fn disk(mesh: &mut MeshData, y: f32, radius: f32, segments: u32, up: bool) {
+    let segments = segments.max(3);
+    let normal = if up { Vec3::Y } else { Vec3::NEG_Y };
+    // Seen from outside, +u is +X and +v is -Z on top, +Z underneath
+    let v_sign = if up { -1.0 } else { 1.0 };
+    let center = mesh.vertex(Vec3::new(0.0, y, 0.0), normal, Vec2::splat(0.5));
+    let first = mesh.positions.len() as u32;
+    for i in 0..segments {
+        let (sin, cos) = (i as f32 / segments as f32 * TAU).sin_cos();
+        let position = Vec3::new(sin * radius, y, cos * radius);
+        let uv = Vec2::new(0.5 + sin * 0.5, 0.5 + cos * 0.5 * v_sign);
+        mesh.vertex(position, normal, uv);
+    }
+    for i in 0..segments {
+        let (a, b) = (first + i, first + (i + 1) % segments);
+        if up {
+            mesh.indices.extend_from_slice(&[center, a, b]);
+        } else {
+            mesh.indices.extend_from_slice(&[center, b, a]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Gives the point a surface point's normal should face away from
+    type Center = fn(Vec3) -> Vec3;
+    
+    /// Every closed shape at a few levels of detail
+    fn shapes() -> Vec<(String, MeshData, Center)> {
+        fn origin(_: Vec3) -> Vec3 {
+            Vec3::ZERO
+        }
+        // Nearest point on the ring the torus tube sweeps along
+        fn ring(p: Vec3) -> Vec3 {
+            Vec3::new(p.x, 0.0, p.z).normalize() * 0.35
+        }
+        
+        let mut shapes = Vec::new();
+        let primitives = [
+            Primitive::Cube,
+            Primitive::UvSphere,
+            Primitive::Icosphere,
+            Primitive::Cylinder,
+            Primitive::Cone,
+            Primitive::Torus,
+            Primitive::Capsule,
+        ];
+        for primitive in primitives {
+            // Icosphere subdivisions are recursion levels
+            let levels: &[u32] = if primitive == Primitive::Icosphere { &[1, 2, 3, 4] } else { &[1, 2, 3, 4, 7, 16] };
+            for &subdivisions in levels {
+                let center = if primitive == Primitive::Torus { ring } else { origin };
+                shapes.push((format!("{primitive:?} {subdivisions}"), primitive.generate(subdivisions), center));
+            }
+        }
+        shapes.push(("icosphere 0".to_string(), icosphere(0), origin));
+        for (segments, rings) in [(3, 1), (8, 3), (16, 6)] {
+            shapes.push((format!("tapered tube {segments}x{rings}"), tapered_tube(segments, rings), origin));
+        }
+        shapes
+    }
+    
+    /// Planes at several cell counts (open, facing +Y)
+    fn planes() -> Vec<(String, MeshData)> {
+        [1, 2, 5, 8].map(|cells| (format!("plane {cells}"), plane(cells))).into()
+    }
+    
+    /// Vertex indices with equal positions merged (-0.0 counts as 0.0)
+    fn welded(mesh: &MeshData) -> Vec<u32> {
+        let mut ids: HashMap<[u32; 3], u32> = HashMap::new();
+        let remap: Vec<u32> = mesh.positions
+            .iter()
+            .map(|p| {
+                let key = p.map(|c| (c + 0.0).to_bits());
+                let next = ids.len() as u32;
+                *ids.entry(key).or_insert(next)
+            })
+            .collect();
+        mesh.indices.iter().map(|&i| remap[i as usize]).collect()
+    }
+    
+    /// How often each directed edge of the welded triangles appears
+    fn edges(mesh: &MeshData) -> HashMap<(u32, u32), u32> {
+        let mut edges = HashMap::new();
+        for triangle in welded(mesh).chunks(3) {
+            for k in 0..3 {
+                *edges.entry((triangle[k], triangle[(k + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+        edges
+    }
+    
+    fn all_meshes() -> Vec<(String, MeshData)> {
+        shapes().into_iter().map(|(name, mesh, _)| (name, mesh)).chain(planes()).collect()
+    }
+    
+    #[test]
+    fn closed_shapes_are_watertight() {
+        for (name, mesh, _) in shapes() {
+            let edges = edges(&mesh);
+            for (&(a, b), &count) in &edges {
+                assert_eq!(count, 1, "{name}: edge {a}-{b} used {count} times in the same direction");
+                let reverse = edges.get(&(b, a)).copied().unwrap_or(0);
+                assert_eq!(reverse, 1, "{name}: edge {a}-{b} has {reverse} opposite neighbours");
+            }
+        }
+    }
+    
+    #[test]
+    fn plane_is_open_only_at_its_border() {
+        for (name, mesh) in planes() {
+            let edges = edges(&mesh);
+            let welded = welded(&mesh);
+            // Any vertex of the original mesh with a given welded id
+            let position = |id: u32| -> Vec3 {
+                let i = welded.iter().position(|&w| w == id).unwrap();
+                mesh.positions[mesh.indices[i] as usize].into()
+            };
+            let on_border = |p: Vec3| p.x.abs() == 0.5 || p.z.abs() == 0.5;
+            for (&(a, b), &count) in &edges {
+                assert_eq!(count, 1, "{name}: edge {a}-{b} repeated");
+                if !edges.contains_key(&(b, a)) {
+                    let (pa, pb) = (position(a), position(b));
+                    assert!(on_border(pa) && on_border(pb), "{name}: open edge {pa} - {pb}");
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn normals_are_unit_length_and_point_outward() {
+        for (name, mesh, center) in shapes() {
+            for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+                let (p, n) = (Vec3::from(*p), Vec3::from(*n));
+                assert!((n.length() - 1.0).abs() < 1e-5, "{name}: normal {n} at {p}");
+                assert!(n.dot(p - center(p)) > 0.0, "{name}: normal {n} at {p} points inward");
+            }
+        }
+        for (name, mesh) in planes() {
+            for n in &mesh.normals {
+                assert_eq!(Vec3::from(*n), Vec3::Y, "{name}");
+            }
+        }
+    }
+    
+    #[test]
+    fn winding_agrees_with_normals() {
+        for (name, mesh) in all_meshes() {
+            for triangle in mesh.indices.chunks(3) {
+                let [a, b, c] = [0, 1, 2].map(|k| Vec3::from(mesh.positions[triangle[k] as usize]));
+                let face = (b - a).cross(c - a);
+                for &i in triangle {
+                    let normal = Vec3::from(mesh.normals[i as usize]);
+                    assert!(face.dot(normal) > 0.0, "{name}: triangle {a} {b} {c} faces away from {normal}");
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn tangents_are_orthogonal_to_normals() {
+        for (name, mesh) in all_meshes() {
+            assert_eq!(mesh.tangents.len(), mesh.positions.len(), "{name}");
+            for (t, n) in mesh.tangents.iter().zip(&mesh.normals) {
+                let tangent = Vec3::new(t[0], t[1], t[2]);
+                assert!((tangent.length() - 1.0).abs() < 1e-4, "{name}: tangent {tangent}");
+                assert!(tangent.dot(Vec3::from(*n)).abs() < 1e-4, "{name}: tangent {tangent} against normal {n:?}");
+                assert!(t[3] == 1.0 || t[3] == -1.0, "{name}: handedness {}", t[3]);
+            }
+        }
+    }
+    
+    #[test]
+    fn uvs_are_in_unit_range() {
+        for (name, mesh) in all_meshes() {
+            for uv in &mesh.uvs {
+                assert!(uv.iter().all(|c| (0.0..=1.0).contains(c)), "{name}: uv {uv:?}");
+            }
+        }
+    }
+}
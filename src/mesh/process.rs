@@ -0,0 +1,539 @@
+// Mesh processing
+//
+// Cleanup passes for imported (or generated) geometry, run on `MeshData`
+// before it is interleaved into vertices and uploaded:
+//   - welding merges duplicate vertices and drops degenerate triangles
+//   - normal generation (smooth or flat) and MikkTSpace tangents,
+//     with matching normal and tangent deltas for morph targets
+//   - vertex cache, overdraw and vertex fetch optimization (meshoptimizer)
+//   - index compaction to u16 when every vertex is addressable with it
+// Typical order: weld, normals, tangents, optimize, then `Indices::compact`
+// on the final index list (after LOD levels are appended).
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use ash::vk;
+use glam::Vec3;
+use super::primitives::{MeshData, MorphTarget};
+
+/// How much vertex cache efficiency the overdraw pass may give up (5%)
+const OVERDRAW_THRESHOLD: f32 = 1.05;
+
+/// Merge vertices whose attributes all match within `tolerance`, drop
+/// unreferenced vertices and degenerate triangles
+///
+/// Attributes are snapped to a grid of `tolerance`, so values just either
+/// side of a grid line stay apart. Returns how many vertices were removed.
+pub fn weld(mesh: &mut MeshData, tolerance: f32) -> usize {
+    let snap = |values: &[f32], key: &mut Vec<i32>| {
+        key.extend(values.iter().map(|&v| (v / tolerance).round() as i32));
+    };
+    let keys: Vec<Vec<i32>> = (0..mesh.positions.len())
+        .map(|i| {
+            let mut key = Vec::with_capacity(12);
+            snap(&mesh.positions[i], &mut key);
+            snap(&mesh.normals[i], &mut key);
+            snap(&mesh.uvs[i], &mut key);
+            if let Some(tangent) = mesh.tangents.get(i) {
+                snap(tangent, &mut key);
+            }
+            if let (Some(joints), Some(weights)) = (mesh.joints.get(i), mesh.weights.get(i)) {
+                key.extend(joints.map(i32::from));
+                snap(weights, &mut key);
+            }
+            for target in &mesh.morph_targets {
+                snap(&target.positions[i], &mut key);
+                snap(&target.normals[i], &mut key);
+                if let Some(tangent) = target.tangents.get(i) {
+                    snap(tangent, &mut key);
+                }
+            }
+            key
+        })
+        .collect();
+    let removed = remap_vertices(mesh, |i| &keys[i]);
+    
+    // Submesh ranges shrink by the triangles dropped from them
+    let mut indices = Vec::with_capacity(mesh.indices.len());
+    let ranges = mesh.index_ranges();
+    for (i, range) in ranges.into_iter().enumerate() {
+        let first = indices.len();
+        for triangle in mesh.indices[range].chunks_exact(3) {
+            if triangle[0] != triangle[1] && triangle[1] != triangle[2] && triangle[2] != triangle[0] {
+                indices.extend_from_slice(triangle);
+            }
+        }
+        if let Some(submesh) = mesh.submeshes.get_mut(i) {
+            submesh.first_index = first as u32;
+            submesh.index_count = (indices.len() - first) as u32;
+        }
+    }
+    mesh.indices = indices;
+    removed
+}
+
+/// Normals to generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalMode {
+    /// Faces meeting at a position share a normal, weighted by the angle
+    /// each face has there (UV seams don't show as lighting seams)
+    Smooth,
+    /// Every triangle gets its own vertices with the face normal
+    Flat,
+}
+
+/// Replace the normals (and those of the morph targets); tangents are
+/// cleared, run `generate_tangents` after
+pub fn generate_normals(mesh: &mut MeshData, mode: NormalMode) {
+    mesh.tangents.clear();
+    if mode == NormalMode::Flat {
+        // Every triangle gets its own copies of its corners
+        let mut flat = MeshData {
+            submeshes: std::mem::take(&mut mesh.submeshes),
+            morph_targets: mesh.morph_targets.iter().map(MorphTarget::empty_like).collect(),
+            ..Default::default()
+        };
+        for &i in &mesh.indices {
+            let index = flat.copy_vertex(mesh, i as usize);
+            flat.indices.push(index);
+        }
+        *mesh = flat;
+    }
+    mesh.normals = vertex_normals(mesh, &mesh.positions, mode);
+    generate_morph_normals(mesh, mode);
+}
+
+/// Normal deltas of the morph targets: the normals of each fully morphed
+/// shape minus the base normals (tangent deltas follow when the mesh has
+/// tangents)
+pub fn generate_morph_normals(mesh: &mut MeshData, mode: NormalMode) {
+    let deltas: Vec<Vec<[f32; 3]>> = mesh.morph_targets
+        .iter()
+        .map(|target| {
+            let positions: Vec<[f32; 3]> = mesh.positions
+                .iter()
+                .zip(&target.positions)
+                .map(|(&p, &d)| (Vec3::from(p) + Vec3::from(d)).to_array())
+                .collect();
+            vertex_normals(mesh, &positions, mode)
+                .into_iter()
+                .zip(&mesh.normals)
+                .map(|(morphed, &base)| (Vec3::from(morphed) - Vec3::from(base)).to_array())
+                .collect()
+        })
+        .collect();
+    for (target, normals) in mesh.morph_targets.iter_mut().zip(deltas) {
+        target.normals = normals;
+    }
+    generate_morph_tangents(mesh);
+}
+
+/// Normals of `mesh`'s triangles placed at `positions`
+///
+/// Smooth normals are shared between vertices at the same position in
+/// `mesh`, so a morphed shape is smoothed where the base shape is. Flat
+/// normals expect every vertex to belong to a single triangle.
+fn vertex_normals(mesh: &MeshData, positions: &[[f32; 3]], mode: NormalMode) -> Vec<[f32; 3]> {
+    let face = |triangle: &[u32]| {
+        let corners = [0, 1, 2].map(|k| Vec3::from(positions[triangle[k] as usize]));
+        let normal = (corners[1] - corners[0]).cross(corners[2] - corners[0]).normalize_or_zero();
+        (corners, normal)
+    };
+    match mode {
+        NormalMode::Smooth => {
+            // -0.0 and 0.0 are the same position
+            let key = |p: [f32; 3]| p.map(|x| if x == 0.0 { 0 } else { x.to_bits() });
+            let mut sums: HashMap<[u32; 3], Vec3> = HashMap::new();
+            for triangle in mesh.indices.chunks_exact(3) {
+                let (corners, normal) = face(triangle);
+                for (k, &i) in triangle.iter().enumerate() {
+                    let angle = corner_angle(corners, k);
+                    *sums.entry(key(mesh.positions[i as usize])).or_default() += normal * angle;
+                }
+            }
+            mesh.positions
+                .iter()
+                .map(|&position| {
+                    let sum = sums.get(&key(position)).copied().unwrap_or(Vec3::ZERO);
+                    sum.try_normalize().unwrap_or(Vec3::Y).to_array()
+                })
+                .collect()
+        }
+        NormalMode::Flat => {
+            let mut normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+            for triangle in mesh.indices.chunks_exact(3) {
+                let (_, normal) = face(triangle);
+                let normal = if normal == Vec3::ZERO { Vec3::Y } else { normal };
+                for &i in triangle {
+                    normals[i as usize] = normal.to_array();
+                }
+            }
+            normals
+        }
+    }
+}
+
+/// Tangent deltas of the morph targets: each base tangent made
+/// perpendicular to the morphed normal again, minus the base tangent
+fn generate_morph_tangents(mesh: &mut MeshData) {
+    for target in &mut mesh.morph_targets {
+        if mesh.tangents.is_empty() {
+            target.tangents.clear();
+            continue;
+        }
+        target.tangents = mesh.tangents
+            .iter()
+            .zip(&mesh.normals)
+            .zip(&target.normals)
+            .map(|((tangent, &normal), &delta)| {
+                let tangent = Vec3::new(tangent[0], tangent[1], tangent[2]);
+                let normal = (Vec3::from(normal) + Vec3::from(delta)).normalize_or_zero();
+                let morphed = (tangent - normal * normal.dot(tangent)).try_normalize().unwrap_or(tangent);
+                (morphed - tangent).to_array()
+            })
+            .collect();
+    }
+}
+
+/// Tangents from the UVs with MikkTSpace, so normal maps baked by other
+/// tools (which use it too) shade the same here
+///
+/// MikkTSpace gives every triangle corner a tangent. A vertex whose corners
+/// get different ones, e.g. where mirrored UVs flip the handedness, is split
+/// so each copy keeps one. Corners without usable UVs get an arbitrary
+/// tangent perpendicular to the normal.
+pub fn generate_tangents(mesh: &mut MeshData) {
+    let mut corners = Corners { mesh, tangents: vec![None; mesh.indices.len()] };
+    if !bevy_mikktspace::generate_tangents(&mut corners) {
+        corners.tangents.fill(None);
+    }
+    let corner_tangents = corners.tangents;
+    
+    let fallback = |normal: [f32; 3]| Vec3::from(normal).any_orthonormal_vector().extend(1.0).to_array();
+    let mut tangents: Vec<Option<[f32; 4]>> = vec![None; mesh.positions.len()];
+    let mut copies: HashMap<(u32, [u32; 4]), u32> = HashMap::new();
+    for (corner, tangent) in corner_tangents.into_iter().enumerate() {
+        let i = mesh.indices[corner];
+        let tangent = tangent.unwrap_or_else(|| fallback(mesh.normals[i as usize]));
+        match tangents[i as usize] {
+            None => tangents[i as usize] = Some(tangent),
+            Some(existing) if existing == tangent => {}
+            Some(_) => {
+                let key = (i, tangent.map(f32::to_bits));
+                let copy = match copies.get(&key) {
+                    Some(&copy) => copy,
+                    None => {
+                        let copy = mesh.clone_vertex(i as usize);
+                        tangents.push(Some(tangent));
+                        copies.insert(key, copy);
+                        copy
+                    }
+                };
+                mesh.indices[corner] = copy;
+            }
+        }
+    }
+    
+    mesh.tangents = tangents
+        .into_iter()
+        .zip(&mesh.normals)
+        .map(|(tangent, &normal)| tangent.unwrap_or_else(|| fallback(normal)))
+        .collect();
+    generate_morph_tangents(mesh);
+}
+
+/// `MeshData` triangles as MikkTSpace sees them, collecting the tangent it
+/// picks for every corner
+struct Corners<'a> {
+    mesh: &'a MeshData,
+    tangents: Vec<Option<[f32; 4]>>,
+}
+
+impl Corners<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> usize {
+        self.mesh.indices[face * 3 + vert] as usize
+    }
+}
+
+impl bevy_mikktspace::Geometry for Corners<'_> {
+    fn num_faces(&self) -> usize {
+        self.mesh.indices.len() / 3
+    }
+    
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+    
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.mesh.positions[self.vertex(face, vert)]
+    }
+    
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.mesh.normals[self.vertex(face, vert)]
+    }
+    
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.mesh.uvs[self.vertex(face, vert)]
+    }
+    
+    /// Zero-length tangents (degenerate UVs) are left to the fallback
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        let [x, y, z, sign] = tangent;
+        self.tangents[face * 3 + vert] = Vec3::new(x, y, z)
+            .try_normalize()
+            .map(|tangent| tangent.extend(sign).to_array());
+    }
+}
+
+/// Reorder triangles for the post-transform vertex cache and to reduce
+/// overdraw, then vertices in the order they are first used
+///
+/// Triangles only move within their submesh.
+pub fn optimize(mesh: &mut MeshData) {
+    let vertex_count = mesh.positions.len();
+    for range in mesh.index_ranges() {
+        let mut indices = meshopt::optimize_vertex_cache(&mesh.indices[range.clone()], vertex_count);
+        // meshopt's safe wrapper writes through a shared slice, so call the
+        // C function with our own buffer (it supports reordering in place)
+        let ptr = indices.as_mut_ptr();
+        unsafe {
+            meshopt::ffi::meshopt_optimizeOverdraw(
+                ptr,
+                ptr,
+                indices.len(),
+                mesh.positions.as_ptr().cast(),
+                vertex_count,
+                std::mem::size_of::<[f32; 3]>(),
+                OVERDRAW_THRESHOLD,
+            );
+        }
+        mesh.indices[range].copy_from_slice(&indices);
+    }
+    
+    remap_vertices(mesh, |i| i);
+}
+
+/// An index list in the smallest type that addresses every vertex
+#[derive(Debug, Clone)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    /// u16 when there are at most 65535 vertices (0xFFFF itself is left
+    /// out since it restarts strips when primitive restart is on)
+    pub fn compact(indices: &[u32], vertex_count: usize) -> Self {
+        if vertex_count <= u16::MAX as usize {
+            Indices::U16(indices.iter().map(|&i| i as u16).collect())
+        } else {
+            Indices::U32(indices.to_vec())
+        }
+    }
+    
+    pub fn index_type(&self) -> vk::IndexType {
+        match self {
+            Indices::U16(_) => vk::IndexType::UINT16,
+            Indices::U32(_) => vk::IndexType::UINT32,
+        }
+    }
+    
+    /// The indices as they are laid out in an index buffer
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Indices::U16(indices) => meshopt::typed_to_bytes(indices),
+            Indices::U32(indices) => meshopt::typed_to_bytes(indices),
+        }
+    }
+}
+
+/// Angle of the triangle at corner `k`
+fn corner_angle(corners: [Vec3; 3], k: usize) -> f32 {
+    let a = corners[(k + 1) % 3] - corners[k];
+    let b = corners[(k + 2) % 3] - corners[k];
+    match (a.try_normalize(), b.try_normalize()) {
+        (Some(a), Some(b)) => a.dot(b).clamp(-1.0, 1.0).acos(),
+        _ => 0.0,
+    }
+}
+
+/// Rebuild the vertex arrays with only the vertices the indices use, in the
+/// order they are first used, merging vertices with equal `key`s
+///
+/// Returns how many vertices went away.
+fn remap_vertices<K: Hash + Eq>(mesh: &mut MeshData, key: impl Fn(usize) -> K) -> usize {
+    let before = mesh.positions.len();
+    let mut new_index: HashMap<K, u32> = HashMap::new();
+    let mut order = Vec::new();
+    for index in &mut mesh.indices {
+        let old = *index as usize;
+        *index = *new_index.entry(key(old)).or_insert_with(|| {
+            order.push(old);
+            order.len() as u32 - 1
+        });
+    }
+    
+    mesh.positions = order.iter().map(|&i| mesh.positions[i]).collect();
+    mesh.normals = order.iter().map(|&i| mesh.normals[i]).collect();
+    mesh.uvs = order.iter().map(|&i| mesh.uvs[i]).collect();
+    if !mesh.tangents.is_empty() {
+        mesh.tangents = order.iter().map(|&i| mesh.tangents[i]).collect();
+    }
+    if !mesh.joints.is_empty() {
+        mesh.joints = order.iter().map(|&i| mesh.joints[i]).collect();
+        mesh.weights = order.iter().map(|&i| mesh.weights[i]).collect();
+    }
+    for target in &mut mesh.morph_targets {
+        target.positions = order.iter().map(|&i| target.positions[i]).collect();
+        target.normals = order.iter().map(|&i| target.normals[i]).collect();
+        if !target.tangents.is_empty() {
+            target.tangents = order.iter().map(|&i| target.tangents[i]).collect();
+        }
+    }
+    before - order.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec2;
+    use super::super::primitives::Submesh;
+    
+    /// Triangles facing +Z from (x, y) corners, UVs given per corner and
+    /// every corner its own vertex
+    fn triangles(corners: &[([f32; 2], [f32; 2])]) -> MeshData {
+        let mut mesh = MeshData::default();
+        for &(position, uv) in corners {
+            let index = mesh.vertex(Vec2::from(position).extend(0.0), Vec3::Z, uv.into());
+            mesh.indices.push(index);
+        }
+        mesh
+    }
+    
+    /// A unit quad split along its diagonal, UVs matching positions
+    fn quad() -> MeshData {
+        let corner = |x: f32, y: f32| ([x, y], [x, y]);
+        triangles(&[
+            corner(0.0, 0.0), corner(1.0, 0.0), corner(1.0, 1.0),
+            corner(1.0, 1.0), corner(0.0, 1.0), corner(0.0, 0.0),
+        ])
+    }
+    
+    #[test]
+    fn weld_merges_matching_vertices_and_drops_degenerate_triangles() {
+        let mut mesh = quad();
+        // A corner a hair off its neighbour still merges
+        mesh.positions[3][0] += 1e-6;
+        assert_eq!(weld(&mut mesh, 1e-4), 2);
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.indices, [0, 1, 2, 2, 3, 0]);
+        
+        // Different UVs keep vertices apart; a collapsed triangle goes
+        let mut mesh = quad();
+        mesh.uvs[3] = [0.5, 0.5];
+        mesh.indices.extend_from_slice(&[0, 5, 1]);
+        mesh.submeshes = vec![
+            Submesh { material: "a".into(), first_index: 0, index_count: 6 },
+            Submesh { material: "b".into(), first_index: 6, index_count: 3 },
+        ];
+        assert_eq!(weld(&mut mesh, 1e-4), 1);
+        assert_eq!(mesh.positions.len(), 5);
+        assert_eq!(mesh.indices.len(), 6);
+        assert_eq!((mesh.submeshes[0].first_index, mesh.submeshes[0].index_count), (0, 6));
+        assert_eq!((mesh.submeshes[1].first_index, mesh.submeshes[1].index_count), (6, 0));
+    }
+    
+    #[test]
+    fn smooth_normals_average_by_angle_and_flat_normals_split() {
+        // Two faces of a roof meeting at a ridge along z
+        let mut mesh = MeshData::default();
+        for (position, uv) in [
+            ([-1.0, 0.0, 0.0], [0.0, 0.0]),
+            ([0.0, 1.0, 0.0], [0.5, 0.0]),
+            ([0.0, 1.0, 1.0], [0.5, 1.0]),
+            ([-1.0, 0.0, 1.0], [0.0, 1.0]),
+            ([1.0, 0.0, 0.0], [1.0, 0.0]),
+            ([1.0, 0.0, 1.0], [1.0, 1.0]),
+            // The ridge again, as a UV seam
+            ([0.0, 1.0, 0.0], [0.0, 0.0]),
+            ([0.0, 1.0, 1.0], [0.0, 1.0]),
+        ] {
+            mesh.vertex(position.into(), Vec3::ZERO, uv.into());
+        }
+        mesh.indices = vec![0, 2, 1, 0, 3, 2, 6, 5, 4, 6, 7, 5];
+        
+        let mut smooth = mesh.clone();
+        generate_normals(&mut smooth, NormalMode::Smooth);
+        let left = Vec3::new(-1.0, 1.0, 0.0).normalize();
+        let right = Vec3::new(1.0, 1.0, 0.0).normalize();
+        for i in [1, 2, 6, 7] {
+            assert!(Vec3::from(smooth.normals[i]).abs_diff_eq(Vec3::Y, 1e-5), "ridge {i}: {:?}", smooth.normals[i]);
+        }
+        assert!(Vec3::from(smooth.normals[0]).abs_diff_eq(left, 1e-5));
+        assert!(Vec3::from(smooth.normals[4]).abs_diff_eq(right, 1e-5));
+        assert!(smooth.tangents.is_empty());
+        
+        let mut flat = mesh.clone();
+        generate_normals(&mut flat, NormalMode::Flat);
+        assert_eq!(flat.positions.len(), 12);
+        assert_eq!(flat.indices, (0..12).collect::<Vec<u32>>());
+        for (i, normal) in flat.normals.iter().enumerate() {
+            let expected = if i < 6 { left } else { right };
+            assert!(Vec3::from(*normal).abs_diff_eq(expected, 1e-5), "corner {i}: {normal:?}");
+        }
+    }
+    
+    #[test]
+    fn compact_uses_u16_up_to_65535_vertices() {
+        let indices = Indices::compact(&[0, 65534, 7], 65535);
+        assert!(matches!(&indices, Indices::U16(values) if values == &[0, 65534, 7]));
+        assert_eq!(indices.index_type(), vk::IndexType::UINT16);
+        assert_eq!(indices.as_bytes(), [0, 0, 0xFE, 0xFF, 7, 0]);
+        
+        let indices = Indices::compact(&[0, 65535], 65536);
+        assert!(matches!(&indices, Indices::U32(values) if values == &[0, 65535]));
+        assert_eq!(indices.index_type(), vk::IndexType::UINT32);
+        assert_eq!(indices.as_bytes().len(), 8);
+    }
+    
+    #[test]
+    fn tangents_follow_uvs_and_split_mirrored_vertices() {
+        // Shared vertices with continuous UVs stay shared
+        let mut mesh = quad();
+        weld(&mut mesh, 1e-4);
+        generate_tangents(&mut mesh);
+        assert_eq!(mesh.positions.len(), 4);
+        for tangent in &mesh.tangents {
+            assert!(Vec3::from_slice(tangent).abs_diff_eq(Vec3::X, 1e-5), "{tangent:?}");
+            assert_eq!(tangent[3], 1.0);
+        }
+        
+        // u mirrored at x = 0: the two halves meet with opposite tangents
+        // and handedness, so the shared edge is split
+        let corner = |x: f32, y: f32| ([x, y], [x.abs(), y]);
+        let mut mesh = triangles(&[
+            corner(-1.0, 0.0), corner(0.0, 0.0), corner(0.0, 1.0),
+            corner(0.0, 0.0), corner(1.0, 0.0), corner(0.0, 1.0),
+        ]);
+        weld(&mut mesh, 1e-4);
+        assert_eq!(mesh.positions.len(), 4);
+        generate_tangents(&mut mesh);
+        assert_eq!(mesh.positions.len(), 6);
+        for (t, triangle) in mesh.indices.chunks_exact(3).enumerate() {
+            let (direction, sign) = if t == 0 { (Vec3::NEG_X, -1.0) } else { (Vec3::X, 1.0) };
+            for &i in triangle {
+                let tangent = mesh.tangents[i as usize];
+                assert!(Vec3::from_slice(&tangent).abs_diff_eq(direction, 1e-5), "triangle {t}: {tangent:?}");
+                assert_eq!(tangent[3], sign, "triangle {t}");
+            }
+        }
+        
+        // Degenerate UVs still get a unit tangent perpendicular to the normal
+        let mut mesh = triangles(&[([0.0, 0.0], [0.0; 2]), ([1.0, 0.0], [0.0; 2]), ([0.0, 1.0], [0.0; 2])]);
+        generate_tangents(&mut mesh);
+        for tangent in &mesh.tangents {
+            let direction = Vec3::from_slice(tangent);
+            assert!((direction.length() - 1.0).abs() < 1e-5 && direction.dot(Vec3::Z).abs() < 1e-5, "{tangent:?}");
+        }
+    }
+}
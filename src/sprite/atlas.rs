@@ -0,0 +1,144 @@
+// Texture atlases
+//
+// Many small images packed into one texture, so sprites that use any of
+// them share a descriptor set and batch into one draw. `AtlasBuilder` packs
+// RGBA images on the CPU; `SpriteRenderer::add_atlas` uploads the result and
+// returns a `SpriteAtlas` that hands out sprites by region name.
+
+use std::collections::HashMap;
+use anyhow::{Context, Result};
+use glam::Vec2;
+use super::batch::{Sprite, TextureId};
+
+/// Texels left empty around every packed image, so linear filtering never
+/// blends in a neighbour
+const PADDING: u32 = 1;
+
+/// Place rectangles in rows at most `width` wide, tallest first, with
+/// `padding` texels between them
+///
+/// Returns each rectangle's top-left corner (in input order) and the height
+/// of all rows.
+pub fn pack_rows(sizes: &[(u32, u32)], width: u32, padding: u32) -> Result<(Vec<(u32, u32)>, u32)> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i].1));
+    
+    let mut origins = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    for i in order {
+        let (w, h) = sizes[i];
+        if w > width {
+            anyhow::bail!("A {}x{} image doesn't fit in a {} texel wide atlas", w, h, width);
+        }
+        if x + w > width {
+            x = 0;
+            y += row_height + padding;
+            row_height = 0;
+        }
+        origins[i] = (x, y);
+        x += w + padding;
+        row_height = row_height.max(h);
+    }
+    Ok((origins, y + row_height))
+}
+
+/// Texel rectangle of one image inside an atlas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AtlasRegion {
+    /// `[u0, v0, u1, v1]` in an atlas of `atlas_size` texels
+    pub fn uv(&self, atlas_size: Vec2) -> [f32; 4] {
+        [
+            self.x as f32 / atlas_size.x,
+            self.y as f32 / atlas_size.y,
+            (self.x + self.width) as f32 / atlas_size.x,
+            (self.y + self.height) as f32 / atlas_size.y,
+        ]
+    }
+}
+
+/// Packed RGBA pixels (sRGB) and where each named image ended up
+pub struct AtlasImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+    pub regions: HashMap<String, AtlasRegion>,
+}
+
+/// Collects named RGBA images to pack into one `AtlasImage`
+pub struct AtlasBuilder {
+    width: u32,
+    images: Vec<(String, u32, u32, Vec<u8>)>,
+}
+
+impl AtlasBuilder {
+    /// Atlas `width` texels wide (rows are added as needed)
+    pub fn new(width: u32) -> Self {
+        Self { width, images: Vec::new() }
+    }
+    
+    /// Add a tightly packed RGBA8 image
+    pub fn add(&mut self, name: impl Into<String>, width: u32, height: u32, rgba: Vec<u8>) -> Result<&mut Self> {
+        let name = name.into();
+        if rgba.len() != (width * height * 4) as usize {
+            anyhow::bail!(
+                "Atlas image {:?} has {} bytes, {}x{} RGBA needs {}",
+                name,
+                rgba.len(),
+                width,
+                height,
+                width * height * 4
+            );
+        }
+        self.images.push((name, width, height, rgba));
+        Ok(self)
+    }
+    
+    pub fn build(self) -> Result<AtlasImage> {
+        let sizes: Vec<(u32, u32)> = self.images.iter().map(|&(_, w, h, _)| (w, h)).collect();
+        let (origins, height) = pack_rows(&sizes, self.width, PADDING).context("Failed to pack atlas")?;
+        let height = height.max(1);
+        
+        let row_bytes = (self.width * 4) as usize;
+        let mut pixels = vec![0u8; row_bytes * height as usize];
+        let mut regions = HashMap::with_capacity(self.images.len());
+        for ((name, width, image_height, rgba), (x, y)) in self.images.into_iter().zip(origins) {
+            let image_row = (width * 4) as usize;
+            for row in 0..image_height as usize {
+                let start = (y as usize + row) * row_bytes + x as usize * 4;
+                pixels[start..start + image_row].copy_from_slice(&rgba[row * image_row..(row + 1) * image_row]);
+            }
+            regions.insert(name, AtlasRegion { x, y, width, height: image_height });
+        }
+        
+        Ok(AtlasImage { width: self.width, height, pixels, regions })
+    }
+}
+
+/// An uploaded atlas: its texture in the sprite renderer plus the regions
+#[derive(Debug, Clone)]
+pub struct SpriteAtlas {
+    pub texture: TextureId,
+    /// Texture size in texels
+    pub size: Vec2,
+    pub regions: HashMap<String, AtlasRegion>,
+}
+
+impl SpriteAtlas {
+    /// A sprite showing region `name` at its texel size
+    pub fn sprite(&self, name: &str) -> Result<Sprite> {
+        let region = self.regions.get(name)
+            .with_context(|| format!("No region {:?} in the sprite atlas", name))?;
+        Ok(Sprite::new(
+            self.texture,
+            region.uv(self.size),
+            Vec2::new(region.width as f32, region.height as f32),
+        ))
+    }
+}
@@ -0,0 +1,451 @@
+// Sprite batching
+//
+// Sprites are queued on a `SpriteBatch` during the frame in any order. At
+// upload they are sorted by layer (draw order) and then by texture, turned
+// into quads (nine quads for nine-slice sprites) and written to this
+// frame-in-flight's vertex buffer; consecutive sprites with the same texture
+// become one draw. The 2D layer is drawn after the 3D scene, with alpha
+// blending and no depth test.
+
+use anyhow::Result;
+use ash::vk;
+use glam::{Mat4, Vec2};
+use crate::backend::buffer::DynamicBuffer;
+use crate::backend::descriptor::DescriptorWriter;
+use crate::backend::layout::{PushConstants, Std430, VertexLayout};
+use crate::backend::permutation::SpecializationConstants;
+use crate::backend::pipeline::{BlendMode, GraphicsPipeline, PipelineState};
+use crate::backend::texture::Texture;
+use crate::backend::VulkanDevice;
+use super::atlas::{AtlasImage, SpriteAtlas};
+use super::camera::Camera2D;
+
+/// Vertices per frame in flight (6 per quad); more are dropped
+const MAX_VERTICES: usize = 6 * 32768;
+/// Textures (atlases) a renderer can hold
+const MAX_TEXTURES: u32 = 64;
+
+/// A texture added to a `SpriteRenderer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextureId(u32);
+
+/// Vertices drawn with one texture bound
+#[derive(Debug, Clone, Copy)]
+struct SpriteRun {
+    texture: TextureId,
+    first: u32,
+    count: u32,
+}
+
+/// Borders of a nine-slice sprite in texels of its region
+///
+/// Corners keep their size, edges stretch along one axis and the center
+/// along both, so panels and buttons scale without distorting their frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NineSlice {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl NineSlice {
+    pub fn uniform(border: f32) -> Self {
+        Self { left: border, right: border, top: border, bottom: border }
+    }
+}
+
+/// One textured quad of the 2D layer
+#[derive(Debug, Clone, Copy)]
+pub struct Sprite {
+    pub texture: TextureId,
+    /// Atlas UV rectangle `[u0, v0, u1, v1]`
+    pub uv: [f32; 4],
+    /// Texel size of the region (what nine-slice borders are measured in)
+    pub region_size: Vec2,
+    /// World position of the pivot
+    pub position: Vec2,
+    /// Size in world units
+    pub size: Vec2,
+    /// Point the sprite is placed and rotated around, 0..1 across it
+    pub pivot: Vec2,
+    /// Clockwise rotation in radians
+    pub rotation: f32,
+    /// Multiplies the texture color (linear RGBA)
+    pub tint: [f32; 4],
+    /// Higher layers are drawn on top; equal layers keep submission order
+    pub layer: i32,
+    pub nine_slice: Option<NineSlice>,
+}
+
+impl Sprite {
+    /// The region `uv` of `texture`, drawn at its texel size with the pivot in the middle
+    pub fn new(texture: TextureId, uv: [f32; 4], region_size: Vec2) -> Self {
+        Self {
+            texture,
+            uv,
+            region_size,
+            position: Vec2::ZERO,
+            size: region_size,
+            pivot: Vec2::splat(0.5),
+            rotation: 0.0,
+            tint: [1.0; 4],
+            layer: 0,
+            nine_slice: None,
+        }
+    }
+    
+    pub fn at(self, position: Vec2) -> Self {
+        Self { position, ..self }
+    }
+    
+    pub fn size(self, size: Vec2) -> Self {
+        Self { size, ..self }
+    }
+    
+    pub fn pivot(self, pivot: Vec2) -> Self {
+        Self { pivot, ..self }
+    }
+    
+    pub fn rotation(self, rotation: f32) -> Self {
+        Self { rotation, ..self }
+    }
+    
+    pub fn tint(self, tint: [f32; 4]) -> Self {
+        Self { tint, ..self }
+    }
+    
+    pub fn layer(self, layer: i32) -> Self {
+        Self { layer, ..self }
+    }
+    
+    pub fn nine_slice(self, nine_slice: NineSlice) -> Self {
+        Self { nine_slice: Some(nine_slice), ..self }
+    }
+}
+
+/// Sprites queued for the current frame and the camera they are seen through
+#[derive(Debug, Default)]
+pub struct SpriteBatch {
+    pub camera: Camera2D,
+    sprites: Vec<Sprite>,
+}
+
+impl SpriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+    
+    pub fn draw(&mut self, sprite: Sprite) {
+        self.sprites.push(sprite);
+    }
+    
+    /// Quads of every sprite in draw order, and the vertex range of each texture run
+    fn build(&self) -> (Vec<SpriteVertex>, Vec<SpriteRun>) {
+        let mut order: Vec<&Sprite> = self.sprites.iter().collect();
+        order.sort_by_key(|sprite| (sprite.layer, sprite.texture));
+        
+        let mut vertices = Vec::with_capacity(order.len() * 6);
+        let mut runs: Vec<SpriteRun> = Vec::new();
+        for sprite in order {
+            let first = vertices.len() as u32;
+            push_sprite(&mut vertices, sprite);
+            let count = vertices.len() as u32 - first;
+            match runs.last_mut() {
+                Some(run) if run.texture == sprite.texture => run.count += count,
+                _ => runs.push(SpriteRun { texture: sprite.texture, first, count }),
+            }
+        }
+        (vertices, runs)
+    }
+}
+
+/// Quads for one sprite: one, or nine with a nine-slice
+fn push_sprite(vertices: &mut Vec<SpriteVertex>, sprite: &Sprite) {
+    let [u0, v0, u1, v1] = sprite.uv;
+    let (sin, cos) = sprite.rotation.sin_cos();
+    let origin = sprite.pivot * sprite.size;
+    // Local point (0..size, y down) to world
+    let place = |local: Vec2| {
+        let p = local - origin;
+        sprite.position + Vec2::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos)
+    };
+    
+    // Cell edges along each axis: [start, after the first border, before
+    // the second, end] in local units and in UV
+    let (xs, us, ys, vs) = match sprite.nine_slice {
+        None => (
+            vec![0.0, sprite.size.x],
+            vec![u0, u1],
+            vec![0.0, sprite.size.y],
+            vec![v0, v1],
+        ),
+        Some(slice) => {
+            // Borders shrink together when the sprite is smaller than its frame
+            let scale_x = (sprite.size.x / (slice.left + slice.right)).min(1.0);
+            let scale_y = (sprite.size.y / (slice.top + slice.bottom)).min(1.0);
+            let du = (u1 - u0) / sprite.region_size.x;
+            let dv = (v1 - v0) / sprite.region_size.y;
+            (
+                vec![0.0, slice.left * scale_x, sprite.size.x - slice.right * scale_x, sprite.size.x],
+                vec![u0, u0 + slice.left * du, u1 - slice.right * du, u1],
+                vec![0.0, slice.top * scale_y, sprite.size.y - slice.bottom * scale_y, sprite.size.y],
+                vec![v0, v0 + slice.top * dv, v1 - slice.bottom * dv, v1],
+            )
+        }
+    };
+    
+    for row in 0..ys.len() - 1 {
+        for column in 0..xs.len() - 1 {
+            let corner = |i: usize, j: usize| SpriteVertex {
+                position: place(Vec2::new(xs[column + i], ys[row + j])).to_array(),
+                uv: [us[column + i], vs[row + j]],
+                color: sprite.tint,
+            };
+            let (top_left, top_right) = (corner(0, 0), corner(1, 0));
+            let (bottom_left, bottom_right) = (corner(0, 1), corner(1, 1));
+            vertices.extend_from_slice(&[
+                top_left, bottom_left, bottom_right,
+                bottom_right, top_right, top_left,
+            ]);
+        }
+    }
+}
+
+// =============================================================================
+// RENDERING
+// =============================================================================
+
+/// One sprite vertex (locations 0-2 in sprite.vert)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, VertexLayout)]
+pub struct SpriteVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+/// Push constants for sprite.vert
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PushConstants)]
+struct SpritePushConstants {
+    view_proj: Mat4,
+}
+
+/// Sprite textures, the pipeline and per-frame vertex buffers that draw a `SpriteBatch`
+pub struct SpriteRenderer {
+    pipeline: GraphicsPipeline,
+    descriptor_pool: vk::DescriptorPool,
+    /// Indexed by `TextureId`
+    textures: Vec<(Texture, vk::DescriptorSet)>,
+    /// One vertex buffer per frame in flight
+    buffers: Vec<DynamicBuffer>,
+    /// Per frame: the camera and one run per draw
+    frames: Vec<(Mat4, Vec<SpriteRun>)>,
+    warned_overflow: bool,
+}
+
+impl SpriteRenderer {
+    pub fn new(
+        device: &VulkanDevice,
+        render_pass: vk::RenderPass,
+        extent: vk::Extent2D,
+        frames_in_flight: usize,
+    ) -> Result<Self> {
+        let vert_shader = crate::load_shader!(device, "sprite.vert")?;
+        let shader_push_size = vert_shader.reflection.push_constant_size.unwrap_or(0) as usize;
+        if shader_push_size != SpritePushConstants::SIZE {
+            vert_shader.destroy(&device.device);
+            anyhow::bail!(
+                "sprite.vert declares {} bytes of push constants, SpritePushConstants is {} bytes",
+                shader_push_size,
+                SpritePushConstants::SIZE
+            );
+        }
+        let frag_shader = match crate::load_shader!(device, "sprite.frag") {
+            Ok(shader) => shader,
+            Err(e) => {
+                vert_shader.destroy(&device.device);
+                return Err(e);
+            }
+        };
+        
+        // Over the 3D scene, back to front in layer order
+        let state = PipelineState {
+            cull_mode: vk::CullModeFlags::NONE,
+            depth_test: false,
+            depth_write: false,
+            blend: BlendMode::Alpha,
+            ..Default::default()
+        };
+        let pipeline_result = GraphicsPipeline::new(
+            device,
+            render_pass,
+            extent,
+            &crate::backend::pipeline::get_vertex_input_info::<SpriteVertex>(),
+            (&vert_shader, &frag_shader),
+            &SpecializationConstants::new(),
+            &state,
+        );
+        
+        vert_shader.destroy(&device.device);
+        frag_shader.destroy(&device.device);
+        let pipeline = pipeline_result?;
+        
+        let descriptor_pool = crate::backend::descriptor::create_descriptor_pool(
+            device,
+            MAX_TEXTURES,
+            &[
+                (vk::DescriptorType::SAMPLED_IMAGE, MAX_TEXTURES),
+                (vk::DescriptorType::SAMPLER, MAX_TEXTURES),
+            ],
+        )?;
+        
+        let capacity = (MAX_VERTICES * std::mem::size_of::<SpriteVertex>()) as vk::DeviceSize;
+        let buffers = (0..frames_in_flight)
+            .map(|_| DynamicBuffer::new(device, capacity, vk::BufferUsageFlags::VERTEX_BUFFER))
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(Self {
+            pipeline,
+            descriptor_pool,
+            textures: Vec::new(),
+            buffers,
+            frames: vec![(Mat4::IDENTITY, Vec::new()); frames_in_flight],
+            warned_overflow: false,
+        })
+    }
+    
+    /// Upload a packed atlas; sprites come from the returned `SpriteAtlas`
+    pub fn add_atlas(&mut self, device: &VulkanDevice, image: AtlasImage) -> Result<SpriteAtlas> {
+        let texture = self.add_texture(
+            device,
+            vk::Extent2D { width: image.width, height: image.height },
+            &image.pixels,
+        )?;
+        Ok(SpriteAtlas {
+            texture,
+            size: self.texture_size(texture),
+            regions: image.regions,
+        })
+    }
+    
+    /// Upload RGBA8 (sRGB) pixels as a sprite texture
+    pub fn add_texture(&mut self, device: &VulkanDevice, extent: vk::Extent2D, rgba: &[u8]) -> Result<TextureId> {
+        if self.textures.len() as u32 >= MAX_TEXTURES {
+            anyhow::bail!("Sprite renderer is full ({} textures)", MAX_TEXTURES);
+        }
+        
+        let texture = Texture::new(device, extent, vk::Format::R8G8B8A8_SRGB, rgba, vk::Filter::LINEAR)?;
+        let descriptor_set = match crate::backend::descriptor::allocate_descriptor_sets(
+            device,
+            self.descriptor_pool,
+            &self.pipeline.set_layouts,
+        ) {
+            Ok(sets) => sets[0],
+            Err(e) => {
+                texture.destroy(&device.device);
+                return Err(e);
+            }
+        };
+        DescriptorWriter::new()
+            .sampled_image(0, texture.view)
+            .sampler(1, texture.sampler)
+            .write(device, descriptor_set);
+        
+        self.textures.push((texture, descriptor_set));
+        Ok(TextureId(self.textures.len() as u32 - 1))
+    }
+    
+    /// Swap the pixels behind `id` (a loaded image replacing its placeholder)
+    ///
+    /// Waits for the device: frames in flight may still sample the old texture.
+    pub fn replace_texture(&mut self, device: &VulkanDevice, id: TextureId, extent: vk::Extent2D, rgba: &[u8]) -> Result<()> {
+        let texture = Texture::new(device, extent, vk::Format::R8G8B8A8_SRGB, rgba, vk::Filter::LINEAR)?;
+        device.wait_idle()?;
+        let (old, descriptor_set) = &mut self.textures[id.0 as usize];
+        DescriptorWriter::new()
+            .sampled_image(0, texture.view)
+            .sampler(1, texture.sampler)
+            .write(device, *descriptor_set);
+        std::mem::replace(old, texture).destroy(&device.device);
+        Ok(())
+    }
+    
+    /// Texture size in texels
+    pub fn texture_size(&self, id: TextureId) -> Vec2 {
+        let extent = self.textures[id.0 as usize].0.extent;
+        Vec2::new(extent.width as f32, extent.height as f32)
+    }
+    
+    /// Sort and copy this frame's sprites into the buffer of frame-in-flight
+    /// `frame` (its fence must have signaled)
+    pub fn upload(&mut self, device: &ash::Device, frame: usize, batch: &SpriteBatch, extent: vk::Extent2D) -> Result<()> {
+        let (vertices, mut runs) = batch.build();
+        if vertices.len() > MAX_VERTICES && !self.warned_overflow {
+            log::warn!("Sprites: {} vertices queued, only {} are drawn", vertices.len(), MAX_VERTICES);
+            self.warned_overflow = true;
+        }
+        
+        // Whole quads only; runs past the end are cut
+        let written = self.buffers[frame].write(device, &vertices)?;
+        let written = (written - written % 6) as u32;
+        runs.retain_mut(|run| {
+            run.count = run.count.min(written.saturating_sub(run.first));
+            run.count > 0
+        });
+        
+        self.frames[frame] = (batch.camera.view_proj(extent), runs);
+        Ok(())
+    }
+    
+    /// Draw calls `cmd_draw` records for frame `frame` (one per texture run)
+    pub fn draw_count(&self, frame: usize) -> u32 {
+        self.frames[frame].1.len() as u32
+    }
+    
+    /// Draw frame `frame`'s sprites (inside the render pass, after the 3D scene)
+    pub fn cmd_draw(&self, device: &ash::Device, cmd: vk::CommandBuffer, frame: usize) {
+        let (view_proj, runs) = &self.frames[frame];
+        if runs.is_empty() {
+            return;
+        }
+        
+        let mut push_bytes = [0u8; SpritePushConstants::SIZE];
+        SpritePushConstants { view_proj: *view_proj }.write_bytes(&mut push_bytes);
+        
+        unsafe {
+            device.cmd_bind_pipeline(cmd, vk::PipelineBindPoint::GRAPHICS, self.pipeline.pipeline);
+            device.cmd_push_constants(cmd, self.pipeline.layout, vk::ShaderStageFlags::VERTEX, 0, &push_bytes);
+            device.cmd_bind_vertex_buffers(cmd, 0, &[self.buffers[frame].buffer], &[0]);
+            for run in runs {
+                device.cmd_bind_descriptor_sets(
+                    cmd,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    self.pipeline.layout,
+                    0,
+                    &[self.textures[run.texture.0 as usize].1],
+                    &[],
+                );
+                device.cmd_draw(cmd, run.count, 1, run.first, 0);
+            }
+        }
+    }
+    
+    pub fn destroy(&self, device: &ash::Device) {
+        for buffer in &self.buffers {
+            buffer.destroy(device);
+        }
+        unsafe {
+            device.destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+        for (texture, _) in &self.textures {
+            texture.destroy(device);
+        }
+        self.pipeline.destroy(device);
+    }
+}
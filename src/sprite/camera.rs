@@ -0,0 +1,53 @@
+// 2D camera
+//
+// An orthographic camera for the sprite layer. 2D world coordinates have Y
+// pointing down like screen pixels (and like Vulkan clip space), so a
+// camera with zoom 1 centered on half the viewport maps world units 1:1 to
+// pixels with the origin in the top-left corner.
+
+use ash::vk;
+use glam::{Mat4, Vec2, Vec3};
+
+/// What part of the 2D world the viewport shows
+#[derive(Debug, Clone, Copy)]
+pub struct Camera2D {
+    /// World point at the center of the viewport
+    pub position: Vec2,
+    /// Pixels per world unit
+    pub zoom: f32,
+    /// Clockwise rotation of the view in radians
+    pub rotation: f32,
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self { position: Vec2::ZERO, zoom: 1.0, rotation: 0.0 }
+    }
+}
+
+impl Camera2D {
+    /// World units are pixels, origin top-left
+    pub fn screen(extent: vk::Extent2D) -> Self {
+        Self {
+            position: Vec2::new(extent.width as f32, extent.height as f32) * 0.5,
+            ..Default::default()
+        }
+    }
+    
+    /// World to clip space for a viewport of `extent` pixels
+    pub fn view_proj(&self, extent: vk::Extent2D) -> Mat4 {
+        let scale = 2.0 * self.zoom / Vec2::new(extent.width as f32, extent.height as f32);
+        Mat4::from_scale(scale.extend(1.0))
+            * Mat4::from_rotation_z(-self.rotation)
+            * Mat4::from_translation(-self.position.extend(0.0))
+    }
+    
+    /// World point under a pixel of the viewport (e.g. the mouse cursor)
+    /// Used in: picking and dragging sprites
+    #[allow(dead_code)]
+    pub fn screen_to_world(&self, pixel: Vec2, extent: vk::Extent2D) -> Vec2 {
+        let size = Vec2::new(extent.width as f32, extent.height as f32);
+        let clip = pixel / size * 2.0 - 1.0;
+        self.view_proj(extent).inverse().transform_point3(Vec3::new(clip.x, clip.y, 0.0)).truncate()
+    }
+}
@@ -0,0 +1,10 @@
+// 2D sprite layer
+//
+// - camera: orthographic 2D camera (pixels, or zoomed and rotated world units)
+// - atlas: images packed into one texture, sprites looked up by region name
+// - batch: sprites queued per frame, sorted by layer and texture and drawn
+//   after the 3D scene
+
+pub mod atlas;
+pub mod batch;
+pub mod camera;
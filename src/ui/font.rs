@@ -0,0 +1,110 @@
+// Bitmap font
+//
+// The bundled DejaVu Sans Mono is rasterized once at startup into a grid of
+// fixed-size cells (printable ASCII), so drawing text is one textured quad
+// per character at the font's native size. Monospace keeps layout trivial:
+// every character advances by one cell.
+
+use anyhow::{Context, Result};
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+
+/// DejaVu Sans Mono (Bitstream Vera license, see assets/fonts/LICENSE-DejaVu.txt)
+pub const FONT_TTF: &[u8] = include_bytes!("../../assets/fonts/DejaVuSansMono.ttf");
+
+const FIRST_CHAR: char = ' ';
+const LAST_CHAR: char = '~';
+/// Cells per atlas row
+const COLUMNS: u32 = 16;
+
+/// Layout of a rasterized glyph atlas (the pixels go to a texture)
+#[derive(Debug, Clone, Copy)]
+pub struct BitmapFont {
+    /// Advance and line height in pixels
+    pub cell_width: u32,
+    pub cell_height: u32,
+    pub atlas_width: u32,
+    pub atlas_height: u32,
+}
+
+impl BitmapFont {
+    /// Rasterize printable ASCII at `px` pixels per em
+    ///
+    /// Returns the layout and the R8 coverage atlas. The cell after the
+    /// last glyph is filled solid, so untextured shapes can sample it.
+    pub fn rasterize(px: f32) -> Result<(Self, Vec<u8>)> {
+        let font = FontRef::try_from_slice(FONT_TTF).context("Failed to parse the bundled font")?;
+        let scale = PxScale::from(px);
+        let scaled = font.as_scaled(scale);
+        
+        let cell_width = scaled.h_advance(font.glyph_id('M')).ceil() as u32;
+        let cell_height = (scaled.ascent() - scaled.descent()).ceil() as u32;
+        let cells = (LAST_CHAR as u32 - FIRST_CHAR as u32 + 1) + 1;
+        let rows = cells.div_ceil(COLUMNS);
+        
+        let layout = Self {
+            cell_width,
+            cell_height,
+            atlas_width: COLUMNS * cell_width,
+            atlas_height: rows * cell_height,
+        };
+        let mut pixels = vec![0u8; (layout.atlas_width * layout.atlas_height) as usize];
+        
+        for c in FIRST_CHAR..=LAST_CHAR {
+            let (cell_x, cell_y) = layout.cell_origin(c as u32 - FIRST_CHAR as u32);
+            let glyph = font.glyph_id(c).with_scale_and_position(
+                scale,
+                ab_glyph::point(cell_x as f32, cell_y as f32 + scaled.ascent()),
+            );
+            let Some(outline) = font.outline_glyph(glyph) else {
+                continue; // space
+            };
+            
+            // Anything spilling out of the cell is clipped
+            let bounds = outline.px_bounds();
+            outline.draw(|x, y, coverage| {
+                let px = bounds.min.x as i32 + x as i32;
+                let py = bounds.min.y as i32 + y as i32;
+                let inside_x = (cell_x as i32..(cell_x + cell_width) as i32).contains(&px);
+                let inside_y = (cell_y as i32..(cell_y + cell_height) as i32).contains(&py);
+                if inside_x && inside_y {
+                    let i = (py as u32 * layout.atlas_width + px as u32) as usize;
+                    pixels[i] = pixels[i].max((coverage * 255.0).round() as u8);
+                }
+            });
+        }
+        
+        let (solid_x, solid_y) = layout.cell_origin(cells - 1);
+        for y in solid_y..solid_y + cell_height {
+            let row = (y * layout.atlas_width) as usize;
+            pixels[row + solid_x as usize..row + (solid_x + cell_width) as usize].fill(255);
+        }
+        
+        Ok((layout, pixels))
+    }
+    
+    /// Top-left pixel of cell `index`
+    fn cell_origin(&self, index: u32) -> (u32, u32) {
+        ((index % COLUMNS) * self.cell_width, (index / COLUMNS) * self.cell_height)
+    }
+    
+    /// Atlas UV rectangle `[u0, v0, u1, v1]` of `c` (unknown characters show '?')
+    pub fn glyph_uv(&self, c: char) -> [f32; 4] {
+        let c = if (FIRST_CHAR..=LAST_CHAR).contains(&c) { c } else { '?' };
+        let (x, y) = self.cell_origin(c as u32 - FIRST_CHAR as u32);
+        [
+            x as f32 / self.atlas_width as f32,
+            y as f32 / self.atlas_height as f32,
+            (x + self.cell_width) as f32 / self.atlas_width as f32,
+            (y + self.cell_height) as f32 / self.atlas_height as f32,
+        ]
+    }
+    
+    /// UV of a fully covered texel (the center of the solid cell)
+    pub fn solid_uv(&self) -> [f32; 2] {
+        let (x, y) = self.cell_origin(LAST_CHAR as u32 - FIRST_CHAR as u32 + 1);
+        [
+            (x as f32 + self.cell_width as f32 * 0.5) / self.atlas_width as f32,
+            (y as f32 + self.cell_height as f32 * 0.5) / self.atlas_height as f32,
+        ]
+    }
+}
@@ -0,0 +1,57 @@
+// Immediate-mode GUI (egui)
+//
+// `Gui` owns the egui context and its winit input state: window events are
+// forwarded from `window_event`, and `run` builds one frame of UI and
+// tessellates it for ui::painter::GuiRenderer.
+
+use winit::event::WindowEvent;
+use winit::window::Window;
+
+/// What one `Gui::run` produced for the painter
+pub struct GuiFrame {
+    pub textures_delta: egui::TexturesDelta,
+    pub primitives: Vec<egui::ClippedPrimitive>,
+    pub pixels_per_point: f32,
+}
+
+/// egui context plus the input collected from the window
+pub struct Gui {
+    context: egui::Context,
+    state: egui_winit::State,
+}
+
+impl Gui {
+    /// `max_texture_side` is the device's 2D image limit (the font atlas
+    /// grows up to it)
+    pub fn new(window: &Window, max_texture_side: usize) -> Self {
+        let context = egui::Context::default();
+        let state = egui_winit::State::new(
+            context.clone(),
+            egui::ViewportId::ROOT,
+            window,
+            Some(window.scale_factor() as f32),
+            None,
+            Some(max_texture_side),
+        );
+        Self { context, state }
+    }
+    
+    /// Feed a window event to egui; true when egui used it (a click on a
+    /// panel, typing into a field) and the app should ignore it
+    pub fn on_window_event(&mut self, window: &Window, event: &WindowEvent) -> bool {
+        self.state.on_window_event(window, event).consumed
+    }
+    
+    /// Build this frame's UI with `build` and tessellate it
+    pub fn run(&mut self, window: &Window, build: impl FnMut(&egui::Context)) -> GuiFrame {
+        let input = self.state.take_egui_input(window);
+        let output = self.context.run(input, build);
+        self.state.handle_platform_output(window, output.platform_output);
+        
+        GuiFrame {
+            primitives: self.context.tessellate(output.shapes, output.pixels_per_point),
+            textures_delta: output.textures_delta,
+            pixels_per_point: output.pixels_per_point,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+// Inspector panel
+//
+// An egui window over the scene that edits the live `Config`: clear color,
+// LOD settings, camera and lights take effect on the next frame, everything
+// else is written to config.toml by "Save" and applies on restart.
+
+use crate::config::{self, Config};
+
+const PRESENT_MODES: [&str; 4] = ["immediate", "mailbox", "fifo", "fifo_relaxed"];
+const CULLING_MODES: [&str; 3] = ["gpu", "cpu", "off"];
+const LIGHTING_MODELS: [&str; 2] = ["lambert", "half_lambert"];
+
+/// Inspector visibility (F9) and the result of the last save
+pub struct Inspector {
+    pub visible: bool,
+    status: Option<String>,
+}
+
+impl Inspector {
+    pub fn new(visible: bool) -> Self {
+        Self { visible, status: None }
+    }
+    
+    /// Add the inspector window to this frame's UI
+    pub fn show(&mut self, context: &egui::Context, config: &mut Config) {
+        let mut open = self.visible;
+        egui::Window::new("Inspector")
+            .open(&mut open)
+            .default_pos([context.content_rect().width() - 300.0, 8.0])
+            .default_width(280.0)
+            .show(context, |ui| {
+                egui::CollapsingHeader::new("Graphics").default_open(true).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Clear color");
+                        // Edit a copy: the picker's HSV round trip nudges the
+                        // stored values every frame even when nothing changed
+                        let mut color = config.graphics.clear_color;
+                        if ui.color_edit_button_rgba_unmultiplied(&mut color).changed() {
+                            config.graphics.clear_color = color;
+                        }
+                    });
+                    ui.add(egui::Slider::new(&mut config.graphics.lod_threshold, 0.1..=16.0)
+                        .logarithmic(true)
+                        .text("LOD threshold (px)"));
+                    ui.add(egui::Slider::new(&mut config.graphics.lod_fade_range, 0.0..=1.0)
+                        .text("LOD fade range"));
+                    ui.add(egui::Slider::new(&mut config.graphics.fps_limit, 0..=480).text("FPS limit (0 = off)"));
+                    ui.checkbox(&mut config.graphics.low_latency, "Low latency");
+                });
+                
+                egui::CollapsingHeader::new("Camera").default_open(true).show(ui, |ui| {
+                    let camera = &mut config.camera;
+                    ui.add(egui::Slider::new(&mut camera.fov_y, 20.0..=120.0).text("FOV (deg)"));
+                    ui.add(egui::Slider::new(&mut camera.yaw, -180.0..=180.0).text("Yaw (deg)"));
+                    ui.add(egui::Slider::new(&mut camera.pitch, -89.0..=89.0).text("Pitch (deg)"));
+                    ui.add(egui::Slider::new(&mut camera.distance, 0.0..=500.0)
+                        .logarithmic(true)
+                        .text("Distance (0 = fit)"));
+                    if ui.button("Reset").clicked() {
+                        *camera = config::CameraConfig::default();
+                    }
+                });
+                
+                egui::CollapsingHeader::new("Lighting").default_open(true).show(ui, |ui| {
+                    let lighting = &mut config.lighting;
+                    direction_row(ui, "Key direction", &mut lighting.key_direction);
+                    ui.add(egui::Slider::new(&mut lighting.key_intensity, 0.0..=2.0).text("Key intensity"));
+                    direction_row(ui, "Fill direction", &mut lighting.fill_direction);
+                    ui.add(egui::Slider::new(&mut lighting.fill_intensity, 0.0..=2.0).text("Fill intensity"));
+                    ui.add(egui::Slider::new(&mut lighting.ambient, 0.0..=1.0).text("Ambient"));
+                    if ui.button("Reset").clicked() {
+                        *lighting = config::LightingConfig::default();
+                    }
+                });
+                
+                egui::CollapsingHeader::new("On restart").show(ui, |ui| {
+                    choice_row(ui, "Present mode", &mut config.graphics.present_mode, &PRESENT_MODES);
+                    choice_row(ui, "Culling", &mut config.graphics.culling, &CULLING_MODES);
+                    choice_row(ui, "Lighting model", &mut config.lighting.model, &LIGHTING_MODELS);
+                    ui.add(egui::Slider::new(&mut config.scene.cube_count, 1..=100_000)
+                        .logarithmic(true)
+                        .text("Cubes"));
+                    ui.add(egui::Slider::new(&mut config.scene.cube_spacing, 1.0..=10.0).text("Cube spacing"));
+                });
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Save to config.toml").clicked() {
+                        self.status = Some(match config.save_to_path(config::CONFIG_PATH) {
+                            Ok(()) => "Saved".to_string(),
+                            Err(e) => {
+                                log::error!("Failed to save config: {:#}", e);
+                                format!("Save failed: {}", e)
+                            }
+                        });
+                    }
+                    if let Some(status) = &self.status {
+                        ui.weak(status.as_str());
+                    }
+                });
+            });
+        self.visible = open;
+    }
+}
+
+/// Three drag values for a light direction
+fn direction_row(ui: &mut egui::Ui, label: &str, direction: &mut [f32; 3]) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        for component in direction.iter_mut() {
+            ui.add(egui::DragValue::new(component).speed(0.01).range(-1.0..=1.0));
+        }
+    });
+}
+
+/// A combo box over the accepted spellings of a string setting
+fn choice_row(ui: &mut egui::Ui, label: &str, value: &mut String, choices: &[&str]) {
+    egui::ComboBox::from_label(label)
+        .selected_text(value.as_str())
+        .show_ui(ui, |ui| {
+            for &choice in choices {
+                ui.selectable_value(value, choice.to_string(), choice);
+            }
+        });
+}
@@ -0,0 +1,17 @@
+// UI and text
+//
+// - font: the bundled monospace font rasterized into a glyph atlas
+// - overlay: rectangles and text in pixel coordinates, drawn over the scene
+// - gui: egui context and window input
+// - painter: draws egui output with its own pipeline and textures
+// - inspector: egui window editing the live config
+// - sdf: TTF/OTF fonts rasterized into a signed distance field atlas
+// - text: text layout and batched screen/world text drawn from the SDF atlas
+
+pub mod font;
+pub mod gui;
+pub mod inspector;
+pub mod overlay;
+pub mod painter;
+pub mod sdf;
+pub mod text;
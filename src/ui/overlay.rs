@@ -0,0 +1,261 @@
+// Screen-space overlay
+//
+// `Overlay` collects solid rectangles and text for one frame in pixel
+// coordinates (origin top-left); `OverlayRenderer` copies them into this
+// frame-in-flight's vertex buffer and draws them after the scene with alpha
+// blending. Text samples glyph cells of the font atlas and rectangles sample
+// its solid cell, so one pipeline and one draw cover everything.
+
+use anyhow::Result;
+use ash::vk;
+use glam::Vec2;
+use crate::backend::buffer::DynamicBuffer;
+use crate::backend::descriptor::DescriptorWriter;
+use crate::backend::layout::{PushConstants, Std430, VertexLayout};
+use crate::backend::permutation::SpecializationConstants;
+use crate::backend::pipeline::{BlendMode, GraphicsPipeline, PipelineState};
+use crate::backend::texture::Texture;
+use crate::backend::VulkanDevice;
+use super::font::BitmapFont;
+
+/// Vertices per frame in flight (6 per quad); more are dropped
+const MAX_VERTICES: usize = 6 * 16384;
+
+/// One overlay vertex (locations 0-2 in overlay.vert)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, VertexLayout)]
+pub struct OverlayVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+/// Push constants for overlay.vert
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PushConstants)]
+struct OverlayPushConstants {
+    screen_size: Vec2,
+}
+
+/// Quads queued for the current frame
+pub struct Overlay {
+    font: BitmapFont,
+    vertices: Vec<OverlayVertex>,
+}
+
+impl Overlay {
+    pub fn new(font: BitmapFont) -> Self {
+        Self { font, vertices: Vec::new() }
+    }
+    
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+    
+    /// Pixel size of `text` (lines split at '\n')
+    pub fn text_size(&self, text: &str) -> Vec2 {
+        let columns = text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+        let lines = text.lines().count().max(1);
+        Vec2::new(
+            (columns as u32 * self.font.cell_width) as f32,
+            (lines as u32 * self.font.cell_height) as f32,
+        )
+    }
+    
+    /// A solid rectangle
+    pub fn rect(&mut self, position: Vec2, size: Vec2, color: [f32; 4]) {
+        let [u, v] = self.font.solid_uv();
+        self.quad(position, size, [u, v, u, v], color);
+    }
+    
+    /// Text with its top-left corner at `position` (lines split at '\n')
+    pub fn text(&mut self, position: Vec2, text: &str, color: [f32; 4]) {
+        let cell = Vec2::new(self.font.cell_width as f32, self.font.cell_height as f32);
+        // Whole pixels keep the 1:1 glyphs crisp
+        let origin = position.round();
+        for (row, line) in text.lines().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let uv = self.font.glyph_uv(c);
+                self.quad(origin + cell * Vec2::new(column as f32, row as f32), cell, uv, color);
+            }
+        }
+    }
+    
+    fn quad(&mut self, position: Vec2, size: Vec2, [u0, v0, u1, v1]: [f32; 4], color: [f32; 4]) {
+        let corner = |x: f32, y: f32, u: f32, v: f32| OverlayVertex {
+            position: (position + size * Vec2::new(x, y)).to_array(),
+            uv: [u, v],
+            color,
+        };
+        let (top_left, top_right) = (corner(0.0, 0.0, u0, v0), corner(1.0, 0.0, u1, v0));
+        let (bottom_left, bottom_right) = (corner(0.0, 1.0, u0, v1), corner(1.0, 1.0, u1, v1));
+        self.vertices.extend_from_slice(&[
+            top_left, bottom_left, bottom_right,
+            bottom_right, top_right, top_left,
+        ]);
+    }
+}
+
+// =============================================================================
+// RENDERING
+// =============================================================================
+
+/// Font atlas, pipeline and per-frame vertex buffers that draw an `Overlay`
+pub struct OverlayRenderer {
+    atlas: Texture,
+    pipeline: GraphicsPipeline,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+    /// One vertex buffer per frame in flight
+    buffers: Vec<DynamicBuffer>,
+    /// Vertices uploaded per frame
+    counts: Vec<u32>,
+    warned_overflow: bool,
+}
+
+impl OverlayRenderer {
+    /// `atlas_pixels` is the R8 atlas from `BitmapFont::rasterize`
+    pub fn new(
+        device: &VulkanDevice,
+        render_pass: vk::RenderPass,
+        extent: vk::Extent2D,
+        frames_in_flight: usize,
+        font: &BitmapFont,
+        atlas_pixels: &[u8],
+    ) -> Result<Self> {
+        let vert_shader = crate::load_shader!(device, "overlay.vert")?;
+        let shader_push_size = vert_shader.reflection.push_constant_size.unwrap_or(0) as usize;
+        if shader_push_size != OverlayPushConstants::SIZE {
+            vert_shader.destroy(&device.device);
+            anyhow::bail!(
+                "overlay.vert declares {} bytes of push constants, OverlayPushConstants is {} bytes",
+                shader_push_size,
+                OverlayPushConstants::SIZE
+            );
+        }
+        let frag_shader = match crate::load_shader!(device, "overlay.frag") {
+            Ok(shader) => shader,
+            Err(e) => {
+                vert_shader.destroy(&device.device);
+                return Err(e);
+            }
+        };
+        
+        // Drawn last, over everything, in whatever order quads were added
+        let state = PipelineState {
+            cull_mode: vk::CullModeFlags::NONE,
+            depth_test: false,
+            depth_write: false,
+            blend: BlendMode::Alpha,
+            ..Default::default()
+        };
+        let pipeline_result = GraphicsPipeline::new(
+            device,
+            render_pass,
+            extent,
+            &crate::backend::pipeline::get_vertex_input_info::<OverlayVertex>(),
+            (&vert_shader, &frag_shader),
+            &SpecializationConstants::new(),
+            &state,
+        );
+        
+        vert_shader.destroy(&device.device);
+        frag_shader.destroy(&device.device);
+        let pipeline = pipeline_result?;
+        
+        // Glyphs are drawn 1:1, so nearest filtering keeps them sharp
+        let atlas = Texture::new(
+            device,
+            vk::Extent2D { width: font.atlas_width, height: font.atlas_height },
+            vk::Format::R8_UNORM,
+            atlas_pixels,
+            vk::Filter::NEAREST,
+        )?;
+        
+        let descriptor_pool = crate::backend::descriptor::create_descriptor_pool(
+            device,
+            1,
+            &[(vk::DescriptorType::SAMPLED_IMAGE, 1), (vk::DescriptorType::SAMPLER, 1)],
+        )?;
+        let descriptor_set = crate::backend::descriptor::allocate_descriptor_sets(
+            device,
+            descriptor_pool,
+            &pipeline.set_layouts,
+        )?[0];
+        DescriptorWriter::new()
+            .sampled_image(0, atlas.view)
+            .sampler(1, atlas.sampler)
+            .write(device, descriptor_set);
+        
+        let capacity = (MAX_VERTICES * std::mem::size_of::<OverlayVertex>()) as vk::DeviceSize;
+        let buffers = (0..frames_in_flight)
+            .map(|_| DynamicBuffer::new(device, capacity, vk::BufferUsageFlags::VERTEX_BUFFER))
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(Self {
+            atlas,
+            pipeline,
+            descriptor_pool,
+            descriptor_set,
+            buffers,
+            counts: vec![0; frames_in_flight],
+            warned_overflow: false,
+        })
+    }
+    
+    /// Copy this frame's quads into the buffer of frame-in-flight `frame`
+    /// (its fence must have signaled)
+    pub fn upload(&mut self, device: &ash::Device, frame: usize, overlay: &Overlay) -> Result<()> {
+        if overlay.vertices.len() > MAX_VERTICES && !self.warned_overflow {
+            log::warn!("Overlay: {} vertices queued, only {} are drawn", overlay.vertices.len(), MAX_VERTICES);
+            self.warned_overflow = true;
+        }
+        
+        // Whole quads only
+        let written = self.buffers[frame].write(device, &overlay.vertices)?;
+        self.counts[frame] = (written - written % 6) as u32;
+        Ok(())
+    }
+    
+    /// Draw frame `frame`'s quads (inside the render pass, after everything else)
+    pub fn cmd_draw(&self, device: &ash::Device, cmd: vk::CommandBuffer, frame: usize, extent: vk::Extent2D) {
+        let count = self.counts[frame];
+        if count == 0 {
+            return;
+        }
+        
+        let mut push_bytes = [0u8; OverlayPushConstants::SIZE];
+        OverlayPushConstants {
+            screen_size: Vec2::new(extent.width as f32, extent.height as f32),
+        }.write_bytes(&mut push_bytes);
+        
+        unsafe {
+            device.cmd_bind_pipeline(cmd, vk::PipelineBindPoint::GRAPHICS, self.pipeline.pipeline);
+            device.cmd_bind_descriptor_sets(
+                cmd,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline.layout,
+                0,
+                &[self.descriptor_set],
+                &[],
+            );
+            device.cmd_push_constants(cmd, self.pipeline.layout, vk::ShaderStageFlags::VERTEX, 0, &push_bytes);
+            device.cmd_bind_vertex_buffers(cmd, 0, &[self.buffers[frame].buffer], &[0]);
+            device.cmd_draw(cmd, count, 1, 0, 0);
+        }
+    }
+    
+    pub fn destroy(&self, device: &ash::Device) {
+        for buffer in &self.buffers {
+            buffer.destroy(device);
+        }
+        unsafe {
+            device.destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+        self.pipeline.destroy(device);
+        self.atlas.destroy(device);
+    }
+}
@@ -0,0 +1,362 @@
+// egui painter
+//
+// Draws tessellated egui output with its own pipeline: every egui texture
+// (the font atlas and any user images) becomes a `Texture` with its own
+// descriptor set, meshes of a frame are packed into this frame-in-flight's
+// vertex and index buffers, and each mesh is one indexed draw clipped with a
+// dynamic scissor.
+
+use anyhow::Result;
+use ash::vk;
+use glam::Vec2;
+use std::collections::HashMap;
+use crate::backend::buffer::DynamicBuffer;
+use crate::backend::descriptor::DescriptorWriter;
+use crate::backend::layout::{PushConstants, Std430, VertexLayout};
+use crate::backend::permutation::SpecializationConstants;
+use crate::backend::pipeline::{BlendMode, GraphicsPipeline, PipelineState};
+use crate::backend::texture::Texture;
+use crate::backend::VulkanDevice;
+
+/// Vertices and indices per frame in flight; meshes past either are dropped
+const MAX_VERTICES: usize = 1 << 16;
+const MAX_INDICES: usize = 3 * MAX_VERTICES;
+
+/// `egui::epaint::Vertex` as gui.vert reads it (locations 0-2)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, VertexLayout)]
+struct GuiVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    /// sRGB, premultiplied alpha
+    #[vertex(normalized)]
+    color: [u8; 4],
+}
+
+/// Push constants for gui.vert
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PushConstants)]
+struct GuiPushConstants {
+    /// Screen size in points (egui coordinates)
+    screen_size: Vec2,
+}
+
+/// An egui texture and the set that binds it
+struct GuiTexture {
+    texture: Texture,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+}
+
+impl GuiTexture {
+    fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+        self.texture.destroy(device);
+    }
+}
+
+/// One mesh of the uploaded frame
+struct GuiDraw {
+    texture: egui::TextureId,
+    scissor: vk::Rect2D,
+    first_index: u32,
+    index_count: u32,
+    vertex_offset: i32,
+}
+
+/// Pipeline, textures and per-frame buffers that draw egui output
+pub struct GuiRenderer {
+    pipeline: GraphicsPipeline,
+    textures: HashMap<egui::TextureId, GuiTexture>,
+    /// Freed by egui last frame; destroyed once the GPU is done with them
+    pending_free: Vec<egui::TextureId>,
+    /// One vertex and index buffer per frame in flight
+    vertex_buffers: Vec<DynamicBuffer>,
+    index_buffers: Vec<DynamicBuffer>,
+    /// Draws and screen size in points uploaded per frame
+    draws: Vec<Vec<GuiDraw>>,
+    screen_sizes: Vec<Vec2>,
+    warned_overflow: bool,
+}
+
+impl GuiRenderer {
+    pub fn new(
+        device: &VulkanDevice,
+        render_pass: vk::RenderPass,
+        extent: vk::Extent2D,
+        frames_in_flight: usize,
+    ) -> Result<Self> {
+        let vert_shader = crate::load_shader!(device, "gui.vert")?;
+        let shader_push_size = vert_shader.reflection.push_constant_size.unwrap_or(0) as usize;
+        if shader_push_size != GuiPushConstants::SIZE {
+            vert_shader.destroy(&device.device);
+            anyhow::bail!(
+                "gui.vert declares {} bytes of push constants, GuiPushConstants is {} bytes",
+                shader_push_size,
+                GuiPushConstants::SIZE
+            );
+        }
+        let frag_shader = match crate::load_shader!(device, "gui.frag") {
+            Ok(shader) => shader,
+            Err(e) => {
+                vert_shader.destroy(&device.device);
+                return Err(e);
+            }
+        };
+        
+        // Drawn last, in egui's order, each mesh clipped to its rectangle
+        let state = PipelineState {
+            cull_mode: vk::CullModeFlags::NONE,
+            depth_test: false,
+            depth_write: false,
+            blend: BlendMode::Premultiplied,
+            dynamic_scissor: true,
+            ..Default::default()
+        };
+        let pipeline_result = GraphicsPipeline::new(
+            device,
+            render_pass,
+            extent,
+            &crate::backend::pipeline::get_vertex_input_info::<GuiVertex>(),
+            (&vert_shader, &frag_shader),
+            &SpecializationConstants::new(),
+            &state,
+        );
+        
+        vert_shader.destroy(&device.device);
+        frag_shader.destroy(&device.device);
+        let pipeline = pipeline_result?;
+        
+        let vertex_capacity = (MAX_VERTICES * std::mem::size_of::<GuiVertex>()) as vk::DeviceSize;
+        let index_capacity = (MAX_INDICES * std::mem::size_of::<u32>()) as vk::DeviceSize;
+        let vertex_buffers = (0..frames_in_flight)
+            .map(|_| DynamicBuffer::new(device, vertex_capacity, vk::BufferUsageFlags::VERTEX_BUFFER))
+            .collect::<Result<Vec<_>>>()?;
+        let index_buffers = (0..frames_in_flight)
+            .map(|_| DynamicBuffer::new(device, index_capacity, vk::BufferUsageFlags::INDEX_BUFFER))
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(Self {
+            pipeline,
+            textures: HashMap::new(),
+            pending_free: Vec::new(),
+            vertex_buffers,
+            index_buffers,
+            draws: (0..frames_in_flight).map(|_| Vec::new()).collect(),
+            screen_sizes: vec![Vec2::ONE; frames_in_flight],
+            warned_overflow: false,
+        })
+    }
+    
+    /// Apply egui's texture changes (before `upload`)
+    ///
+    /// They are rare (the font atlas on the first frames, new images), so
+    /// instead of tracking which frames still sample a texture this waits for
+    /// the device before touching any.
+    pub fn update_textures(&mut self, device: &VulkanDevice, delta: &egui::TexturesDelta) -> Result<()> {
+        if !delta.set.is_empty() || !self.pending_free.is_empty() {
+            device.wait_idle()?;
+        }
+        
+        for id in self.pending_free.drain(..) {
+            if let Some(texture) = self.textures.remove(&id) {
+                texture.destroy(&device.device);
+            }
+        }
+        
+        for (id, image_delta) in &delta.set {
+            let egui::ImageData::Color(image) = &image_delta.image;
+            let extent = vk::Extent2D {
+                width: image.size[0] as u32,
+                height: image.size[1] as u32,
+            };
+            let pixels: Vec<u8> = image.pixels.iter().flat_map(|color| color.to_array()).collect();
+            
+            match image_delta.pos {
+                // Patch of an existing texture
+                Some([x, y]) => {
+                    let Some(existing) = self.textures.get(id) else {
+                        log::warn!("egui updated texture {:?} before creating it", id);
+                        continue;
+                    };
+                    let offset = vk::Offset2D { x: x as i32, y: y as i32 };
+                    existing.texture.update(device, offset, extent, &pixels)?;
+                }
+                // Whole texture: (re)create it
+                None => {
+                    let texture = self.create_texture(device, extent, &pixels, image_delta.options)?;
+                    if let Some(old) = self.textures.insert(*id, texture) {
+                        old.destroy(&device.device);
+                    }
+                }
+            }
+        }
+        
+        // Still used by this frame's meshes
+        self.pending_free.extend_from_slice(&delta.free);
+        Ok(())
+    }
+    
+    /// Upload an sRGB egui image and give it a descriptor set of its own
+    fn create_texture(
+        &self,
+        device: &VulkanDevice,
+        extent: vk::Extent2D,
+        pixels: &[u8],
+        options: egui::TextureOptions,
+    ) -> Result<GuiTexture> {
+        // One filter for both directions; wrap modes are ignored (always clamp)
+        let filter = match options.magnification {
+            egui::TextureFilter::Nearest => vk::Filter::NEAREST,
+            egui::TextureFilter::Linear => vk::Filter::LINEAR,
+        };
+        let texture = Texture::new(device, extent, vk::Format::R8G8B8A8_SRGB, pixels, filter)?;
+        
+        let descriptors = crate::backend::descriptor::create_descriptor_pool(
+            device,
+            1,
+            &[(vk::DescriptorType::SAMPLED_IMAGE, 1), (vk::DescriptorType::SAMPLER, 1)],
+        ).and_then(|pool| {
+            match crate::backend::descriptor::allocate_descriptor_sets(device, pool, &self.pipeline.set_layouts) {
+                Ok(sets) => Ok((pool, sets[0])),
+                Err(e) => {
+                    unsafe {
+                        device.device.destroy_descriptor_pool(pool, None);
+                    }
+                    Err(e)
+                }
+            }
+        });
+        let (descriptor_pool, descriptor_set) = match descriptors {
+            Ok(descriptors) => descriptors,
+            Err(e) => {
+                texture.destroy(&device.device);
+                return Err(e);
+            }
+        };
+        
+        DescriptorWriter::new()
+            .sampled_image(0, texture.view)
+            .sampler(1, texture.sampler)
+            .write(device, descriptor_set);
+        
+        Ok(GuiTexture { texture, descriptor_pool, descriptor_set })
+    }
+    
+    /// Pack this frame's meshes into the buffers of frame-in-flight `frame`
+    /// (its fence must have signaled)
+    pub fn upload(
+        &mut self,
+        device: &ash::Device,
+        frame: usize,
+        primitives: &[egui::ClippedPrimitive],
+        pixels_per_point: f32,
+        extent: vk::Extent2D,
+    ) -> Result<()> {
+        let mut vertices: Vec<GuiVertex> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        let draws = &mut self.draws[frame];
+        draws.clear();
+        
+        for primitive in primitives {
+            let egui::epaint::Primitive::Mesh(mesh) = &primitive.primitive else {
+                // Paint callbacks need backend-specific code; none are used
+                continue;
+            };
+            if mesh.indices.is_empty() {
+                continue;
+            }
+            if vertices.len() + mesh.vertices.len() > MAX_VERTICES
+                || indices.len() + mesh.indices.len() > MAX_INDICES
+            {
+                if !self.warned_overflow {
+                    log::warn!("egui: frame exceeds {} vertices, dropping meshes", MAX_VERTICES);
+                    self.warned_overflow = true;
+                }
+                break;
+            }
+            
+            // Clip rectangle in points -> scissor in pixels, inside the screen
+            let clip = primitive.clip_rect;
+            let min_x = (clip.min.x * pixels_per_point).round().clamp(0.0, extent.width as f32) as u32;
+            let min_y = (clip.min.y * pixels_per_point).round().clamp(0.0, extent.height as f32) as u32;
+            let max_x = (clip.max.x * pixels_per_point).round().clamp(min_x as f32, extent.width as f32) as u32;
+            let max_y = (clip.max.y * pixels_per_point).round().clamp(min_y as f32, extent.height as f32) as u32;
+            if max_x == min_x || max_y == min_y {
+                continue;
+            }
+            
+            draws.push(GuiDraw {
+                texture: mesh.texture_id,
+                scissor: vk::Rect2D {
+                    offset: vk::Offset2D { x: min_x as i32, y: min_y as i32 },
+                    extent: vk::Extent2D { width: max_x - min_x, height: max_y - min_y },
+                },
+                first_index: indices.len() as u32,
+                index_count: mesh.indices.len() as u32,
+                vertex_offset: vertices.len() as i32,
+            });
+            vertices.extend(mesh.vertices.iter().map(|v| GuiVertex {
+                position: [v.pos.x, v.pos.y],
+                uv: [v.uv.x, v.uv.y],
+                color: v.color.to_array(),
+            }));
+            indices.extend_from_slice(&mesh.indices);
+        }
+        
+        self.vertex_buffers[frame].write(device, &vertices)?;
+        self.index_buffers[frame].write(device, &indices)?;
+        self.screen_sizes[frame] = Vec2::new(extent.width as f32, extent.height as f32) / pixels_per_point;
+        Ok(())
+    }
+    
+    /// Draw frame `frame`'s meshes (inside the render pass, after everything else)
+    pub fn cmd_draw(&self, device: &ash::Device, cmd: vk::CommandBuffer, frame: usize) {
+        let draws = &self.draws[frame];
+        if draws.is_empty() {
+            return;
+        }
+        
+        let mut push_bytes = [0u8; GuiPushConstants::SIZE];
+        GuiPushConstants { screen_size: self.screen_sizes[frame] }.write_bytes(&mut push_bytes);
+        
+        unsafe {
+            device.cmd_bind_pipeline(cmd, vk::PipelineBindPoint::GRAPHICS, self.pipeline.pipeline);
+            device.cmd_push_constants(cmd, self.pipeline.layout, vk::ShaderStageFlags::VERTEX, 0, &push_bytes);
+            device.cmd_bind_vertex_buffers(cmd, 0, &[self.vertex_buffers[frame].buffer], &[0]);
+            device.cmd_bind_index_buffer(cmd, self.index_buffers[frame].buffer, 0, vk::IndexType::UINT32);
+            
+            let mut bound = None;
+            for draw in draws {
+                let Some(texture) = self.textures.get(&draw.texture) else {
+                    continue;
+                };
+                if bound != Some(draw.texture) {
+                    device.cmd_bind_descriptor_sets(
+                        cmd,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        self.pipeline.layout,
+                        0,
+                        &[texture.descriptor_set],
+                        &[],
+                    );
+                    bound = Some(draw.texture);
+                }
+                device.cmd_set_scissor(cmd, 0, &[draw.scissor]);
+                device.cmd_draw_indexed(cmd, draw.index_count, 1, draw.first_index, draw.vertex_offset, 0);
+            }
+        }
+    }
+    
+    pub fn destroy(&self, device: &ash::Device) {
+        for texture in self.textures.values() {
+            texture.destroy(device);
+        }
+        for buffer in self.vertex_buffers.iter().chain(&self.index_buffers) {
+            buffer.destroy(device);
+        }
+        self.pipeline.destroy(device);
+    }
+}
@@ -0,0 +1,310 @@
+// Signed distance field fonts
+//
+// Any TTF/OTF font is rasterized once into an atlas of signed distance
+// fields: every texel stores how far it is from the glyph outline (0.5 on
+// the edge, higher inside), so the same atlas draws sharp text at any size
+// and under any transform. Glyphs are rendered `SDF_PX` pixels high and
+// packed into rows (sprite::atlas::pack_rows); metrics are kept in units of
+// the font's line height, which is what `TextStyle::size` sets (see ui::text).
+
+use std::collections::HashMap;
+use std::path::Path;
+use anyhow::{Context, Result};
+use ab_glyph::{Font, FontVec, GlyphId, PxScale, ScaleFont};
+use glam::Vec2;
+use crate::sprite::atlas::pack_rows;
+
+/// Line height (ascent - descent) glyphs are rasterized at
+const SDF_PX: f32 = 48.0;
+/// Distance in atlas pixels from the outline to where the field saturates;
+/// also the padding around every glyph
+const SDF_SPREAD: usize = 8;
+/// Atlas width; rows are added until every glyph fits
+const ATLAS_WIDTH: usize = 1024;
+
+/// Printable ASCII and Latin-1
+pub fn default_charset() -> impl Iterator<Item = char> {
+    (' '..='~').chain('\u{a1}'..='\u{ff}')
+}
+
+/// Where a glyph's field sits in the atlas and relative to the pen
+///
+/// Lengths are in line heights (multiply by the text size).
+#[derive(Debug, Clone, Copy)]
+pub struct SdfGlyph {
+    id: GlyphId,
+    /// Atlas UV rectangle `[u0, v0, u1, v1]` (empty for blank glyphs)
+    pub uv: [f32; 4],
+    /// Top-left corner of the quad from the pen position on the baseline (y down)
+    pub offset: Vec2,
+    /// Quad size (zero for blank glyphs such as space)
+    pub size: Vec2,
+    /// Horizontal pen advance
+    pub advance: f32,
+}
+
+/// A font with its glyphs rendered into an SDF atlas (the pixels go to a texture)
+pub struct SdfFont {
+    font: FontVec,
+    glyphs: HashMap<char, SdfGlyph>,
+    /// Shown for characters outside the charset
+    fallback: SdfGlyph,
+    /// Baseline below the top of a line, in line heights
+    pub ascent: f32,
+    /// Extra space between lines, in line heights
+    pub line_gap: f32,
+    pub atlas_width: u32,
+    pub atlas_height: u32,
+}
+
+impl SdfFont {
+    /// Load a TTF/OTF file and rasterize the default charset
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<(Self, Vec<u8>)> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read font: {:?}", path))?;
+        Self::from_bytes(data, default_charset())
+            .with_context(|| format!("Failed to load font: {:?}", path))
+    }
+    
+    /// The bundled DejaVu Sans Mono
+    pub fn bundled() -> Result<(Self, Vec<u8>)> {
+        Self::from_bytes(super::font::FONT_TTF.to_vec(), default_charset())
+    }
+    
+    /// Rasterize `charset` (plus '?') from TTF/OTF data
+    ///
+    /// Returns the font and the R8 distance atlas. Characters the font has no
+    /// glyph for are left out and drawn as '?'.
+    pub fn from_bytes(data: Vec<u8>, charset: impl IntoIterator<Item = char>) -> Result<(Self, Vec<u8>)> {
+        let font = FontVec::try_from_vec(data).context("Not a TTF/OTF font")?;
+        let scale = PxScale::from(SDF_PX);
+        let scaled = font.as_scaled(scale);
+        
+        // Rasterize every glyph into its own padded field
+        let mut chars: Vec<char> = charset.into_iter().chain(['?']).collect();
+        chars.sort_unstable();
+        chars.dedup();
+        chars.retain(|&c| c == ' ' || font.glyph_id(c) != GlyphId(0));
+        
+        let fields: Vec<(char, Option<GlyphField>)> = chars
+            .iter()
+            .map(|&c| (c, GlyphField::rasterize(&font, font.glyph_id(c), scale)))
+            .collect();
+        
+        // Blank glyphs take no room; the fields' own padding keeps neighbours apart
+        let sizes: Vec<(u32, u32)> = fields
+            .iter()
+            .map(|(_, field)| field.as_ref().map_or((0, 0), |f| (f.width as u32, f.height as u32)))
+            .collect();
+        let (origins, height) = pack_rows(&sizes, ATLAS_WIDTH as u32, 0)?;
+        let atlas_width = ATLAS_WIDTH;
+        let atlas_height = (height as usize).max(1);
+        
+        let mut pixels = vec![0u8; atlas_width * atlas_height];
+        let mut glyphs = HashMap::with_capacity(fields.len());
+        for (i, (c, field)) in fields.iter().enumerate() {
+            let id = font.glyph_id(*c);
+            let advance = scaled.h_advance(id) / SDF_PX;
+            let glyph = match field {
+                Some(field) => {
+                    let (x0, y0) = (origins[i].0 as usize, origins[i].1 as usize);
+                    for row in 0..field.height {
+                        let start = (y0 + row) * atlas_width + x0;
+                        pixels[start..start + field.width]
+                            .copy_from_slice(&field.distances[row * field.width..(row + 1) * field.width]);
+                    }
+                    SdfGlyph {
+                        id,
+                        uv: [
+                            x0 as f32 / atlas_width as f32,
+                            y0 as f32 / atlas_height as f32,
+                            (x0 + field.width) as f32 / atlas_width as f32,
+                            (y0 + field.height) as f32 / atlas_height as f32,
+                        ],
+                        offset: field.offset / SDF_PX,
+                        size: Vec2::new(field.width as f32, field.height as f32) / SDF_PX,
+                        advance,
+                    }
+                }
+                None => SdfGlyph { id, uv: [0.0; 4], offset: Vec2::ZERO, size: Vec2::ZERO, advance },
+            };
+            glyphs.insert(*c, glyph);
+        }
+        
+        let fallback = *glyphs.get(&'?').context("Font has no '?' glyph")?;
+        let height = scaled.ascent() - scaled.descent();
+        let (ascent, line_gap) = (scaled.ascent() / height, scaled.line_gap() / height);
+        log::info!(
+            "SDF font: {} glyphs in a {}x{} atlas",
+            glyphs.len(),
+            atlas_width,
+            atlas_height
+        );
+        
+        Ok((
+            Self {
+                font,
+                glyphs,
+                fallback,
+                ascent,
+                line_gap,
+                atlas_width: atlas_width as u32,
+                atlas_height: atlas_height as u32,
+            },
+            pixels,
+        ))
+    }
+    
+    /// Glyph for `c` ('?' when it was not rasterized)
+    pub fn glyph(&self, c: char) -> &SdfGlyph {
+        self.glyphs.get(&c).unwrap_or(&self.fallback)
+    }
+    
+    /// Kerning adjustment between two glyphs, in line heights
+    pub fn kern(&self, first: &SdfGlyph, second: &SdfGlyph) -> f32 {
+        self.font.as_scaled(PxScale::from(SDF_PX)).kern(first.id, second.id) / SDF_PX
+    }
+}
+
+// =============================================================================
+// DISTANCE TRANSFORM
+// =============================================================================
+
+/// One glyph's distance field before packing
+struct GlyphField {
+    width: usize,
+    height: usize,
+    /// Top-left from the pen position, in `SDF_PX` pixels
+    offset: Vec2,
+    distances: Vec<u8>,
+}
+
+impl GlyphField {
+    /// Coverage of the outline, turned into distances (None for blank glyphs)
+    fn rasterize(font: &FontVec, id: GlyphId, scale: PxScale) -> Option<Self> {
+        let outline = font.outline_glyph(id.with_scale_and_position(scale, ab_glyph::point(0.0, 0.0)))?;
+        let bounds = outline.px_bounds();
+        let width = bounds.width() as usize + 2 * SDF_SPREAD;
+        let height = bounds.height() as usize + 2 * SDF_SPREAD;
+        
+        let mut coverage = vec![0.0f32; width * height];
+        outline.draw(|x, y, c| {
+            let (x, y) = (x as usize + SDF_SPREAD, y as usize + SDF_SPREAD);
+            // 8-bit steps, so interior texels are exactly 1 rather than 0.9999
+            if x < width && y < height {
+                coverage[y * width + x] = (c.clamp(0.0, 1.0) * 255.0).round() / 255.0;
+            }
+        });
+        
+        Some(Self {
+            width,
+            height,
+            offset: Vec2::new(bounds.min.x, bounds.min.y) - SDF_SPREAD as f32,
+            distances: distance_field(&coverage, width, height),
+        })
+    }
+}
+
+/// Signed distances from antialiased coverage, 0.5 on the edge
+///
+/// Exact Euclidean distance transforms (Felzenszwalb & Huttenlocher) to the
+/// nearest inside and outside texel; partially covered texels start at
+/// their estimated sub-texel distance to the edge, as in Mapbox's TinySDF.
+fn distance_field(coverage: &[f32], width: usize, height: usize) -> Vec<u8> {
+    let mut outside = vec![0.0f64; coverage.len()];
+    let mut inside = vec![0.0f64; coverage.len()];
+    for (i, &a) in coverage.iter().enumerate() {
+        (outside[i], inside[i]) = if a >= 1.0 {
+            (0.0, f64::INFINITY)
+        } else if a <= 0.0 {
+            (f64::INFINITY, 0.0)
+        } else {
+            let d = 0.5 - a as f64;
+            (if d > 0.0 { d * d } else { 0.0 }, if d < 0.0 { d * d } else { 0.0 })
+        };
+    }
+    
+    euclidean_distance_transform(&mut outside, width, height);
+    euclidean_distance_transform(&mut inside, width, height);
+    
+    outside
+        .iter()
+        .zip(&inside)
+        .map(|(&o, &i)| {
+            let distance = o.sqrt() - i.sqrt();
+            let value = 0.5 - distance / (2.0 * SDF_SPREAD as f64);
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+        .collect()
+}
+
+/// Squared distance transform of a grid in place (columns, then rows)
+fn euclidean_distance_transform(grid: &mut [f64], width: usize, height: usize) {
+    let length = width.max(height);
+    let mut f = vec![0.0; length];
+    let mut v = vec![0usize; length];
+    let mut z = vec![0.0; length + 1];
+    
+    for x in 0..width {
+        transform_line(grid, x, width, height, &mut f, &mut v, &mut z);
+    }
+    for y in 0..height {
+        transform_line(grid, y * width, 1, width, &mut f, &mut v, &mut z);
+    }
+}
+
+/// 1D pass over `length` cells starting at `offset`, `stride` apart: the
+/// lower envelope of the parabolas rooted at every cell
+fn transform_line(
+    grid: &mut [f64],
+    offset: usize,
+    stride: usize,
+    length: usize,
+    f: &mut [f64],
+    v: &mut [usize],
+    z: &mut [f64],
+) {
+    for q in 0..length {
+        f[q] = grid[offset + q * stride];
+    }
+    
+    // Skip leading cells at infinity: they can't root a parabola
+    let Some(first) = (0..length).find(|&q| f[q].is_finite()) else {
+        return;
+    };
+    v[0] = first;
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+    let mut k = 0;
+    for q in first + 1..length {
+        if !f[q].is_finite() {
+            continue;
+        }
+        let q2 = (q * q) as f64;
+        // Drop parabolas the new one hides (z[0] is -inf, so k stops at 0)
+        let mut s;
+        loop {
+            let r = v[k];
+            s = (f[q] - f[r] + q2 - (r * r) as f64) / (q - r) as f64 / 2.0;
+            if s > z[k] {
+                break;
+            }
+            k -= 1;
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+    
+    let mut k = 0;
+    for q in 0..length {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let r = v[k];
+        let d = q as f64 - r as f64;
+        grid[offset + q * stride] = f[r] + d * d;
+    }
+}
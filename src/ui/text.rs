@@ -0,0 +1,467 @@
+// Text
+//
+// `TextLayout` breaks a string into lines (explicit '\n', then word wrap at
+// `max_width`), applies kerning and alignment and places one quad per
+// visible glyph. `TextBatch` collects laid-out text for one frame, either in
+// pixels (screen space, origin top-left) or on a plane in the world, and
+// `TextRenderer` draws both from one vertex buffer per frame in flight:
+// world text depth tested against the scene, screen text over it.
+
+use anyhow::Result;
+use ash::vk;
+use glam::{Mat4, Vec2, Vec3};
+use crate::backend::buffer::DynamicBuffer;
+use crate::backend::descriptor::DescriptorWriter;
+use crate::backend::layout::{PushConstants, Std430, VertexLayout};
+use crate::backend::permutation::SpecializationConstants;
+use crate::backend::pipeline::{BlendMode, GraphicsPipeline, PipelineState};
+use crate::backend::texture::Texture;
+use crate::backend::VulkanDevice;
+use super::sdf::SdfFont;
+
+/// Vertices per frame in flight (6 per glyph); more are dropped
+const MAX_VERTICES: usize = 6 * 16384;
+
+/// Horizontal placement of each line relative to the anchor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    /// Lines start at the anchor
+    #[default]
+    Left,
+    /// Lines are centered on the anchor
+    Center,
+    /// Lines end at the anchor
+    /// Used in: right-aligned labels (columns of numbers)
+    #[allow(dead_code)]
+    Right,
+}
+
+/// How a string is laid out and colored
+#[derive(Debug, Clone, Copy)]
+pub struct TextStyle {
+    /// Line height: pixels for screen text, world units for world text
+    pub size: f32,
+    pub color: [f32; 4],
+    pub align: Align,
+    /// Wrap lines at spaces once they get wider than this (same units as `size`)
+    pub max_width: Option<f32>,
+    /// Multiplier on the font's line spacing
+    pub line_spacing: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            size: 16.0,
+            color: [1.0; 4],
+            align: Align::Left,
+            max_width: None,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn new(size: f32, color: [f32; 4]) -> Self {
+        Self { size, color, ..Default::default() }
+    }
+    
+    pub fn align(self, align: Align) -> Self {
+        Self { align, ..self }
+    }
+    
+    /// Used in: labels and tooltips with a fixed width
+    #[allow(dead_code)]
+    pub fn max_width(self, max_width: f32) -> Self {
+        Self { max_width: Some(max_width), ..self }
+    }
+}
+
+/// One glyph quad of a layout
+#[derive(Debug, Clone, Copy)]
+pub struct PlacedGlyph {
+    /// Top-left corner relative to the anchor (y down)
+    pub position: Vec2,
+    pub size: Vec2,
+    pub uv: [f32; 4],
+}
+
+/// A string broken into lines and placed glyph by glyph
+#[derive(Debug, Clone, Default)]
+pub struct TextLayout {
+    pub glyphs: Vec<PlacedGlyph>,
+    /// Widest line by total height
+    pub size: Vec2,
+}
+
+impl TextLayout {
+    /// Lay out `text` with the anchor at the top of the first line
+    ///
+    /// Words wider than `max_width` on their own are not broken.
+    pub fn new(font: &SdfFont, text: &str, style: &TextStyle) -> Self {
+        let lines: Vec<&str> = text
+            .split('\n')
+            .flat_map(|paragraph| wrap(font, paragraph, style.max_width.map(|w| w / style.size)))
+            .collect();
+        let line_advance = (1.0 + font.line_gap) * style.line_spacing * style.size;
+        
+        let mut layout = Self::default();
+        for (row, line) in lines.iter().enumerate() {
+            let width = line_width(font, line) * style.size;
+            let start = match style.align {
+                Align::Left => 0.0,
+                Align::Center => -width * 0.5,
+                Align::Right => -width,
+            };
+            let baseline = row as f32 * line_advance + font.ascent * style.size;
+            
+            let mut pen = start;
+            let mut previous = None;
+            for c in line.chars() {
+                let glyph = font.glyph(c);
+                if let Some(previous) = previous {
+                    pen += font.kern(previous, glyph) * style.size;
+                }
+                if glyph.size != Vec2::ZERO {
+                    layout.glyphs.push(PlacedGlyph {
+                        position: Vec2::new(pen, baseline) + glyph.offset * style.size,
+                        size: glyph.size * style.size,
+                        uv: glyph.uv,
+                    });
+                }
+                pen += glyph.advance * style.size;
+                previous = Some(glyph);
+            }
+            layout.size.x = layout.size.x.max(width);
+        }
+        layout.size.y = lines.len() as f32 * line_advance;
+        layout
+    }
+}
+
+/// Width of one line in line heights, kerning included
+fn line_width(font: &SdfFont, line: &str) -> f32 {
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in line.chars() {
+        let glyph = font.glyph(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, glyph);
+        }
+        width += glyph.advance;
+        previous = Some(glyph);
+    }
+    width
+}
+
+/// Greedy word wrap of one paragraph (widths in line heights)
+fn wrap<'a>(font: &SdfFont, paragraph: &'a str, max_width: Option<f32>) -> Vec<&'a str> {
+    let Some(max_width) = max_width else {
+        return vec![paragraph];
+    };
+    
+    let mut lines = Vec::new();
+    let mut start = 0;
+    // End of the last word on the current line
+    let mut line_end = None;
+    for (word_end, _) in paragraph.match_indices(' ').chain([(paragraph.len(), "")]) {
+        // A line always takes its first word, even one that's too wide
+        if let Some(end) = line_end {
+            if line_width(font, &paragraph[start..word_end]) > max_width {
+                lines.push(&paragraph[start..end]);
+                start = end + 1;
+            }
+        }
+        line_end = Some(word_end);
+    }
+    lines.push(&paragraph[start..]);
+    lines
+}
+
+/// One text vertex (locations 0-2 in text.vert)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, VertexLayout)]
+pub struct TextVertex {
+    pub position: [f32; 3],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+/// Push constants for text.vert
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PushConstants)]
+struct TextPushConstants {
+    transform: Mat4,
+}
+
+/// Text queued for the current frame
+pub struct TextBatch {
+    font: SdfFont,
+    world: Vec<TextVertex>,
+    screen: Vec<TextVertex>,
+}
+
+impl TextBatch {
+    pub fn new(font: SdfFont) -> Self {
+        Self { font, world: Vec::new(), screen: Vec::new() }
+    }
+    
+    pub fn clear(&mut self) {
+        self.world.clear();
+        self.screen.clear();
+    }
+    
+    pub fn layout(&self, text: &str, style: &TextStyle) -> TextLayout {
+        TextLayout::new(&self.font, text, style)
+    }
+    
+    /// Text in pixels, anchored at `position` (top of the first line)
+    pub fn screen(&mut self, position: Vec2, text: &str, style: &TextStyle) {
+        let layout = self.layout(text, style);
+        push_quads(&mut self.screen, &layout, style.color, |p| position.extend(0.0) + p.extend(0.0));
+    }
+    
+    /// Text on the XY plane of `transform` (+X right, +Y up), anchored at its origin
+    pub fn world(&mut self, transform: Mat4, text: &str, style: &TextStyle) {
+        let layout = self.layout(text, style);
+        push_quads(&mut self.world, &layout, style.color, |p| {
+            transform.transform_point3(Vec3::new(p.x, -p.y, 0.0))
+        });
+    }
+    
+    /// World text at `position` that always faces a camera with this `view`
+    /// matrix, centered on the anchor vertically
+    pub fn billboard(&mut self, position: Vec3, view: Mat4, text: &str, style: &TextStyle) {
+        let rotation = Mat4::from_quat(view.to_scale_rotation_translation().1.inverse());
+        let height = self.layout(text, style).size.y;
+        let transform = Mat4::from_translation(position)
+            * rotation
+            * Mat4::from_translation(Vec3::new(0.0, height * 0.5, 0.0));
+        self.world(transform, text, style);
+    }
+}
+
+/// Two triangles per glyph, corners mapped through `place`
+fn push_quads(vertices: &mut Vec<TextVertex>, layout: &TextLayout, color: [f32; 4], place: impl Fn(Vec2) -> Vec3) {
+    for glyph in &layout.glyphs {
+        let [u0, v0, u1, v1] = glyph.uv;
+        let corner = |x: f32, y: f32, u: f32, v: f32| TextVertex {
+            position: place(glyph.position + glyph.size * Vec2::new(x, y)).to_array(),
+            uv: [u, v],
+            color,
+        };
+        let (top_left, top_right) = (corner(0.0, 0.0, u0, v0), corner(1.0, 0.0, u1, v0));
+        let (bottom_left, bottom_right) = (corner(0.0, 1.0, u0, v1), corner(1.0, 1.0, u1, v1));
+        vertices.extend_from_slice(&[
+            top_left, bottom_left, bottom_right,
+            bottom_right, top_right, top_left,
+        ]);
+    }
+}
+
+// =============================================================================
+// RENDERING
+// =============================================================================
+
+/// SDF atlas, pipelines and per-frame vertex buffers that draw a `TextBatch`
+pub struct TextRenderer {
+    atlas: Texture,
+    world: GraphicsPipeline,
+    screen: GraphicsPipeline,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+    /// One vertex buffer per frame in flight
+    buffers: Vec<DynamicBuffer>,
+    /// Vertices uploaded per frame: world first, then screen
+    counts: Vec<(u32, u32)>,
+    warned_overflow: bool,
+}
+
+impl TextRenderer {
+    /// `atlas_pixels` is the R8 atlas from `SdfFont::from_bytes`
+    pub fn new(
+        device: &VulkanDevice,
+        render_pass: vk::RenderPass,
+        extent: vk::Extent2D,
+        frames_in_flight: usize,
+        font: &SdfFont,
+        atlas_pixels: &[u8],
+    ) -> Result<Self> {
+        let vert_shader = crate::load_shader!(device, "text.vert")?;
+        let shader_push_size = vert_shader.reflection.push_constant_size.unwrap_or(0) as usize;
+        if shader_push_size != TextPushConstants::SIZE {
+            vert_shader.destroy(&device.device);
+            anyhow::bail!(
+                "text.vert declares {} bytes of push constants, TextPushConstants is {} bytes",
+                shader_push_size,
+                TextPushConstants::SIZE
+            );
+        }
+        let frag_shader = match crate::load_shader!(device, "text.frag") {
+            Ok(shader) => shader,
+            Err(e) => {
+                vert_shader.destroy(&device.device);
+                return Err(e);
+            }
+        };
+        
+        // Glyph quads overlap their neighbours' padding, so neither writes depth
+        let world_state = PipelineState {
+            cull_mode: vk::CullModeFlags::NONE,
+            depth_write: false,
+            depth_compare: vk::CompareOp::LESS_OR_EQUAL,
+            blend: BlendMode::Alpha,
+            ..Default::default()
+        };
+        let screen_state = PipelineState { depth_test: false, ..world_state };
+        
+        let vertex_input = crate::backend::pipeline::get_vertex_input_info::<TextVertex>();
+        let create = |state: &PipelineState| GraphicsPipeline::new(
+            device,
+            render_pass,
+            extent,
+            &vertex_input,
+            (&vert_shader, &frag_shader),
+            &SpecializationConstants::new(),
+            state,
+        );
+        let pipelines = create(&world_state).and_then(|world| {
+            match create(&screen_state) {
+                Ok(screen) => Ok((world, screen)),
+                Err(e) => {
+                    world.destroy(&device.device);
+                    Err(e)
+                }
+            }
+        });
+        
+        vert_shader.destroy(&device.device);
+        frag_shader.destroy(&device.device);
+        
+        let (world, screen) = pipelines?;
+        
+        // Distances interpolate, which is what keeps scaled glyphs smooth
+        let atlas = Texture::new(
+            device,
+            vk::Extent2D { width: font.atlas_width, height: font.atlas_height },
+            vk::Format::R8_UNORM,
+            atlas_pixels,
+            vk::Filter::LINEAR,
+        )?;
+        
+        let descriptor_pool = crate::backend::descriptor::create_descriptor_pool(
+            device,
+            1,
+            &[(vk::DescriptorType::SAMPLED_IMAGE, 1), (vk::DescriptorType::SAMPLER, 1)],
+        )?;
+        let descriptor_set = crate::backend::descriptor::allocate_descriptor_sets(
+            device,
+            descriptor_pool,
+            &world.set_layouts,
+        )?[0];
+        DescriptorWriter::new()
+            .sampled_image(0, atlas.view)
+            .sampler(1, atlas.sampler)
+            .write(device, descriptor_set);
+        
+        let capacity = (MAX_VERTICES * std::mem::size_of::<TextVertex>()) as vk::DeviceSize;
+        let buffers = (0..frames_in_flight)
+            .map(|_| DynamicBuffer::new(device, capacity, vk::BufferUsageFlags::VERTEX_BUFFER))
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(Self {
+            atlas,
+            world,
+            screen,
+            descriptor_pool,
+            descriptor_set,
+            buffers,
+            counts: vec![(0, 0); frames_in_flight],
+            warned_overflow: false,
+        })
+    }
+    
+    /// Copy this frame's text into the buffer of frame-in-flight `frame`
+    /// (its fence must have signaled)
+    pub fn upload(&mut self, device: &ash::Device, frame: usize, batch: &TextBatch) -> Result<()> {
+        let total = batch.world.len() + batch.screen.len();
+        if total > MAX_VERTICES && !self.warned_overflow {
+            log::warn!("Text: {} vertices queued, only {} are drawn", total, MAX_VERTICES);
+            self.warned_overflow = true;
+        }
+        
+        let mut vertices = Vec::with_capacity(total.min(MAX_VERTICES));
+        vertices.extend_from_slice(&batch.world);
+        vertices.extend_from_slice(&batch.screen);
+        // Whole quads only
+        let written = self.buffers[frame].write(device, &vertices)?;
+        let written = written - written % 6;
+        
+        let world = written.min(batch.world.len());
+        self.counts[frame] = (world as u32, (written - world) as u32);
+        Ok(())
+    }
+    
+    /// Draw calls `cmd_draw` records for frame `frame` (0-2)
+    pub fn draw_count(&self, frame: usize) -> u32 {
+        let (world, screen) = self.counts[frame];
+        (world > 0) as u32 + (screen > 0) as u32
+    }
+    
+    /// Draw frame `frame`'s text (inside the render pass, after the scene)
+    pub fn cmd_draw(
+        &self,
+        device: &ash::Device,
+        cmd: vk::CommandBuffer,
+        frame: usize,
+        view_proj: Mat4,
+        extent: vk::Extent2D,
+    ) {
+        let (world, screen) = self.counts[frame];
+        if world + screen == 0 {
+            return;
+        }
+        
+        // Pixels to clip space (Vulkan clip space already has Y pointing down)
+        let pixels_to_clip = Mat4::from_translation(Vec3::new(-1.0, -1.0, 0.0))
+            * Mat4::from_scale(Vec3::new(2.0 / extent.width as f32, 2.0 / extent.height as f32, 1.0));
+        
+        unsafe {
+            device.cmd_bind_vertex_buffers(cmd, 0, &[self.buffers[frame].buffer], &[0]);
+            for (pipeline, transform, first, count) in [
+                (&self.world, view_proj, 0, world),
+                (&self.screen, pixels_to_clip, world, screen),
+            ] {
+                if count == 0 {
+                    continue;
+                }
+                let mut push_bytes = [0u8; TextPushConstants::SIZE];
+                TextPushConstants { transform }.write_bytes(&mut push_bytes);
+                
+                device.cmd_bind_pipeline(cmd, vk::PipelineBindPoint::GRAPHICS, pipeline.pipeline);
+                device.cmd_bind_descriptor_sets(
+                    cmd,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    pipeline.layout,
+                    0,
+                    &[self.descriptor_set],
+                    &[],
+                );
+                device.cmd_push_constants(cmd, pipeline.layout, vk::ShaderStageFlags::VERTEX, 0, &push_bytes);
+                device.cmd_draw(cmd, count, 1, first, 0);
+            }
+        }
+    }
+    
+    pub fn destroy(&self, device: &ash::Device) {
+        for buffer in &self.buffers {
+            buffer.destroy(device);
+        }
+        unsafe {
+            device.destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+        self.world.destroy(device);
+        self.screen.destroy(device);
+        self.atlas.destroy(device);
+    }
+}